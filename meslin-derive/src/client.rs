@@ -0,0 +1,120 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{DeriveInput, Ident, Type};
+
+/// Generate `<Protocol>Client<S>`, with one method per variant.
+pub fn derive(
+    input: &DeriveInput,
+    variant_names: &[&Ident],
+    variant_types: &[&Type],
+    flat_names: &[&Ident],
+    flat_types: &[&Type],
+) -> syn::Result<TokenStream> {
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "A client can not be generated for generic protocols",
+        ));
+    }
+
+    let vis = &input.vis;
+    let name = &input.ident;
+    let client = format_ident!("{}Client", name);
+    let doc = format!(
+        "A client of [`{name}`], with one method per message, which is generated by \
+         `#[meslin(client)]`."
+    );
+
+    let methods = variant_names.iter().zip(variant_types).map(|(ident, ty)| {
+        let method = format_ident!("{}", to_snake_case(&ident.to_string()));
+        let doc = format!(
+            "Send a `{}` message, waiting asynchronously until space becomes available.",
+            quote!(#ty).to_string().replace(' ', "")
+        );
+        quote! {
+            #[doc = #doc]
+            pub async fn #method(
+                &self,
+                input: impl ::core::convert::Into<<#ty as ::meslin::Message>::Input>,
+            ) -> ::core::result::Result<
+                <#ty as ::meslin::Message>::Output,
+                ::meslin::SendError<<#ty as ::meslin::Message>::Input>,
+            >
+            where
+                _S: ::meslin::Sends<#ty>,
+                _S::With: ::core::default::Default,
+            {
+                ::meslin::IsSenderExt::send::<#ty>(&self.sender, input).await
+            }
+        }
+    });
+
+    // Flattened variants are sent through the messages of the inner protocol, which are named
+    // when calling the method.
+    let flat_methods = flat_names.iter().zip(flat_types).map(|(ident, ty)| {
+        let method = format_ident!("{}", to_snake_case(&ident.to_string()));
+        let doc = format!(
+            "Send a message of the flattened `{}`, waiting asynchronously until space becomes \
+             available.",
+            quote!(#ty).to_string().replace(' ', "")
+        );
+        quote! {
+            #[doc = #doc]
+            pub async fn #method<_M: ::meslin::Message>(
+                &self,
+                input: impl ::core::convert::Into<_M::Input>,
+            ) -> ::core::result::Result<_M::Output, ::meslin::SendError<_M::Input>>
+            where
+                #ty: ::core::convert::From<_M>,
+                _S: ::meslin::Sends<_M>,
+                _S::With: ::core::default::Default,
+            {
+                ::meslin::IsSenderExt::send::<_M>(&self.sender, input).await
+            }
+        }
+    });
+
+    Ok(quote! {
+        #[doc = #doc]
+        #[derive(Debug, Clone)]
+        #vis struct #client<_S> {
+            sender: _S,
+        }
+
+        #[automatically_derived]
+        impl<_S> #client<_S> {
+            pub fn new(sender: _S) -> Self {
+                Self { sender }
+            }
+
+            pub fn into_inner(self) -> _S {
+                self.sender
+            }
+
+            pub fn inner_ref(&self) -> &_S {
+                &self.sender
+            }
+
+            #(#methods)*
+            #(#flat_methods)*
+        }
+    })
+}
+
+/// Convert a variant name to snake-case, keeping acronyms together: `HTTPRequest` becomes
+/// `http_request`.
+pub(crate) fn to_snake_case(ident: &str) -> String {
+    let chars = ident.chars().collect::<Vec<_>>();
+    let mut snake = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+            if (!prev.is_uppercase() && prev != '_') || (prev.is_uppercase() && next_is_lower) {
+                snake.push('_');
+            }
+        }
+        snake.extend(c.to_lowercase());
+    }
+    snake
+}
@@ -0,0 +1,144 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput};
+
+pub fn derive(input: DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input,
+            "Dispatch can only be derived for enums",
+        ));
+    };
+
+    let mut variant_names = Vec::new();
+    let mut variant_types = Vec::new();
+    let mut flat_names = Vec::new();
+    let mut flat_types = Vec::new();
+    for variant in &data.variants {
+        let ty = match &variant.fields {
+            syn::Fields::Unnamed(fields) if fields.unnamed.len() == 1 => &fields.unnamed[0].ty,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    variant,
+                    "Dispatch can only be derived for enums with exactly one unnamed field",
+                ))
+            }
+        };
+        match variant
+            .attrs
+            .iter()
+            .any(|attr| attr.path().is_ident("meslin"))
+        {
+            true => {
+                flat_names.push(&variant.ident);
+                flat_types.push(ty);
+            }
+            false => {
+                variant_names.push(&variant.ident);
+                variant_types.push(ty);
+            }
+        }
+    }
+
+    let attrs = ContainerAttrs::parse(&input)?;
+    let client = match attrs.client {
+        true => crate::client::derive(
+            &input,
+            &variant_names,
+            &variant_types,
+            &flat_names,
+            &flat_types,
+        )?,
+        false => TokenStream::new(),
+    };
+    if attrs.service {
+        let service = crate::service::derive(
+            &input,
+            &variant_names,
+            &variant_types,
+            &flat_names,
+            &flat_types,
+        )?;
+        return Ok(quote! {
+            #service
+            #client
+        });
+    }
+
+    let mut generics = input.generics.clone();
+    generics.params.push(parse_quote!(_S: Send));
+    let (impl_generics, _, _) = generics.split_for_impl();
+    let (_, ty_generics, where_clause) = input.generics.split_for_impl();
+    let mut where_clause = where_clause.cloned().unwrap_or_else(|| parse_quote!(where));
+    where_clause.predicates.push(parse_quote!(Self: Send));
+    for ty in &variant_types {
+        where_clause
+            .predicates
+            .push(parse_quote!(_S: ::meslin::Handler<#ty>));
+    }
+    for ty in &flat_types {
+        where_clause
+            .predicates
+            .push(parse_quote!(#ty: ::meslin::Dispatch<_S>));
+    }
+
+    Ok(quote! {
+        #[automatically_derived]
+        impl #impl_generics ::meslin::Dispatch<_S> for #name #ty_generics #where_clause {
+            fn dispatch(self, state: &mut _S) -> impl ::core::future::Future<Output = ()> + Send {
+                async move {
+                    match self {
+                        #(
+                            Self::#variant_names(msg) => {
+                                <_S as ::meslin::Handler<#variant_types>>::handle(state, msg).await
+                            }
+                        )*
+                        #(
+                            Self::#flat_names(protocol) => {
+                                ::meslin::Dispatch::dispatch(protocol, state).await
+                            }
+                        )*
+                    }
+                }
+            }
+        }
+
+        #client
+    })
+}
+
+/// The options of `#[meslin(..)]` on the protocol.
+struct ContainerAttrs {
+    /// `#[meslin(client)]`: generate `<Protocol>Client<S>`.
+    client: bool,
+    /// `#[meslin(service)]`: generate `<Protocol>Service`, and dispatch the protocol to it.
+    service: bool,
+}
+
+impl ContainerAttrs {
+    fn parse(input: &DeriveInput) -> syn::Result<Self> {
+        let mut attrs = Self {
+            client: false,
+            service: false,
+        };
+        for attr in input
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("meslin"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("client") {
+                    attrs.client = true;
+                } else if meta.path.is_ident("service") {
+                    attrs.service = true;
+                } else {
+                    return Err(meta.error("expected `client` or `service`"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(attrs)
+    }
+}
@@ -1,6 +1,6 @@
 use proc_macro2::TokenStream;
 use quote::quote;
-use syn::{Data, DeriveInput};
+use syn::{parse::Parse, Data, DeriveInput};
 
 pub fn derive(input: DeriveInput) -> syn::Result<TokenStream> {
     let name = &input.ident;
@@ -13,33 +13,74 @@ pub fn derive(input: DeriveInput) -> syn::Result<TokenStream> {
         ));
     };
 
-    let variant_names = data
-        .variants
+    let mut variant_names = Vec::new();
+    let mut variant_types = Vec::new();
+    let mut flattened = Vec::new();
+    for variant in &data.variants {
+        let fields = match &variant.fields {
+            syn::Fields::Unnamed(fields) => fields.unnamed.iter().collect::<Vec<_>>(),
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    variant,
+                    "DynFromInto can only be derived for enums with unnamed fields",
+                ))
+            }
+        };
+        if fields.len() != 1 {
+            return Err(syn::Error::new_spanned(
+                variant,
+                "DynFromInto can only be derived for enums with exactly one field",
+            ));
+        }
+        match flatten_attr(variant)? {
+            Some(msgs) => flattened.push((&variant.ident, &fields[0].ty, msgs)),
+            None => {
+                variant_names.push(&variant.ident);
+                variant_types.push(&fields[0].ty);
+            }
+        }
+    }
+
+    let flat_names = flattened
         .iter()
-        .map(|variant| &variant.ident)
+        .map(|(name, _, _)| *name)
         .collect::<Vec<_>>();
-    let variant_types = data
-        .variants
+    let flat_types = flattened.iter().map(|(_, ty, _)| *ty).collect::<Vec<_>>();
+    let flat_msgs = flattened
         .iter()
-        .map(|variant| {
-            let fields = match &variant.fields {
-                syn::Fields::Unnamed(fields) => fields.unnamed.iter().collect::<Vec<_>>(),
-                _ => {
-                    return Err(syn::Error::new_spanned(
-                        variant,
-                        "DynFromInto can only be derived for enums with unnamed fields",
-                    ))
+        .flat_map(|(name, ty, msgs)| msgs.iter().map(move |msg| (*name, *ty, msg)))
+        .map(|(flat_name, flat_type, msg)| {
+            quote! {
+                #[automatically_derived]
+                impl #impl_generics ::core::convert::From<#msg> for #name #ty_generics #where_clause {
+                    fn from(msg: #msg) -> Self {
+                        Self::#flat_name(<#flat_type as ::core::convert::From<#msg>>::from(msg))
+                    }
+                }
+
+                #[automatically_derived]
+                impl #impl_generics ::core::convert::TryFrom<#name #ty_generics> for #msg #where_clause {
+                    type Error = #name #ty_generics;
+
+                    fn try_from(protocol: #name #ty_generics) -> Result<Self, Self::Error> {
+                        #[allow(unreachable_patterns)]
+                        match protocol {
+                            #name::#flat_name(protocol) => {
+                                ::meslin::__downcast_protocol::<#flat_type, #msg>(protocol)
+                                    .map_err(#name::#flat_name)
+                            }
+                            protocol => Err(protocol),
+                        }
+                    }
                 }
-            };
-            if fields.len() != 1 {
-                return Err(syn::Error::new_spanned(
-                    variant,
-                    "DynFromInto can only be derived for enums with exactly one field",
-                ));
             }
-            Ok(&fields[0].ty)
         })
-        .collect::<Result<Vec<_>, _>>()?;
+        .collect::<Vec<_>>();
+    let members = variant_types
+        .iter()
+        .copied()
+        .chain(flattened.iter().flat_map(|(_, _, msgs)| msgs.iter()))
+        .collect::<Vec<_>>();
 
     Ok(quote! {
         #[automatically_derived]
@@ -53,6 +94,12 @@ pub fn derive(input: DeriveInput) -> syn::Result<TokenStream> {
                         Err(msg) => msg,
                     };
                 )*
+                #(
+                    let msg = match <#flat_types as ::meslin::DynProtocol>::try_from_boxed_msg(msg) {
+                        Ok((protocol, with)) => return Ok((Self::#flat_names(protocol), with)),
+                        Err(msg) => msg,
+                    };
+                )*
                 Err(msg)
             }
 
@@ -61,13 +108,51 @@ pub fn derive(input: DeriveInput) -> syn::Result<TokenStream> {
                     #(
                         Self::#variant_names(msg) => ::meslin::BoxedMsg::new(msg, with),
                     )*
+                    #(
+                        Self::#flat_names(protocol) => ::meslin::DynProtocol::into_boxed_msg(protocol, with),
+                    )*
                 }
             }
+
+            fn protocol_info() -> ::meslin::ProtocolInfo {
+                ::meslin::ProtocolInfo::new::<Self>(::std::vec![
+                    #(::meslin::MessageInfo::of::<#members>(),)*
+                ])
+            }
         }
 
         #[automatically_derived]
         impl #impl_generics ::meslin::type_sets::AsSet for #name #ty_generics #where_clause {
-            type Set = ::meslin::type_sets::Set![#(#variant_types),*];
+            type Set = ::meslin::type_sets::Set![#(#members),*];
         }
+
+        #(#flat_msgs)*
     })
 }
+
+/// Parse `#[meslin(flatten(Msg1, Msg2, ...))]` on a variant, returning the flattened messages.
+fn flatten_attr(variant: &syn::Variant) -> syn::Result<Option<Vec<syn::Type>>> {
+    let mut msgs = None;
+    for attr in variant
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("meslin"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if !meta.path.is_ident("flatten") {
+                return Err(meta.error("expected `flatten`"));
+            }
+            if meta.input.is_empty() {
+                return Err(meta.error(
+                    "flatten requires the messages of the inner protocol: `flatten(Msg1, Msg2)`",
+                ));
+            }
+            let content;
+            parenthesized!(content in meta.input);
+            let types = content.parse_terminated(syn::Type::parse, Token![,])?;
+            msgs = Some(types.into_iter().collect());
+            Ok(())
+        })?;
+    }
+    Ok(msgs)
+}
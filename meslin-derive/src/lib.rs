@@ -3,10 +3,13 @@ extern crate quote;
 #[macro_use]
 extern crate syn;
 
+mod client;
+mod dispatch;
 mod from_into_boxed;
 mod message;
+mod service;
 
-#[proc_macro_derive(DynProtocol, attributes())]
+#[proc_macro_derive(DynProtocol, attributes(meslin))]
 pub fn derive_from_into_boxed(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = syn::parse_macro_input!(input as syn::DeriveInput);
     from_into_boxed::derive(input)
@@ -21,3 +24,11 @@ pub fn derive_message(input: proc_macro::TokenStream) -> proc_macro::TokenStream
         .unwrap_or_else(|e| e.to_compile_error())
         .into()
 }
+
+#[proc_macro_derive(Dispatch, attributes(meslin))]
+pub fn derive_dispatch(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as syn::DeriveInput);
+    dispatch::derive(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
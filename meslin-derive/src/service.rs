@@ -0,0 +1,80 @@
+use crate::client::to_snake_case;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{DeriveInput, Ident, Type};
+
+/// Generate `<Protocol>Service`, and dispatch the protocol to it.
+pub fn derive(
+    input: &DeriveInput,
+    variant_names: &[&Ident],
+    variant_types: &[&Type],
+    flat_names: &[&Ident],
+    flat_types: &[&Type],
+) -> syn::Result<TokenStream> {
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "A service can not be generated for generic protocols",
+        ));
+    }
+
+    let vis = &input.vis;
+    let name = &input.ident;
+    let service = format_ident!("{}Service", name);
+    let doc = format!(
+        "A service that handles [`{name}`], with one method per message, which is generated by \
+         `#[meslin(service)]`."
+    );
+
+    let methods = variant_names
+        .iter()
+        .chain(flat_names)
+        .map(|ident| format_ident!("{}", to_snake_case(&ident.to_string())))
+        .collect::<Vec<_>>();
+    let (variant_methods, flat_methods) = methods.split_at(variant_names.len());
+
+    Ok(quote! {
+        #[doc = #doc]
+        #vis trait #service: Send {
+            #(
+                fn #variant_methods(
+                    &mut self,
+                    msg: #variant_types,
+                ) -> impl ::core::future::Future<Output = ()> + Send;
+            )*
+            #(
+                fn #flat_methods(
+                    &mut self,
+                    protocol: #flat_types,
+                ) -> impl ::core::future::Future<Output = ()> + Send;
+            )*
+
+            /// Receive protocols and handle them, until the channel is closed. Returns the final
+            /// service.
+            fn serve<_R>(receiver: _R, service: Self) -> impl ::core::future::Future<Output = Self> + Send
+            where
+                Self: Sized,
+                _R: ::meslin::IsReceiver<Protocol = #name> + Send,
+                _R::With: Send,
+            {
+                ::meslin::run_mailbox(receiver, service)
+            }
+        }
+
+        #[automatically_derived]
+        impl<_S: #service> ::meslin::Dispatch<_S> for #name {
+            fn dispatch(self, state: &mut _S) -> impl ::core::future::Future<Output = ()> + Send {
+                async move {
+                    match self {
+                        #(
+                            Self::#variant_names(msg) => state.#variant_methods(msg).await,
+                        )*
+                        #(
+                            Self::#flat_names(protocol) => state.#flat_methods(protocol).await,
+                        )*
+                    }
+                }
+            }
+        }
+    })
+}
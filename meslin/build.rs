@@ -0,0 +1,11 @@
+fn main() {
+    println!("cargo::rustc-check-cfg=cfg(blocking)");
+
+    // Blocking methods are not available on single-threaded wasm targets, where blocking the
+    // only thread would deadlock or panic, or when they are removed with `forbid-blocking`.
+    let target_arch = std::env::var("CARGO_CFG_TARGET_ARCH").unwrap_or_default();
+    let forbid_blocking = std::env::var_os("CARGO_FEATURE_FORBID_BLOCKING").is_some();
+    if target_arch != "wasm32" && !forbid_blocking {
+        println!("cargo::rustc-cfg=blocking");
+    }
+}
@@ -44,7 +44,7 @@ async fn receive_messages(receiver: mpmc::Receiver<MyProtocol>) {
                 println!("Received number: {msg:?}");
             }
             MyProtocol::Message(msg) => {
-                println!("Received message: {msg:?}");
+                println!("Received message: {:?}", msg.0);
             }
             MyProtocol::Request(Request { msg, tx }) => {
                 println!("Received request: {msg:?}");
@@ -0,0 +1,138 @@
+use crate::*;
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+/// A unique id of an [`Address`].
+///
+/// Ids created with [`AddressId::new`] are unique within the process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AddressId(u64);
+
+impl AddressId {
+    /// Create a new, unique address-id.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+
+    pub fn from_u64(id: u64) -> Self {
+        Self(id)
+    }
+
+    pub fn as_u64(&self) -> u64 {
+        self.0
+    }
+}
+
+/// The way to reach an actor: a sender together with an [`AddressId`] and an optional name.
+///
+/// An address delegates all sending to its sender, so it can be used wherever the sender
+/// could be used. Clones of an address share the same id.
+///
+/// ```
+/// # use meslin::*;
+/// let (sender, _receiver) = mpmc::unbounded::<u32>();
+/// let address = Address::new(sender).with_name("counter");
+/// assert_eq!(address.name(), Some("counter"));
+/// address.try_send::<u32>(1u32).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Address<S> {
+    sender: S,
+    id: AddressId,
+    name: Option<Arc<str>>,
+}
+
+impl<S> Address<S> {
+    /// Create a new address for the sender, with a new unique id.
+    pub fn new(sender: S) -> Self {
+        Self::with_id(sender, AddressId::new())
+    }
+
+    /// Create a new address for the sender, with the given id.
+    pub fn with_id(sender: S, id: AddressId) -> Self {
+        Self {
+            sender,
+            id,
+            name: None,
+        }
+    }
+
+    /// Set the name of the address.
+    pub fn with_name(mut self, name: impl Into<Arc<str>>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn id(&self) -> AddressId {
+        self.id
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn sender(&self) -> &S {
+        &self.sender
+    }
+
+    pub fn into_inner(self) -> S {
+        self.sender
+    }
+
+    /// Convert the address into a [`DynSender`], which can be downcast back into the address.
+    #[cfg(feature = "dynamic")]
+    pub fn into_dyn<T>(self) -> DynSender<T, S::With>
+    where
+        S: IsSender,
+        Self: IntoDynSender<T, S::With>,
+    {
+        self.into_dyn_sender()
+    }
+}
+
+impl<S: IsSender> IsSender for Address<S> {
+    type With = S::With;
+
+    forward_sender_methods!();
+}
+
+impl<S: IsStaticSender> IsStaticSender for Address<S> {
+    type Protocol = S::Protocol;
+
+    fn send_protocol_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        with: Self::With,
+    ) -> impl Future<Output = Result<(), SendError<(Self::Protocol, Self::With)>>> + Send {
+        S::send_protocol_with(&this.sender, protocol, with)
+    }
+
+    fn try_send_protocol_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        with: Self::With,
+    ) -> Result<(), TrySendError<(Self::Protocol, Self::With)>> {
+        S::try_send_protocol_with(&this.sender, protocol, with)
+    }
+
+    fn wait_for_space(this: &Self) -> Option<impl Future<Output = bool> + Send + '_> {
+        S::wait_for_space(&this.sender)
+    }
+
+    #[cfg(blocking)]
+    fn send_protocol_blocking_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        with: Self::With,
+    ) -> Result<(), SendError<(Self::Protocol, Self::With)>> {
+        S::send_protocol_blocking_with(&this.sender, protocol, with)
+    }
+}
@@ -0,0 +1,37 @@
+use crate::*;
+
+/// A channel implementation that can be created generically, with the sender and receiver
+/// types determined by the backend.
+///
+/// Every channel module provides a backend, e.g. [`mpmc::Mpmc`](crate::mpmc::Mpmc) or
+/// [`broadcast::Broadcast`](crate::broadcast::Broadcast). This allows higher-level code, like
+/// pools or spawn helpers, to be generic over the channel that is used:
+///
+/// ```
+/// # use meslin::*;
+/// fn create<B: ChannelBackend<u32>>(config: B::Config) -> B::Sender {
+///     let (sender, _receiver) = channel::<u32, B>(config);
+///     sender
+/// }
+///
+/// let sender = create::<mpmc::Mpmc>(mpmc::Config::bounded(10));
+/// assert_eq!(sender.capacity(), Some(10));
+/// ```
+pub trait ChannelBackend<P> {
+    /// The configuration used when creating the channel, e.g. its capacity.
+    type Config;
+    /// The sender of the channel.
+    type Sender: IsStaticSender<Protocol = P>;
+    /// The receiver of the channel.
+    type Receiver;
+
+    /// Create a new channel with the given configuration.
+    fn create(config: Self::Config) -> (Self::Sender, Self::Receiver);
+}
+
+/// Create a new channel for protocol `P`, using the backend `B`.
+///
+/// See [`ChannelBackend`] for more information.
+pub fn channel<P, B: ChannelBackend<P>>(config: B::Config) -> (B::Sender, B::Receiver) {
+    B::create(config)
+}
@@ -0,0 +1,41 @@
+use thiserror::Error;
+
+/// The strategy that a bounded channel applies when a protocol is sent while it is full.
+///
+/// The strategy is applied by all send methods of the sender, including those of the
+/// [`IsStaticSender`](crate::IsStaticSender) and dynamic senders. Channels that support a
+/// strategy can be created with e.g. [`mpmc::bounded_with`](crate::mpmc::bounded_with).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Backpressure {
+    /// Wait until space becomes available, or fail with `TrySendError::Full` when trying to send.
+    #[default]
+    Block,
+    /// Fail immediately. When sending asynchronously or blocking, the protocol is returned as a
+    /// [`SendError`](crate::SendError). To distinguish a rejected protocol from a closed channel,
+    /// use [`mpmc::Sender::send_or_reject`](crate::mpmc::Sender::send_or_reject), which returns a
+    /// [`BackpressureError`], or `try_send`, which returns `TrySendError::Full`.
+    Fail,
+    /// Drop the oldest protocol in the channel to make space for the new one.
+    DropOldest,
+    /// Drop the new protocol, while still reporting it as sent.
+    DropNewest,
+}
+
+/// Error that is returned when a protocol could not be sent, distinguishing a protocol that was
+/// rejected by the [`Backpressure`] strategy from a closed channel.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Error)]
+pub enum BackpressureError<T> {
+    #[error("Channel is closed: Failed to send message {0:?}.")]
+    Closed(T),
+    #[error("Channel is full: Message {0:?} was rejected.")]
+    Rejected(T),
+}
+
+impl<T> BackpressureError<T> {
+    pub fn into_inner(self) -> T {
+        match self {
+            Self::Closed(t) => t,
+            Self::Rejected(t) => t,
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use std::{
+    future::Future,
+    pin::{pin, Pin},
+    sync::RwLock,
+    task::{Context, Poll},
+};
+
+/// A function that runs a future to completion on the current thread.
+pub type BlockOnFn = fn(Pin<&mut dyn Future<Output = ()>>);
+
+static BLOCK_ON: RwLock<Option<BlockOnFn>> = RwLock::new(None);
+
+/// Set the function that is used by all blocking methods to wait for a future, replacing
+/// [`futures::executor::block_on`].
+///
+/// This allows blocking methods to be called from within an async runtime, by routing them
+/// through e.g. [`tokio::task::block_in_place`], or to use the executor of another runtime.
+///
+/// ```
+/// # use meslin::*;
+/// # #[tokio::main(flavor = "multi_thread")]
+/// # async fn main() {
+/// set_block_on(|fut| {
+///     tokio::task::block_in_place(|| tokio::runtime::Handle::current().block_on(fut))
+/// });
+///
+/// let (sender, receiver) = mpmc::unbounded::<Request<u32, u32>>();
+/// tokio::spawn(async move {
+///     let request = receiver.recv_async().await.unwrap();
+///     let msg = request.msg;
+///     request.reply(msg + 1).unwrap();
+/// });
+/// assert_eq!(sender.request_blocking::<Request<u32, u32>>(1u32).unwrap(), 2);
+/// # reset_block_on();
+/// # }
+/// ```
+///
+/// [`tokio::task::block_in_place`]: https://docs.rs/tokio/latest/tokio/task/fn.block_in_place.html
+pub fn set_block_on(block_on: BlockOnFn) {
+    *BLOCK_ON.write().unwrap() = Some(block_on);
+}
+
+/// Reset the function used by blocking methods to [`futures::executor::block_on`].
+pub fn reset_block_on() {
+    *BLOCK_ON.write().unwrap() = None;
+}
+
+/// Returns `false` if blocking the current thread would deadlock.
+///
+/// With the `tokio` feature, this is the case within a current-thread Tokio runtime, where the
+/// task that would wake the blocked thread can never run. Blocking methods still succeed there
+/// if they don't have to wait, but panic if they do. Check this first to fall back to the
+/// `try_{...}` or async methods instead.
+///
+/// Always returns `true` when a function is set with [`set_block_on`].
+pub fn can_block() -> bool {
+    if BLOCK_ON.read().unwrap().is_some() {
+        return true;
+    }
+    #[cfg(feature = "tokio")]
+    if let Ok(handle) = tokio::runtime::Handle::try_current() {
+        return handle.runtime_flavor() != tokio::runtime::RuntimeFlavor::CurrentThread;
+    }
+    true
+}
+
+/// Panics if the current thread [can not block](can_block).
+pub(crate) fn assert_can_block() {
+    assert!(
+        can_block(),
+        "blocking within a current-thread Tokio runtime would deadlock, check `can_block` first"
+    );
+}
+
+/// Run the future to completion on the current thread, using the function set with
+/// [`set_block_on`].
+///
+/// The future is polled once first, so that it does not block if it completes immediately.
+/// Otherwise, with the `tokio` feature, a blocking call from within a multi-threaded Tokio
+/// runtime uses [`block_in_place`](https://docs.rs/tokio/latest/tokio/task/fn.block_in_place.html),
+/// so that other tasks can continue, and [`futures::executor::block_on`] is used outside of a
+/// runtime.
+///
+/// # Panics
+/// If the future has to wait while the current thread [can not block](can_block).
+pub fn block_on<F: Future>(fut: F) -> F::Output {
+    let mut fut = pin!(fut);
+    let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+    if let Poll::Ready(output) = fut.as_mut().poll(&mut cx) {
+        return output;
+    }
+    assert_can_block();
+
+    let Some(block_on) = *BLOCK_ON.read().unwrap() else {
+        #[cfg(feature = "tokio")]
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            return tokio::task::block_in_place(|| handle.block_on(fut));
+        }
+        return futures::executor::block_on(fut);
+    };
+    let mut output = None;
+    block_on(pin!(async {
+        output = Some(fut.await);
+    }));
+    output.expect("the future was not run to completion by the `set_block_on` function")
+}
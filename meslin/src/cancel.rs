@@ -0,0 +1,229 @@
+use crate::{wakers::*, IsSenderExt, Message, Sends, TrySendError};
+use std::{
+    fmt::Debug,
+    future::{pending, poll_fn, Future},
+    pin::{pin, Pin},
+    sync::{Arc, Mutex, Weak},
+    task::{Poll, Waker},
+};
+use thiserror::Error;
+
+/// A token that cancels the sends, requests and receives it is passed to, once it is cancelled.
+///
+/// Clones of the token share the same state. Tokens created with
+/// [`CancellationToken::child_token`] are cancelled together with their parent, but can also be
+/// cancelled individually.
+///
+/// ```
+/// # use meslin::*;
+/// # futures::executor::block_on(async {
+/// let (sender, mut receiver) = mpmc::unbounded::<u32>();
+/// let token = CancellationToken::new();
+/// token.cancel();
+///
+/// let result = receiver.recv_protocol_cancellable(Some(&token)).await;
+/// assert_eq!(result, Err(CancelRecvError::Cancelled));
+/// let result = sender.send_cancellable::<u32>(1u32, Some(&token)).await;
+/// assert_eq!(result, Err(CancelSendError::Cancelled(Some(1))));
+/// # });
+/// ```
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<Mutex<State>>,
+}
+
+#[derive(Default)]
+struct State {
+    cancelled: bool,
+    wakers: Wakers,
+    children: Vec<Weak<Mutex<State>>>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a token that is cancelled when this token is cancelled.
+    pub fn child_token(&self) -> Self {
+        let child = Self::new();
+        let mut state = self.inner.lock().unwrap();
+        match state.cancelled {
+            true => child.inner.lock().unwrap().cancelled = true,
+            false => {
+                state.children.retain(|child| child.strong_count() > 0);
+                state.children.push(Arc::downgrade(&child.inner));
+            }
+        }
+        child
+    }
+
+    /// Cancel the token and all of its children, waking everything that waits on them.
+    pub fn cancel(&self) {
+        let mut cancelling = vec![self.inner.clone()];
+        while let Some(inner) = cancelling.pop() {
+            let wakers = {
+                let mut state = inner.lock().unwrap();
+                if state.cancelled {
+                    continue;
+                }
+                state.cancelled = true;
+                cancelling.extend(state.children.drain(..).filter_map(|child| child.upgrade()));
+                state.wakers.take()
+            };
+            wakers.into_iter().for_each(Waker::wake);
+        }
+    }
+
+    /// Returns `true` if the token is cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.lock().unwrap().cancelled
+    }
+
+    /// Wait until the token is cancelled.
+    pub fn cancelled(&self) -> impl Future<Output = ()> + Send + '_ {
+        let mut slot = WakerSlot::new(&*self.inner, |state: &mut State| &mut state.wakers);
+        poll_fn(move |cx| {
+            let mut state = self.inner.lock().unwrap();
+            if state.cancelled {
+                return Poll::Ready(());
+            }
+            slot.register(&mut state, cx);
+            Poll::Pending
+        })
+    }
+}
+
+impl Debug for CancellationToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CancellationToken")
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+/// Run the future until it completes, or return `None` once the token is cancelled. A token
+/// that is already cancelled is detected before the future is polled.
+pub(crate) async fn until_cancelled<F: Future>(
+    token: Option<&CancellationToken>,
+    fut: F,
+) -> Option<F::Output> {
+    match token {
+        Some(token) => until(pin!(token.cancelled()), fut).await,
+        None => Some(fut.await),
+    }
+}
+
+/// Run the future until it completes, or return `None` once `stop` completes. The `stop` future
+/// is polled first.
+pub(crate) async fn until<F: Future>(
+    mut stop: Pin<&mut impl Future<Output = ()>>,
+    fut: F,
+) -> Option<F::Output> {
+    let mut fut = pin!(fut);
+    poll_fn(|cx| {
+        if stop.as_mut().poll(cx).is_ready() {
+            return Poll::Ready(None);
+        }
+        fut.as_mut().poll(cx).map(Some)
+    })
+    .await
+}
+
+/// The reason that [`send_until`] did not send the message.
+pub(crate) enum Unsent<T> {
+    Closed(T),
+    /// The message is `None` if `stop` completed after it was handed to the channel.
+    Stopped(Option<T>),
+}
+
+/// Send the message, or give up once `stop` completes.
+///
+/// While the channel is full, the message is kept until the sender reports space with
+/// [`Sends::wait_for_space`], so that it can be returned when giving up. Only senders that can not
+/// wait for space without sending are handed the message to wait with, in which case it is lost
+/// when giving up.
+pub(crate) async fn send_until<S, M>(
+    sender: &S,
+    mut msg: M::Input,
+    stop: impl Future<Output = ()>,
+) -> Result<M::Output, Unsent<M::Input>>
+where
+    S: Sends<M>,
+    S::With: Default,
+    M: Message,
+{
+    let mut stop = pin!(stop);
+    loop {
+        msg = match sender.try_send::<M>(msg) {
+            Ok(output) => return Ok(output),
+            Err(TrySendError::Closed(msg)) => return Err(Unsent::Closed(msg)),
+            Err(TrySendError::Full(msg)) => msg,
+        };
+        let Some(space) = S::wait_for_space(sender) else {
+            break;
+        };
+        match until(stop.as_mut(), space).await {
+            Some(true) => continue,
+            // The channel is closed, which the send below reports.
+            Some(false) => break,
+            None => return Err(Unsent::Stopped(Some(msg))),
+        }
+    }
+    match until(stop, sender.send::<M>(msg)).await {
+        Some(sent) => sent.map_err(|e| Unsent::Closed(e.0)),
+        None => Err(Unsent::Stopped(None)),
+    }
+}
+
+/// Wait until the token is cancelled, or forever if there is no token.
+pub(crate) async fn cancelled(token: Option<&CancellationToken>) {
+    match token {
+        Some(token) => token.cancelled().await,
+        None => pending().await,
+    }
+}
+
+/// Error that is returned when a channel is closed, or the send was cancelled.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Error)]
+pub enum CancelSendError<T> {
+    #[error("Channel is closed: Failed to send message {0:?}.")]
+    Closed(T),
+    /// The message is returned if it was not sent yet. It is only lost if the token was
+    /// cancelled while waiting for space in a channel that does not support
+    /// [`IsStaticSender::wait_for_space`](crate::IsStaticSender::wait_for_space).
+    #[error("Cancelled: Failed to send message {0:?}.")]
+    Cancelled(Option<T>),
+}
+
+/// Error that is returned when a channel is closed, the request did not receive a reply, or the
+/// request was cancelled.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Error)]
+pub enum CancelRequestError<M, E> {
+    #[error("Channel is closed: Failed to send message {0:?}.")]
+    Closed(M),
+    /// The message is returned if it was not sent yet, see [`CancelSendError::Cancelled`]. It is
+    /// lost if the token was cancelled while waiting for the reply.
+    #[error("Cancelled: Failed to send message {0:?}.")]
+    Cancelled(Option<M>),
+    #[error("No reply received: {0}")]
+    NoReply(#[source] E),
+}
+
+impl<T, E> From<CancelSendError<T>> for CancelRequestError<T, E> {
+    fn from(e: CancelSendError<T>) -> Self {
+        match e {
+            CancelSendError::Closed(t) => Self::Closed(t),
+            CancelSendError::Cancelled(t) => Self::Cancelled(t),
+        }
+    }
+}
+
+/// Error that is returned when a channel is closed and empty, or the receive was cancelled.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Error)]
+pub enum CancelRecvError {
+    #[error("Channel is closed: Failed to receive message.")]
+    Closed,
+    #[error("Cancelled: Failed to receive message.")]
+    Cancelled,
+}
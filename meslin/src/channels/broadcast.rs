@@ -1,13 +1,66 @@
-use crate::*;
-use std::fmt::Debug;
+use crate::{wakers::*, *};
+use futures::ready;
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    future::poll_fn,
+    pin::pin,
+    sync::{Arc, Mutex},
+    task::Poll,
+};
+use thiserror::Error;
 
 /// A wrapper around [`async_broadcast::Sender`].
+///
+/// The sender can store the most recent messages, which are replayed to receivers created with
+/// [`Sender::subscribe`]. See [`Sender::with_replay`]. To reserve space, use a
+/// [`ReservableSender`].
 pub struct Sender<P> {
     sender: async_broadcast::Sender<P>,
+    replay: Option<Arc<Mutex<Replay<P>>>>,
+    receiver_changes: Arc<Mutex<Changes>>,
+}
+
+/// The most recent messages, stored for replay.
+struct Replay<P> {
+    values: VecDeque<P>,
+    capacity: usize,
+}
+
+impl<P> Replay<P> {
+    fn push(&mut self, protocol: P) {
+        if self.values.len() == self.capacity {
+            self.values.pop_front();
+        }
+        self.values.push_back(protocol);
+    }
 }
 
 /// Re-export of [`async_broadcast::Receiver`].
 pub use async_broadcast::Receiver;
+
+/// Re-export of [`async_broadcast::InactiveReceiver`].
+///
+/// A receiver can be [deactivated](Receiver::deactivate), after which it no longer receives
+/// messages and does not hold up the channel, until it is [activated](InactiveReceiver::activate)
+/// again. This allows a consumer to stop listening temporarily without stalling the senders.
+///
+/// ```
+/// # use meslin::*;
+/// let (sender, receiver) = broadcast::channel::<u32>(1);
+/// let mut receiver2 = receiver.clone();
+///
+/// let inactive = receiver.deactivate();
+/// assert_eq!(sender.inactive_receiver_count(), 1);
+/// sender.try_send::<u32>(1u32).unwrap();
+/// assert_eq!(receiver2.try_recv_protocol().unwrap(), 1);
+/// sender.try_send::<u32>(2u32).unwrap();
+///
+/// // Messages sent while inactive are not received.
+/// let mut receiver = inactive.activate();
+/// assert!(receiver.try_recv_protocol().is_err());
+/// ```
+pub use async_broadcast::InactiveReceiver;
 use futures::Future;
 
 impl<P> Sender<P> {
@@ -24,7 +77,168 @@ impl<P> Sender<P> {
     }
 
     pub fn from_inner(sender: async_broadcast::Sender<P>) -> Self {
-        Self { sender }
+        Self {
+            sender,
+            replay: None,
+            receiver_changes: Default::default(),
+        }
+    }
+
+    /// Store the last `count` messages that are sent, which are replayed to every receiver that
+    /// is created with [`Sender::subscribe`]. This way late subscribers don't miss the current
+    /// state.
+    ///
+    /// Only messages sent by this sender and its clones are stored, and only once they were sent
+    /// successfully. A receiver that subscribes while a message waits for space in the channel
+    /// receives it exactly once, either from the replay or from the channel.
+    ///
+    /// ```
+    /// # use meslin::*;
+    /// let (sender, _receiver) = broadcast::channel::<u32>(4);
+    /// let sender = sender.with_replay(1);
+    /// sender.try_send::<u32>(1u32).unwrap();
+    /// sender.try_send::<u32>(2u32).unwrap();
+    ///
+    /// let mut late = sender.subscribe();
+    /// sender.try_send::<u32>(3u32).unwrap();
+    /// assert_eq!(late.try_recv_protocol().unwrap(), 2);
+    /// assert_eq!(late.try_recv_protocol().unwrap(), 3);
+    /// ```
+    pub fn with_replay(mut self, count: usize) -> Self {
+        self.replay = (count > 0).then(|| {
+            Arc::new(Mutex::new(Replay {
+                values: VecDeque::with_capacity(count),
+                capacity: count,
+            }))
+        });
+        self
+    }
+
+    /// Create a new receiver, which first receives the messages stored for replay, and then all
+    /// messages sent from now on.
+    ///
+    /// Without [`Sender::with_replay`], this is the same as [`Sender::new_receiver`].
+    pub fn subscribe(&self) -> ReplayReceiver<P>
+    where
+        P: Clone,
+    {
+        let (replay, receiver) = match &self.replay {
+            Some(replay) => {
+                let replay = replay.lock().unwrap();
+                (replay.values.clone(), self.sender.new_receiver())
+            }
+            None => (VecDeque::new(), self.sender.new_receiver()),
+        };
+        Changes::notify(&self.receiver_changes);
+        ReplayReceiver {
+            replay,
+            receiver,
+            _changes: NotifyOnDrop(self.receiver_changes.clone()),
+        }
+    }
+
+    /// Create a new receiver, which receives all messages sent from now on.
+    ///
+    /// This allows late subscribers to attach without access to another receiver. Note that
+    /// the channel is closed once all receivers are dropped, so at least one receiver, which may
+    /// be [inactive](InactiveReceiver), has to be kept alive.
+    ///
+    /// ```
+    /// # use meslin::*;
+    /// let (sender, receiver) = broadcast::channel::<u32>(4);
+    /// let _inactive = receiver.deactivate();
+    ///
+    /// let mut receiver = sender.new_receiver();
+    /// sender.try_send::<u32>(1u32).unwrap();
+    /// assert_eq!(receiver.try_recv_protocol().unwrap(), 1);
+    /// ```
+    pub fn new_receiver(&self) -> ReplayReceiver<P> {
+        let receiver = self.sender.new_receiver();
+        Changes::notify(&self.receiver_changes);
+        ReplayReceiver {
+            replay: VecDeque::new(),
+            receiver,
+            _changes: NotifyOnDrop(self.receiver_changes.clone()),
+        }
+    }
+
+    /// Wait until a receiver is created or dropped, returning the new receiver count.
+    ///
+    /// Changes are tracked from the moment this method is called, not from when the future is
+    /// first polled. Since [`async_broadcast`] does not report changes of its receivers, only the
+    /// receivers that are created with [`Sender::subscribe`] or [`Sender::new_receiver`], and
+    /// the [`ReplayReceiver`]s that are dropped, are observed. Receivers that are cloned,
+    /// deactivated or dropped directly are not.
+    ///
+    /// ```
+    /// # use meslin::*;
+    /// # futures::executor::block_on(async {
+    /// let (sender, receiver) = broadcast::channel::<u32>(4);
+    /// let _inactive = receiver.deactivate();
+    ///
+    /// let (count, receiver) = futures::join!(sender.receiver_count_changed(), async {
+    ///     sender.subscribe()
+    /// });
+    /// assert_eq!(count, 1);
+    /// # });
+    /// ```
+    pub fn receiver_count_changed(&self) -> impl Future<Output = usize> + Send + '_
+    where
+        P: Send,
+    {
+        let changed = Changes::wait(&self.receiver_changes);
+        async move {
+            changed.await;
+            self.sender.receiver_count()
+        }
+    }
+
+    /// The amount of receivers that are inactive.
+    pub fn inactive_receiver_count(&self) -> usize {
+        self.sender.inactive_receiver_count()
+    }
+
+    /// Whether the oldest message is dropped when a message is sent to a full channel, instead
+    /// of waiting for space.
+    pub fn overflow(&self) -> bool {
+        self.sender.overflow()
+    }
+
+    /// Set whether the oldest message is dropped when a message is sent to a full channel.
+    ///
+    /// This affects all senders and receivers of the channel. Receivers that missed messages
+    /// because of this will skip them.
+    ///
+    /// ```
+    /// # use meslin::*;
+    /// let (mut sender, mut receiver) = broadcast::channel::<u32>(1);
+    /// sender.set_overflow(true);
+    /// sender.try_send::<u32>(1u32).unwrap();
+    /// sender.try_send::<u32>(2u32).unwrap();
+    /// assert_eq!(receiver.try_recv_protocol().unwrap(), 2);
+    /// ```
+    pub fn set_overflow(&mut self, overflow: bool) {
+        self.sender.set_overflow(overflow)
+    }
+
+    /// Set the capacity of the channel.
+    ///
+    /// If the new capacity is lower than the amount of messages in the channel, the oldest
+    /// messages are dropped.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.sender.set_capacity(capacity)
+    }
+
+    /// Whether sending waits until there is an active receiver, instead of failing.
+    pub fn await_active(&self) -> bool {
+        self.sender.await_active()
+    }
+
+    /// Set whether sending waits until there is an active receiver, instead of failing.
+    ///
+    /// This is `true` by default.
+    pub fn set_await_active(&mut self, await_active: bool) {
+        self.sender.set_await_active(await_active)
     }
 }
 
@@ -50,6 +264,24 @@ impl<P> IsSender for Sender<P> {
     fn sender_count(&self) -> usize {
         self.sender.sender_count()
     }
+
+    fn is_full(&self) -> bool {
+        self.sender.is_full() && !self.sender.overflow()
+    }
+
+    fn remaining(&self) -> Option<usize> {
+        match self.sender.overflow() {
+            true => None,
+            false => Some(self.sender.capacity().saturating_sub(self.sender.len())),
+        }
+    }
+}
+
+/// [Inactive](InactiveReceiver) receivers are subscribed, but messages are not delivered to them.
+impl<P> IsBroadcastSender for Sender<P> {
+    fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count() + self.sender.inactive_receiver_count()
+    }
 }
 
 impl<P: Clone + Send + Sync> IsStaticSender for Sender<P> {
@@ -60,23 +292,189 @@ impl<P: Clone + Send + Sync> IsStaticSender for Sender<P> {
         protocol: Self::Protocol,
         _with: (),
     ) -> Result<(), TrySendError<(P, ())>> {
-        this.sender
-            .try_broadcast(protocol)
-            .map(|_| ())
-            .map_err(|e| match e {
-                async_broadcast::TrySendError::Full(p) => TrySendError::Full((p, ())),
-                async_broadcast::TrySendError::Closed(p) => TrySendError::Closed((p, ())),
-                async_broadcast::TrySendError::Inactive(p) => TrySendError::Closed((p, ())),
-            })
+        this.try_broadcast_and_store(protocol).map_err(|e| match e {
+            async_broadcast::TrySendError::Full(p) => TrySendError::Full((p, ())),
+            async_broadcast::TrySendError::Closed(p) => TrySendError::Closed((p, ())),
+            async_broadcast::TrySendError::Inactive(p) => TrySendError::Closed((p, ())),
+        })
     }
 
-    fn send_protocol_with(
+    async fn send_protocol_with(
         this: &Self,
         protocol: Self::Protocol,
         _with: (),
-    ) -> impl Future<Output = Result<(), SendError<(P, ())>>> + Send {
-        let fut = this.sender.broadcast_direct(protocol);
-        async { fut.await.map(|_| ()).map_err(|e| SendError((e.0, ()))) }
+    ) -> Result<(), SendError<(P, ())>> {
+        let mut stored = this.replay.as_ref().map(|_| protocol.clone());
+        let mut fut = pin!(this.sender.broadcast_direct(protocol));
+        poll_fn(|cx| {
+            // The message is only sent while polling, so the replay is locked during every poll.
+            // This way it is stored in the same step as it is sent, and a receiver that
+            // subscribes in the meantime receives it exactly once.
+            let mut replay = this.replay.as_ref().map(|replay| replay.lock().unwrap());
+            ready!(fut.as_mut().poll(cx)).map_err(|e| SendError((e.0, ())))?;
+            if let (Some(replay), Some(stored)) = (&mut replay, stored.take()) {
+                replay.push(stored);
+            }
+            Poll::Ready(Ok(()))
+        })
+        .await
+    }
+}
+
+impl<P: Clone> Sender<P> {
+    /// Try to broadcast the message, storing it for replay if it was sent.
+    ///
+    /// The replay is locked while sending, so that subscribers receive the message exactly once.
+    fn try_broadcast_and_store(&self, protocol: P) -> Result<(), async_broadcast::TrySendError<P>> {
+        match &self.replay {
+            Some(replay) => {
+                let mut replay = replay.lock().unwrap();
+                let stored = protocol.clone();
+                self.sender.try_broadcast(protocol)?;
+                replay.push(stored);
+                Ok(())
+            }
+            None => self.sender.try_broadcast(protocol).map(|_| ()),
+        }
+    }
+}
+
+impl<P: Clone + Send + Sync> IsReceiver for Receiver<P> {
+    type Protocol = P;
+    type With = ();
+
+    async fn recv_protocol_with(this: &mut Self) -> Result<(Self::Protocol, ()), RecvError> {
+        loop {
+            match this.recv_direct().await {
+                Ok(p) => break Ok((p, ())),
+                Err(async_broadcast::RecvError::Overflowed(_)) => continue,
+                Err(async_broadcast::RecvError::Closed) => break Err(RecvError),
+            }
+        }
+    }
+
+    fn try_recv_protocol_with(this: &mut Self) -> Result<(Self::Protocol, ()), TryRecvError> {
+        loop {
+            match this.try_recv() {
+                Ok(p) => break Ok((p, ())),
+                Err(async_broadcast::TryRecvError::Overflowed(_)) => continue,
+                Err(async_broadcast::TryRecvError::Empty) => break Err(TryRecvError::Empty),
+                Err(async_broadcast::TryRecvError::Closed) => break Err(TryRecvError::Closed),
+            }
+        }
+    }
+}
+
+/// A broadcast [`Receiver`] that first receives the messages stored for replay, created with
+/// [`Sender::subscribe`] or [`Sender::new_receiver`]. Its drop is observed by
+/// [`Sender::receiver_count_changed`].
+#[derive(Debug)]
+pub struct ReplayReceiver<P> {
+    replay: VecDeque<P>,
+    receiver: Receiver<P>,
+    /// Reports the drop to [`Sender::receiver_count_changed`], after the receiver is dropped.
+    _changes: NotifyOnDrop,
+}
+
+impl<P> ReplayReceiver<P> {
+    pub fn inner(&self) -> &Receiver<P> {
+        &self.receiver
+    }
+
+    pub fn inner_mut(&mut self) -> &mut Receiver<P> {
+        &mut self.receiver
+    }
+
+    /// Returns the messages that are still to be replayed, and the inner receiver.
+    pub fn into_parts(self) -> (VecDeque<P>, Receiver<P>) {
+        (self.replay, self.receiver)
+    }
+}
+
+impl<P: Clone + Send + Sync> IsReceiver for ReplayReceiver<P> {
+    type Protocol = P;
+    type With = ();
+
+    async fn recv_protocol_with(this: &mut Self) -> Result<(Self::Protocol, ()), RecvError> {
+        match this.replay.pop_front() {
+            Some(protocol) => Ok((protocol, ())),
+            None => <Receiver<P> as IsReceiver>::recv_protocol_with(&mut this.receiver).await,
+        }
+    }
+
+    fn try_recv_protocol_with(this: &mut Self) -> Result<(Self::Protocol, ()), TryRecvError> {
+        match this.replay.pop_front() {
+            Some(protocol) => Ok((protocol, ())),
+            None => <Receiver<P> as IsReceiver>::try_recv_protocol_with(&mut this.receiver),
+        }
+    }
+}
+
+/// Error that is returned by [`LaggedReceiverExt::recv_lagged`].
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Error)]
+pub enum RecvLaggedError {
+    #[error("Channel is closed: Failed to receive message.")]
+    Closed,
+    #[error("Receiver lagged behind: {0} messages were missed.")]
+    Lagged(u64),
+}
+
+/// Error that is returned by [`LaggedReceiverExt::try_recv_lagged`].
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Error)]
+pub enum TryRecvLaggedError {
+    #[error("Channel is empty: No message available.")]
+    Empty,
+    #[error("Channel is closed: Failed to receive message.")]
+    Closed,
+    #[error("Receiver lagged behind: {0} messages were missed.")]
+    Lagged(u64),
+}
+
+/// Extension methods for a broadcast [`Receiver`], which report when messages were missed.
+///
+/// When the channel [overflows](Sender::set_overflow), receivers that lag behind miss the
+/// oldest messages. [`IsReceiver`] skips these silently, while these methods return
+/// [`RecvLaggedError::Lagged`] with the amount of missed messages, so that the consumer can
+/// resynchronize. After a lag is reported, the next call receives the oldest message that is
+/// still available.
+///
+/// ```
+/// # use meslin::{*, broadcast::{LaggedReceiverExt, TryRecvLaggedError}};
+/// let (mut sender, mut receiver) = broadcast::channel::<u32>(2);
+/// sender.set_overflow(true);
+/// for i in 0..5u32 {
+///     sender.try_send::<u32>(i).unwrap();
+/// }
+/// assert_eq!(receiver.try_recv_lagged(), Err(TryRecvLaggedError::Lagged(3)));
+/// assert_eq!(receiver.try_recv_lagged(), Ok(3));
+/// ```
+pub trait LaggedReceiverExt<P> {
+    /// Receive a message, waiting asynchronously until one becomes available, or report how
+    /// many messages were missed.
+    fn recv_lagged(&mut self) -> impl Future<Output = Result<P, RecvLaggedError>> + Send;
+
+    /// Receive a message, returning an error if none is available, or report how many
+    /// messages were missed.
+    fn try_recv_lagged(&mut self) -> Result<P, TryRecvLaggedError>;
+}
+
+impl<P: Clone + Send + Sync> LaggedReceiverExt<P> for Receiver<P> {
+    fn recv_lagged(&mut self) -> impl Future<Output = Result<P, RecvLaggedError>> + Send {
+        let fut = self.recv_direct();
+        async {
+            fut.await.map_err(|e| match e {
+                async_broadcast::RecvError::Overflowed(n) => RecvLaggedError::Lagged(n),
+                async_broadcast::RecvError::Closed => RecvLaggedError::Closed,
+            })
+        }
+    }
+
+    fn try_recv_lagged(&mut self) -> Result<P, TryRecvLaggedError> {
+        self.try_recv().map_err(|e| match e {
+            async_broadcast::TryRecvError::Overflowed(n) => TryRecvLaggedError::Lagged(n),
+            async_broadcast::TryRecvError::Empty => TryRecvLaggedError::Empty,
+            async_broadcast::TryRecvError::Closed => TryRecvLaggedError::Closed,
+        })
     }
 }
 
@@ -84,6 +482,8 @@ impl<P> Clone for Sender<P> {
     fn clone(&self) -> Self {
         Self {
             sender: self.sender.clone(),
+            replay: self.replay.clone(),
+            receiver_changes: self.receiver_changes.clone(),
         }
     }
 }
@@ -98,5 +498,19 @@ impl<P: Debug> std::fmt::Debug for Sender<P> {
 
 pub fn channel<P: Clone>(buffer: usize) -> (Sender<P>, async_broadcast::Receiver<P>) {
     let (sender, receiver) = async_broadcast::broadcast(buffer);
-    (Sender { sender }, receiver)
+    (Sender::from_inner(sender), receiver)
+}
+
+/// The [`ChannelBackend`] of a broadcast-channel, configured with the size of its buffer.
+#[derive(Debug, Clone, Copy)]
+pub struct Broadcast;
+
+impl<P: Clone + Send + Sync> ChannelBackend<P> for Broadcast {
+    type Config = usize;
+    type Sender = Sender<P>;
+    type Receiver = async_broadcast::Receiver<P>;
+
+    fn create(buffer: Self::Config) -> (Self::Sender, Self::Receiver) {
+        channel(buffer)
+    }
 }
@@ -0,0 +1,384 @@
+//! A channel that only keeps the latest protocol for every key.
+//!
+//! Protocols are sent with a key as their `with`-value. When a protocol is sent for a key that
+//! is still queued, it overwrites the queued protocol while keeping its position in the queue.
+//! Receivers therefore only receive the most recent state per key, which is standard for feeds
+//! of market-data or telemetry, where stale updates are worthless.
+//!
+//! The capacity of a bounded channel limits the number of distinct keys that are queued. Sending
+//! a protocol for a key that is already queued never waits.
+//!
+//! ```
+//! # use meslin::*;
+//! # futures::executor::block_on(async {
+//! let (sender, mut receiver) = conflate::unbounded::<u32, &str>();
+//! sender.send_with::<u32>(1u32, "a").await.unwrap();
+//! sender.send_with::<u32>(2u32, "b").await.unwrap();
+//! sender.send_with::<u32>(3u32, "a").await.unwrap();
+//! assert_eq!(receiver.recv().await.unwrap(), (3, "a"));
+//! assert_eq!(receiver.recv().await.unwrap(), (2, "b"));
+//! # });
+//! ```
+//!
+//! The key can also be derived from the protocol, using [`IsSenderExt::with_fn`].
+use crate::{wakers::*, *};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Debug,
+    future::{poll_fn, Future},
+    hash::Hash,
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+    task::{Poll, Waker},
+};
+
+struct State<P, K> {
+    order: VecDeque<K>,
+    latest: HashMap<K, P>,
+    conflated: u64,
+    capacity: Option<usize>,
+    /// The number of slots that are reserved with [`Reserve::try_reserve_slot`].
+    reserved: usize,
+    sender_count: usize,
+    receiver_count: usize,
+    recv_wakers: Wakers,
+    send_wakers: Wakers,
+}
+
+impl<P, K: Hash + Eq> State<P, K> {
+    fn is_full(&self) -> bool {
+        self.capacity
+            .is_some_and(|capacity| self.order.len() + self.reserved >= capacity)
+    }
+
+    fn pop(&mut self) -> Option<(P, K)> {
+        let key = self.order.pop_front()?;
+        let protocol = self.latest.remove(&key).unwrap();
+        Some((protocol, key))
+    }
+}
+
+/// The sending half of a [conflating channel](self).
+pub struct Sender<P, K> {
+    shared: Arc<Mutex<State<P, K>>>,
+}
+
+/// The receiving half of a [conflating channel](self).
+///
+/// Receivers can be cloned, in which case every protocol is received by only one of them.
+pub struct Receiver<P, K> {
+    shared: Arc<Mutex<State<P, K>>>,
+}
+
+impl<P, K: Hash + Eq + Clone> Sender<P, K> {
+    /// Returns the number of protocols that were overwritten by a newer protocol for their key.
+    pub fn conflated(&self) -> u64 {
+        self.shared.lock().unwrap().conflated
+    }
+
+    /// Push the protocol, into a slot that was reserved by this sender if `reserved` is `true`.
+    ///
+    /// A reserved slot is released when the protocol overwrites a queued one.
+    fn push(&self, protocol: P, key: K, reserved: bool) -> Result<(), TrySendError<(P, K)>> {
+        let wakers = {
+            let mut state = self.shared.lock().unwrap();
+            if reserved {
+                state.reserved -= 1;
+            }
+            if state.receiver_count == 0 {
+                return Err(TrySendError::Closed((protocol, key)));
+            }
+            if let Some(queued) = state.latest.get_mut(&key) {
+                *queued = protocol;
+                state.conflated += 1;
+                // Release the reserved slot to senders that wait for space.
+                match reserved {
+                    true => state.send_wakers.take(),
+                    false => return Ok(()),
+                }
+            } else if !reserved && state.is_full() {
+                return Err(TrySendError::Full((protocol, key)));
+            } else {
+                state.order.push_back(key.clone());
+                state.latest.insert(key, protocol);
+                state.recv_wakers.take()
+            }
+        };
+        wakers.into_iter().for_each(Waker::wake);
+        Ok(())
+    }
+}
+
+impl<P, K: Hash + Eq> Receiver<P, K> {
+    /// Receive the latest protocol of the next key, waiting until one is available.
+    pub fn recv(&mut self) -> impl Future<Output = Result<(P, K), RecvError>> + Send + '_
+    where
+        P: Send,
+        K: Send,
+    {
+        let this = &*self;
+        let mut slot = WakerSlot::new(&*this.shared, |state: &mut State<P, K>| {
+            &mut state.recv_wakers
+        });
+        poll_fn(move |cx| match this.pop() {
+            Ok(received) => Poll::Ready(Ok(received)),
+            Err(TryRecvError::Closed) => Poll::Ready(Err(RecvError)),
+            Err(TryRecvError::Empty) => {
+                let mut state = this.shared.lock().unwrap();
+                // A protocol might have been sent in the meantime.
+                if !state.order.is_empty() || state.sender_count == 0 {
+                    cx.waker().wake_by_ref();
+                } else {
+                    slot.register(&mut state, cx);
+                }
+                Poll::Pending
+            }
+        })
+    }
+
+    /// Receive the latest protocol of the next key, returning an error if none is available.
+    pub fn try_recv(&mut self) -> Result<(P, K), TryRecvError> {
+        self.pop()
+    }
+
+    fn pop(&self) -> Result<(P, K), TryRecvError> {
+        let (received, wakers) = {
+            let mut state = self.shared.lock().unwrap();
+            match state.pop() {
+                Some(received) => (received, state.send_wakers.take()),
+                None if state.sender_count == 0 => return Err(TryRecvError::Closed),
+                None => return Err(TryRecvError::Empty),
+            }
+        };
+        wakers.into_iter().for_each(Waker::wake);
+        Ok(received)
+    }
+
+    /// Returns the number of keys in the channel.
+    pub fn len(&self) -> usize {
+        self.shared.lock().unwrap().order.len()
+    }
+
+    /// Returns `true` if the channel is empty.
+    pub fn is_empty(&self) -> bool {
+        self.shared.lock().unwrap().order.is_empty()
+    }
+}
+
+impl<P, K> IsSender for Sender<P, K> {
+    type With = K;
+
+    fn is_closed(&self) -> bool {
+        self.shared.lock().unwrap().receiver_count == 0
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        self.shared.lock().unwrap().capacity
+    }
+
+    fn len(&self) -> usize {
+        self.shared.lock().unwrap().order.len()
+    }
+
+    fn receiver_count(&self) -> usize {
+        self.shared.lock().unwrap().receiver_count
+    }
+
+    fn sender_count(&self) -> usize {
+        self.shared.lock().unwrap().sender_count
+    }
+}
+
+impl<P: Send, K: Hash + Eq + Clone + Send> IsStaticSender for Sender<P, K> {
+    type Protocol = P;
+
+    async fn send_protocol_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        key: K,
+    ) -> Result<(), SendError<(Self::Protocol, K)>> {
+        let mut item = Some((protocol, key));
+        let mut slot = WakerSlot::new(&*this.shared, |state: &mut State<P, K>| {
+            &mut state.send_wakers
+        });
+        poll_fn(|cx| {
+            let (protocol, key) = item.take().unwrap();
+            match this.push(protocol, key, false) {
+                Ok(()) => Poll::Ready(Ok(())),
+                Err(TrySendError::Closed(item)) => Poll::Ready(Err(SendError(item))),
+                Err(TrySendError::Full(full)) => {
+                    let mut state = this.shared.lock().unwrap();
+                    // Space might have become available in the meantime.
+                    if !state.is_full() || state.receiver_count == 0 {
+                        cx.waker().wake_by_ref();
+                    } else {
+                        slot.register(&mut state, cx);
+                    }
+                    item = Some(full);
+                    Poll::Pending
+                }
+            }
+        })
+        .await
+    }
+
+    fn try_send_protocol_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        key: K,
+    ) -> Result<(), TrySendError<(Self::Protocol, K)>> {
+        this.push(protocol, key, false)
+    }
+}
+
+impl<P: Send, K: Hash + Eq + Clone + Send> Reserve for Sender<P, K> {
+    fn try_reserve_slot(this: &Self) -> Result<(), TrySendError<()>> {
+        let mut state = this.shared.lock().unwrap();
+        if state.receiver_count == 0 {
+            return Err(TrySendError::Closed(()));
+        }
+        if state.is_full() {
+            return Err(TrySendError::Full(()));
+        }
+        state.reserved += 1;
+        Ok(())
+    }
+
+    fn send_reserved_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        key: K,
+    ) -> Result<(), TrySendError<(Self::Protocol, K)>> {
+        this.push(protocol, key, true)
+    }
+
+    fn release_slot(this: &Self) {
+        let wakers = {
+            let mut state = this.shared.lock().unwrap();
+            state.reserved -= 1;
+            state.send_wakers.take()
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+impl<P: Send, K: Hash + Eq + Send> IsReceiver for Receiver<P, K> {
+    type Protocol = P;
+    type With = K;
+
+    async fn recv_protocol_with(this: &mut Self) -> Result<(P, K), RecvError> {
+        this.recv().await
+    }
+
+    fn try_recv_protocol_with(this: &mut Self) -> Result<(P, K), TryRecvError> {
+        this.try_recv()
+    }
+}
+
+impl<P, K> Clone for Sender<P, K> {
+    fn clone(&self) -> Self {
+        self.shared.lock().unwrap().sender_count += 1;
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<P, K> Drop for Sender<P, K> {
+    fn drop(&mut self) {
+        let wakers = {
+            let mut state = self.shared.lock().unwrap();
+            state.sender_count -= 1;
+            match state.sender_count {
+                0 => state.recv_wakers.take(),
+                _ => Vec::new(),
+            }
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+impl<P, K> Clone for Receiver<P, K> {
+    fn clone(&self) -> Self {
+        self.shared.lock().unwrap().receiver_count += 1;
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<P, K> Drop for Receiver<P, K> {
+    fn drop(&mut self) {
+        let wakers = {
+            let mut state = self.shared.lock().unwrap();
+            state.receiver_count -= 1;
+            match state.receiver_count {
+                0 => state.send_wakers.take(),
+                _ => Vec::new(),
+            }
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+impl<P, K> Debug for Sender<P, K> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.shared.lock().unwrap();
+        f.debug_struct("Sender")
+            .field("len", &state.order.len())
+            .field("capacity", &state.capacity)
+            .field("conflated", &state.conflated)
+            .finish()
+    }
+}
+
+impl<P, K> Debug for Receiver<P, K> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Receiver").finish_non_exhaustive()
+    }
+}
+
+fn channel<P, K>(capacity: Option<usize>) -> (Sender<P, K>, Receiver<P, K>) {
+    let shared = Arc::new(Mutex::new(State {
+        order: VecDeque::new(),
+        latest: HashMap::new(),
+        conflated: 0,
+        capacity,
+        reserved: 0,
+        sender_count: 1,
+        receiver_count: 1,
+        recv_wakers: Wakers::default(),
+        send_wakers: Wakers::default(),
+    }));
+    (
+        Sender {
+            shared: shared.clone(),
+        },
+        Receiver { shared },
+    )
+}
+
+/// Create a conflating channel that holds at most `capacity` distinct keys.
+pub fn bounded<P, K>(capacity: usize) -> (Sender<P, K>, Receiver<P, K>) {
+    channel(Some(capacity))
+}
+
+/// Create an unbounded conflating channel.
+pub fn unbounded<P, K>() -> (Sender<P, K>, Receiver<P, K>) {
+    channel(None)
+}
+
+/// The [`ChannelBackend`] of a conflating channel with keys `K`, configured with its capacity,
+/// or `None` if it is unbounded.
+#[derive(Debug, Clone, Copy)]
+pub struct Conflate<K>(PhantomData<fn() -> K>);
+
+impl<P: Send, K: Hash + Eq + Clone + Send> ChannelBackend<P> for Conflate<K> {
+    type Config = Option<usize>;
+    type Sender = Sender<P, K>;
+    type Receiver = Receiver<P, K>;
+
+    fn create(capacity: Self::Config) -> (Self::Sender, Self::Receiver) {
+        channel(capacity)
+    }
+}
@@ -0,0 +1,386 @@
+//! A broadcast channel that keeps a bounded journal of all sent protocols.
+//!
+//! Every protocol is assigned a sequence number, and stays in the journal until it is evicted by
+//! newer protocols. New receivers can be created at any position in the journal with
+//! [`Sender::replay_from`] or [`Receiver::replay_from`], after which they continue with live
+//! protocols. This is useful for event-sourced actors and observers that join late.
+//!
+//! Sending never fails: when the journal is full, the oldest protocol is evicted. Receivers
+//! receive the sequence number as the `with`-value, and a receiver that falls behind the journal
+//! skips ahead to the oldest protocol that is still available.
+//!
+//! ```
+//! # use meslin::{*, journal};
+//! # futures::executor::block_on(async {
+//! let (sender, mut receiver) = journal::channel::<u32>(16);
+//! sender.send::<u32>(1u32).await.unwrap();
+//! sender.send::<u32>(2u32).await.unwrap();
+//! assert_eq!(receiver.recv().await.unwrap(), (0, 1));
+//!
+//! // A late receiver replays the journal before receiving live protocols.
+//! let mut late = sender.replay_from(0);
+//! sender.send::<u32>(3u32).await.unwrap();
+//! assert_eq!(late.recv().await.unwrap(), (0, 1));
+//! assert_eq!(late.recv().await.unwrap(), (1, 2));
+//! assert_eq!(late.recv().await.unwrap(), (2, 3));
+//! # });
+//! ```
+use crate::{wakers::*, *};
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    future::{poll_fn, Future},
+    sync::{Arc, Mutex},
+    task::{Poll, Waker},
+};
+
+struct State<P> {
+    journal: VecDeque<P>,
+    first_seq: u64,
+    capacity: usize,
+    sender_count: usize,
+    receiver_count: usize,
+    /// Incremented whenever a receiver is created or dropped.
+    receiver_changes: u64,
+    wakers: Wakers,
+    receiver_wakers: Wakers,
+}
+
+impl<P> State<P> {
+    fn next_seq(&self) -> u64 {
+        self.first_seq + self.journal.len() as u64
+    }
+
+    fn receiver(shared: &Arc<Mutex<Self>>, seq: u64) -> Receiver<P> {
+        let (receiver, wakers) = {
+            let mut state = shared.lock().unwrap();
+            let receiver = Receiver {
+                shared: shared.clone(),
+                seq: seq.max(state.first_seq),
+            };
+            (receiver, state.change_receiver_count(1))
+        };
+        wakers.into_iter().for_each(Waker::wake);
+        receiver
+    }
+
+    /// Read the protocol at `seq` from the journal, advancing `seq` past it.
+    fn read(&self, seq: &mut u64) -> Result<(u64, P), TryRecvError>
+    where
+        P: Clone,
+    {
+        *seq = (*seq).max(self.first_seq);
+        match self.journal.get((*seq - self.first_seq) as usize) {
+            Some(protocol) => {
+                let received = (*seq, protocol.clone());
+                *seq += 1;
+                Ok(received)
+            }
+            None if self.sender_count == 0 => Err(TryRecvError::Closed),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    /// Change the receiver count, returning the wakers to notify of the change.
+    fn change_receiver_count(&mut self, delta: isize) -> Vec<Waker> {
+        self.receiver_count = self.receiver_count.checked_add_signed(delta).unwrap();
+        self.receiver_changes += 1;
+        self.receiver_wakers.take()
+    }
+}
+
+/// The sending half of a [journal-channel](self).
+pub struct Sender<P> {
+    shared: Arc<Mutex<State<P>>>,
+}
+
+/// The receiving half of a [journal-channel](self).
+///
+/// Cloning a receiver creates a new receiver at the same position.
+pub struct Receiver<P> {
+    shared: Arc<Mutex<State<P>>>,
+    seq: u64,
+}
+
+impl<P> Sender<P> {
+    /// Create a new receiver that only receives protocols sent from now on.
+    pub fn subscribe(&self) -> Receiver<P> {
+        let seq = self.shared.lock().unwrap().next_seq();
+        State::receiver(&self.shared, seq)
+    }
+
+    /// Create a new receiver that starts at the given sequence number, or at the oldest
+    /// protocol in the journal if it has already been evicted.
+    pub fn replay_from(&self, seq: u64) -> Receiver<P> {
+        State::receiver(&self.shared, seq)
+    }
+
+    /// The sequence number of the oldest protocol in the journal.
+    pub fn first_seq(&self) -> u64 {
+        self.shared.lock().unwrap().first_seq
+    }
+
+    /// The sequence number that will be assigned to the next protocol.
+    pub fn next_seq(&self) -> u64 {
+        self.shared.lock().unwrap().next_seq()
+    }
+
+    /// Wait until a receiver is created or dropped, returning the new receiver count.
+    ///
+    /// Changes are tracked from the moment this method is called, not from when the future is
+    /// first polled.
+    ///
+    /// This allows a producer to pause expensive work while nobody is listening:
+    ///
+    /// ```
+    /// # use meslin::{*, journal};
+    /// # futures::executor::block_on(async {
+    /// let (sender, receiver) = journal::channel::<u32>(16);
+    /// drop(receiver);
+    ///
+    /// let (count, receiver) = futures::join!(sender.receiver_count_changed(), async {
+    ///     sender.subscribe()
+    /// });
+    /// assert_eq!(count, 1);
+    /// # });
+    /// ```
+    pub fn receiver_count_changed(&self) -> impl Future<Output = usize> + Send + '_
+    where
+        P: Send,
+    {
+        let changes = self.shared.lock().unwrap().receiver_changes;
+        let mut slot = WakerSlot::new(&*self.shared, |state: &mut State<P>| {
+            &mut state.receiver_wakers
+        });
+        poll_fn(move |cx| {
+            let mut state = self.shared.lock().unwrap();
+            if state.receiver_changes != changes {
+                Poll::Ready(state.receiver_count)
+            } else {
+                slot.register(&mut state, cx);
+                Poll::Pending
+            }
+        })
+    }
+
+    /// Append the protocol to the journal, evicting the oldest protocol if it is full.
+    fn push(&self, protocol: P) {
+        let wakers = {
+            let mut state = self.shared.lock().unwrap();
+            if state.journal.len() == state.capacity {
+                state.journal.pop_front();
+                state.first_seq += 1;
+            }
+            state.journal.push_back(protocol);
+            state.wakers.take()
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+impl<P: Clone> Receiver<P> {
+    /// Receive the next protocol and its sequence number, waiting until one is available.
+    pub fn recv(&mut self) -> impl Future<Output = Result<(u64, P), RecvError>> + Send + '_
+    where
+        P: Send,
+    {
+        let Receiver { shared, seq } = self;
+        let shared: &Mutex<State<P>> = shared;
+        let mut slot = WakerSlot::new(shared, |state: &mut State<P>| &mut state.wakers);
+        poll_fn(move |cx| {
+            let mut state = shared.lock().unwrap();
+            match state.read(seq) {
+                Ok(received) => Poll::Ready(Ok(received)),
+                Err(TryRecvError::Closed) => Poll::Ready(Err(RecvError)),
+                Err(TryRecvError::Empty) => {
+                    slot.register(&mut state, cx);
+                    Poll::Pending
+                }
+            }
+        })
+    }
+
+    /// Receive the next protocol and its sequence number, returning an error if none is
+    /// available.
+    pub fn try_recv(&mut self) -> Result<(u64, P), TryRecvError> {
+        self.shared.lock().unwrap().read(&mut self.seq)
+    }
+}
+
+impl<P> Receiver<P> {
+    /// The sequence number of the next protocol this receiver will receive.
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// Create a new receiver of the same channel that starts at the given sequence number.
+    pub fn replay_from(&self, seq: u64) -> Receiver<P> {
+        State::receiver(&self.shared, seq)
+    }
+}
+
+impl<P> IsSender for Sender<P> {
+    type With = ();
+
+    fn is_closed(&self) -> bool {
+        false
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        Some(self.shared.lock().unwrap().capacity)
+    }
+
+    fn len(&self) -> usize {
+        self.shared.lock().unwrap().journal.len()
+    }
+
+    fn receiver_count(&self) -> usize {
+        self.shared.lock().unwrap().receiver_count
+    }
+
+    fn sender_count(&self) -> usize {
+        self.shared.lock().unwrap().sender_count
+    }
+
+    fn is_full(&self) -> bool {
+        false
+    }
+
+    fn remaining(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// Every protocol is kept in the journal, and received by all receivers.
+impl<P> IsBroadcastSender for Sender<P> {}
+
+impl<P: Send> IsStaticSender for Sender<P> {
+    type Protocol = P;
+
+    async fn send_protocol_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        _with: (),
+    ) -> Result<(), SendError<(Self::Protocol, ())>> {
+        this.push(protocol);
+        Ok(())
+    }
+
+    #[cfg(blocking)]
+    fn send_protocol_blocking_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        _with: (),
+    ) -> Result<(), SendError<(Self::Protocol, ())>> {
+        this.push(protocol);
+        Ok(())
+    }
+
+    fn try_send_protocol_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        _with: (),
+    ) -> Result<(), TrySendError<(Self::Protocol, ())>> {
+        this.push(protocol);
+        Ok(())
+    }
+}
+
+impl<P: Clone + Send> IsReceiver for Receiver<P> {
+    type Protocol = P;
+    type With = u64;
+
+    async fn recv_protocol_with(this: &mut Self) -> Result<(P, u64), RecvError> {
+        this.recv().await.map(|(seq, protocol)| (protocol, seq))
+    }
+
+    fn try_recv_protocol_with(this: &mut Self) -> Result<(P, u64), TryRecvError> {
+        this.try_recv().map(|(seq, protocol)| (protocol, seq))
+    }
+}
+
+impl<P> Clone for Sender<P> {
+    fn clone(&self) -> Self {
+        self.shared.lock().unwrap().sender_count += 1;
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<P> Drop for Sender<P> {
+    fn drop(&mut self) {
+        let wakers = {
+            let mut state = self.shared.lock().unwrap();
+            state.sender_count -= 1;
+            match state.sender_count {
+                0 => state.wakers.take(),
+                _ => Vec::new(),
+            }
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+impl<P> Clone for Receiver<P> {
+    fn clone(&self) -> Self {
+        State::receiver(&self.shared, self.seq)
+    }
+}
+
+impl<P> Drop for Receiver<P> {
+    fn drop(&mut self) {
+        let wakers = self.shared.lock().unwrap().change_receiver_count(-1);
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+impl<P> Debug for Sender<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.shared.lock().unwrap();
+        f.debug_struct("Sender")
+            .field("first_seq", &state.first_seq)
+            .field("next_seq", &state.next_seq())
+            .finish()
+    }
+}
+
+impl<P> Debug for Receiver<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Receiver").field("seq", &self.seq).finish()
+    }
+}
+
+/// Create a journal-channel that retains the last `capacity` protocols.
+///
+/// # Panics
+/// Panics if `capacity` is zero.
+pub fn channel<P>(capacity: usize) -> (Sender<P>, Receiver<P>) {
+    assert!(capacity > 0, "capacity must be greater than zero");
+    let shared = Arc::new(Mutex::new(State {
+        journal: VecDeque::with_capacity(capacity),
+        first_seq: 0,
+        capacity,
+        sender_count: 1,
+        receiver_count: 0,
+        receiver_changes: 0,
+        wakers: Wakers::default(),
+        receiver_wakers: Wakers::default(),
+    }));
+    let receiver = State::receiver(&shared, 0);
+    (Sender { shared }, receiver)
+}
+
+/// The [`ChannelBackend`] of a journal-channel, configured with the number of retained protocols.
+#[derive(Debug, Clone, Copy)]
+pub struct Journal;
+
+impl<P: Send> ChannelBackend<P> for Journal {
+    type Config = usize;
+    type Sender = Sender<P>;
+    type Receiver = Receiver<P>;
+
+    fn create(capacity: Self::Config) -> (Self::Sender, Self::Receiver) {
+        channel(capacity)
+    }
+}
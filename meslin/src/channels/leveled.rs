@@ -0,0 +1,485 @@
+//! A priority channel with a fixed number of priority [`Level`]s.
+//!
+//! Every level has its own queue, and protocols are always received from the highest level that
+//! is not empty. Within a level, protocols are received in the order they were sent. Unlike the
+//! [`priority`](crate::priority) channel, which uses a heap, sending and receiving take constant
+//! time, which makes this channel a good fit to separate control messages from data messages.
+//!
+//! The capacity of a bounded channel is shared by all levels.
+//!
+//! ```
+//! # use meslin::{*, leveled::Level};
+//! # futures::executor::block_on(async {
+//! let (sender, mut receiver) = leveled::unbounded::<u32>();
+//! sender.send::<u32>(1u32).await.unwrap();
+//! sender.send_with::<u32>(2u32, Level::Low).await.unwrap();
+//! sender.send_with::<u32>(3u32, Level::High).await.unwrap();
+//! assert_eq!(receiver.recv().await.unwrap(), (3, Level::High));
+//! assert_eq!(receiver.recv().await.unwrap(), (1, Level::Normal));
+//! assert_eq!(receiver.recv().await.unwrap(), (2, Level::Low));
+//! # });
+//! ```
+use crate::{monitor::Watermarks, wakers::*, *};
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    future::{poll_fn, Future},
+    sync::{Arc, Mutex},
+    task::{Poll, Waker},
+};
+
+pub use crate::Watermark;
+
+/// The priority level of a protocol sent through a [leveled channel](self).
+///
+/// Levels are ordered from highest to lowest priority.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    High,
+    #[default]
+    Normal,
+    Low,
+}
+
+impl Level {
+    /// All levels, from highest to lowest priority.
+    pub const ALL: [Level; 3] = [Level::High, Level::Normal, Level::Low];
+}
+
+struct State<P> {
+    queues: [VecDeque<P>; 3],
+    watermarks: Option<Watermarks>,
+    capacity: Option<usize>,
+    /// The number of slots that are reserved with [`Reserve::try_reserve_slot`].
+    reserved: usize,
+    sender_count: usize,
+    receiver_count: usize,
+    recv_wakers: Wakers,
+    send_wakers: Wakers,
+}
+
+impl<P> State<P> {
+    fn len(&self) -> usize {
+        self.queues.iter().map(VecDeque::len).sum()
+    }
+
+    fn is_full(&self) -> bool {
+        self.capacity
+            .is_some_and(|capacity| self.len() + self.reserved >= capacity)
+    }
+
+    /// Returns the callback and watermark to notify, if the length crossed a watermark.
+    fn crossed_watermark(&mut self) -> Option<(Arc<dyn Fn(Watermark) + Send + Sync>, Watermark)> {
+        let len = self.len();
+        self.watermarks.as_mut()?.crossed(len)
+    }
+
+    fn pop(&mut self) -> Option<(P, Level)> {
+        Level::ALL
+            .into_iter()
+            .find_map(|level| Some((self.queues[level as usize].pop_front()?, level)))
+    }
+}
+
+/// The sending half of a [leveled channel](self).
+pub struct Sender<P> {
+    shared: Arc<Mutex<State<P>>>,
+}
+
+/// The receiving half of a [leveled channel](self).
+///
+/// Receivers can be cloned, in which case every protocol is received by only one of them.
+pub struct Receiver<P> {
+    shared: Arc<Mutex<State<P>>>,
+}
+
+impl<P> Sender<P> {
+    /// Returns the number of protocols in the channel at the given level.
+    pub fn len_at(&self, level: Level) -> usize {
+        self.shared.lock().unwrap().queues[level as usize].len()
+    }
+
+    /// Call `callback` whenever the number of protocols in the channel rises to `high`, or
+    /// afterwards falls back to `low`. This allows producers to adapt to the load of the
+    /// channel without polling its length.
+    ///
+    /// The callback is called by the sender or receiver that crossed the watermark, and replaces
+    /// any previously set callback.
+    ///
+    /// ```
+    /// # use meslin::{*, leveled::Watermark};
+    /// # use std::sync::{Arc, Mutex};
+    /// let crossed = Arc::new(Mutex::new(Vec::new()));
+    /// let (sender, mut receiver) = leveled::unbounded::<u32>();
+    /// sender.on_watermark(2, 0, {
+    ///     let crossed = crossed.clone();
+    ///     move |watermark| crossed.lock().unwrap().push(watermark)
+    /// });
+    ///
+    /// for i in 0..3u32 {
+    ///     sender.try_send::<u32>(i).unwrap();
+    /// }
+    /// while receiver.try_recv().is_ok() {}
+    /// assert_eq!(*crossed.lock().unwrap(), [Watermark::High, Watermark::Low]);
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if `low` is not lower than `high`.
+    pub fn on_watermark(
+        &self,
+        high: usize,
+        low: usize,
+        callback: impl Fn(Watermark) + Send + Sync + 'static,
+    ) {
+        let mut state = self.shared.lock().unwrap();
+        let len = state.len();
+        state.watermarks = Some(Watermarks::new(high, low, len, callback));
+    }
+
+    /// Wait until the channel has space for at least `n` protocols, without sending anything.
+    ///
+    /// This allows producers to pace themselves before building a batch. Unbounded channels
+    /// always have space. Returns `false` if the channel is closed instead.
+    ///
+    /// ```
+    /// # use meslin::*;
+    /// # futures::executor::block_on(async {
+    /// let (sender, mut receiver) = leveled::bounded::<u32>(2);
+    /// sender.send::<u32>(1u32).await.unwrap();
+    /// sender.send::<u32>(2u32).await.unwrap();
+    ///
+    /// let (has_space, _) = futures::join!(sender.wait_for_capacity(2), async {
+    ///     receiver.recv().await.unwrap();
+    ///     receiver.recv().await.unwrap();
+    /// });
+    /// assert!(has_space);
+    /// # });
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if `n` is larger than the capacity of the channel.
+    pub fn wait_for_capacity(&self, n: usize) -> impl Future<Output = bool> + Send + '_
+    where
+        P: Send,
+    {
+        let mut slot = WakerSlot::new(&*self.shared, |state: &mut State<P>| &mut state.send_wakers);
+        poll_fn(move |cx| {
+            let mut state = self.shared.lock().unwrap();
+            if state.receiver_count == 0 {
+                return Poll::Ready(false);
+            }
+            match state.capacity {
+                Some(capacity) if state.len() + state.reserved + n > capacity => {
+                    assert!(
+                        n <= capacity,
+                        "n is larger than the capacity of the channel"
+                    );
+                    slot.register(&mut state, cx);
+                    Poll::Pending
+                }
+                _ => Poll::Ready(true),
+            }
+        })
+    }
+
+    /// Push the protocol, into a slot that was reserved by this sender if `reserved` is `true`.
+    fn push(
+        &self,
+        protocol: P,
+        level: Level,
+        reserved: bool,
+    ) -> Result<(), TrySendError<(P, Level)>> {
+        let (wakers, crossed) = {
+            let mut state = self.shared.lock().unwrap();
+            if reserved {
+                state.reserved -= 1;
+            }
+            if state.receiver_count == 0 {
+                return Err(TrySendError::Closed((protocol, level)));
+            }
+            if !reserved && state.is_full() {
+                return Err(TrySendError::Full((protocol, level)));
+            }
+            state.queues[level as usize].push_back(protocol);
+            (state.recv_wakers.take(), state.crossed_watermark())
+        };
+        wakers.into_iter().for_each(Waker::wake);
+        if let Some((callback, watermark)) = crossed {
+            callback(watermark);
+        }
+        Ok(())
+    }
+}
+
+impl<P> Receiver<P> {
+    /// Receive the next protocol and its level, waiting until one is available.
+    pub fn recv(&mut self) -> impl Future<Output = Result<(P, Level), RecvError>> + Send + '_
+    where
+        P: Send,
+    {
+        let this = &*self;
+        let mut slot = WakerSlot::new(&*this.shared, |state: &mut State<P>| &mut state.recv_wakers);
+        poll_fn(move |cx| match this.pop() {
+            Ok(received) => Poll::Ready(Ok(received)),
+            Err(TryRecvError::Closed) => Poll::Ready(Err(RecvError)),
+            Err(TryRecvError::Empty) => {
+                let mut state = this.shared.lock().unwrap();
+                // A protocol might have been sent in the meantime.
+                if state.len() > 0 || state.sender_count == 0 {
+                    cx.waker().wake_by_ref();
+                } else {
+                    slot.register(&mut state, cx);
+                }
+                Poll::Pending
+            }
+        })
+    }
+
+    /// Receive the next protocol and its level, returning an error if none is available.
+    pub fn try_recv(&mut self) -> Result<(P, Level), TryRecvError> {
+        self.pop()
+    }
+
+    fn pop(&self) -> Result<(P, Level), TryRecvError> {
+        let (received, wakers, crossed) = {
+            let mut state = self.shared.lock().unwrap();
+            match state.pop() {
+                Some(received) => (
+                    received,
+                    state.send_wakers.take(),
+                    state.crossed_watermark(),
+                ),
+                None if state.sender_count == 0 => return Err(TryRecvError::Closed),
+                None => return Err(TryRecvError::Empty),
+            }
+        };
+        wakers.into_iter().for_each(Waker::wake);
+        if let Some((callback, watermark)) = crossed {
+            callback(watermark);
+        }
+        Ok(received)
+    }
+
+    /// Returns the number of protocols in the channel at the given level.
+    pub fn len_at(&self, level: Level) -> usize {
+        self.shared.lock().unwrap().queues[level as usize].len()
+    }
+}
+
+impl<P> IsSender for Sender<P> {
+    type With = Level;
+
+    fn is_closed(&self) -> bool {
+        self.shared.lock().unwrap().receiver_count == 0
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        self.shared.lock().unwrap().capacity
+    }
+
+    fn len(&self) -> usize {
+        self.shared.lock().unwrap().len()
+    }
+
+    fn receiver_count(&self) -> usize {
+        self.shared.lock().unwrap().receiver_count
+    }
+
+    fn sender_count(&self) -> usize {
+        self.shared.lock().unwrap().sender_count
+    }
+}
+
+impl<P: Send> IsStaticSender for Sender<P> {
+    type Protocol = P;
+
+    async fn send_protocol_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        level: Level,
+    ) -> Result<(), SendError<(Self::Protocol, Level)>> {
+        let mut item = Some((protocol, level));
+        let mut slot = WakerSlot::new(&*this.shared, |state: &mut State<P>| &mut state.send_wakers);
+        poll_fn(|cx| {
+            let (protocol, level) = item.take().unwrap();
+            match this.push(protocol, level, false) {
+                Ok(()) => Poll::Ready(Ok(())),
+                Err(TrySendError::Closed(item)) => Poll::Ready(Err(SendError(item))),
+                Err(TrySendError::Full(full)) => {
+                    let mut state = this.shared.lock().unwrap();
+                    // Space might have become available in the meantime.
+                    if !state.is_full() || state.receiver_count == 0 {
+                        cx.waker().wake_by_ref();
+                    } else {
+                        slot.register(&mut state, cx);
+                    }
+                    item = Some(full);
+                    Poll::Pending
+                }
+            }
+        })
+        .await
+    }
+
+    fn try_send_protocol_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        level: Level,
+    ) -> Result<(), TrySendError<(Self::Protocol, Level)>> {
+        this.push(protocol, level, false)
+    }
+
+    fn wait_for_space(this: &Self) -> Option<impl Future<Output = bool> + Send + '_> {
+        (this.capacity() != Some(0)).then(|| this.wait_for_capacity(1))
+    }
+}
+
+impl<P: Send> Reserve for Sender<P> {
+    fn try_reserve_slot(this: &Self) -> Result<(), TrySendError<()>> {
+        let mut state = this.shared.lock().unwrap();
+        if state.receiver_count == 0 {
+            return Err(TrySendError::Closed(()));
+        }
+        if state.is_full() {
+            return Err(TrySendError::Full(()));
+        }
+        state.reserved += 1;
+        Ok(())
+    }
+
+    fn send_reserved_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        level: Level,
+    ) -> Result<(), TrySendError<(Self::Protocol, Level)>> {
+        this.push(protocol, level, true)
+    }
+
+    fn release_slot(this: &Self) {
+        let wakers = {
+            let mut state = this.shared.lock().unwrap();
+            state.reserved -= 1;
+            state.send_wakers.take()
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+impl<P: Send> IsReceiver for Receiver<P> {
+    type Protocol = P;
+    type With = Level;
+
+    async fn recv_protocol_with(this: &mut Self) -> Result<(P, Level), RecvError> {
+        this.recv().await
+    }
+
+    fn try_recv_protocol_with(this: &mut Self) -> Result<(P, Level), TryRecvError> {
+        this.try_recv()
+    }
+}
+
+impl<P> Clone for Sender<P> {
+    fn clone(&self) -> Self {
+        self.shared.lock().unwrap().sender_count += 1;
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<P> Drop for Sender<P> {
+    fn drop(&mut self) {
+        let wakers = {
+            let mut state = self.shared.lock().unwrap();
+            state.sender_count -= 1;
+            match state.sender_count {
+                0 => state.recv_wakers.take(),
+                _ => Vec::new(),
+            }
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+impl<P> Clone for Receiver<P> {
+    fn clone(&self) -> Self {
+        self.shared.lock().unwrap().receiver_count += 1;
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<P> Drop for Receiver<P> {
+    fn drop(&mut self) {
+        let wakers = {
+            let mut state = self.shared.lock().unwrap();
+            state.receiver_count -= 1;
+            match state.receiver_count {
+                0 => state.send_wakers.take(),
+                _ => Vec::new(),
+            }
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+impl<P> Debug for Sender<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.shared.lock().unwrap();
+        f.debug_struct("Sender")
+            .field("len", &state.len())
+            .field("capacity", &state.capacity)
+            .finish()
+    }
+}
+
+impl<P> Debug for Receiver<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Receiver").finish_non_exhaustive()
+    }
+}
+
+fn channel<P>(capacity: Option<usize>) -> (Sender<P>, Receiver<P>) {
+    let shared = Arc::new(Mutex::new(State {
+        queues: Default::default(),
+        watermarks: None,
+        capacity,
+        reserved: 0,
+        sender_count: 1,
+        receiver_count: 1,
+        recv_wakers: Wakers::default(),
+        send_wakers: Wakers::default(),
+    }));
+    (
+        Sender {
+            shared: shared.clone(),
+        },
+        Receiver { shared },
+    )
+}
+
+/// Create a leveled channel that holds at most `capacity` protocols, over all levels.
+pub fn bounded<P>(capacity: usize) -> (Sender<P>, Receiver<P>) {
+    channel(Some(capacity))
+}
+
+/// Create an unbounded leveled channel.
+pub fn unbounded<P>() -> (Sender<P>, Receiver<P>) {
+    channel(None)
+}
+
+/// The [`ChannelBackend`] of a leveled channel, configured with its capacity, or `None` if it
+/// is unbounded.
+#[derive(Debug, Clone, Copy)]
+pub struct Leveled;
+
+impl<P: Send> ChannelBackend<P> for Leveled {
+    type Config = Option<usize>;
+    type Sender = Sender<P>;
+    type Receiver = Receiver<P>;
+
+    fn create(capacity: Self::Config) -> (Self::Sender, Self::Receiver) {
+        channel(capacity)
+    }
+}
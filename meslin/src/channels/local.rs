@@ -0,0 +1,294 @@
+//! A single-threaded channel, of which the protocol does not have to be `Send`.
+//!
+//! This allows protocols containing `Rc` or `RefCell` payloads to be sent between tasks on the
+//! same thread, for example between actors on the main thread of a GUI. The sender and receiver
+//! are `!Send` themselves, and their futures can only be awaited on a single-threaded executor.
+//!
+//! Since [`IsStaticSender`] and [`IsReceiver`] require their futures to be `Send`, the channel
+//! only implements [`IsSender`]. Protocols and messages are sent with the inherent methods of the
+//! [`Sender`] instead, which have the same signatures as their [`IsSenderExt`] counterparts.
+//!
+//! ```
+//! # use meslin::*;
+//! # use std::rc::Rc;
+//! # futures::executor::block_on(async {
+//! #[derive(Debug, From, TryInto)]
+//! enum Protocol {
+//!     Shared(Rc<str>),
+//! }
+//!
+//! let (sender, mut receiver) = local::unbounded::<Protocol>();
+//! sender.send::<Rc<str>>(Rc::from("hello")).await.unwrap();
+//! let Protocol::Shared(text) = receiver.recv().await.unwrap();
+//! assert_eq!(&*text, "hello");
+//! # });
+//! ```
+use crate::{wakers::*, *};
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    fmt::Debug,
+    future::{poll_fn, Future},
+    rc::Rc,
+    task::{Poll, Waker},
+};
+
+struct State<P> {
+    queue: VecDeque<P>,
+    capacity: Option<usize>,
+    sender_count: usize,
+    receiver_count: usize,
+    recv_wakers: Wakers,
+    send_wakers: Wakers,
+}
+
+impl<P> State<P> {
+    fn is_full(&self) -> bool {
+        self.capacity
+            .is_some_and(|capacity| self.queue.len() >= capacity)
+    }
+}
+
+/// The sending half of a [local channel](self).
+pub struct Sender<P> {
+    shared: Rc<RefCell<State<P>>>,
+}
+
+/// The receiving half of a [local channel](self).
+///
+/// Receivers can be cloned, in which case every protocol is received by only one of them.
+pub struct Receiver<P> {
+    shared: Rc<RefCell<State<P>>>,
+}
+
+impl<P> Sender<P> {
+    /// Send a protocol, waiting asynchronously until space becomes available.
+    pub fn send_protocol(
+        &self,
+        protocol: P,
+    ) -> impl Future<Output = Result<(), SendError<P>>> + '_ {
+        let mut protocol = Some(protocol);
+        let mut slot = WakerSlot::new(&*self.shared, |state: &mut State<P>| &mut state.send_wakers);
+        poll_fn(
+            move |cx| match self.try_send_protocol(protocol.take().unwrap()) {
+                Ok(()) => Poll::Ready(Ok(())),
+                Err(TrySendError::Closed(protocol)) => Poll::Ready(Err(SendError(protocol))),
+                Err(TrySendError::Full(full)) => {
+                    slot.register(&mut self.shared.borrow_mut(), cx);
+                    protocol = Some(full);
+                    Poll::Pending
+                }
+            },
+        )
+    }
+
+    /// Send a protocol, failing if the channel is full.
+    pub fn try_send_protocol(&self, protocol: P) -> Result<(), TrySendError<P>> {
+        let wakers = {
+            let mut state = self.shared.borrow_mut();
+            if state.receiver_count == 0 {
+                return Err(TrySendError::Closed(protocol));
+            }
+            if state.is_full() {
+                return Err(TrySendError::Full(protocol));
+            }
+            state.queue.push_back(protocol);
+            state.recv_wakers.take()
+        };
+        wakers.into_iter().for_each(Waker::wake);
+        Ok(())
+    }
+
+    /// Send a message, waiting asynchronously until space becomes available.
+    ///
+    /// The local counterpart of [`IsSenderExt::send`].
+    pub async fn send<M>(&self, msg: impl Into<M::Input>) -> Result<M::Output, SendError<M::Input>>
+    where
+        M: Message,
+        P: From<M> + TryInto<M>,
+    {
+        let (msg, output) = M::create(msg.into());
+        match self.send_protocol(P::from(msg)).await {
+            Ok(()) => Ok(output),
+            Err(SendError(protocol)) => {
+                let (msg, ()) = protocol_into_msg((protocol, ()));
+                Err(SendError(M::cancel(msg, output)))
+            }
+        }
+    }
+
+    /// Send a message, failing if the channel is full.
+    ///
+    /// The local counterpart of [`IsSenderExt::try_send`].
+    pub fn try_send<M>(&self, msg: impl Into<M::Input>) -> Result<M::Output, TrySendError<M::Input>>
+    where
+        M: Message,
+        P: From<M> + TryInto<M>,
+    {
+        let (msg, output) = M::create(msg.into());
+        match self.try_send_protocol(P::from(msg)) {
+            Ok(()) => Ok(output),
+            Err(e) => Err(e.map(|protocol| {
+                let (msg, ()) = protocol_into_msg((protocol, ()));
+                M::cancel(msg, output)
+            })),
+        }
+    }
+}
+
+impl<P> Receiver<P> {
+    /// Receive the next protocol, waiting until one is available.
+    pub fn recv(&mut self) -> impl Future<Output = Result<P, RecvError>> + '_ {
+        let this = &*self;
+        let mut slot = WakerSlot::new(&*this.shared, |state: &mut State<P>| &mut state.recv_wakers);
+        poll_fn(move |cx| match this.pop() {
+            Ok(protocol) => Poll::Ready(Ok(protocol)),
+            Err(TryRecvError::Closed) => Poll::Ready(Err(RecvError)),
+            Err(TryRecvError::Empty) => {
+                slot.register(&mut this.shared.borrow_mut(), cx);
+                Poll::Pending
+            }
+        })
+    }
+
+    /// Receive the next protocol, returning an error if none is available.
+    pub fn try_recv(&mut self) -> Result<P, TryRecvError> {
+        self.pop()
+    }
+
+    fn pop(&self) -> Result<P, TryRecvError> {
+        let (protocol, wakers) = {
+            let mut state = self.shared.borrow_mut();
+            match state.queue.pop_front() {
+                Some(protocol) => (protocol, state.send_wakers.take()),
+                None if state.sender_count == 0 => return Err(TryRecvError::Closed),
+                None => return Err(TryRecvError::Empty),
+            }
+        };
+        wakers.into_iter().for_each(Waker::wake);
+        Ok(protocol)
+    }
+
+    /// Returns the number of protocols in the channel.
+    pub fn len(&self) -> usize {
+        self.shared.borrow().queue.len()
+    }
+
+    /// Returns `true` if the channel is empty.
+    pub fn is_empty(&self) -> bool {
+        self.shared.borrow().queue.is_empty()
+    }
+}
+
+impl<P> IsSender for Sender<P> {
+    type With = ();
+
+    fn is_closed(&self) -> bool {
+        self.shared.borrow().receiver_count == 0
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        self.shared.borrow().capacity
+    }
+
+    fn len(&self) -> usize {
+        self.shared.borrow().queue.len()
+    }
+
+    fn receiver_count(&self) -> usize {
+        self.shared.borrow().receiver_count
+    }
+
+    fn sender_count(&self) -> usize {
+        self.shared.borrow().sender_count
+    }
+}
+
+impl<P> Clone for Sender<P> {
+    fn clone(&self) -> Self {
+        self.shared.borrow_mut().sender_count += 1;
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<P> Drop for Sender<P> {
+    fn drop(&mut self) {
+        let wakers = {
+            let mut state = self.shared.borrow_mut();
+            state.sender_count -= 1;
+            match state.sender_count {
+                0 => state.recv_wakers.take(),
+                _ => Vec::new(),
+            }
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+impl<P> Clone for Receiver<P> {
+    fn clone(&self) -> Self {
+        self.shared.borrow_mut().receiver_count += 1;
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<P> Drop for Receiver<P> {
+    fn drop(&mut self) {
+        let wakers = {
+            let mut state = self.shared.borrow_mut();
+            state.receiver_count -= 1;
+            match state.receiver_count {
+                0 => state.send_wakers.take(),
+                _ => Vec::new(),
+            }
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+impl<P> Debug for Sender<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.shared.borrow();
+        f.debug_struct("Sender")
+            .field("len", &state.queue.len())
+            .field("capacity", &state.capacity)
+            .finish()
+    }
+}
+
+impl<P> Debug for Receiver<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Receiver").finish_non_exhaustive()
+    }
+}
+
+fn channel<P>(capacity: Option<usize>) -> (Sender<P>, Receiver<P>) {
+    let shared = Rc::new(RefCell::new(State {
+        queue: VecDeque::new(),
+        capacity,
+        sender_count: 1,
+        receiver_count: 1,
+        recv_wakers: Wakers::default(),
+        send_wakers: Wakers::default(),
+    }));
+    (
+        Sender {
+            shared: shared.clone(),
+        },
+        Receiver { shared },
+    )
+}
+
+/// Create a local channel that holds at most `capacity` protocols.
+pub fn bounded<P>(capacity: usize) -> (Sender<P>, Receiver<P>) {
+    channel(Some(capacity))
+}
+
+/// Create an unbounded local channel.
+pub fn unbounded<P>() -> (Sender<P>, Receiver<P>) {
+    channel(None)
+}
@@ -1,16 +1,31 @@
 #[cfg(feature = "broadcast")]
 pub mod broadcast;
 
+#[cfg(feature = "conflate")]
+pub mod conflate;
+
+#[cfg(feature = "journal")]
+pub mod journal;
+
+#[cfg(feature = "leveled")]
+pub mod leveled;
+
+#[cfg(feature = "local")]
+pub mod local;
+
 #[cfg(feature = "mpmc")]
 pub mod mpmc;
 
+#[cfg(feature = "mpsc")]
+pub mod mpsc;
+
 #[cfg(feature = "priority")]
 pub mod priority;
 
 #[cfg(feature = "request")]
 pub mod oneshot;
 #[cfg(feature = "request")]
-pub use oneshot::Request;
+pub use oneshot::{Barrier, ReplySender, Request, Shutdown};
 
-#[cfg(feature = "derive")]
+#[cfg(feature = "watch")]
 pub mod watch;
@@ -1,13 +1,40 @@
 use crate::*;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+};
 
 /// A wrapper around [`flume::Sender`].
+///
+/// When the channel is full, the sender applies its [`Backpressure`] strategy, which can be set
+/// using [`bounded_with`]. To wait for capacity or observe watermarks, use a [`Monitor`], and to
+/// reserve space, use a [`ReservableSender`].
 pub struct Sender<P> {
     sender: flume::Sender<P>,
+    /// Only set for senders that do not use [`Backpressure::Block`], so that the default sender
+    /// carries no extra state.
+    overflow: Option<Arc<Overflow<P>>>,
+}
+
+/// How a sender handles a full channel, shared by the sender and its clones.
+struct Overflow<P> {
+    backpressure: Backpressure,
+    /// A receiver used to drop the oldest protocol, for [`Backpressure::DropOldest`].
+    oldest: Option<flume::Receiver<P>>,
+    on_overflow: Mutex<Option<Arc<dyn Fn(P) + Send + Sync>>>,
+    /// The number of protocols dropped by the strategy, reported by [`IsSender::stats`].
+    dropped: AtomicU64,
 }
 
 /// Re-export of [`flume::Receiver`].
 pub use flume::Receiver;
 
+/// A [`Receiver`] that supports [`peek`](Inbox::peek) and [`try_peek`](Inbox::try_peek),
+/// created with [`IsReceiverExt::peekable`].
+///
+/// Since [`flume`] does not support peeking, the peeked protocol is held in a one-slot buffer.
+pub type PeekableReceiver<P> = Inbox<Receiver<P>>;
+
 impl<P> Sender<P> {
     pub fn inner(&self) -> &flume::Sender<P> {
         &self.sender
@@ -22,7 +49,124 @@ impl<P> Sender<P> {
     }
 
     pub fn from_inner(sender: flume::Sender<P>) -> Self {
-        Self { sender }
+        Self {
+            sender,
+            overflow: None,
+        }
+    }
+
+    /// Set a callback that is called with every protocol that is dropped by the [`Backpressure`]
+    /// strategy, so that load-shedding can be observed.
+    ///
+    /// Protocols that are rejected with [`Backpressure::Fail`] are returned to the caller, and
+    /// are not passed to the callback. The callback is shared by all clones of the sender,
+    /// including the ones created before, and replaces any previously set callback. Senders that
+    /// use [`Backpressure::Block`] never drop protocols, so their callback is never called.
+    ///
+    /// ```
+    /// # use meslin::*;
+    /// # use std::sync::{Arc, Mutex};
+    /// let dropped = Arc::new(Mutex::new(Vec::new()));
+    /// let (sender, _receiver) = mpmc::bounded_with::<u32>(1, Backpressure::DropNewest);
+    /// let sender = sender.on_overflow({
+    ///     let dropped = dropped.clone();
+    ///     move |protocol| dropped.lock().unwrap().push(protocol)
+    /// });
+    ///
+    /// for i in 0..3u32 {
+    ///     sender.try_send::<u32>(i).unwrap();
+    /// }
+    /// assert_eq!(*dropped.lock().unwrap(), [1, 2]);
+    /// ```
+    pub fn on_overflow(self, callback: impl Fn(P) + Send + Sync + 'static) -> Self {
+        if let Some(overflow) = &self.overflow {
+            *overflow.on_overflow.lock().unwrap() = Some(Arc::new(callback));
+        }
+        self
+    }
+
+    fn overflow(&self, protocol: P) {
+        let Some(overflow) = &self.overflow else {
+            return;
+        };
+        overflow.dropped.fetch_add(1, Ordering::Relaxed);
+        let on_overflow = overflow.on_overflow.lock().unwrap().clone();
+        if let Some(on_overflow) = on_overflow {
+            on_overflow(protocol)
+        }
+    }
+
+    pub fn backpressure(&self) -> Backpressure {
+        self.overflow
+            .as_ref()
+            .map_or(Backpressure::Block, |overflow| overflow.backpressure)
+    }
+
+    fn oldest(&self) -> Option<&flume::Receiver<P>> {
+        self.overflow.as_ref()?.oldest.as_ref()
+    }
+
+    fn receivers(&self) -> usize {
+        self.sender.receiver_count() - self.oldest().is_some() as usize
+    }
+
+    /// Send the protocol, applying the [`Backpressure`] strategy, and report whether it was
+    /// rejected because the channel is full.
+    ///
+    /// Unlike the send methods of [`IsStaticSender`], which return a [`SendError`] in both cases,
+    /// this distinguishes a protocol that was rejected by [`Backpressure::Fail`] from a closed
+    /// channel. With [`Backpressure::Block`], it waits until space becomes available.
+    ///
+    /// ```
+    /// # use meslin::*;
+    /// # futures::executor::block_on(async {
+    /// let (sender, receiver) = mpmc::bounded_with::<u32>(1, Backpressure::Fail);
+    /// sender.send_or_reject(1).await.unwrap();
+    /// assert_eq!(sender.send_or_reject(2).await, Err(BackpressureError::Rejected(2)));
+    /// drop(receiver);
+    /// assert_eq!(sender.send_or_reject(3).await, Err(BackpressureError::Closed(3)));
+    /// # });
+    /// ```
+    pub async fn send_or_reject(&self, protocol: P) -> Result<(), BackpressureError<P>> {
+        match self.backpressure() {
+            Backpressure::Block => self
+                .sender
+                .send_async(protocol)
+                .await
+                .map_err(|e| BackpressureError::Closed(e.0)),
+            _ => self.try_send_inner(protocol).map_err(|e| match e {
+                flume::TrySendError::Disconnected(protocol) => BackpressureError::Closed(protocol),
+                flume::TrySendError::Full(protocol) => BackpressureError::Rejected(protocol),
+            }),
+        }
+    }
+
+    /// Try to send the protocol, applying the [`Backpressure`] strategy when the channel is full.
+    fn try_send_inner(&self, protocol: P) -> Result<(), flume::TrySendError<P>> {
+        if self.receivers() == 0 {
+            return Err(flume::TrySendError::Disconnected(protocol));
+        }
+        match (self.sender.try_send(protocol), self.oldest()) {
+            (Ok(()), _) => Ok(()),
+            (Err(flume::TrySendError::Full(protocol)), Some(oldest)) => match oldest.try_recv() {
+                Ok(dropped) => {
+                    self.overflow(dropped);
+                    self.try_send_inner(protocol)
+                }
+                // The channel has no capacity, so the new protocol is dropped instead.
+                Err(_) => {
+                    self.overflow(protocol);
+                    Ok(())
+                }
+            },
+            (Err(flume::TrySendError::Full(protocol)), None)
+                if self.backpressure() == Backpressure::DropNewest =>
+            {
+                self.overflow(protocol);
+                Ok(())
+            }
+            (result, _) => result,
+        }
     }
 }
 
@@ -30,7 +174,7 @@ impl<P> IsSender for Sender<P> {
     type With = ();
 
     fn is_closed(&self) -> bool {
-        self.sender.is_disconnected()
+        self.receivers() == 0
     }
 
     fn capacity(&self) -> Option<usize> {
@@ -42,12 +186,35 @@ impl<P> IsSender for Sender<P> {
     }
 
     fn receiver_count(&self) -> usize {
-        self.sender.receiver_count()
+        self.receivers()
     }
 
     fn sender_count(&self) -> usize {
         self.sender.sender_count()
     }
+
+    /// Returns `true` if sending would have to wait, or fail with [`Backpressure::Fail`].
+    ///
+    /// Senders that drop protocols when the channel is full are never full.
+    fn is_full(&self) -> bool {
+        match self.backpressure() {
+            Backpressure::DropOldest | Backpressure::DropNewest => false,
+            Backpressure::Block | Backpressure::Fail => self.sender.is_full(),
+        }
+    }
+
+    /// Returns a snapshot of the health of the channel, including the number of protocols that
+    /// were dropped by the [`Backpressure`] strategy of this sender and its clones.
+    fn stats(&self) -> ChannelStats {
+        let dropped = self
+            .overflow
+            .as_ref()
+            .map_or(0, |overflow| overflow.dropped.load(Ordering::Relaxed));
+        ChannelStats {
+            dropped: Some(dropped),
+            ..ChannelStats::without_counters(self)
+        }
+    }
 }
 
 impl<P: Send> IsStaticSender for Sender<P> {
@@ -58,18 +225,33 @@ impl<P: Send> IsStaticSender for Sender<P> {
         protocol: Self::Protocol,
         _with: (),
     ) -> Result<(), SendError<(Self::Protocol, ())>> {
-        this.sender
-            .send_async(protocol)
-            .await
-            .map_err(|e| SendError((e.0, ())))
+        match this.backpressure() {
+            Backpressure::Block => this
+                .sender
+                .send_async(protocol)
+                .await
+                .map_err(|e| SendError((e.0, ()))),
+            _ => this
+                .try_send_inner(protocol)
+                .map_err(|e| SendError((e.into_inner(), ()))),
+        }
     }
 
+    #[cfg(blocking)]
     fn send_protocol_blocking_with(
         this: &Self,
         protocol: Self::Protocol,
         _with: (),
     ) -> Result<(), SendError<(Self::Protocol, ())>> {
-        this.sender.send(protocol).map_err(|e| SendError((e.0, ())))
+        match this.try_send_inner(protocol) {
+            Err(flume::TrySendError::Full(protocol))
+                if this.backpressure() == Backpressure::Block =>
+            {
+                crate::assert_can_block();
+                this.sender.send(protocol).map_err(|e| SendError((e.0, ())))
+            }
+            result => result.map_err(|e| SendError((e.into_inner(), ()))),
+        }
     }
 
     fn try_send_protocol_with(
@@ -77,17 +259,48 @@ impl<P: Send> IsStaticSender for Sender<P> {
         protocol: Self::Protocol,
         _with: (),
     ) -> Result<(), TrySendError<(Self::Protocol, ())>> {
-        this.sender.try_send(protocol).map_err(|e| match e {
+        this.try_send_inner(protocol).map_err(|e| match e {
             flume::TrySendError::Disconnected(protocol) => TrySendError::Closed((protocol, ())),
             flume::TrySendError::Full(protocol) => TrySendError::Full((protocol, ())),
         })
     }
 }
 
+impl<P: Send> IsReceiver for Receiver<P> {
+    type Protocol = P;
+    type With = ();
+
+    async fn recv_protocol_with(this: &mut Self) -> Result<(Self::Protocol, ()), RecvError> {
+        this.recv_async()
+            .await
+            .map(|p| (p, ()))
+            .map_err(|_| RecvError)
+    }
+
+    #[cfg(blocking)]
+    fn recv_protocol_blocking_with(this: &mut Self) -> Result<(Self::Protocol, ()), RecvError> {
+        match <Self as IsReceiver>::try_recv_protocol_with(this) {
+            Err(TryRecvError::Empty) => {
+                crate::assert_can_block();
+                this.recv().map(|p| (p, ())).map_err(|_| RecvError)
+            }
+            result => result.map_err(|_| RecvError),
+        }
+    }
+
+    fn try_recv_protocol_with(this: &mut Self) -> Result<(Self::Protocol, ()), TryRecvError> {
+        this.try_recv().map(|p| (p, ())).map_err(|e| match e {
+            flume::TryRecvError::Empty => TryRecvError::Empty,
+            flume::TryRecvError::Disconnected => TryRecvError::Closed,
+        })
+    }
+}
+
 impl<P> Clone for Sender<P> {
     fn clone(&self) -> Self {
         Self {
             sender: self.sender.clone(),
+            overflow: self.overflow.clone(),
         }
     }
 }
@@ -96,16 +309,88 @@ impl<P> std::fmt::Debug for Sender<P> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Sender")
             .field("sender", &self.sender)
-            .finish()
+            .field("backpressure", &self.backpressure())
+            .finish_non_exhaustive()
     }
 }
 
 pub fn bounded<P>(cap: usize) -> (Sender<P>, flume::Receiver<P>) {
+    bounded_with(cap, Backpressure::Block)
+}
+
+/// Create a bounded channel, of which the sender applies the [`Backpressure`] strategy when the
+/// channel is full.
+///
+/// ```
+/// # use meslin::*;
+/// let (sender, receiver) = mpmc::bounded_with::<u32>(2, Backpressure::DropOldest);
+/// for i in 0..4u32 {
+///     sender.try_send::<u32>(i).unwrap();
+/// }
+/// assert_eq!(receiver.drain().collect::<Vec<_>>(), [2, 3]);
+/// ```
+pub fn bounded_with<P>(cap: usize, backpressure: Backpressure) -> (Sender<P>, flume::Receiver<P>) {
     let (sender, receiver) = flume::bounded(cap);
-    (Sender { sender }, receiver)
+    let overflow = match backpressure {
+        Backpressure::Block => None,
+        _ => Some(Arc::new(Overflow {
+            backpressure,
+            oldest: (backpressure == Backpressure::DropOldest).then(|| receiver.clone()),
+            on_overflow: Mutex::new(None),
+            dropped: AtomicU64::new(0),
+        })),
+    };
+    (Sender { sender, overflow }, receiver)
 }
 
 pub fn unbounded<P>() -> (Sender<P>, flume::Receiver<P>) {
     let (sender, receiver) = flume::unbounded();
-    (Sender { sender }, receiver)
+    (Sender::from_inner(sender), receiver)
+}
+
+/// The [`ChannelBackend`] of an mpmc-channel.
+#[derive(Debug, Clone, Copy)]
+pub struct Mpmc;
+
+/// The configuration of an mpmc-channel, used by the [`Mpmc`] backend.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Config {
+    /// The capacity of the channel, or `None` if it is unbounded.
+    pub capacity: Option<usize>,
+    /// The [`Backpressure`] strategy applied when a bounded channel is full.
+    pub backpressure: Backpressure,
+}
+
+impl Config {
+    /// A bounded channel with the given capacity.
+    pub fn bounded(capacity: usize) -> Self {
+        Self {
+            capacity: Some(capacity),
+            backpressure: Backpressure::default(),
+        }
+    }
+
+    /// An unbounded channel.
+    pub fn unbounded() -> Self {
+        Self::default()
+    }
+
+    /// Set the [`Backpressure`] strategy.
+    pub fn backpressure(mut self, backpressure: Backpressure) -> Self {
+        self.backpressure = backpressure;
+        self
+    }
+}
+
+impl<P: Send> ChannelBackend<P> for Mpmc {
+    type Config = Config;
+    type Sender = Sender<P>;
+    type Receiver = flume::Receiver<P>;
+
+    fn create(config: Self::Config) -> (Self::Sender, Self::Receiver) {
+        match config.capacity {
+            Some(cap) => bounded_with(cap, config.backpressure),
+            None => unbounded(),
+        }
+    }
 }
@@ -0,0 +1,134 @@
+//! A multi-producer, single-consumer channel.
+//!
+//! The channel is the same as the [`mpmc`](crate::mpmc) channel, except that its [`Receiver`]
+//! can not be cloned. Protocols that require a single authoritative handler can use it to rule
+//! out competing consumers at the type level, and the [`Sender`] never reports more than one
+//! receiver.
+//!
+//! ```
+//! # use meslin::*;
+//! # futures::executor::block_on(async {
+//! let (sender, mut receiver) = mpsc::unbounded::<u32>();
+//! sender.send::<u32>(1u32).await.unwrap();
+//! assert_eq!(sender.receiver_count(), 1);
+//! assert_eq!(receiver.recv().await.unwrap(), 1);
+//! # });
+//! ```
+//!
+//! ```compile_fail
+//! # use meslin::*;
+//! let (_sender, receiver) = mpsc::unbounded::<u32>();
+//! let competing = receiver.clone();
+//! ```
+use crate::*;
+
+/// The sending half of an [mpsc-channel](self), which is the sender of the
+/// [`mpmc`](crate::mpmc) channel.
+pub use crate::mpmc::Sender;
+
+/// The receiving half of an [mpsc-channel](self), which can not be cloned.
+pub struct Receiver<P> {
+    receiver: flume::Receiver<P>,
+}
+
+impl<P> Receiver<P> {
+    /// Receive the next protocol, waiting until one is available.
+    pub async fn recv(&mut self) -> Result<P, RecvError> {
+        self.receiver.recv_async().await.map_err(|_| RecvError)
+    }
+
+    /// Receive the next protocol, returning an error if none is available.
+    pub fn try_recv(&mut self) -> Result<P, TryRecvError> {
+        self.receiver.try_recv().map_err(|e| match e {
+            flume::TryRecvError::Empty => TryRecvError::Empty,
+            flume::TryRecvError::Disconnected => TryRecvError::Closed,
+        })
+    }
+
+    /// Take all protocols that are currently in the channel.
+    pub fn drain(&mut self) -> impl Iterator<Item = P> + '_ {
+        self.receiver.drain()
+    }
+
+    /// Returns the number of protocols in the channel.
+    pub fn len(&self) -> usize {
+        self.receiver.len()
+    }
+
+    /// Returns `true` if the channel is empty.
+    pub fn is_empty(&self) -> bool {
+        self.receiver.is_empty()
+    }
+
+    /// Returns the number of senders of the channel.
+    pub fn sender_count(&self) -> usize {
+        self.receiver.sender_count()
+    }
+}
+
+impl<P: Send> IsReceiver for Receiver<P> {
+    type Protocol = P;
+    type With = ();
+
+    async fn recv_protocol_with(this: &mut Self) -> Result<(Self::Protocol, ()), RecvError> {
+        this.recv().await.map(|p| (p, ()))
+    }
+
+    #[cfg(blocking)]
+    fn recv_protocol_blocking_with(this: &mut Self) -> Result<(Self::Protocol, ()), RecvError> {
+        match <Self as IsReceiver>::try_recv_protocol_with(this) {
+            Err(TryRecvError::Empty) => {
+                crate::assert_can_block();
+                this.receiver.recv().map(|p| (p, ())).map_err(|_| RecvError)
+            }
+            result => result.map_err(|_| RecvError),
+        }
+    }
+
+    fn try_recv_protocol_with(this: &mut Self) -> Result<(Self::Protocol, ()), TryRecvError> {
+        this.try_recv().map(|p| (p, ()))
+    }
+}
+
+impl<P> std::fmt::Debug for Receiver<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Receiver")
+            .field("receiver", &self.receiver)
+            .finish()
+    }
+}
+
+/// Create a bounded mpsc-channel.
+pub fn bounded<P>(cap: usize) -> (Sender<P>, Receiver<P>) {
+    bounded_with(cap, Backpressure::Block)
+}
+
+/// Create a bounded mpsc-channel, of which the sender applies the [`Backpressure`] strategy
+/// when the channel is full.
+pub fn bounded_with<P>(cap: usize, backpressure: Backpressure) -> (Sender<P>, Receiver<P>) {
+    let (sender, receiver) = mpmc::bounded_with(cap, backpressure);
+    (sender, Receiver { receiver })
+}
+
+/// Create an unbounded mpsc-channel.
+pub fn unbounded<P>() -> (Sender<P>, Receiver<P>) {
+    let (sender, receiver) = mpmc::unbounded();
+    (sender, Receiver { receiver })
+}
+
+/// The [`ChannelBackend`] of an mpsc-channel, configured with an [`mpmc::Config`].
+#[derive(Debug, Clone, Copy)]
+pub struct Mpsc;
+
+impl<P: Send> ChannelBackend<P> for Mpsc {
+    type Config = mpmc::Config;
+    type Sender = Sender<P>;
+    type Receiver = Receiver<P>;
+
+    fn create(config: Self::Config) -> (Self::Sender, Self::Receiver) {
+        match config.capacity {
+            Some(cap) => bounded_with(cap, config.backpressure),
+            None => unbounded(),
+        }
+    }
+}
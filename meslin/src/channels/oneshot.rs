@@ -1,23 +1,118 @@
 use crate::*;
+use std::{
+    fmt::Debug,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
 
 /// A [`Message`] with input `A`, returning a response `B`.
 ///
-/// This implements [`Message`] with [`oneshot::Receiver`] as output.
+/// This implements [`Message`] with [`oneshot::Receiver`](Receiver) as output.
 #[derive(Debug)]
 pub struct Request<A, B> {
     pub msg: A,
     pub tx: ::oneshot::Sender<B>,
 }
 
-/// Re-export of [`oneshot::Receiver`](::oneshot::Receiver).
-pub use ::oneshot::Receiver;
 /// Re-export of [`oneshot::Sender`](::oneshot::Sender).
 pub use ::oneshot::Sender;
 
 impl<A, B> Request<A, B> {
-    pub fn new(msg: A) -> (Self, ::oneshot::Receiver<B>) {
+    pub fn new(msg: A) -> (Self, Receiver<B>) {
         let (sender, receiver) = ::oneshot::channel();
-        (Self { msg, tx: sender }, receiver)
+        (Self { msg, tx: sender }, Receiver { receiver })
+    }
+
+    /// Send the reply, returning it if the requester is no longer waiting.
+    pub fn reply(self, reply: B) -> Result<(), B> {
+        self.tx.send(reply).map_err(|e| e.into_inner())
+    }
+
+    /// Split the request into its message and the [`ReplySender`], which can be stored or
+    /// moved to another task to reply later.
+    pub fn into_parts(self) -> (A, ReplySender<B>) {
+        (self.msg, ReplySender { tx: self.tx })
+    }
+
+    /// Reassemble a request from its message and [`ReplySender`].
+    pub fn from_parts(msg: A, reply_sender: ReplySender<B>) -> Self {
+        Self {
+            msg,
+            tx: reply_sender.tx,
+        }
+    }
+
+    /// Forward the request to another sender, which takes over the obligation to reply.
+    ///
+    /// The requester keeps waiting on the same [`Receiver`], so an intermediary can delegate
+    /// the request without awaiting and re-sending the reply itself. If sending fails, the
+    /// request is returned.
+    ///
+    /// ```
+    /// # use meslin::*;
+    /// # futures::executor::block_on(async {
+    /// let (proxy, proxy_rx) = mpmc::unbounded::<Request<u32, u32>>();
+    /// let (worker, worker_rx) = mpmc::unbounded::<Request<u32, u32>>();
+    ///
+    /// let reply = proxy.send::<Request<u32, u32>>(10u32).await.unwrap();
+    /// proxy_rx.recv_async().await.unwrap().forward_to(&worker).await.unwrap();
+    ///
+    /// let request = worker_rx.recv_async().await.unwrap();
+    /// let msg = request.msg;
+    /// request.reply(msg * 2).unwrap();
+    /// assert_eq!(reply.await.unwrap(), 20);
+    /// # });
+    /// ```
+    pub async fn forward_to<S>(self, sender: &S) -> Result<(), SendError<Self>>
+    where
+        S: Sends<Self>,
+        S::With: Default,
+        A: Send + 'static,
+        B: Send + 'static,
+    {
+        sender.send_msg(self).await
+    }
+
+    /// Like [`Request::forward_to`], but fails if the sender is full.
+    pub fn try_forward_to<S>(self, sender: &S) -> Result<(), TrySendError<Self>>
+    where
+        S: Sends<Self>,
+        S::With: Default,
+        A: Send + 'static,
+        B: Send + 'static,
+    {
+        sender.try_send_msg(self)
+    }
+}
+
+/// The sending half of the reply to a [`Request`], created with [`Request::into_parts`].
+pub struct ReplySender<B> {
+    tx: ::oneshot::Sender<B>,
+}
+
+impl<B> ReplySender<B> {
+    pub fn inner(&self) -> &::oneshot::Sender<B> {
+        &self.tx
+    }
+
+    pub fn into_inner(self) -> ::oneshot::Sender<B> {
+        self.tx
+    }
+
+    pub fn from_inner(tx: ::oneshot::Sender<B>) -> Self {
+        Self { tx }
+    }
+
+    /// Send the reply, returning it if the requester is no longer waiting.
+    pub fn reply(self, reply: B) -> Result<(), B> {
+        self.tx.send(reply).map_err(|e| e.into_inner())
+    }
+}
+
+impl<B: Debug> Debug for ReplySender<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReplySender").field("tx", &self.tx).finish()
     }
 }
 
@@ -27,7 +122,7 @@ where
     B: Send + 'static,
 {
     type Input = A;
-    type Output = ::oneshot::Receiver<B>;
+    type Output = Receiver<B>;
 
     fn create(input: Self::Input) -> (Self, Self::Output) {
         Self::new(input)
@@ -37,3 +132,223 @@ where
         self.msg
     }
 }
+
+/// The receiver of the reply to a [`Request`], which is a wrapper around
+/// [`oneshot::Receiver`](::oneshot::Receiver).
+///
+/// The reply can be awaited, or received without waiting using [`Receiver::try_recv`]. When the
+/// reply is no longer needed, the receiver can be [closed](Receiver::close), after which
+/// [`Request::reply`] fails.
+pub struct Receiver<T> {
+    receiver: ::oneshot::Receiver<T>,
+}
+
+impl<T> Receiver<T> {
+    pub fn inner(&self) -> &::oneshot::Receiver<T> {
+        &self.receiver
+    }
+
+    pub fn into_inner(self) -> ::oneshot::Receiver<T> {
+        self.receiver
+    }
+
+    pub fn inner_mut(&mut self) -> &mut ::oneshot::Receiver<T> {
+        &mut self.receiver
+    }
+
+    pub fn from_inner(receiver: ::oneshot::Receiver<T>) -> Self {
+        Self { receiver }
+    }
+
+    /// Receive the reply, returning an error if it has not been sent yet.
+    ///
+    /// After the reply has been received, this returns [`TryRecvError::Closed`].
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        self.receiver.try_recv().map_err(|e| match e {
+            ::oneshot::TryRecvError::Empty => TryRecvError::Empty,
+            ::oneshot::TryRecvError::Disconnected => TryRecvError::Closed,
+        })
+    }
+
+    /// Receive the reply, blocking the current thread until it is sent.
+    #[cfg(blocking)]
+    pub fn recv_blocking(self) -> Result<T, RecvError> {
+        match self.try_recv() {
+            Err(TryRecvError::Empty) => {
+                crate::assert_can_block();
+                self.receiver.recv().map_err(|_| RecvError)
+            }
+            result => result.map_err(|_| RecvError),
+        }
+    }
+
+    /// Signal that the reply is no longer needed.
+    ///
+    /// This drops the receiver, so that the [`Request::reply`] of the actor fails.
+    pub fn close(self) {
+        drop(self)
+    }
+}
+
+impl<T> Future for Receiver<T> {
+    type Output = Result<T, RecvError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.receiver).poll(cx).map_err(|_| RecvError)
+    }
+}
+
+impl<T: Debug> Debug for Receiver<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Receiver")
+            .field("receiver", &self.receiver)
+            .finish()
+    }
+}
+
+/// A flush marker, which is released by the receiver once it has processed all messages that
+/// were sent before it.
+///
+/// A barrier is usually sent with [`IsSenderExt::barrier`], which resolves once the receiver
+/// calls [`Barrier::release`]. Because channels are FIFO, this gives the caller a
+/// "happens-before" guarantee for everything it sent before the barrier. A barrier that is
+/// dropped without being released fails with [`RecvError`].
+///
+/// ```
+/// # use meslin::*;
+/// #[derive(Debug, From, TryInto)]
+/// enum Protocol {
+///     Add(u32),
+///     Barrier(Barrier),
+/// }
+///
+/// # futures::executor::block_on(async {
+/// let (sender, receiver) = mpmc::unbounded::<Protocol>();
+/// sender.send::<u32>(1u32).await.unwrap();
+/// sender.send::<u32>(2u32).await.unwrap();
+///
+/// let process = async {
+///     let mut sum = 0;
+///     loop {
+///         match receiver.recv_async().await.unwrap() {
+///             Protocol::Add(n) => sum += n,
+///             Protocol::Barrier(barrier) => {
+///                 assert!(barrier.release());
+///                 break sum;
+///             }
+///         }
+///     }
+/// };
+/// let (flushed, sum) = futures::join!(sender.barrier(), process);
+/// flushed.unwrap();
+/// assert_eq!(sum, 3);
+/// # });
+/// ```
+#[derive(Debug)]
+pub struct Barrier {
+    tx: ::oneshot::Sender<()>,
+}
+
+impl Barrier {
+    pub fn new() -> (Self, Receiver<()>) {
+        let (tx, receiver) = ::oneshot::channel();
+        (Self { tx }, Receiver { receiver })
+    }
+
+    /// Signal that all messages before the barrier have been processed.
+    ///
+    /// Returns whether the sender of the barrier was still waiting.
+    pub fn release(self) -> bool {
+        self.tx.send(()).is_ok()
+    }
+}
+
+impl Message for Barrier {
+    type Input = ();
+    type Output = Receiver<()>;
+
+    fn create((): Self::Input) -> (Self, Self::Output) {
+        Self::new()
+    }
+
+    fn cancel(self, _: Self::Output) -> Self::Input {}
+}
+
+/// A request for the receiver to shut down gracefully, which is completed by the receiver once
+/// it has drained its backlog.
+///
+/// A shutdown is usually sent with [`IsSenderExt::shutdown_and_drain`], which resolves once the
+/// receiver calls [`Shutdown::complete`]. To stop accepting new sends while draining, the
+/// senders should be [gated](IsSenderExt::gated): the receiver then drains its backlog with
+/// [`Gate::close_and_drain`], which closes the gate first and waits for sends that were already
+/// in progress, so no message is lost between draining and completing. A shutdown that is
+/// dropped without being completed fails with [`RecvError`].
+///
+/// ```
+/// # use meslin::*;
+/// #[derive(Debug, From, TryInto)]
+/// enum Protocol {
+///     Add(u32),
+///     Shutdown(Shutdown),
+/// }
+///
+/// # futures::executor::block_on(async {
+/// let gate = Gate::new();
+/// let (sender, mut receiver) = mpmc::unbounded::<Protocol>();
+/// let sender = sender.gated(&gate);
+/// let actor = async move {
+///     let mut sum = 0;
+///     while let Ok(protocol) = receiver.recv_protocol().await {
+///         match protocol {
+///             Protocol::Add(n) => sum += n,
+///             Protocol::Shutdown(shutdown) => {
+///                 for (protocol, ()) in gate.close_and_drain(&mut receiver).await {
+///                     if let Protocol::Add(n) = protocol {
+///                         sum += n;
+///                     }
+///                 }
+///                 shutdown.complete();
+///                 break;
+///             }
+///         }
+///     }
+///     sum
+/// };
+/// let shutdown = async {
+///     sender.send::<u32>(1u32).await.unwrap();
+///     sender.shutdown_and_drain().await.unwrap();
+///     assert!(sender.send::<u32>(2u32).await.is_err());
+/// };
+/// let (sum, ()) = futures::join!(actor, shutdown);
+/// assert_eq!(sum, 1);
+/// # });
+/// ```
+#[derive(Debug)]
+pub struct Shutdown {
+    tx: ::oneshot::Sender<()>,
+}
+
+impl Shutdown {
+    pub fn new() -> (Self, Receiver<()>) {
+        let (tx, receiver) = ::oneshot::channel();
+        (Self { tx }, Receiver { receiver })
+    }
+
+    /// Signal that the receiver has shut down.
+    ///
+    /// Returns whether the sender of the shutdown was still waiting.
+    pub fn complete(self) -> bool {
+        self.tx.send(()).is_ok()
+    }
+}
+
+impl Message for Shutdown {
+    type Input = ();
+    type Output = Receiver<()>;
+
+    fn create((): Self::Input) -> (Self, Self::Output) {
+        Self::new()
+    }
+
+    fn cancel(self, _: Self::Output) -> Self::Input {}
+}
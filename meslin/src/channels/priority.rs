@@ -1,8 +1,11 @@
 use crate::*;
 use async_priority_channel as prio;
-use std::fmt::Debug;
+use std::{cmp::Reverse, fmt::Debug, marker::PhantomData};
 
 /// Wrapper around [`async_priority_channel::Sender`].
+///
+/// To wait for capacity or observe watermarks, use a [`Monitor`], and to reserve space, use a
+/// [`ReservableSender`].
 pub struct Sender<P, O: Ord> {
     sender: prio::Sender<P, O>,
 }
@@ -50,6 +53,10 @@ impl<P, O: Ord> IsSender for Sender<P, O> {
     fn sender_count(&self) -> usize {
         self.sender.sender_count()
     }
+
+    fn is_full(&self) -> bool {
+        self.sender.is_full()
+    }
 }
 
 impl<P: Send, O: Ord + Send> IsStaticSender for Sender<P, O> {
@@ -78,6 +85,140 @@ impl<P: Send, O: Ord + Send> IsStaticSender for Sender<P, O> {
     }
 }
 
+impl<P: Send, O: Ord + Send> IsReceiver for Receiver<P, O> {
+    type Protocol = P;
+    type With = O;
+
+    async fn recv_protocol_with(this: &mut Self) -> Result<(Self::Protocol, O), RecvError> {
+        this.recv().await.map_err(|_| RecvError)
+    }
+
+    fn try_recv_protocol_with(this: &mut Self) -> Result<(Self::Protocol, O), TryRecvError> {
+        this.try_recv().map_err(|e| match e {
+            prio::TryRecvError::Empty => TryRecvError::Empty,
+            prio::TryRecvError::Closed => TryRecvError::Closed,
+        })
+    }
+}
+
+/// Trait to derive the priority of a protocol from the protocol itself.
+///
+/// This is used by the [`DerivedSender`], so that for example a `Shutdown` message is always sent
+/// with a high priority, without every call site having to pass it.
+pub trait WithPriority<O: Ord> {
+    fn priority(&self) -> O;
+}
+
+impl<P, O: Ord> Sender<P, O> {
+    /// Convert into a [`DerivedSender`], which derives the priority from the protocol.
+    pub fn into_derived(self) -> DerivedSender<P, O> {
+        DerivedSender { sender: self }
+    }
+}
+
+/// A wrapper around a priority [`Sender`], which derives the priority of every protocol with
+/// [`WithPriority`].
+///
+/// Since its `with`-value is `()`, all ergonomic methods like `send::<M>` can be used.
+pub struct DerivedSender<P, O: Ord> {
+    sender: Sender<P, O>,
+}
+
+impl<P, O: Ord> DerivedSender<P, O> {
+    pub fn inner(&self) -> &Sender<P, O> {
+        &self.sender
+    }
+
+    pub fn into_inner(self) -> Sender<P, O> {
+        self.sender
+    }
+
+    pub fn inner_mut(&mut self) -> &mut Sender<P, O> {
+        &mut self.sender
+    }
+
+    pub fn from_inner(sender: Sender<P, O>) -> Self {
+        Self { sender }
+    }
+}
+
+impl<P, O: Ord> IsSender for DerivedSender<P, O> {
+    type With = ();
+
+    fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        self.sender.capacity()
+    }
+
+    fn len(&self) -> usize {
+        self.sender.len()
+    }
+
+    fn receiver_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+
+    fn sender_count(&self) -> usize {
+        self.sender.sender_count()
+    }
+
+    fn is_full(&self) -> bool {
+        self.sender.is_full()
+    }
+
+    fn remaining(&self) -> Option<usize> {
+        self.sender.remaining()
+    }
+}
+
+impl<P, O> IsStaticSender for DerivedSender<P, O>
+where
+    P: WithPriority<O> + Send,
+    O: Ord + Send,
+{
+    type Protocol = P;
+
+    async fn send_protocol_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        _with: (),
+    ) -> Result<(), SendError<(Self::Protocol, ())>> {
+        let priority = protocol.priority();
+        Sender::send_protocol_with(&this.sender, protocol, priority)
+            .await
+            .map_err(|e| e.map(|(protocol, _)| (protocol, ())))
+    }
+
+    fn try_send_protocol_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        _with: (),
+    ) -> Result<(), TrySendError<(Self::Protocol, ())>> {
+        let priority = protocol.priority();
+        Sender::try_send_protocol_with(&this.sender, protocol, priority)
+            .map_err(|e| e.map(|(protocol, _)| (protocol, ())))
+    }
+}
+
+impl<P: Debug, O: Ord + Debug> Debug for DerivedSender<P, O> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DerivedSender")
+            .field("sender", &self.sender)
+            .finish()
+    }
+}
+
+impl<P, O: Ord> Clone for DerivedSender<P, O> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+        }
+    }
+}
+
 impl<P: Debug, O: Ord + Debug> Debug for Sender<P, O> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Sender")
@@ -103,3 +244,115 @@ pub fn unbounded<P, O: Ord>() -> (Sender<P, O>, prio::Receiver<P, O>) {
     let (sender, receiver) = prio::unbounded();
     (Sender { sender }, receiver)
 }
+
+/// The [`ChannelBackend`] of a priority-channel with priority `O`, configured with its capacity,
+/// or `None` if it is unbounded.
+pub struct Priority<O>(PhantomData<fn() -> O>);
+
+impl<P: Send, O: Ord + Send> ChannelBackend<P> for Priority<O> {
+    type Config = Option<usize>;
+    type Sender = Sender<P, O>;
+    type Receiver = prio::Receiver<P, O>;
+
+    fn create(capacity: Self::Config) -> (Self::Sender, Self::Receiver) {
+        match capacity {
+            Some(size) => bounded(size),
+            None => unbounded(),
+        }
+    }
+}
+
+/// A priority [`Sender`] that sends with the lowest priority first, created with [`bounded_min`]
+/// or [`unbounded_min`].
+///
+/// The priority is wrapped in [`Reverse`] before it is sent.
+pub type MinSender<P, O> = MappedWithSender<Sender<P, Reverse<O>>, O>;
+
+/// A priority [`Receiver`] that receives the lowest priority first, created with
+/// [`bounded_min`] or [`unbounded_min`].
+///
+/// This is useful for deadline-style scheduling, where a smaller priority should be received
+/// sooner.
+pub struct MinReceiver<P, O: Ord> {
+    receiver: prio::Receiver<P, Reverse<O>>,
+}
+
+impl<P, O: Ord> MinReceiver<P, O> {
+    pub fn inner(&self) -> &prio::Receiver<P, Reverse<O>> {
+        &self.receiver
+    }
+
+    pub fn into_inner(self) -> prio::Receiver<P, Reverse<O>> {
+        self.receiver
+    }
+
+    pub fn inner_mut(&mut self) -> &mut prio::Receiver<P, Reverse<O>> {
+        &mut self.receiver
+    }
+
+    pub fn from_inner(receiver: prio::Receiver<P, Reverse<O>>) -> Self {
+        Self { receiver }
+    }
+
+    /// Receive the protocol with the lowest priority, waiting until one is available.
+    pub async fn recv(&self) -> Result<(P, O), RecvError> {
+        match self.receiver.recv().await {
+            Ok((protocol, Reverse(priority))) => Ok((protocol, priority)),
+            Err(_) => Err(RecvError),
+        }
+    }
+
+    /// Receive the protocol with the lowest priority, returning an error if none is available.
+    pub fn try_recv(&self) -> Result<(P, O), TryRecvError> {
+        match self.receiver.try_recv() {
+            Ok((protocol, Reverse(priority))) => Ok((protocol, priority)),
+            Err(prio::TryRecvError::Empty) => Err(TryRecvError::Empty),
+            Err(prio::TryRecvError::Closed) => Err(TryRecvError::Closed),
+        }
+    }
+}
+
+impl<P: Send, O: Ord + Send> IsReceiver for MinReceiver<P, O> {
+    type Protocol = P;
+    type With = O;
+
+    async fn recv_protocol_with(this: &mut Self) -> Result<(Self::Protocol, O), RecvError> {
+        this.recv().await
+    }
+
+    fn try_recv_protocol_with(this: &mut Self) -> Result<(Self::Protocol, O), TryRecvError> {
+        this.try_recv()
+    }
+}
+
+impl<P: Debug, O: Ord + Debug> Debug for MinReceiver<P, O> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MinReceiver")
+            .field("receiver", &self.receiver)
+            .finish()
+    }
+}
+
+impl<P, O: Ord> Clone for MinReceiver<P, O> {
+    fn clone(&self) -> Self {
+        Self {
+            receiver: self.receiver.clone(),
+        }
+    }
+}
+
+/// Create a bounded priority channel that receives the lowest priority first.
+pub fn bounded_min<P, O: Ord>(size: usize) -> (MinSender<P, O>, MinReceiver<P, O>) {
+    let (sender, receiver) = bounded(size);
+    (min_sender(sender), MinReceiver { receiver })
+}
+
+/// Create an unbounded priority channel that receives the lowest priority first.
+pub fn unbounded_min<P, O: Ord>() -> (MinSender<P, O>, MinReceiver<P, O>) {
+    let (sender, receiver) = unbounded();
+    (min_sender(sender), MinReceiver { receiver })
+}
+
+fn min_sender<P, O: Ord>(sender: Sender<P, Reverse<O>>) -> MinSender<P, O> {
+    MappedWithSender::new(sender, Reverse, |Reverse(priority)| priority)
+}
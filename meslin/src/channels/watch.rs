@@ -1,14 +1,47 @@
-use std::{fmt::Debug, sync::Arc};
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    future::Future,
+    sync::{Arc, Mutex},
+};
 
-use crate::*;
+use crate::{wakers::Changes, *};
 use tokio::sync::watch;
 
 /// Wrapper around [`tokio::sync::watch::Sender`].
+///
+/// The sender can keep a history of the last values, see [`Sender::with_history`].
 pub struct Sender<P> {
     sender: Arc<watch::Sender<P>>,
+    history: Option<Arc<Mutex<History<P>>>>,
+    receiver_changes: Arc<Mutex<Changes>>,
+}
+
+/// The last values of the channel, oldest first.
+struct History<P> {
+    values: VecDeque<P>,
+    capacity: usize,
+    clone: fn(&P) -> P,
 }
 
 /// Re-export of [`tokio::sync::watch::Receiver`].
+///
+/// Besides receiving every new value as a protocol, the receiver can be used as a state cell
+/// using [`borrow`](Receiver::borrow), [`borrow_and_update`](Receiver::borrow_and_update) and
+/// [`changed`](Receiver::changed):
+///
+/// ```
+/// # use meslin::*;
+/// # futures::executor::block_on(async {
+/// let (sender, mut receiver) = watch::channel(0u32);
+/// sender.send::<u32>(1u32).await.unwrap();
+///
+/// receiver.changed().await.unwrap();
+/// assert_eq!(*receiver.borrow_and_update(), 1);
+/// assert!(!receiver.has_changed().unwrap());
+/// assert_eq!(*receiver.borrow(), 1);
+/// # });
+/// ```
 pub use watch::Receiver;
 
 impl<P> Sender<P> {
@@ -25,7 +58,160 @@ impl<P> Sender<P> {
     }
 
     pub fn from_inner(sender: Arc<watch::Sender<P>>) -> Self {
-        Self { sender }
+        Self {
+            sender,
+            history: None,
+            receiver_changes: Default::default(),
+        }
+    }
+
+    /// Keep a history of the last `count` values, including the current one, which can be
+    /// retrieved with [`Sender::history`]. This is useful for e.g. debuggers or UIs that attach
+    /// to a state cell that is already running.
+    ///
+    /// Only values sent by this sender and its clones are recorded.
+    ///
+    /// ```
+    /// # use meslin::*;
+    /// let (sender, _receiver) = watch::channel(0u32);
+    /// let sender = sender.with_history(3);
+    /// for i in 1..=3u32 {
+    ///     sender.try_send::<u32>(i).unwrap();
+    /// }
+    /// assert_eq!(sender.history(), [1, 2, 3]);
+    /// ```
+    pub fn with_history(mut self, count: usize) -> Self
+    where
+        P: Clone,
+    {
+        self.history = (count > 0).then(|| {
+            let mut values = VecDeque::with_capacity(count);
+            values.push_back(self.sender.borrow().clone());
+            Arc::new(Mutex::new(History {
+                values,
+                capacity: count,
+                clone: P::clone,
+            }))
+        });
+        self
+    }
+
+    /// Returns the history of the last values, oldest first, or an empty `Vec` if no history is
+    /// kept.
+    pub fn history(&self) -> Vec<P> {
+        match &self.history {
+            Some(history) => {
+                let history = history.lock().unwrap();
+                history.values.iter().map(history.clone).collect()
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Modify the value using `send`, and record the new value in the history if `send` returns
+    /// that it was changed.
+    fn record<T>(&self, send: impl FnOnce(&watch::Sender<P>) -> (T, bool)) -> T {
+        match &self.history {
+            Some(history) => {
+                // The history is locked while sending, so that values are recorded in order.
+                let mut history = history.lock().unwrap();
+                let (output, changed) = send(&self.sender);
+                if changed {
+                    let value = (history.clone)(&self.sender.borrow());
+                    if history.values.len() == history.capacity {
+                        history.values.pop_front();
+                    }
+                    history.values.push_back(value);
+                }
+                output
+            }
+            None => send(&self.sender).0,
+        }
+    }
+
+    /// Modify the value in place and notify all receivers, even if there are none.
+    ///
+    /// See [`watch::Sender::send_modify`].
+    ///
+    /// ```
+    /// # use meslin::*;
+    /// let (sender, receiver) = watch::channel(vec![1]);
+    /// sender.send_modify(|value| value.push(2));
+    /// assert_eq!(*receiver.borrow(), [1, 2]);
+    /// ```
+    pub fn send_modify(&self, modify: impl FnOnce(&mut P)) {
+        self.record(|sender| (sender.send_modify(modify), true))
+    }
+
+    /// Modify the value in place, and notify all receivers if `modify` returns `true`.
+    ///
+    /// See [`watch::Sender::send_if_modified`].
+    pub fn send_if_modified(&self, modify: impl FnOnce(&mut P) -> bool) -> bool {
+        self.record(|sender| {
+            let modified = sender.send_if_modified(modify);
+            (modified, modified)
+        })
+    }
+
+    /// Replace the value and notify all receivers, returning the previous value.
+    ///
+    /// See [`watch::Sender::send_replace`].
+    pub fn send_replace(&self, value: P) -> P {
+        self.record(|sender| (sender.send_replace(value), true))
+    }
+
+    /// Borrow the current value.
+    pub fn borrow(&self) -> watch::Ref<'_, P> {
+        self.sender.borrow()
+    }
+
+    /// Create a new receiver, which sees the current value as seen, and receives all values
+    /// sent from now on.
+    ///
+    /// See [`watch::Sender::subscribe`].
+    pub fn subscribe(&self) -> Receiver<P> {
+        let receiver = self.sender.subscribe();
+        Changes::notify(&self.receiver_changes);
+        receiver
+    }
+
+    /// Wait until a receiver is created or dropped, returning the new receiver count.
+    ///
+    /// Changes are tracked from the moment this method is called, not from when the future is
+    /// first polled. Since [`watch`] does not report changes of its receivers, only the
+    /// receivers that are created with [`Sender::subscribe`], and the last receiver being
+    /// dropped, are observed. Receivers that are cloned or dropped while others remain are not.
+    ///
+    /// ```
+    /// # use meslin::*;
+    /// # futures::executor::block_on(async {
+    /// let (sender, receiver) = watch::channel(0u32);
+    /// let (count, _) = futures::join!(sender.receiver_count_changed(), async { drop(receiver) });
+    /// assert_eq!(count, 0);
+    ///
+    /// let (count, receiver) = futures::join!(sender.receiver_count_changed(), async {
+    ///     sender.subscribe()
+    /// });
+    /// assert_eq!(count, 1);
+    /// # });
+    /// ```
+    pub fn receiver_count_changed(&self) -> impl Future<Output = usize> + Send + '_
+    where
+        P: Send + Sync,
+    {
+        let changed = Changes::wait(&self.receiver_changes);
+        let open = !self.sender.is_closed();
+        async move {
+            let closed = async {
+                match open {
+                    true => self.sender.closed().await,
+                    false => std::future::pending().await,
+                }
+            };
+            futures::pin_mut!(changed, closed);
+            futures::future::select(changed, closed).await;
+            self.sender.receiver_count()
+        }
     }
 }
 
@@ -53,6 +239,8 @@ impl<P> IsSender for Sender<P> {
     }
 }
 
+impl<P> IsBroadcastSender for Sender<P> {}
+
 impl<P: Clone + Send + Sync> IsStaticSender for Sender<P> {
     type Protocol = P;
 
@@ -61,9 +249,11 @@ impl<P: Clone + Send + Sync> IsStaticSender for Sender<P> {
         protocol: Self::Protocol,
         _with: (),
     ) -> Result<(), TrySendError<(P, ())>> {
-        this.sender
-            .send(protocol)
-            .map_err(|e| TrySendError::Closed((e.0, ())))
+        this.record(|sender| {
+            let result = sender.send(protocol);
+            let sent = result.is_ok();
+            (result.map_err(|e| TrySendError::Closed((e.0, ()))), sent)
+        })
     }
 
     async fn send_protocol_with(
@@ -71,7 +261,25 @@ impl<P: Clone + Send + Sync> IsStaticSender for Sender<P> {
         protocol: Self::Protocol,
         _with: (),
     ) -> Result<(), SendError<(Self::Protocol, ())>> {
-        this.sender.send(protocol).map_err(|e| SendError((e.0, ())))
+        Self::try_send_protocol_with(this, protocol, ()).map_err(|e| SendError(e.into_inner()))
+    }
+}
+
+impl<P: Clone + Send + Sync> IsReceiver for Receiver<P> {
+    type Protocol = P;
+    type With = ();
+
+    async fn recv_protocol_with(this: &mut Self) -> Result<(Self::Protocol, ()), RecvError> {
+        this.changed().await.map_err(|_| RecvError)?;
+        Ok((this.borrow_and_update().clone(), ()))
+    }
+
+    fn try_recv_protocol_with(this: &mut Self) -> Result<(Self::Protocol, ()), TryRecvError> {
+        match this.has_changed() {
+            Ok(true) => Ok((this.borrow_and_update().clone(), ())),
+            Ok(false) => Err(TryRecvError::Empty),
+            Err(_) => Err(TryRecvError::Closed),
+        }
     }
 }
 
@@ -87,16 +295,27 @@ impl<P> Clone for Sender<P> {
     fn clone(&self) -> Self {
         Self {
             sender: self.sender.clone(),
+            history: self.history.clone(),
+            receiver_changes: self.receiver_changes.clone(),
         }
     }
 }
 
 pub fn channel<P>(init: P) -> (Sender<P>, watch::Receiver<P>) {
     let (sender, receiver) = watch::channel::<P>(init);
-    (
-        Sender {
-            sender: Arc::new(sender),
-        },
-        receiver,
-    )
+    (Sender::from_inner(Arc::new(sender)), receiver)
+}
+
+/// The [`ChannelBackend`] of a watch-channel, configured with the initial protocol.
+#[derive(Debug, Clone, Copy)]
+pub struct Watch;
+
+impl<P: Clone + Send + Sync> ChannelBackend<P> for Watch {
+    type Config = P;
+    type Sender = Sender<P>;
+    type Receiver = watch::Receiver<P>;
+
+    fn create(init: Self::Config) -> (Self::Sender, Self::Receiver) {
+        channel(init)
+    }
 }
@@ -0,0 +1,347 @@
+use crate::*;
+use futures::{future::BoxFuture, lock::Mutex as AsyncMutex};
+use std::{
+    collections::HashMap,
+    future::poll_fn,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    task::{Poll, Waker},
+};
+
+/// A unique id that correlates a request with its reply.
+///
+/// Ids created with [`CorrelationId::new`] are unique within the process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CorrelationId(u64);
+
+impl CorrelationId {
+    /// Create a new, unique correlation-id.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+
+    pub fn from_u64(id: u64) -> Self {
+        Self(id)
+    }
+
+    pub fn as_u64(&self) -> u64 {
+        self.0
+    }
+}
+
+/// A `with`-value that stamps a message with a [`CorrelationId`], wrapping the original
+/// `with`-value `W`.
+///
+/// Sending with [`Correlated::default`] stamps every message with a new id. The reply can be
+/// matched back to its request using a [`Correlator`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Correlated<W = ()> {
+    pub id: CorrelationId,
+    pub with: W,
+}
+
+impl<W> Correlated<W> {
+    /// Stamp the `with`-value with a new, unique id.
+    pub fn new(with: W) -> Self {
+        Self::with_id(CorrelationId::new(), with)
+    }
+
+    /// Stamp the `with`-value with the given id.
+    pub fn with_id(id: CorrelationId, with: W) -> Self {
+        Self { id, with }
+    }
+
+    pub fn into_inner(self) -> (CorrelationId, W) {
+        (self.id, self.with)
+    }
+}
+
+impl<W: Default> Default for Correlated<W> {
+    fn default() -> Self {
+        Self::new(W::default())
+    }
+}
+
+/// Matches replies that are received over a shared channel back to their requests, using
+/// their [`CorrelationId`].
+///
+/// A request is registered before it is sent, which returns the id to stamp it with and a
+/// [`Receiver`](crate::oneshot::Receiver) for the reply. Whoever receives the replies then
+/// resolves them by their id.
+///
+/// ```
+/// # use meslin::*;
+/// # futures::executor::block_on(async {
+/// // All replies are sent over a single channel, together with the id of their request.
+/// let (reply_sender, reply_receiver) = mpmc::unbounded::<(CorrelationId, u32)>();
+/// let correlator = Correlator::<u32>::new();
+///
+/// let (id1, reply1) = correlator.register();
+/// let (id2, reply2) = correlator.register();
+/// reply_sender.send::<(CorrelationId, u32)>((id2, 20)).await.unwrap();
+/// reply_sender.send::<(CorrelationId, u32)>((id1, 10)).await.unwrap();
+///
+/// for _ in 0..2 {
+///     let (id, reply) = reply_receiver.recv_async().await.unwrap();
+///     correlator.resolve(id, reply).unwrap();
+/// }
+/// assert_eq!(reply1.await.unwrap(), 10);
+/// assert_eq!(reply2.await.unwrap(), 20);
+/// assert!(correlator.is_empty());
+/// # });
+/// ```
+#[cfg(feature = "request")]
+pub struct Correlator<T> {
+    pending: std::sync::Mutex<std::collections::HashMap<CorrelationId, ::oneshot::Sender<T>>>,
+}
+
+#[cfg(feature = "request")]
+impl<T> Correlator<T> {
+    pub fn new() -> Self {
+        Self {
+            pending: Default::default(),
+        }
+    }
+
+    /// Register a new request, returning the id to stamp it with and the receiver of its
+    /// reply.
+    pub fn register(&self) -> (CorrelationId, crate::oneshot::Receiver<T>) {
+        let id = CorrelationId::new();
+        let (tx, rx) = ::oneshot::channel();
+        self.pending.lock().unwrap().insert(id, tx);
+        (id, crate::oneshot::Receiver::from_inner(rx))
+    }
+
+    /// Resolve the request with the given id, returning the reply if the request is unknown
+    /// or if its receiver was dropped.
+    pub fn resolve(&self, id: CorrelationId, reply: T) -> Result<(), T> {
+        let tx = self.pending.lock().unwrap().remove(&id);
+        match tx {
+            Some(tx) => tx.send(reply).map_err(|e| e.into_inner()),
+            None => Err(reply),
+        }
+    }
+
+    /// Stop waiting for the reply of the request with the given id, returning whether it was
+    /// pending.
+    pub fn unregister(&self, id: CorrelationId) -> bool {
+        self.pending.lock().unwrap().remove(&id).is_some()
+    }
+
+    /// Whether the request with the given id is still waiting for its reply.
+    pub fn is_pending(&self, id: CorrelationId) -> bool {
+        self.pending.lock().unwrap().contains_key(&id)
+    }
+
+    /// The amount of requests that are waiting for their reply.
+    pub fn len(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(feature = "request")]
+impl<T> Default for Correlator<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "request")]
+impl<T> std::fmt::Debug for Correlator<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Correlator")
+            .field("pending", &self.len())
+            .finish()
+    }
+}
+
+/// Sends requests stamped with a [`CorrelationId`] and routes their replies, which are received
+/// over a single shared reply channel, back to the request that is awaiting them.
+///
+/// Unlike a [`Correlator`], no oneshot channel is created per request, and nobody has to resolve
+/// the replies: whichever request is awaited receives from the reply channel, and hands replies
+/// for other requests over to them. Replies of requests that are no longer awaited are dropped.
+///
+/// ```
+/// # use meslin::*;
+/// # futures::executor::block_on(async {
+/// let (request_sender, request_receiver) = mpmc::unbounded::<(CorrelationId, u32)>();
+/// let (reply_sender, reply_receiver) = mpmc::unbounded::<(CorrelationId, u32)>();
+/// let mux = RequestMux::new(request_sender, reply_receiver);
+///
+/// let server = async {
+///     for _ in 0..2 {
+///         let (id, msg) = request_receiver.recv_async().await.unwrap();
+///         reply_sender.send::<(CorrelationId, u32)>((id, msg * 10)).await.unwrap();
+///     }
+/// };
+/// let (reply1, reply2, ()) = futures::join!(mux.request(1u32), mux.request(2u32), server);
+/// assert_eq!(reply1.unwrap(), 10);
+/// assert_eq!(reply2.unwrap(), 20);
+/// # });
+/// ```
+pub struct RequestMux<S, R: IsReceiver> {
+    sender: S,
+    replies: Arc<AsyncMutex<R>>,
+    pending: Arc<Mutex<PendingReplies<R::Protocol>>>,
+}
+
+struct PendingReplies<P> {
+    slots: HashMap<CorrelationId, ReplySlot<P>>,
+    closed: bool,
+}
+
+struct ReplySlot<P> {
+    reply: Option<P>,
+    waker: Option<Waker>,
+}
+
+impl<S, R: IsReceiver> RequestMux<S, R> {
+    pub fn new(sender: S, replies: R) -> Self {
+        Self {
+            sender,
+            replies: Arc::new(AsyncMutex::new(replies)),
+            pending: Arc::new(Mutex::new(PendingReplies {
+                slots: HashMap::new(),
+                closed: false,
+            })),
+        }
+    }
+
+    pub fn inner_ref(&self) -> &S {
+        &self.sender
+    }
+
+    /// The amount of requests that are waiting for their reply.
+    pub fn len(&self) -> usize {
+        self.pending.lock().unwrap().slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<S, R, T> RequestMux<S, R>
+where
+    S: IsSender,
+    S::With: Default,
+    R: IsReceiver<Protocol = (CorrelationId, T)> + Send + 'static,
+    R::With: Send,
+    T: Send,
+{
+    /// Send the message stamped with a new [`CorrelationId`], and wait for its reply.
+    ///
+    /// Fails if the message could not be sent, or if the reply channel closed before the reply
+    /// was received.
+    pub async fn request<M>(&self, msg: M) -> Result<T, RequestError<M, RecvError>>
+    where
+        S: Sends<(CorrelationId, M)>,
+        M: 'static,
+    {
+        let id = CorrelationId::new();
+        // The slot is registered before sending, so that an early reply is not dropped.
+        let _slot = PendingSlot {
+            id,
+            pending: &self.pending,
+        };
+        self.pending.lock().unwrap().slots.insert(
+            id,
+            ReplySlot {
+                reply: None,
+                waker: None,
+            },
+        );
+        self.sender
+            .send::<(CorrelationId, M)>((id, msg))
+            .await
+            .map_err(|e| RequestError::Full(e.0 .1))?;
+
+        let mut recv: Option<BoxFuture<'static, Result<(R::Protocol, R::With), RecvError>>> = None;
+        poll_fn(|cx| loop {
+            {
+                let mut pending = self.pending.lock().unwrap();
+                let closed = pending.closed;
+                let slot = pending.slots.get_mut(&id).unwrap();
+                if let Some((_, reply)) = slot.reply.take() {
+                    return Poll::Ready(Ok(reply));
+                }
+                if closed {
+                    return Poll::Ready(Err(RequestError::NoReply(RecvError)));
+                }
+                slot.waker = Some(cx.waker().clone());
+            }
+
+            let replies = self.replies.clone();
+            let fut = recv.get_or_insert_with(|| {
+                Box::pin(async move {
+                    let mut replies = replies.lock_owned().await;
+                    R::recv_protocol_with(&mut *replies).await
+                })
+            });
+            let received = match fut.as_mut().poll(cx) {
+                Poll::Ready(received) => received,
+                Poll::Pending => return Poll::Pending,
+            };
+            recv = None;
+            self.route(received);
+        })
+        .await
+    }
+
+    /// Hand the received reply over to its request.
+    fn route(&self, received: Result<((CorrelationId, T), R::With), RecvError>) {
+        let wakers: Vec<_> = {
+            let mut pending = self.pending.lock().unwrap();
+            match received {
+                Ok((reply, _)) => match pending.slots.get_mut(&reply.0) {
+                    Some(slot) => {
+                        slot.reply = Some(reply);
+                        slot.waker.take().into_iter().collect()
+                    }
+                    None => Vec::new(),
+                },
+                Err(RecvError) => {
+                    pending.closed = true;
+                    pending
+                        .slots
+                        .values_mut()
+                        .filter_map(|slot| slot.waker.take())
+                        .collect()
+                }
+            }
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+/// Unregisters the slot of a request when it is no longer awaited.
+struct PendingSlot<'a, P> {
+    id: CorrelationId,
+    pending: &'a Mutex<PendingReplies<P>>,
+}
+
+impl<P> Drop for PendingSlot<'_, P> {
+    fn drop(&mut self) {
+        self.pending.lock().unwrap().slots.remove(&self.id);
+    }
+}
+
+impl<S: std::fmt::Debug, R: IsReceiver> std::fmt::Debug for RequestMux<S, R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RequestMux")
+            .field("sender", &self.sender)
+            .field("pending", &self.len())
+            .finish()
+    }
+}
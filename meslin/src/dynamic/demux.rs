@@ -0,0 +1,196 @@
+use crate::*;
+use futures::{future::BoxFuture, lock::Mutex as AsyncMutex, Stream};
+use std::{
+    any::TypeId,
+    collections::{HashMap, VecDeque},
+    fmt::Debug,
+    marker::PhantomData,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+/// A receiver that is split into one [`Stream`] per message type, created with
+/// [`IsReceiverExt::demux`].
+///
+/// Every received protocol is converted into its message, which is buffered until it is
+/// received by a [`DemuxStream`] of that message type. Messages of which no stream exists are
+/// dropped, so all streams should be created before any of them is polled. Receiving is done
+/// by whichever stream is polled, so the receiver must be cancel-safe, like all receivers of
+/// this crate.
+///
+/// ```
+/// # use meslin::*;
+/// # use futures::StreamExt;
+/// #[derive(Debug, From, TryInto, DynProtocol)]
+/// enum Protocol {
+///     Number(u32),
+///     Text(String),
+/// }
+///
+/// # futures::executor::block_on(async {
+/// let (sender, receiver) = mpmc::unbounded::<Protocol>();
+/// let demux = receiver.demux();
+/// let mut numbers = demux.stream::<u32>();
+/// let mut texts = demux.stream::<String>();
+///
+/// sender.send::<String>("hi").await.unwrap();
+/// sender.send::<u32>(1u32).await.unwrap();
+/// drop(sender);
+///
+/// assert_eq!(numbers.next().await, Some(1));
+/// assert_eq!(numbers.next().await, None);
+/// assert_eq!(texts.next().await.as_deref(), Some("hi"));
+/// # });
+/// ```
+pub struct Demux<R> {
+    receiver: Arc<AsyncMutex<R>>,
+    shared: Arc<Mutex<Buffers>>,
+}
+
+/// A stream of all messages `M` received by a [`Demux`].
+pub struct DemuxStream<R: IsReceiver, M: 'static> {
+    receiver: Arc<AsyncMutex<R>>,
+    shared: Arc<Mutex<Buffers>>,
+    recv: Option<BoxFuture<'static, Result<R::Protocol, RecvError>>>,
+    _marker: PhantomData<fn() -> M>,
+}
+
+#[derive(Default)]
+struct Buffers {
+    buffers: HashMap<TypeId, Buffer>,
+    closed: bool,
+}
+
+#[derive(Default)]
+struct Buffer {
+    msgs: VecDeque<BoxedMsg>,
+    streams: usize,
+    wakers: Vec<Waker>,
+}
+
+impl<R> Demux<R> {
+    pub fn new(receiver: R) -> Self {
+        Self {
+            receiver: Arc::new(AsyncMutex::new(receiver)),
+            shared: Arc::new(Mutex::new(Buffers::default())),
+        }
+    }
+
+    /// Create a stream of the message `M`.
+    ///
+    /// Multiple streams of the same message can be created, in which case every message is
+    /// received by only one of them.
+    pub fn stream<M: 'static>(&self) -> DemuxStream<R, M>
+    where
+        R: IsReceiver,
+    {
+        let mut shared = self.shared.lock().unwrap();
+        shared.buffers.entry(key::<M>()).or_default().streams += 1;
+        DemuxStream {
+            receiver: self.receiver.clone(),
+            shared: self.shared.clone(),
+            recv: None,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<R, M> Stream for DemuxStream<R, M>
+where
+    R: IsReceiver + Send + 'static,
+    R::Protocol: DynProtocol + Send,
+    M: Send + 'static,
+{
+    type Item = M;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<M>> {
+        loop {
+            {
+                let mut shared = self.shared.lock().unwrap();
+                let closed = shared.closed;
+                let buffer = shared.buffers.get_mut(&key::<M>()).unwrap();
+                if let Some(msg) = buffer.msgs.pop_front() {
+                    // Stop receiving, so other streams can continue while this one is busy.
+                    drop(shared);
+                    self.recv = None;
+                    let (msg, ()) = msg.downcast::<M>().unwrap_silent();
+                    return Poll::Ready(Some(msg));
+                }
+                if closed {
+                    return Poll::Ready(None);
+                }
+                buffer.wakers.push(cx.waker().clone());
+            }
+
+            let receiver = self.receiver.clone();
+            let recv = self.recv.get_or_insert_with(|| {
+                Box::pin(async move {
+                    let mut receiver = receiver.lock_owned().await;
+                    R::recv_protocol_with(&mut *receiver)
+                        .await
+                        .map(|(protocol, _)| protocol)
+                })
+            });
+            let received = match recv.as_mut().poll(cx) {
+                Poll::Ready(received) => received,
+                Poll::Pending => return Poll::Pending,
+            };
+            self.recv = None;
+
+            let mut shared = self.shared.lock().unwrap();
+            let wakers = match received {
+                Ok(protocol) => {
+                    let msg = protocol.into_boxed_msg(());
+                    match shared.buffers.get_mut(&msg.type_id()) {
+                        Some(buffer) => {
+                            buffer.msgs.push_back(msg);
+                            std::mem::take(&mut buffer.wakers)
+                        }
+                        None => Vec::new(),
+                    }
+                }
+                Err(RecvError) => {
+                    shared.closed = true;
+                    shared
+                        .buffers
+                        .values_mut()
+                        .flat_map(|buffer| std::mem::take(&mut buffer.wakers))
+                        .collect()
+                }
+            };
+            drop(shared);
+            wakers.into_iter().for_each(Waker::wake);
+        }
+    }
+}
+
+impl<R: IsReceiver, M: 'static> Drop for DemuxStream<R, M> {
+    fn drop(&mut self) {
+        let mut shared = self.shared.lock().unwrap();
+        let buffer = shared.buffers.get_mut(&key::<M>()).unwrap();
+        buffer.streams -= 1;
+        if buffer.streams == 0 {
+            shared.buffers.remove(&key::<M>());
+        }
+    }
+}
+
+impl<R> Debug for Demux<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Demux").finish_non_exhaustive()
+    }
+}
+
+impl<R: IsReceiver, M: 'static> Debug for DemuxStream<R, M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DemuxStream")
+            .field("msg", &std::any::type_name::<M>())
+            .finish_non_exhaustive()
+    }
+}
+
+/// The key of the buffer of message `M`, which is the [`BoxedMsg::type_id`] of the message.
+fn key<M: 'static>() -> TypeId {
+    TypeId::of::<M>()
+}
@@ -1,6 +1,9 @@
 use crate::*;
 use ::type_sets::Members;
-use std::{fmt::Debug, marker::PhantomData};
+use std::{
+    any::{type_name, TypeId},
+    fmt::Debug,
+};
 
 /// Trait that allows usage of dynamic senders for a protocol
 ///
@@ -13,17 +16,123 @@ pub trait DynProtocol: Members + Sized {
     /// Convert the full protocol (enum) into a boxed [`Message`].
     #[must_use]
     fn into_boxed_msg<W: Send + 'static>(self, with: W) -> BoxedMsg<W>;
+
+    /// Describe the protocol and the messages it accepts.
+    fn protocol_info() -> ProtocolInfo;
+}
+
+/// Check whether the protocol (or set) `P` accepts the message with the given [`TypeId`].
+///
+/// This is the runtime counterpart of the [`Contains`](type_sets::Contains) bound, which checks
+/// acceptance at compile time when both types are known. It can not be a `const fn`, since
+/// [`TypeId`] can not yet be compared in const contexts on stable Rust.
+///
+/// ```
+/// # use meslin::*;
+/// # use std::any::TypeId;
+/// assert!(accepts_type_id::<Set![u32, String]>(TypeId::of::<u32>()));
+/// assert!(!accepts_type_id::<Set![u32, String]>(TypeId::of::<u64>()));
+/// ```
+pub fn accepts_type_id<P: Members + ?Sized>(id: TypeId) -> bool {
+    P::members().contains(&id)
+}
+
+/// Check whether the protocol (or set) `P` accepts all messages with the given [`TypeId`]s.
+pub fn accepts_all_type_ids<P: Members + ?Sized>(ids: &[TypeId]) -> bool {
+    ids.iter().all(|id| accepts_type_id::<P>(*id))
+}
+
+/// Assert at compile time that the protocol (or set) accepts all of the given messages.
+///
+/// This pins the contract of a protocol at its definition site, so that removing a message from
+/// the protocol fails here instead of at a distant conversion into a [`struct@DynSender`].
+///
+/// ```
+/// # use meslin::*;
+/// #[derive(Debug, From, TryInto, DynProtocol)]
+/// enum Protocol {
+///     A(u32),
+///     B(String),
+/// }
+///
+/// assert_accepts!(Protocol, u32, String);
+/// assert_accepts!(Set![u32, u64], u64);
+/// ```
+///
+/// ```compile_fail
+/// # use meslin::*;
+/// # #[derive(Debug, From, TryInto, DynProtocol)]
+/// # enum Protocol {
+/// #     A(u32),
+/// # }
+/// assert_accepts!(Protocol, u64);
+/// ```
+#[macro_export]
+macro_rules! assert_accepts {
+    ($protocol:ty, $($msg:ty),+ $(,)?) => {
+        const _: () = {
+            fn assert_accepts<P: $crate::type_sets::Contains<M> + ?Sized, M>() {}
+            #[allow(dead_code)]
+            fn assert_all() {
+                $(assert_accepts::<$protocol, $msg>();)+
+            }
+        };
+    };
+}
+
+/// Assert at compile time that all messages of the set are accepted by the protocol (or set).
+///
+/// This is the bound that is checked when converting a sender of the protocol into a
+/// [`struct@DynSender`] of the set.
+///
+/// ```
+/// # use meslin::*;
+/// #[derive(Debug, From, TryInto, DynProtocol)]
+/// enum Protocol {
+///     A(u32),
+///     B(String),
+/// }
+///
+/// assert_subset!(Set![u32], Protocol);
+/// assert_subset!(Set![String, u32], Protocol);
+/// ```
+///
+/// ```compile_fail
+/// # use meslin::*;
+/// # #[derive(Debug, From, TryInto, DynProtocol)]
+/// # enum Protocol {
+/// #     A(u32),
+/// # }
+/// assert_subset!(Set![u32, u64], Protocol);
+/// ```
+#[macro_export]
+macro_rules! assert_subset {
+    ($set:ty, $protocol:ty $(,)?) => {
+        const _: () = {
+            fn assert_subset<S: $crate::type_sets::SubsetOf<P> + ?Sized, P>() {}
+            #[allow(dead_code)]
+            fn assert_all() {
+                assert_subset::<$set, $protocol>();
+            }
+        };
+    };
 }
 
 /// A boxed message with a `with` value, used for dynamic dispatch.
+///
+/// The message can be inspected with [`BoxedMsg::type_id`] and [`BoxedMsg::type_name`], and
+/// recovered with [`BoxedMsg::downcast`] or [`BoxedMsg::downcast_ref`]. This is useful when
+/// handling messages that could not be delivered.
 pub struct BoxedMsg<W = ()> {
     msg: AnyBox,
-    _with: PhantomData<fn() -> W>,
+    with: W,
+    type_id: TypeId,
+    type_name: &'static str,
 }
 
 impl<W> Debug for BoxedMsg<W> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_tuple("BoxedMsgWith").field(&self.msg).finish()
+        f.debug_tuple("BoxedMsg").field(&self.type_name).finish()
     }
 }
 
@@ -34,22 +143,62 @@ impl<W> BoxedMsg<W> {
         W: Send + 'static,
     {
         Self {
-            _with: PhantomData,
-            msg: Box::new((msg, with)),
+            msg: Box::new(msg),
+            with,
+            type_id: TypeId::of::<M>(),
+            type_name: type_name::<M>(),
         }
     }
 
+    /// The [`TypeId`] of the contained message.
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+
+    /// The type name of the contained message, as given by [`std::any::type_name`].
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Returns `true` if the contained message is of type `M`.
+    pub fn is<M: 'static>(&self) -> bool {
+        self.type_id == TypeId::of::<M>()
+    }
+
+    /// Attempt to take out the message and its `with` value, returning the `BoxedMsg` if the
+    /// message is not of type `M`.
     pub fn downcast<M>(self) -> Result<(M, W), Self>
     where
         M: 'static,
         W: 'static,
     {
-        match self.msg.downcast::<(M, W)>() {
-            Ok(t) => Ok(*t),
-            Err(boxed) => Err(Self {
-                _with: PhantomData,
-                msg: boxed,
-            }),
+        match self.msg.downcast::<M>() {
+            Ok(msg) => Ok((*msg, self.with)),
+            Err(msg) => Err(Self { msg, ..self }),
+        }
+    }
+
+    /// Attempt to get a reference to the message and its `with` value.
+    pub fn downcast_ref<M>(&self) -> Option<(&M, &W)>
+    where
+        M: 'static,
+    {
+        let msg = self.msg.downcast_ref::<M>()?;
+        Some((msg, &self.with))
+    }
+
+    /// The `with` value of the message.
+    pub fn with(&self) -> &W {
+        &self.with
+    }
+
+    /// Map the `with` value of the message, without having to know the type of the message.
+    pub fn map_with<W2>(self, f: impl FnOnce(W) -> W2) -> BoxedMsg<W2> {
+        BoxedMsg {
+            msg: self.msg,
+            with: f(self.with),
+            type_id: self.type_id,
+            type_name: self.type_name,
         }
     }
 }
@@ -139,6 +139,32 @@ impl<T, W> DynSender<T, W> {
     {
         self.sender.as_any().downcast_ref::<S>()
     }
+
+    /// Downcast the inner sender mutably to a statically typed sender.
+    ///
+    /// This can be used to reconfigure a sender after it has been type-erased.
+    pub fn downcast_mut<S>(&mut self) -> Option<&mut S>
+    where
+        S: IsSender<With = W> + 'static,
+        W: 'static,
+    {
+        self.sender.as_any_mut().downcast_mut::<S>()
+    }
+
+    /// Downcast the inner sender to a statically typed sender, consuming the `DynSender`.
+    ///
+    /// If the inner sender is not of type `S`, the `DynSender` is returned unchanged.
+    pub fn downcast<S>(self) -> Result<S, Self>
+    where
+        S: IsSender<With = W> + 'static,
+        W: 'static,
+    {
+        if self.sender.as_any().is::<S>() {
+            Ok(*self.sender.into_any().downcast::<S>().unwrap_silent())
+        } else {
+            Err(self)
+        }
+    }
 }
 
 impl<T, W> IsSender for DynSender<T, W> {
@@ -163,6 +189,18 @@ impl<T, W> IsSender for DynSender<T, W> {
     fn sender_count(&self) -> usize {
         self.sender.sender_count()
     }
+
+    fn is_full(&self) -> bool {
+        self.sender.is_full()
+    }
+
+    fn remaining(&self) -> Option<usize> {
+        self.sender.remaining()
+    }
+
+    fn stats(&self) -> ChannelStats {
+        self.sender.stats()
+    }
 }
 
 impl<T, W> IsDynSender for DynSender<T, W>
@@ -173,10 +211,11 @@ where
     fn dyn_send_boxed_msg_with(
         &self,
         msg: BoxedMsg<Self::With>,
-    ) -> BoxFuture<Result<(), DynSendError<BoxedMsg<Self::With>>>> {
+    ) -> BoxFuture<'_, Result<(), DynSendError<BoxedMsg<Self::With>>>> {
         self.sender.dyn_send_boxed_msg_with(msg)
     }
 
+    #[cfg(blocking)]
     fn dyn_send_boxed_msg_blocking_with(
         &self,
         msg: BoxedMsg<Self::With>,
@@ -195,6 +234,10 @@ where
         self.sender.members()
     }
 
+    fn protocol_info(&self) -> Option<ProtocolInfo> {
+        self.sender.protocol_info()
+    }
+
     fn clone_boxed(&self) -> Box<dyn IsDynSender<With = Self::With>> {
         self.sender.clone_boxed()
     }
@@ -202,6 +245,14 @@ where
     fn as_any(&self) -> &dyn Any {
         self.sender.as_any()
     }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self.sender.as_any_mut()
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self.sender.into_any()
+    }
 }
 
 impl<T, W, M> Sends<M> for DynSender<T, W>
@@ -0,0 +1,202 @@
+use crate::*;
+use futures::future::BoxFuture;
+use std::{
+    any::{type_name, Any, TypeId},
+    fmt::Debug,
+    marker::PhantomData,
+};
+
+/// A [`struct@DynSender`] of which the `with` type is erased as well.
+///
+/// Senders with different `With` types can be stored together as an `ErasedSender`, as long as
+/// the caller always sends with the default `with` value. An `ErasedSender` is created with
+/// [`DynSender::erase_with`].
+///
+/// ```
+/// # use meslin::*;
+/// #[derive(Debug, From, TryInto, DynProtocol)]
+/// enum MyProtocol {
+///     A(u32),
+/// }
+///
+/// let (mpmc, _rx) = mpmc::unbounded::<MyProtocol>();
+/// let (priority, _rx) = priority::unbounded::<MyProtocol, u32>();
+///
+/// let mpmc: DynSender![u32] = mpmc.into_dyn_sender();
+/// let priority: DynSender![u32; u32] = priority.into_dyn_sender();
+/// let senders: Vec<ErasedSender![u32]> = vec![mpmc.erase_with(), priority.erase_with()];
+/// ```
+pub type ErasedSender<T> = DynSender<T, ()>;
+
+/// A macro that defines an [`ErasedSender`].
+///
+/// Example:
+/// - `ErasedSender![u32, u64]` == `ErasedSender<Set![u32, u64]>`
+#[macro_export]
+macro_rules! ErasedSender {
+    ($($msg:ty),* $(,)?) => {
+        $crate::ErasedSender::<$crate::Set![$($msg),*]>
+    };
+}
+
+impl<T, W> DynSender<T, W>
+where
+    W: Send + 'static,
+{
+    /// Erase the `with` type of the sender, sending all messages with [`Default::default`].
+    ///
+    /// If sending fails, the `with` value of the returned message is lost.
+    pub fn erase_with(self) -> ErasedSender<T>
+    where
+        W: Default,
+    {
+        self.map_with(|()| W::default(), |_| ())
+    }
+
+    /// Map the `with` value of the sender to `()`, by providing the `with` to use.
+    ///
+    /// This is the dynamic counterpart of [`IsSenderExt::with`]. If sending fails, the `with`
+    /// value of the returned message is lost.
+    pub fn with(self, with: W) -> DynSender<T>
+    where
+        W: Clone + Sync,
+    {
+        self.map_with(move |()| with.clone(), |_| ())
+    }
+
+    /// Map the `with` value of the sender to a custom `with` value, by providing a mapping in
+    /// both directions.
+    ///
+    /// This is the dynamic counterpart of [`IsSenderExt::map_with`].
+    pub fn map_with<W2, F1, F2>(self, f1: F1, f2: F2) -> DynSender<T, W2>
+    where
+        W2: Send + 'static,
+        F1: Fn(W2) -> W + Clone + Send + Sync + 'static,
+        F2: Fn(W) -> W2 + Clone + Send + Sync + 'static,
+    {
+        DynSender::from_inner_unchecked(Box::new(MapWith {
+            sender: self.into_inner(),
+            f1,
+            f2,
+            _with: PhantomData,
+        }))
+    }
+}
+
+/// Wraps a sender, mapping its `with` value in both directions.
+struct MapWith<W, W2, F1, F2> {
+    sender: Box<dyn IsDynSender<With = W>>,
+    f1: F1,
+    f2: F2,
+    _with: PhantomData<fn() -> W2>,
+}
+
+impl<W, W2, F1, F2> Debug for MapWith<W, W2, F1, F2> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MapWith")
+            .field("sender", &self.sender)
+            .field("with", &type_name::<W2>())
+            .finish()
+    }
+}
+
+impl<W, W2, F1, F2> IsSender for MapWith<W, W2, F1, F2> {
+    type With = W2;
+
+    fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        self.sender.capacity()
+    }
+
+    fn len(&self) -> usize {
+        self.sender.len()
+    }
+
+    fn receiver_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+
+    fn sender_count(&self) -> usize {
+        self.sender.sender_count()
+    }
+
+    fn is_full(&self) -> bool {
+        self.sender.is_full()
+    }
+
+    fn remaining(&self) -> Option<usize> {
+        self.sender.remaining()
+    }
+
+    fn stats(&self) -> ChannelStats {
+        self.sender.stats()
+    }
+}
+
+impl<W, W2, F1, F2> IsDynSender for MapWith<W, W2, F1, F2>
+where
+    W: Send + 'static,
+    W2: Send + 'static,
+    F1: Fn(W2) -> W + Clone + Send + Sync + 'static,
+    F2: Fn(W) -> W2 + Clone + Send + Sync + 'static,
+{
+    fn dyn_send_boxed_msg_with(
+        &self,
+        msg: BoxedMsg<Self::With>,
+    ) -> BoxFuture<'_, Result<(), DynSendError<BoxedMsg<Self::With>>>> {
+        let fut = self.sender.dyn_send_boxed_msg_with(msg.map_with(&self.f1));
+        let f2 = &self.f2;
+        Box::pin(async move { fut.await.map_err(|e| e.map(|msg| msg.map_with(f2))) })
+    }
+
+    #[cfg(blocking)]
+    fn dyn_send_boxed_msg_blocking_with(
+        &self,
+        msg: BoxedMsg<Self::With>,
+    ) -> Result<(), DynSendError<BoxedMsg<Self::With>>> {
+        self.sender
+            .dyn_send_boxed_msg_blocking_with(msg.map_with(&self.f1))
+            .map_err(|e| e.map(|msg| msg.map_with(&self.f2)))
+    }
+
+    fn dyn_try_send_boxed_msg_with(
+        &self,
+        msg: BoxedMsg<Self::With>,
+    ) -> Result<(), DynTrySendError<BoxedMsg<Self::With>>> {
+        self.sender
+            .dyn_try_send_boxed_msg_with(msg.map_with(&self.f1))
+            .map_err(|e| e.map(|msg| msg.map_with(&self.f2)))
+    }
+
+    fn members(&self) -> &'static [TypeId] {
+        self.sender.members()
+    }
+
+    fn protocol_info(&self) -> Option<ProtocolInfo> {
+        self.sender.protocol_info()
+    }
+
+    fn clone_boxed(&self) -> Box<dyn IsDynSender<With = Self::With>> {
+        Box::new(Self {
+            sender: self.sender.clone(),
+            f1: self.f1.clone(),
+            f2: self.f2.clone(),
+            _with: PhantomData,
+        })
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self.sender.as_any()
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self.sender.as_any_mut()
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self.sender.into_any()
+    }
+}
@@ -47,6 +47,27 @@ impl<T> From<SendError<T>> for DynSendError<T> {
     }
 }
 
+/// Error that is returned when a channel is closed, the message was not accepted, or the request
+/// did not receive a reply.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Error)]
+pub enum DynRequestError<M, E> {
+    #[error("Message {0:?} was not accepted.")]
+    NotAccepted(M),
+    #[error("Channel is closed: Failed to send message {0:?}.")]
+    Closed(M),
+    #[error("No reply received: {0}")]
+    NoReply(#[source] E),
+}
+
+impl<T, E> From<DynSendError<T>> for DynRequestError<T, E> {
+    fn from(e: DynSendError<T>) -> Self {
+        match e {
+            DynSendError::NotAccepted(t) => Self::NotAccepted(t),
+            DynSendError::Closed(t) => Self::Closed(t),
+        }
+    }
+}
+
 /// Error that is returned when a channel is closed, full, or the message was not accepted.
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Error)]
 pub enum DynTrySendError<T> {
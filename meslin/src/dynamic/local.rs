@@ -0,0 +1,490 @@
+use crate::*;
+use ::type_sets::{Contains, Members, SubsetOf};
+use futures::{
+    future::{BoxFuture, LocalBoxFuture},
+    Future,
+};
+use std::{
+    any::{type_name, Any, TypeId},
+    fmt::Debug,
+    marker::PhantomData,
+};
+
+/// A macro that defines a [`struct@LocalDynSender`].
+///
+/// Example:
+/// - `LocalDynSender![u32, u64]` == `LocalDynSender<Set![u32, u64]>`
+/// - `LocalDynSender![u32, u64; i32]` == `LocalDynSender<Set![u32, u64], i32>`
+#[macro_export]
+macro_rules! LocalDynSender {
+    ($($msg:ty),* $(,)? $(; $with:ty)?) => {
+        $crate::LocalDynSender::<
+            $crate::Set![$($msg),*],
+            $($with)?
+        >
+    };
+}
+
+/// Like [`IsDynSender`], but without the `Send` bound on the sender and its futures.
+///
+/// This allows senders that are `!Send` to be used dynamically on a single-threaded executor.
+/// It is automatically implemented for all senders that send a protocol which implements
+/// [`DynProtocol`], and for `Box<dyn IsDynSender>`.
+pub trait IsLocalDynSender: IsSender + 'static + Debug {
+    fn local_send_boxed_msg_with(
+        &self,
+        msg: BoxedMsg<Self::With>,
+    ) -> LocalBoxFuture<'_, Result<(), DynSendError<BoxedMsg<Self::With>>>>;
+
+    fn local_try_send_boxed_msg_with(
+        &self,
+        msg: BoxedMsg<Self::With>,
+    ) -> Result<(), DynTrySendError<BoxedMsg<Self::With>>>;
+
+    /// Get the message types that the sender accepts.
+    fn local_members(&self) -> &'static [TypeId];
+    fn local_clone_boxed(&self) -> Box<dyn IsLocalDynSender<With = Self::With>>;
+    fn local_as_any(&self) -> &dyn Any;
+}
+
+impl<T> IsLocalDynSender for T
+where
+    T: IsStaticSender + Clone + 'static + Debug,
+    T::Protocol: DynProtocol,
+    T::With: Send,
+{
+    fn local_send_boxed_msg_with(
+        &self,
+        msg: BoxedMsg<Self::With>,
+    ) -> LocalBoxFuture<'_, Result<(), DynSendError<BoxedMsg<Self::With>>>> {
+        Box::pin(async move {
+            let (protocol, with) = <T::Protocol as DynProtocol>::try_from_boxed_msg(msg)
+                .map_err(DynSendError::NotAccepted)?;
+
+            T::send_protocol_with(self, protocol, with).await.map_err(
+                |SendError((protocol, with))| DynSendError::Closed(protocol.into_boxed_msg(with)),
+            )
+        })
+    }
+
+    fn local_try_send_boxed_msg_with(
+        &self,
+        msg: BoxedMsg<Self::With>,
+    ) -> Result<(), DynTrySendError<BoxedMsg<Self::With>>> {
+        let (protocol, with) =
+            T::Protocol::try_from_boxed_msg(msg).map_err(DynTrySendError::NotAccepted)?;
+
+        T::try_send_protocol_with(self, protocol, with).map_err(|e| match e {
+            TrySendError::Closed((protocol, with)) => {
+                DynTrySendError::Closed(protocol.into_boxed_msg(with))
+            }
+            TrySendError::Full((protocol, with)) => {
+                DynTrySendError::Full(protocol.into_boxed_msg(with))
+            }
+        })
+    }
+
+    fn local_members(&self) -> &'static [TypeId] {
+        <T::Protocol as Members>::members()
+    }
+
+    fn local_clone_boxed(&self) -> Box<dyn IsLocalDynSender<With = Self::With>> {
+        Box::new(self.clone())
+    }
+
+    fn local_as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl<W: 'static> IsLocalDynSender for Box<dyn IsDynSender<With = W>> {
+    fn local_send_boxed_msg_with(
+        &self,
+        msg: BoxedMsg<Self::With>,
+    ) -> LocalBoxFuture<'_, Result<(), DynSendError<BoxedMsg<Self::With>>>> {
+        let fut: BoxFuture<'_, _> = (**self).dyn_send_boxed_msg_with(msg);
+        fut
+    }
+
+    fn local_try_send_boxed_msg_with(
+        &self,
+        msg: BoxedMsg<Self::With>,
+    ) -> Result<(), DynTrySendError<BoxedMsg<Self::With>>> {
+        (**self).dyn_try_send_boxed_msg_with(msg)
+    }
+
+    fn local_members(&self) -> &'static [TypeId] {
+        (**self).members()
+    }
+
+    fn local_clone_boxed(&self) -> Box<dyn IsLocalDynSender<With = Self::With>> {
+        Box::new(self.clone())
+    }
+
+    fn local_as_any(&self) -> &dyn Any {
+        (**self).as_any()
+    }
+}
+
+impl<W: 'static> Clone for Box<dyn IsLocalDynSender<With = W>> {
+    fn clone(&self) -> Self {
+        (**self).local_clone_boxed()
+    }
+}
+
+/// Like [`struct@DynSender`], but wraps a [`Box<dyn IsLocalDynSender>`](IsLocalDynSender), which
+/// does not have to be `Send`.
+///
+/// Since the futures of a [`struct@LocalDynSender`] are not `Send`, it does not implement
+/// [`Sends<M>`]. Instead, messages accepted by the protocol can be sent with the inherent `send`
+/// methods, which panic if the message is not accepted. Other messages can be sent with the
+/// `local_{...}`-send methods of [`IsLocalDynSenderExt`].
+pub struct LocalDynSender<T, W = ()> {
+    sender: Box<dyn IsLocalDynSender<With = W>>,
+    t: PhantomData<fn() -> T>,
+}
+
+impl<T, W> LocalDynSender<T, W> {
+    /// Create a new `LocalDynSender` from a statically typed sender.
+    pub fn new<S>(sender: S) -> Self
+    where
+        S: IsStaticSender + IsLocalDynSender<With = W>,
+        T: SubsetOf<S::Protocol>,
+    {
+        Self::new_unchecked(sender)
+    }
+
+    /// Create a new `LocalDynSender` from a sender, without checking if the protocol accepts
+    /// the messages.
+    pub fn new_unchecked<S>(sender: S) -> Self
+    where
+        S: IsLocalDynSender<With = W>,
+    {
+        Self::from_inner_unchecked(Box::new(sender))
+    }
+
+    /// Transform the `LocalDynSender` into one that accepts a subset of the messages.
+    pub fn transform<R>(self) -> LocalDynSender<R, W>
+    where
+        R: SubsetOf<T>,
+    {
+        LocalDynSender::from_inner_unchecked(self.sender)
+    }
+
+    /// Convert a [`Box<dyn IsLocalDynSender>`](IsLocalDynSender) into a `LocalDynSender`,
+    /// without checking if the protocol accepts the messages.
+    pub fn from_inner_unchecked(sender: Box<dyn IsLocalDynSender<With = W>>) -> Self {
+        Self {
+            sender,
+            t: PhantomData,
+        }
+    }
+
+    /// Convert into a [`Box<dyn IsLocalDynSender>`](IsLocalDynSender).
+    pub fn into_inner(self) -> Box<dyn IsLocalDynSender<With = W>> {
+        self.sender
+    }
+
+    /// Downcast the inner sender to a statically typed sender.
+    pub fn downcast_ref<S>(&self) -> Option<&S>
+    where
+        S: IsSender<With = W> + 'static,
+        W: 'static,
+    {
+        self.sender.local_as_any().downcast_ref::<S>()
+    }
+
+    /// Send a message accepted by the protocol, waiting asynchronously until space becomes
+    /// available.
+    pub async fn send_msg_with<M>(&self, msg: M, with: W) -> Result<(), SendError<(M, W)>>
+    where
+        T: Contains<M>,
+        M: Send + 'static,
+        W: Send + 'static,
+    {
+        match self.sender.local_send_msg_with(msg, with).await {
+            Ok(()) => Ok(()),
+            Err(DynSendError::NotAccepted(_)) => {
+                panic!("Message not accepted: {}", type_name::<(M, W)>())
+            }
+            Err(DynSendError::Closed(msg)) => Err(SendError(msg)),
+        }
+    }
+
+    /// Send a message accepted by the protocol, failing if the channel is full.
+    pub fn try_send_msg_with<M>(&self, msg: M, with: W) -> Result<(), TrySendError<(M, W)>>
+    where
+        T: Contains<M>,
+        M: Send + 'static,
+        W: Send + 'static,
+    {
+        match self.sender.local_try_send_msg_with(msg, with) {
+            Ok(()) => Ok(()),
+            Err(DynTrySendError::NotAccepted(_)) => {
+                panic!("Message not accepted: {}", type_name::<(M, W)>())
+            }
+            Err(DynTrySendError::Closed(msg)) => Err(TrySendError::Closed(msg)),
+            Err(DynTrySendError::Full(msg)) => Err(TrySendError::Full(msg)),
+        }
+    }
+
+    /// Like [`LocalDynSender::send_msg_with`], but creates the message from its input and
+    /// returns its output.
+    pub async fn send<M>(&self, msg: impl Into<M::Input>) -> Result<M::Output, SendError<M::Input>>
+    where
+        T: Contains<M>,
+        M: Message + Send + 'static,
+        W: Default + Send + 'static,
+    {
+        let (msg, output) = M::create(msg.into());
+        match self.send_msg_with(msg, W::default()).await {
+            Ok(()) => Ok(output),
+            Err(SendError((msg, _))) => Err(SendError(msg.cancel(output))),
+        }
+    }
+
+    /// Like [`LocalDynSender::try_send_msg_with`], but creates the message from its input and
+    /// returns its output.
+    pub fn try_send<M>(&self, msg: impl Into<M::Input>) -> Result<M::Output, TrySendError<M::Input>>
+    where
+        T: Contains<M>,
+        M: Message + Send + 'static,
+        W: Default + Send + 'static,
+    {
+        let (msg, output) = M::create(msg.into());
+        match self.try_send_msg_with(msg, W::default()) {
+            Ok(()) => Ok(output),
+            Err(e) => Err(e.map(|(msg, _)| msg.cancel(output))),
+        }
+    }
+}
+
+impl<T, W> IsSender for LocalDynSender<T, W> {
+    type With = W;
+
+    fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        self.sender.capacity()
+    }
+
+    fn len(&self) -> usize {
+        self.sender.len()
+    }
+
+    fn receiver_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+
+    fn sender_count(&self) -> usize {
+        self.sender.sender_count()
+    }
+
+    fn is_full(&self) -> bool {
+        self.sender.is_full()
+    }
+
+    fn remaining(&self) -> Option<usize> {
+        self.sender.remaining()
+    }
+
+    fn stats(&self) -> ChannelStats {
+        self.sender.stats()
+    }
+}
+
+impl<T, W> IsLocalDynSender for LocalDynSender<T, W>
+where
+    T: 'static,
+    W: 'static,
+{
+    fn local_send_boxed_msg_with(
+        &self,
+        msg: BoxedMsg<Self::With>,
+    ) -> LocalBoxFuture<'_, Result<(), DynSendError<BoxedMsg<Self::With>>>> {
+        self.sender.local_send_boxed_msg_with(msg)
+    }
+
+    fn local_try_send_boxed_msg_with(
+        &self,
+        msg: BoxedMsg<Self::With>,
+    ) -> Result<(), DynTrySendError<BoxedMsg<Self::With>>> {
+        self.sender.local_try_send_boxed_msg_with(msg)
+    }
+
+    fn local_members(&self) -> &'static [TypeId] {
+        self.sender.local_members()
+    }
+
+    fn local_clone_boxed(&self) -> Box<dyn IsLocalDynSender<With = Self::With>> {
+        self.sender.local_clone_boxed()
+    }
+
+    fn local_as_any(&self) -> &dyn Any {
+        self.sender.local_as_any()
+    }
+}
+
+impl<T, W: 'static> From<DynSender<T, W>> for LocalDynSender<T, W> {
+    fn from(sender: DynSender<T, W>) -> Self {
+        Self::from_inner_unchecked(Box::new(sender.into_inner()))
+    }
+}
+
+impl<T, W> Debug for LocalDynSender<T, W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LocalDynSender")
+            .field("sender", &self.sender)
+            .field("accepts", &type_name::<T>())
+            .finish()
+    }
+}
+
+impl<T, W: 'static> Clone for LocalDynSender<T, W> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            t: PhantomData,
+        }
+    }
+}
+
+/// Extension trait for [`IsLocalDynSender`], the local counterpart of [`IsDynSenderExt`].
+///
+/// The returned futures are not `Send`, and can only be awaited on a single-threaded executor.
+pub trait IsLocalDynSenderExt: IsLocalDynSender {
+    /// Check if the sender accepts the message `M`.
+    fn local_accepts_msg<M: 'static>(&self) -> bool {
+        self.local_members().contains(&TypeId::of::<M>())
+    }
+
+    /// Convert the sender into a boxed sender.
+    fn local_boxed(self) -> Box<dyn IsLocalDynSender<With = Self::With>>
+    where
+        Self: Sized,
+    {
+        Box::new(self)
+    }
+
+    /// Like [`IsDynSenderExt::dyn_send_msg_with`], but the future is not `Send`.
+    fn local_send_msg_with<M>(
+        &self,
+        msg: M,
+        with: Self::With,
+    ) -> impl Future<Output = Result<(), DynSendError<(M, Self::With)>>>
+    where
+        M: Send + 'static,
+        Self::With: Send + 'static,
+    {
+        let fut = self.local_send_boxed_msg_with(BoxedMsg::new(msg, with));
+        async {
+            match fut.await {
+                Ok(()) => Ok(()),
+                Err(e) => Err(e.downcast::<M>().unwrap_silent()),
+            }
+        }
+    }
+
+    /// Like [`IsDynSenderExt::dyn_try_send_msg_with`].
+    fn local_try_send_msg_with<M>(
+        &self,
+        msg: M,
+        with: Self::With,
+    ) -> Result<(), DynTrySendError<(M, Self::With)>>
+    where
+        M: Send + 'static,
+        Self::With: Send + 'static,
+    {
+        match self.local_try_send_boxed_msg_with(BoxedMsg::new(msg, with)) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(e.downcast::<M>().unwrap_silent()),
+        }
+    }
+
+    /// Like [`IsDynSenderExt::dyn_send_msg`], but the future is not `Send`.
+    fn local_send_msg<M>(&self, msg: M) -> impl Future<Output = Result<(), DynSendError<M>>>
+    where
+        M: Send + 'static,
+        Self::With: Default + Send + 'static,
+    {
+        let fut = self.local_send_msg_with(msg, Default::default());
+        async {
+            match fut.await {
+                Ok(()) => Ok(()),
+                Err(e) => Err(e.map(|(t, _)| t)),
+            }
+        }
+    }
+
+    /// Like [`IsDynSenderExt::dyn_try_send_msg`].
+    fn local_try_send_msg<M>(&self, msg: M) -> Result<(), DynTrySendError<M>>
+    where
+        M: Send + 'static,
+        Self::With: Default + Send + 'static,
+    {
+        match self.local_try_send_msg_with(msg, Default::default()) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(e.map(|(t, _)| t)),
+        }
+    }
+
+    /// Like [`IsDynSenderExt::dyn_send`], but the future is not `Send`.
+    fn local_send<M>(
+        &self,
+        msg: impl Into<M::Input>,
+    ) -> impl Future<Output = Result<M::Output, DynSendError<M::Input>>>
+    where
+        M: Message + Send + 'static,
+        Self::With: Default + Send + 'static,
+    {
+        let (msg, output) = M::create(msg.into());
+        let fut = self.local_send_msg(msg);
+        async {
+            match fut.await {
+                Ok(()) => Ok(output),
+                Err(e) => Err(e.map(|t| t.cancel(output))),
+            }
+        }
+    }
+
+    /// Like [`IsDynSenderExt::dyn_try_send`].
+    fn local_try_send<M>(
+        &self,
+        msg: impl Into<M::Input>,
+    ) -> Result<M::Output, DynTrySendError<M::Input>>
+    where
+        M: Message + Send + 'static,
+        Self::With: Default + Send + 'static,
+    {
+        let (msg, output) = M::create(msg.into());
+        match self.local_try_send_msg(msg) {
+            Ok(()) => Ok(output),
+            Err(e) => Err(e.map(|t| t.cancel(output))),
+        }
+    }
+
+    /// Like [`IsDynSenderExt::dyn_request`], but the future is not `Send`.
+    fn local_request<M>(
+        &self,
+        msg: impl Into<M::Input>,
+    ) -> impl Future<
+        Output = Result<
+            <M::Output as ResultFuture>::Ok,
+            DynRequestError<M::Input, <M::Output as ResultFuture>::Error>,
+        >,
+    >
+    where
+        M: Message + Send + 'static,
+        Self::With: Default + Send + 'static,
+        M::Output: ResultFuture,
+    {
+        let fut = self.local_send::<M>(msg);
+        async {
+            let rx = fut.await?;
+            rx.await.map_err(DynRequestError::NoReply)
+        }
+    }
+}
+impl<T> IsLocalDynSenderExt for T where T: IsLocalDynSender + ?Sized {}
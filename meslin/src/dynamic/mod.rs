@@ -7,12 +7,40 @@ pub use dyn_protocol::*;
 mod dyn_sender;
 pub use dyn_sender::*;
 
+mod erased;
+pub use erased::*;
+
 mod errors;
 pub use errors::*;
 
 mod into_dyn;
 pub use into_dyn::*;
 
+mod local;
+pub use local::*;
+
+mod protocol_info;
+pub use protocol_info::*;
+
+mod union;
+pub use union::*;
+
+mod demux;
+pub use demux::*;
+
+mod reply_to;
+pub use reply_to::*;
+
+#[cfg(feature = "serde")]
+mod registry;
+#[cfg(feature = "serde")]
+pub use registry::*;
+
+#[cfg(feature = "serde")]
+mod wire_format;
+#[cfg(feature = "serde")]
+pub use wire_format::*;
+
 /// Re-export of [`type_sets`](::type_sets).
 pub use type_sets;
 pub use type_sets::Set;
\ No newline at end of file
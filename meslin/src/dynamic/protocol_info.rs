@@ -0,0 +1,82 @@
+use crate::*;
+use std::any::{type_name, TypeId};
+
+/// Runtime description of a protocol, listing the messages it accepts.
+///
+/// This is generated by [`derive@DynProtocol`] and [`union_protocol!`], and can be retrieved
+/// with [`DynProtocol::protocol_info`] or [`IsDynSender::protocol_info`]. It is meant for
+/// runtime tooling, like debug endpoints or code generation.
+///
+/// ```
+/// # use meslin::*;
+/// #[derive(Debug, From, TryInto, DynProtocol)]
+/// enum Protocol {
+///     A(u32),
+///     B(Request<String, u64>),
+/// }
+///
+/// let info = Protocol::protocol_info();
+/// assert_eq!(info.messages.len(), 2);
+/// assert!(!info.message::<u32>().unwrap().request);
+/// assert!(info.message::<Request<String, u64>>().unwrap().request);
+///
+/// let (sender, _receiver) = mpmc::unbounded::<Protocol>();
+/// let sender: DynSender![u32] = sender.into_dyn_sender();
+/// assert_eq!(sender.protocol_info(), Some(info));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtocolInfo {
+    /// The type name of the protocol.
+    pub name: &'static str,
+    /// The messages that are accepted by the protocol, including those of flattened protocols.
+    pub messages: Vec<MessageInfo>,
+}
+
+impl ProtocolInfo {
+    /// Create the info of protocol `P`, which accepts the given messages.
+    pub fn new<P: ?Sized>(messages: Vec<MessageInfo>) -> Self {
+        Self {
+            name: type_name::<P>(),
+            messages,
+        }
+    }
+
+    /// Get the info of the message `M`, if it is accepted.
+    pub fn message<M: 'static>(&self) -> Option<&MessageInfo> {
+        self.message_by_type_id(TypeId::of::<M>())
+    }
+
+    /// Get the info of the message with the given [`TypeId`], if it is accepted.
+    pub fn message_by_type_id(&self, type_id: TypeId) -> Option<&MessageInfo> {
+        self.messages.iter().find(|info| info.type_id == type_id)
+    }
+}
+
+/// Runtime description of a [`Message`], as part of a [`ProtocolInfo`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MessageInfo {
+    /// The type name of the message.
+    pub name: &'static str,
+    /// The [`TypeId`] of the message.
+    pub type_id: TypeId,
+    /// The type name of [`Message::Input`].
+    pub input: &'static str,
+    /// The type name of [`Message::Output`].
+    pub output: &'static str,
+    /// Whether the message returns an output when sent, like a request, instead of being
+    /// fire-and-forget.
+    pub request: bool,
+}
+
+impl MessageInfo {
+    /// Create the info of the message `M`.
+    pub fn of<M: Message + 'static>() -> Self {
+        Self {
+            name: type_name::<M>(),
+            type_id: TypeId::of::<M>(),
+            input: type_name::<M::Input>(),
+            output: type_name::<M::Output>(),
+            request: type_name::<M::Output>() != type_name::<()>(),
+        }
+    }
+}
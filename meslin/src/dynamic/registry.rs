@@ -0,0 +1,355 @@
+use crate::*;
+use futures::future::BoxFuture;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    any::{type_name, TypeId},
+    collections::{BTreeSet, HashMap},
+    fmt::Debug,
+    marker::PhantomData,
+    sync::{Mutex, OnceLock},
+};
+use thiserror::Error;
+
+/// A [`Message`] that can be serialized, tagged by a stable name.
+///
+/// The name is used to identify the message when it is deserialized, and should therefore not
+/// change between versions of a program.
+pub trait SerializableMessage: Serialize + DeserializeOwned + Send + 'static {
+    /// The stable name of the message.
+    const NAME: &'static str;
+
+    /// The version of the message, which should be incremented whenever its serialized
+    /// representation changes incompatibly.
+    const VERSION: u32 = 0;
+}
+
+/// A registry of [`SerializableMessage`]s, which allows serialization of a [`BoxedMsg`] to
+/// bytes, and deserialization back into a [`BoxedMsg`].
+///
+/// The `with`-value is serialized along with the message, using the [`WireFormat`] `F`.
+///
+/// ```
+/// # use meslin::*;
+/// # use serde::{Serialize, Deserialize};
+/// #[derive(Debug, Serialize, Deserialize, PartialEq)]
+/// struct Ping(u32);
+///
+/// impl SerializableMessage for Ping {
+///     const NAME: &'static str = "ping";
+/// }
+///
+/// let registry = MessageRegistry::<()>::new().with::<Ping>();
+/// let bytes = registry.serialize(&BoxedMsg::new(Ping(10), ())).unwrap();
+/// let msg = registry.deserialize(&bytes).unwrap();
+/// assert_eq!(msg.downcast::<Ping>().unwrap(), (Ping(10), ()));
+/// ```
+pub struct MessageRegistry<W = (), F = Bincode> {
+    entries: HashMap<&'static str, Entry<W>>,
+    names: HashMap<TypeId, &'static str>,
+    members: BTreeSet<TypeId>,
+    leaked_members: OnceLock<&'static [TypeId]>,
+    interned_members: Mutex<HashMap<BTreeSet<TypeId>, &'static [TypeId]>>,
+    format: PhantomData<fn() -> F>,
+}
+
+struct Entry<W> {
+    type_name: &'static str,
+    type_id: TypeId,
+    version: u32,
+    serialize: fn(&BoxedMsg<W>) -> Option<Result<Vec<u8>, WireError>>,
+    deserialize: fn(&[u8]) -> Result<(BoxedMsg<W>, Option<ReplyFuture>), WireError>,
+    take_reply: Option<fn(BoxedMsg<W>) -> Option<ReplyHandler>>,
+}
+
+/// Resolves to the serialized reply of a deserialized request, or `None` if no reply was sent.
+pub(crate) type ReplyFuture = BoxFuture<'static, Option<Result<Vec<u8>, WireError>>>;
+
+/// Completes a serialized request by deserializing and sending its reply.
+pub(crate) type ReplyHandler = Box<dyn FnOnce(&[u8]) + Send>;
+
+#[derive(Serialize, Deserialize)]
+struct Tagged<'a> {
+    name: &'a str,
+    version: u32,
+    #[serde(with = "serde_bytes_compat")]
+    payload: &'a [u8],
+}
+
+impl<W> MessageRegistry<W>
+where
+    W: Serialize + DeserializeOwned + Send + 'static,
+{
+    /// Create a new registry that uses the [`Bincode`] format.
+    pub fn new() -> Self {
+        Self::with_format(Bincode)
+    }
+}
+
+impl<W, F> MessageRegistry<W, F>
+where
+    W: Serialize + DeserializeOwned + Send + 'static,
+    F: WireFormat,
+{
+    /// Create a new registry that uses the given [`WireFormat`].
+    pub fn with_format(_format: F) -> Self {
+        Self {
+            entries: HashMap::new(),
+            names: HashMap::new(),
+            members: BTreeSet::new(),
+            leaked_members: OnceLock::new(),
+            interned_members: Mutex::new(HashMap::new()),
+            format: PhantomData,
+        }
+    }
+
+    /// Register the message `M`, panicking if another message with the same name is already
+    /// registered.
+    pub fn register<M: SerializableMessage>(&mut self) -> &mut Self {
+        self.insert::<M>(
+            M::NAME,
+            Entry {
+                type_name: type_name::<M>(),
+                type_id: TypeId::of::<M>(),
+                version: M::VERSION,
+                serialize: |msg| msg.downcast_ref::<M>().map(|msg| F::serialize(&msg)),
+                deserialize: |bytes| {
+                    let (msg, with) = F::deserialize::<(M, W)>(bytes)?;
+                    Ok((BoxedMsg::new(msg, with), None))
+                },
+                take_reply: None,
+            },
+        )
+    }
+
+    /// Register the request [`Request<A, B>`] under the given name, panicking if another
+    /// message with the same name is already registered.
+    ///
+    /// Only the input `A` is serialized. When a request is deserialized, a new reply channel is
+    /// created, of which the reply can be forwarded by a remote transport. Requests are always
+    /// registered with version `0`; an incompatible change requires a new name.
+    #[cfg(feature = "request")]
+    pub fn register_request<A, B>(&mut self, name: &'static str) -> &mut Self
+    where
+        A: Serialize + DeserializeOwned + Send + 'static,
+        B: Serialize + DeserializeOwned + Send + 'static,
+    {
+        self.insert::<Request<A, B>>(
+            name,
+            Entry {
+                type_name: type_name::<Request<A, B>>(),
+                type_id: TypeId::of::<Request<A, B>>(),
+                version: 0,
+                serialize: |msg| {
+                    msg.downcast_ref::<Request<A, B>>()
+                        .map(|(request, with)| F::serialize(&(&request.msg, with)))
+                },
+                deserialize: |bytes| {
+                    let (msg, with) = F::deserialize::<(A, W)>(bytes)?;
+                    let (request, rx) = Request::<A, B>::new(msg);
+                    let reply: ReplyFuture =
+                        Box::pin(async move { rx.await.ok().map(|b| F::serialize(&b)) });
+                    Ok((BoxedMsg::new(request, with), Some(reply)))
+                },
+                take_reply: Some(|msg| {
+                    let (request, _) = msg.downcast::<Request<A, B>>().ok()?;
+                    Some(Box::new(move |bytes: &[u8]| {
+                        if let Ok(reply) = F::deserialize::<B>(bytes) {
+                            let _ = request.tx.send(reply);
+                        }
+                    }))
+                }),
+            },
+        )
+    }
+
+    /// Like [`MessageRegistry::register_request`], but takes and returns the registry by value.
+    #[cfg(feature = "request")]
+    pub fn with_request<A, B>(mut self, name: &'static str) -> Self
+    where
+        A: Serialize + DeserializeOwned + Send + 'static,
+        B: Serialize + DeserializeOwned + Send + 'static,
+    {
+        self.register_request::<A, B>(name);
+        self
+    }
+
+    fn insert<M: Send + 'static>(&mut self, name: &'static str, entry: Entry<W>) -> &mut Self {
+        if let Some(entry) = self.entries.insert(name, entry) {
+            panic!(
+                "Message name {:?} of {} is already registered by {}",
+                name,
+                type_name::<M>(),
+                entry.type_name
+            )
+        }
+        self.names.insert(TypeId::of::<M>(), name);
+        self.members.insert(TypeId::of::<M>());
+        self
+    }
+
+    /// Like [`MessageRegistry::register`], but takes and returns the registry by value.
+    pub fn with<M: SerializableMessage>(mut self) -> Self {
+        self.register::<M>();
+        self
+    }
+
+    /// Returns `true` if a message with the given name is registered.
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.contains_key(name)
+    }
+
+    /// Returns the name of the given message, if it is registered.
+    pub fn name_of(&self, msg: &BoxedMsg<W>) -> Option<&'static str> {
+        self.names.get(&msg.type_id()).copied()
+    }
+
+    /// Returns the names of all registered messages.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.entries.keys().copied()
+    }
+
+    /// Returns the version of the message with the given name, if it is registered.
+    pub fn version_of(&self, name: &str) -> Option<u32> {
+        self.entries.get(name).map(|entry| entry.version)
+    }
+
+    /// Returns the names and versions of all registered messages.
+    pub fn versions(&self) -> impl Iterator<Item = (&'static str, u32)> + '_ {
+        self.entries
+            .iter()
+            .map(|(name, entry)| (*name, entry.version))
+    }
+
+    /// Serialize the message and its `with`-value, tagged by the name of the message.
+    pub fn serialize(&self, msg: &BoxedMsg<W>) -> Result<Vec<u8>, SerializeError> {
+        let name = self.name_of(msg).ok_or(SerializeError::NotRegistered)?;
+        let payload = (self.entries[name].serialize)(msg)
+            .ok_or(SerializeError::NotRegistered)?
+            .map_err(SerializeError::Encode)?;
+        F::serialize(&Tagged {
+            name,
+            version: self.entries[name].version,
+            payload: &payload,
+        })
+        .map_err(SerializeError::Encode)
+    }
+
+    /// Deserialize a message that was serialized with [`MessageRegistry::serialize`].
+    pub fn deserialize(&self, bytes: &[u8]) -> Result<BoxedMsg<W>, DeserializeError> {
+        self.deserialize_with_reply(bytes).map(|(msg, _)| msg)
+    }
+
+    /// The [`TypeId`]s of all registered messages.
+    ///
+    /// The slice is leaked once per registry, since [`IsDynSender::members`] requires a
+    /// `'static` lifetime.
+    pub fn members(&self) -> &'static [TypeId] {
+        self.leaked_members
+            .get_or_init(|| Vec::leak(self.members.iter().copied().collect()))
+    }
+
+    /// The [`TypeId`]s of the registered messages with the given names.
+    ///
+    /// Like [`MessageRegistry::members`], the slice is leaked, but only once per distinct set
+    /// of messages, so this can be called repeatedly, e.g. on every reconnect.
+    pub(crate) fn interned_members<'a>(
+        &self,
+        names: impl IntoIterator<Item = &'a str>,
+    ) -> &'static [TypeId] {
+        let set = names
+            .into_iter()
+            .filter_map(|name| self.type_id_of(name))
+            .collect::<BTreeSet<_>>();
+        let mut interned = self.interned_members.lock().unwrap();
+        interned
+            .entry(set)
+            .or_insert_with_key(|set| Vec::leak(set.iter().copied().collect()))
+    }
+
+    /// Like [`MessageRegistry::deserialize`], but also returns the reply of a request.
+    pub(crate) fn deserialize_with_reply(
+        &self,
+        bytes: &[u8],
+    ) -> Result<(BoxedMsg<W>, Option<ReplyFuture>), DeserializeError> {
+        let tagged = F::deserialize::<Tagged>(bytes).map_err(DeserializeError::Decode)?;
+        let entry = self
+            .entries
+            .get(tagged.name)
+            .ok_or_else(|| DeserializeError::NotRegistered(tagged.name.to_string()))?;
+        if entry.version != tagged.version {
+            return Err(DeserializeError::Incompatible {
+                name: tagged.name.to_string(),
+                version: tagged.version,
+                expected: entry.version,
+            });
+        }
+        (entry.deserialize)(tagged.payload).map_err(DeserializeError::Decode)
+    }
+
+    /// Returns the [`TypeId`] of the message with the given name, if it is registered.
+    pub(crate) fn type_id_of(&self, name: &str) -> Option<TypeId> {
+        self.entries.get(name).map(|entry| entry.type_id)
+    }
+
+    /// Take the reply-sender out of a request, returning `None` if the message is not a
+    /// registered request.
+    pub(crate) fn take_reply(&self, msg: BoxedMsg<W>) -> Option<ReplyHandler> {
+        let name = self.name_of(&msg)?;
+        (self.entries[name].take_reply?)(msg)
+    }
+}
+
+impl<W> Default for MessageRegistry<W>
+where
+    W: Serialize + DeserializeOwned + Send + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<W, F: WireFormat> Debug for MessageRegistry<W, F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MessageRegistry")
+            .field("format", &type_name::<F>())
+            .field("messages", &self.entries.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// Error that is returned when a message could not be serialized.
+#[derive(Debug, Error)]
+pub enum SerializeError {
+    #[error("Message is not registered.")]
+    NotRegistered,
+    #[error("Failed to encode message: {0}")]
+    Encode(#[source] WireError),
+}
+
+/// Error that is returned when a message could not be deserialized.
+#[derive(Debug, Error)]
+pub enum DeserializeError {
+    #[error("Message {0:?} is not registered.")]
+    NotRegistered(String),
+    #[error("Message {name:?} has version {version}, but version {expected} is registered.")]
+    Incompatible {
+        name: String,
+        version: u32,
+        expected: u32,
+    },
+    #[error("Failed to decode message: {0}")]
+    Decode(#[source] WireError),
+}
+
+/// Serializes a byte-slice as bytes instead of as a sequence.
+mod serde_bytes_compat {
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &&[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(bytes)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<&'de [u8], D::Error> {
+        <&[u8] as serde::Deserialize>::deserialize(deserializer)
+    }
+}
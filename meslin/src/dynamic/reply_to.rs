@@ -0,0 +1,96 @@
+use crate::*;
+use std::{fmt::Debug, future::Future};
+
+/// A message that carries a payload `M` together with a [`DynSender`] to reply to with `R`.
+///
+/// This is an alternative to [`Request`] for when replies should be received by the inbox of
+/// the sender, instead of by a oneshot channel. The reply address is given when sending:
+///
+/// ```
+/// # use meslin::*;
+/// #[derive(Debug, From, TryInto, DynProtocol)]
+/// enum ClientProtocol {
+///     Reply(String),
+/// }
+///
+/// # futures::executor::block_on(async {
+/// let (sender, receiver) = mpmc::unbounded::<ReplyTo<u32, String>>();
+/// let (reply_sender, reply_receiver) = mpmc::unbounded::<ClientProtocol>();
+///
+/// sender
+///     .send::<ReplyTo<u32, String>>((10u32, reply_sender.into_dyn_sender()))
+///     .await
+///     .unwrap();
+///
+/// let msg = receiver.recv_async().await.unwrap();
+/// let reply = (msg.msg * 2).to_string();
+/// msg.reply(reply).await.unwrap();
+/// let ClientProtocol::Reply(reply) = reply_receiver.recv_async().await.unwrap();
+/// assert_eq!(reply, "20");
+/// # });
+/// ```
+pub struct ReplyTo<M, R> {
+    pub msg: M,
+    pub reply_to: DynSender![R],
+}
+
+impl<M, R> ReplyTo<M, R> {
+    pub fn new(msg: M, reply_to: DynSender![R]) -> Self {
+        Self { msg, reply_to }
+    }
+
+    pub fn into_parts(self) -> (M, DynSender![R]) {
+        (self.msg, self.reply_to)
+    }
+
+    /// Send the reply to the reply address.
+    pub fn reply(&self, reply: R) -> impl Future<Output = Result<(), SendError<R>>> + Send + '_
+    where
+        R: Message + Send + 'static,
+    {
+        self.reply_to.send_msg(reply)
+    }
+
+    /// Send the reply to the reply address, failing if it is full.
+    pub fn try_reply(&self, reply: R) -> Result<(), TrySendError<R>>
+    where
+        R: Message + Send + 'static,
+    {
+        self.reply_to.try_send_msg(reply)
+    }
+}
+
+impl<M, R> Message for ReplyTo<M, R>
+where
+    M: Send + 'static,
+    R: 'static,
+{
+    type Input = (M, DynSender![R]);
+    type Output = ();
+
+    fn create((msg, reply_to): Self::Input) -> (Self, Self::Output) {
+        (Self { msg, reply_to }, ())
+    }
+
+    fn cancel(self, _: Self::Output) -> Self::Input {
+        self.into_parts()
+    }
+}
+
+impl<M: Debug, R> Debug for ReplyTo<M, R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReplyTo")
+            .field("msg", &self.msg)
+            .field("reply_to", &self.reply_to)
+            .finish()
+    }
+}
+
+impl<M: Clone, R: 'static> Clone for ReplyTo<M, R> {
+    fn clone(&self) -> Self {
+        Self {
+            msg: self.msg.clone(),
+            reply_to: self.reply_to.clone(),
+        }
+    }
+}
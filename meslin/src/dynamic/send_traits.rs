@@ -6,14 +6,48 @@ use std::{
     fmt::Debug,
 };
 
+/// `Send` on all targets except `wasm32`, where every task runs on the same thread.
+///
+/// This is a supertrait of [`IsDynSender`], so that senders that are `!Send`, for example because
+/// they are shared through an `Rc`, can be used as a [`struct@DynSender`] on `wasm32` targets.
+#[cfg(not(target_arch = "wasm32"))]
+pub trait MaybeSend: Send {}
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Send + ?Sized> MaybeSend for T {}
+
+/// `Send` on all targets except `wasm32`, where every task runs on the same thread.
+///
+/// This is a supertrait of [`IsDynSender`], so that senders that are `!Send`, for example because
+/// they are shared through an `Rc`, can be used as a [`struct@DynSender`] on `wasm32` targets.
+#[cfg(target_arch = "wasm32")]
+pub trait MaybeSend {}
+#[cfg(target_arch = "wasm32")]
+impl<T: ?Sized> MaybeSend for T {}
+
+/// `Sync` on all targets except `wasm32`, see [`MaybeSend`].
+#[cfg(not(target_arch = "wasm32"))]
+pub trait MaybeSync: Sync {}
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Sync + ?Sized> MaybeSync for T {}
+
+/// `Sync` on all targets except `wasm32`, see [`MaybeSend`].
+#[cfg(target_arch = "wasm32")]
+pub trait MaybeSync {}
+#[cfg(target_arch = "wasm32")]
+impl<T: ?Sized> MaybeSync for T {}
+
 /// Automatically implemented when [`IsStaticSender`] is implemented for a protocol
 /// that implements [`DynProtocol`].
-pub trait IsDynSender: IsSender + Send + 'static + Debug {
+///
+/// The sender has to be [`MaybeSend`], which is only `Send` on targets that are not `wasm32`.
+/// The futures it returns are `Send` on all targets.
+pub trait IsDynSender: IsSender + MaybeSend + 'static + Debug {
     fn dyn_send_boxed_msg_with(
         &self,
         msg: BoxedMsg<Self::With>,
-    ) -> BoxFuture<Result<(), DynSendError<BoxedMsg<Self::With>>>>;
+    ) -> BoxFuture<'_, Result<(), DynSendError<BoxedMsg<Self::With>>>>;
 
+    #[cfg(blocking)]
     fn dyn_send_boxed_msg_blocking_with(
         &self,
         msg: BoxedMsg<Self::With>,
@@ -26,30 +60,42 @@ pub trait IsDynSender: IsSender + Send + 'static + Debug {
 
     /// Get the message types that the sender accepts.
     fn members(&self) -> &'static [TypeId];
+
+    /// Describe the protocol of the sender, or `None` if it is not known locally.
+    fn protocol_info(&self) -> Option<ProtocolInfo> {
+        None
+    }
+
     fn clone_boxed(&self) -> Box<dyn IsDynSender<With = Self::With>>;
     fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
 }
 
 impl<T> IsDynSender for T
 where
-    T: IsStaticSender + Clone + Send + Sync + 'static + Debug,
+    T: IsStaticSender + Clone + MaybeSend + MaybeSync + 'static + Debug,
     T::Protocol: DynProtocol,
     T::With: Send,
 {
     fn dyn_send_boxed_msg_with(
         &self,
         msg: BoxedMsg<Self::With>,
-    ) -> BoxFuture<Result<(), DynSendError<BoxedMsg<Self::With>>>> {
+    ) -> BoxFuture<'_, Result<(), DynSendError<BoxedMsg<Self::With>>>> {
+        let (protocol, with) = match <T::Protocol as DynProtocol>::try_from_boxed_msg(msg) {
+            Ok(protocol) => protocol,
+            Err(msg) => return Box::pin(async { Err(DynSendError::NotAccepted(msg)) }),
+        };
+        // The future of the sender is `Send`, and doesn't require the sender itself to be `Sync`.
+        let fut = T::send_protocol_with(self, protocol, with);
         Box::pin(async move {
-            let (protocol, with) = <T::Protocol as DynProtocol>::try_from_boxed_msg(msg)
-                .map_err(DynSendError::NotAccepted)?;
-
-            T::send_protocol_with(self, protocol, with).await.map_err(
-                |SendError((protocol, with))| DynSendError::Closed(protocol.into_boxed_msg(with)),
-            )
+            fut.await.map_err(|SendError((protocol, with))| {
+                DynSendError::Closed(protocol.into_boxed_msg(with))
+            })
         })
     }
 
+    #[cfg(blocking)]
     fn dyn_send_boxed_msg_blocking_with(
         &self,
         msg: BoxedMsg<Self::With>,
@@ -83,6 +129,10 @@ where
         <T::Protocol as Members>::members()
     }
 
+    fn protocol_info(&self) -> Option<ProtocolInfo> {
+        Some(<T::Protocol as DynProtocol>::protocol_info())
+    }
+
     fn clone_boxed(&self) -> Box<dyn IsDynSender<With = Self::With>> {
         Box::new(self.clone())
     }
@@ -90,29 +140,13 @@ where
     fn as_any(&self) -> &dyn Any {
         self
     }
-}
-
-impl<W> IsSender for Box<dyn IsDynSender<With = W>> {
-    type With = W;
 
-    fn is_closed(&self) -> bool {
-        (**self).is_closed()
-    }
-
-    fn capacity(&self) -> Option<usize> {
-        (**self).capacity()
-    }
-
-    fn len(&self) -> usize {
-        (**self).len()
-    }
-
-    fn receiver_count(&self) -> usize {
-        (**self).receiver_count()
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
     }
 
-    fn sender_count(&self) -> usize {
-        (**self).sender_count()
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
     }
 }
 
@@ -120,10 +154,11 @@ impl<W: 'static> IsDynSender for Box<dyn IsDynSender<With = W>> {
     fn dyn_send_boxed_msg_with(
         &self,
         msg: BoxedMsg<Self::With>,
-    ) -> BoxFuture<Result<(), DynSendError<BoxedMsg<Self::With>>>> {
+    ) -> BoxFuture<'_, Result<(), DynSendError<BoxedMsg<Self::With>>>> {
         (**self).dyn_send_boxed_msg_with(msg)
     }
 
+    #[cfg(blocking)]
     fn dyn_send_boxed_msg_blocking_with(
         &self,
         msg: BoxedMsg<Self::With>,
@@ -142,6 +177,10 @@ impl<W: 'static> IsDynSender for Box<dyn IsDynSender<With = W>> {
         (**self).members()
     }
 
+    fn protocol_info(&self) -> Option<ProtocolInfo> {
+        (**self).protocol_info()
+    }
+
     fn clone_boxed(&self) -> Box<dyn IsDynSender<With = Self::With>> {
         (**self).clone_boxed()
     }
@@ -149,6 +188,14 @@ impl<W: 'static> IsDynSender for Box<dyn IsDynSender<With = W>> {
     fn as_any(&self) -> &dyn Any {
         (**self).as_any()
     }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        (**self).as_any_mut()
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        (*self).into_any()
+    }
 }
 
 impl<T: 'static> Clone for Box<dyn IsDynSender<With = T>> {
@@ -159,7 +206,7 @@ impl<T: 'static> Clone for Box<dyn IsDynSender<With = T>> {
 
 impl<W, T> From<T> for Box<dyn IsDynSender<With = W>>
 where
-    T: IsStaticSender<With = W> + Clone + Send + Sync + 'static + Debug,
+    T: IsStaticSender<With = W> + Clone + MaybeSend + MaybeSync + 'static + Debug,
     T::Protocol: DynProtocol,
     W: Send + 'static,
 {
@@ -177,15 +224,29 @@ impl<W, T> From<DynSender<T, W>> for Box<dyn IsDynSender<With = W>> {
 /// Extension trait for [`IsDynSender`], providing methods for dynamic dispatch.
 ///
 /// This trait is automatically implemented for any senders that send a protocol which
-/// implements [`DynProtocol`]. It is also implemented for `Box<dyn DynSends>` and [`struct@DynSender`].
-pub trait IsDynSenderExt: IsDynSender + Sized {
+/// implements [`DynProtocol`]. It is also implemented for `Box<dyn DynSends>`, [`struct@DynSender`]
+/// and unsized trait objects like `dyn IsDynSender<With = ()>`.
+pub trait IsDynSenderExt: IsDynSender {
     /// Check if the sender accepts a message.
     fn accepts(&self, msg_id: TypeId) -> bool {
         self.members().contains(&msg_id)
     }
 
+    /// Check if the sender accepts all messages.
+    fn accepts_all(&self, msg_ids: &[TypeId]) -> bool {
+        msg_ids.iter().all(|msg_id| self.accepts(*msg_id))
+    }
+
+    /// Check if the sender accepts the message `M`.
+    fn accepts_msg<M: 'static>(&self) -> bool {
+        self.accepts(TypeId::of::<M>())
+    }
+
     /// Convert the sender into a boxed sender.
-    fn boxed(self) -> Box<dyn IsDynSender<With = Self::With>> {
+    fn boxed(self) -> Box<dyn IsDynSender<With = Self::With>>
+    where
+        Self: Sized,
+    {
         Box::new(self)
     }
 
@@ -209,6 +270,7 @@ pub trait IsDynSenderExt: IsDynSender + Sized {
     }
 
     /// Like [`SendsExt::send_msg_blocking_with`], but fails if the message is not accepted by the protocol.
+    #[cfg(blocking)]
     fn dyn_send_msg_blocking_with<M>(
         &self,
         msg: M,
@@ -256,6 +318,7 @@ pub trait IsDynSenderExt: IsDynSender + Sized {
     }
 
     /// Like [`SendsExt::send_blocking_with`], but fails if the message is not accepted by the protocol.
+    #[cfg(blocking)]
     fn dyn_send_msg_blocking<M>(&self, msg: M) -> Result<(), DynSendError<M>>
     where
         M: Send + 'static,
@@ -280,13 +343,13 @@ pub trait IsDynSenderExt: IsDynSender + Sized {
     }
 
     /// Like [`SendsExt::send_with`], but fails if the message is not accepted by the protocol.
-    fn dyn_send_with<M: Message>(
+    fn dyn_send_with<M>(
         &self,
         msg: impl Into<M::Input>,
         with: Self::With,
     ) -> impl Future<Output = Result<M::Output, DynSendError<(M::Input, Self::With)>>> + Send
     where
-        M: Send + 'static,
+        M: Message + Send + 'static,
         Self::With: Send + 'static,
         M::Output: Send,
     {
@@ -301,13 +364,14 @@ pub trait IsDynSenderExt: IsDynSender + Sized {
     }
 
     /// Like [`SendsExt::send_blocking_with`], but fails if the message is not accepted by the protocol.
-    fn dyn_send_blocking_with<M: Message>(
+    #[cfg(blocking)]
+    fn dyn_send_blocking_with<M>(
         &self,
         msg: impl Into<M::Input>,
         with: Self::With,
     ) -> Result<M::Output, DynSendError<(M::Input, Self::With)>>
     where
-        M: Send + 'static,
+        M: Message + Send + 'static,
         Self::With: Send + 'static,
         M::Output: Send,
     {
@@ -319,13 +383,13 @@ pub trait IsDynSenderExt: IsDynSender + Sized {
     }
 
     /// Like [`SendsExt::try_send_with`], but fails if the message is not accepted by the protocol.
-    fn dyn_try_send_with<M: Message>(
+    fn dyn_try_send_with<M>(
         &self,
         msg: impl Into<M::Input>,
         with: Self::With,
     ) -> Result<M::Output, DynTrySendError<(M::Input, Self::With)>>
     where
-        M: Send + 'static,
+        M: Message + Send + 'static,
         Self::With: Send + 'static,
         M::Output: Send,
     {
@@ -337,12 +401,12 @@ pub trait IsDynSenderExt: IsDynSender + Sized {
     }
 
     /// Like [`SendsExt::send_with`], but fails if the message is not accepted by the protocol.
-    fn dyn_send<M: Message>(
+    fn dyn_send<M>(
         &self,
         msg: impl Into<M::Input>,
     ) -> impl Future<Output = Result<M::Output, DynSendError<M::Input>>> + Send
     where
-        M: Send + 'static,
+        M: Message + Send + 'static,
         Self::With: Default + Send + 'static,
         M::Output: Send,
     {
@@ -356,12 +420,13 @@ pub trait IsDynSenderExt: IsDynSender + Sized {
     }
 
     /// Like [`SendsExt::send_blocking_with`], but fails if the message is not accepted by the protocol.
-    fn dyn_send_blocking<M: Message>(
+    #[cfg(blocking)]
+    fn dyn_send_blocking<M>(
         &self,
         msg: impl Into<M::Input>,
     ) -> Result<M::Output, DynSendError<M::Input>>
     where
-        M: Send + 'static,
+        M: Message + Send + 'static,
         Self::With: Default + Send + 'static,
         M::Output: Send,
     {
@@ -372,12 +437,12 @@ pub trait IsDynSenderExt: IsDynSender + Sized {
     }
 
     /// Like [`SendsExt::try_send_with`], but fails if the message is not accepted by the protocol.
-    fn dyn_try_send<M: Message>(
+    fn dyn_try_send<M>(
         &self,
         msg: impl Into<M::Input>,
     ) -> Result<M::Output, DynTrySendError<M::Input>>
     where
-        M: Send + 'static,
+        M: Message + Send + 'static,
         Self::With: Default + Send + 'static,
         M::Output: Send,
     {
@@ -386,5 +451,54 @@ pub trait IsDynSenderExt: IsDynSender + Sized {
             Err(e) => Err(e.map(|(t, _)| t)),
         }
     }
+
+    /// Like [`IsSenderExt::request_with`], but fails if the message is not accepted by the
+    /// protocol.
+    fn dyn_request_with<M>(
+        &self,
+        msg: impl Into<M::Input>,
+        with: Self::With,
+    ) -> impl Future<
+        Output = Result<
+            <M::Output as ResultFuture>::Ok,
+            DynRequestError<(M::Input, Self::With), <M::Output as ResultFuture>::Error>,
+        >,
+    > + Send
+    where
+        M: Message + Send + 'static,
+        Self::With: Send + 'static,
+        M::Output: ResultFuture + Send,
+    {
+        let fut = self.dyn_send_with::<M>(msg, with);
+        async {
+            let rx = fut.await?;
+            rx.await.map_err(DynRequestError::NoReply)
+        }
+    }
+
+    /// Like [`IsSenderExt::request`], but fails if the message is not accepted by the protocol.
+    fn dyn_request<M>(
+        &self,
+        msg: impl Into<M::Input>,
+    ) -> impl Future<
+        Output = Result<
+            <M::Output as ResultFuture>::Ok,
+            DynRequestError<M::Input, <M::Output as ResultFuture>::Error>,
+        >,
+    > + Send
+    where
+        M: Message + Send + 'static,
+        Self::With: Default + Send + 'static,
+        M::Output: ResultFuture + Send,
+    {
+        let fut = self.dyn_request_with::<M>(msg, Default::default());
+        async {
+            fut.await.map_err(|e| match e {
+                DynRequestError::NotAccepted(e) => DynRequestError::NotAccepted(e.0),
+                DynRequestError::Closed(e) => DynRequestError::Closed(e.0),
+                DynRequestError::NoReply(e) => DynRequestError::NoReply(e),
+            })
+        }
+    }
 }
-impl<T> IsDynSenderExt for T where T: IsDynSender {}
+impl<T> IsDynSenderExt for T where T: IsDynSender + ?Sized {}
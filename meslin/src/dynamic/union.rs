@@ -0,0 +1,158 @@
+use crate::*;
+
+/// A macro that defines a protocol combining multiple existing protocols.
+///
+/// Every variant wraps an inner protocol, followed by the messages it accepts. The union-protocol
+/// implements [`From`] and [`TryFrom`] for the inner protocols and all their messages, forwarding
+/// to the inner protocol. It also implements [`trait@DynProtocol`] and
+/// [`AsSet`](type_sets::AsSet), so the inner protocols must implement [`trait@DynProtocol`].
+///
+/// Example:
+/// ```
+/// # use meslin::*;
+/// #[derive(Debug, From, TryInto, DynProtocol)]
+/// enum ProtoA {
+///     Ping(u32),
+///     Pong(u64),
+/// }
+///
+/// #[derive(Debug, From, TryInto, DynProtocol)]
+/// enum ProtoB {
+///     Stop(()),
+/// }
+///
+/// union_protocol! {
+///     #[derive(Debug)]
+///     enum Both {
+///         A(ProtoA) { u32, u64 },
+///         B(ProtoB) { () },
+///     }
+/// }
+///
+/// let (sender, receiver) = mpmc::unbounded::<Both>();
+/// sender.try_send::<u32>(1u32).unwrap();
+/// sender.try_send::<()>(()).unwrap();
+/// let _: DynSender![u64, ()] = sender.into_dyn_sender();
+///
+/// assert!(matches!(receiver.try_recv().unwrap(), Both::A(ProtoA::Ping(1))));
+/// assert!(matches!(receiver.try_recv().unwrap(), Both::B(ProtoB::Stop(()))));
+/// ```
+#[macro_export]
+macro_rules! union_protocol {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $(
+                $(#[$variant_meta:meta])*
+                $variant:ident($protocol:ty) { $($msg:ty),* $(,)? }
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis enum $name {
+            $(
+                $(#[$variant_meta])*
+                $variant($protocol),
+            )*
+        }
+
+        $(
+            #[automatically_derived]
+            impl ::core::convert::From<$protocol> for $name {
+                fn from(protocol: $protocol) -> Self {
+                    Self::$variant(protocol)
+                }
+            }
+
+            #[automatically_derived]
+            impl ::core::convert::TryFrom<$name> for $protocol {
+                type Error = $name;
+
+                fn try_from(protocol: $name) -> ::core::result::Result<Self, $name> {
+                    #[allow(unreachable_patterns)]
+                    match protocol {
+                        $name::$variant(protocol) => Ok(protocol),
+                        protocol => Err(protocol),
+                    }
+                }
+            }
+
+            $(
+                #[automatically_derived]
+                impl ::core::convert::From<$msg> for $name {
+                    fn from(msg: $msg) -> Self {
+                        Self::$variant(<$protocol as ::core::convert::From<$msg>>::from(msg))
+                    }
+                }
+
+                #[automatically_derived]
+                impl ::core::convert::TryFrom<$name> for $msg {
+                    type Error = $name;
+
+                    fn try_from(protocol: $name) -> ::core::result::Result<Self, $name> {
+                        #[allow(unreachable_patterns)]
+                        match protocol {
+                            $name::$variant(protocol) => {
+                                $crate::__downcast_protocol::<$protocol, $msg>(protocol)
+                                    .map_err($name::$variant)
+                            }
+                            protocol => Err(protocol),
+                        }
+                    }
+                }
+            )*
+        )*
+
+        #[automatically_derived]
+        impl $crate::DynProtocol for $name {
+            fn try_from_boxed_msg<_W: 'static>(
+                msg: $crate::BoxedMsg<_W>,
+            ) -> ::core::result::Result<(Self, _W), $crate::BoxedMsg<_W>> {
+                $(
+                    let msg = match <$protocol as $crate::DynProtocol>::try_from_boxed_msg(msg) {
+                        Ok((protocol, with)) => return Ok((Self::$variant(protocol), with)),
+                        Err(msg) => msg,
+                    };
+                )*
+                Err(msg)
+            }
+
+            fn into_boxed_msg<_W: Send + 'static>(self, with: _W) -> $crate::BoxedMsg<_W> {
+                match self {
+                    $(
+                        Self::$variant(protocol) => $crate::DynProtocol::into_boxed_msg(protocol, with),
+                    )*
+                }
+            }
+
+            fn protocol_info() -> $crate::ProtocolInfo {
+                $crate::ProtocolInfo::new::<Self>(::std::vec![
+                    $($($crate::MessageInfo::of::<$msg>(),)*)*
+                ])
+            }
+        }
+
+        #[automatically_derived]
+        impl $crate::type_sets::AsSet for $name {
+            type Set = $crate::Set![$($($msg,)*)*];
+        }
+    };
+}
+
+/// Convert the protocol into the message `M`, returning the protocol if it is a different message.
+///
+/// Used by [`union_protocol!`], since the error of the inner protocol's [`TryInto`] is unknown.
+#[doc(hidden)]
+pub fn __downcast_protocol<P, M>(protocol: P) -> Result<M, P>
+where
+    P: DynProtocol,
+    M: 'static,
+{
+    match protocol.into_boxed_msg(()).downcast::<M>() {
+        Ok((msg, ())) => Ok(msg),
+        Err(msg) => match P::try_from_boxed_msg(msg) {
+            Ok((protocol, ())) => Err(protocol),
+            Err(_) => unreachable!("the message was created by the protocol"),
+        },
+    }
+}
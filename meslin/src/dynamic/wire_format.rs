@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use thiserror::Error;
+
+/// A binary format used to serialize messages with a [`MessageRegistry`](crate::MessageRegistry).
+///
+/// Both sides of a connection must use the same format. [`Bincode`] is used by default; with
+/// the `postcard` feature, [`Postcard`] can be used for a more compact encoding that is also
+/// supported by embedded peers.
+pub trait WireFormat: Send + Sync + Debug + 'static {
+    fn serialize<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, WireError>;
+
+    fn deserialize<'de, T: Deserialize<'de>>(bytes: &'de [u8]) -> Result<T, WireError>;
+}
+
+/// The [`bincode`] [`WireFormat`], used by default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Bincode;
+
+impl WireFormat for Bincode {
+    fn serialize<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, WireError> {
+        bincode::serialize(value).map_err(WireError::new)
+    }
+
+    fn deserialize<'de, T: Deserialize<'de>>(bytes: &'de [u8]) -> Result<T, WireError> {
+        bincode::deserialize(bytes).map_err(WireError::new)
+    }
+}
+
+/// The [`postcard`] [`WireFormat`].
+#[cfg(feature = "postcard")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Postcard;
+
+#[cfg(feature = "postcard")]
+impl WireFormat for Postcard {
+    fn serialize<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, WireError> {
+        postcard::to_allocvec(value).map_err(WireError::new)
+    }
+
+    fn deserialize<'de, T: Deserialize<'de>>(bytes: &'de [u8]) -> Result<T, WireError> {
+        postcard::from_bytes(bytes).map_err(WireError::new)
+    }
+}
+
+/// Error that is returned by a [`WireFormat`].
+#[derive(Debug, Error)]
+#[error(transparent)]
+pub struct WireError(Box<dyn std::error::Error + Send + Sync>);
+
+impl WireError {
+    pub fn new(error: impl std::error::Error + Send + Sync + 'static) -> Self {
+        Self(Box::new(error))
+    }
+}
@@ -56,4 +56,59 @@ impl<T, E> From<SendError<T>> for RequestError<T, E> {
     }
 }
 
+/// Error that is returned when a channel is closed or full, or the request did not receive a
+/// reply.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Error)]
+pub enum TryRequestError<M, E> {
+    #[error("Channel is closed: Failed to send message {0:?}.")]
+    Closed(M),
+    #[error("Channel is full: Failed to send message {0:?}.")]
+    Full(M),
+    #[error("No reply received: {0}")]
+    NoReply(#[source] E),
+}
+
+impl<T, E> From<TrySendError<T>> for TryRequestError<T, E> {
+    fn from(e: TrySendError<T>) -> Self {
+        match e {
+            TrySendError::Closed(t) => Self::Closed(t),
+            TrySendError::Full(t) => Self::Full(t),
+        }
+    }
+}
+
+/// Error that is returned when a channel is closed and empty.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Error)]
+#[error("Channel is closed: Failed to receive message.")]
+pub struct RecvError;
+
+/// Error that is returned when a channel is closed, or the received protocol is not the expected
+/// message.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Error)]
+pub enum RecvAsError<T> {
+    #[error("Channel is closed: Failed to receive message.")]
+    Closed,
+    #[error("Unexpected message: Received {0:?}.")]
+    Unexpected(T),
+}
+
+impl<T> From<RecvError> for RecvAsError<T> {
+    fn from(_: RecvError) -> Self {
+        Self::Closed
+    }
+}
+
+/// Error that is returned when a channel is closed or empty.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Error)]
+pub enum TryRecvError {
+    #[error("Channel is empty: No message available.")]
+    Empty,
+    #[error("Channel is closed: Failed to receive message.")]
+    Closed,
+}
 
+impl From<RecvError> for TryRecvError {
+    fn from(_: RecvError) -> Self {
+        Self::Closed
+    }
+}
@@ -0,0 +1,221 @@
+use crate::*;
+use std::{
+    fmt::Debug,
+    future::{poll_fn, Future},
+    sync::{Arc, Mutex},
+    task::{Poll, Waker},
+};
+
+/// A gate that is checked by every [`GatedSender`] before it sends, so that the receiver can
+/// stop accepting new sends while it drains its backlog.
+///
+/// Once the gate is closed, all senders that were created with [`IsSenderExt::gated`] fail as if
+/// the channel was closed. Sends that were already in progress are allowed to finish, and
+/// [`Gate::close_and_drain`] waits for them, so no protocol is lost between closing and
+/// draining. Clones of the gate share the same state.
+///
+/// ```
+/// # use meslin::*;
+/// # futures::executor::block_on(async {
+/// let gate = Gate::new();
+/// let (sender, mut receiver) = mpmc::unbounded::<u32>();
+/// let sender = sender.gated(&gate);
+/// sender.send::<u32>(1u32).await.unwrap();
+///
+/// let drained = gate.close_and_drain(&mut receiver).await;
+/// assert_eq!(drained, [(1, ())]);
+/// assert!(sender.send::<u32>(2u32).await.is_err());
+/// # });
+/// ```
+#[derive(Clone, Default)]
+pub struct Gate {
+    inner: Arc<Mutex<State>>,
+}
+
+#[derive(Default)]
+struct State {
+    closed: bool,
+    in_flight: usize,
+    waker: Option<Waker>,
+}
+
+impl Gate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Close the gate, after which all [`GatedSender`]s fail to send.
+    pub fn close(&self) {
+        self.inner.lock().unwrap().closed = true;
+    }
+
+    /// Returns `true` if the gate is closed.
+    pub fn is_closed(&self) -> bool {
+        self.inner.lock().unwrap().closed
+    }
+
+    /// Close the gate and receive all protocols that are queued, waiting until the sends that
+    /// were in progress when the gate was closed have finished.
+    ///
+    /// Afterwards, the receiver is guaranteed to have received every protocol that was sent
+    /// successfully through the gate.
+    pub async fn close_and_drain<R: IsReceiver>(
+        &self,
+        receiver: &mut R,
+    ) -> Vec<(R::Protocol, R::With)> {
+        self.close();
+        let mut drained = Vec::new();
+        poll_fn(|cx| {
+            let in_flight = {
+                let mut state = self.inner.lock().unwrap();
+                state.waker = Some(cx.waker().clone());
+                state.in_flight
+            };
+            // Draining makes space for in-flight sends that are waiting on a full channel.
+            // Sends deliver their protocol before they leave the gate, so once none are in
+            // flight, this drain receives everything.
+            drained.extend(receiver.drain_queued());
+            match in_flight {
+                0 => Poll::Ready(()),
+                _ => Poll::Pending,
+            }
+        })
+        .await;
+        drained
+    }
+
+    /// Enter the gate for the duration of a send, or return `None` if it is closed.
+    fn enter(&self) -> Option<Entered<'_>> {
+        let mut state = self.inner.lock().unwrap();
+        match state.closed {
+            true => None,
+            false => {
+                state.in_flight += 1;
+                Some(Entered(self))
+            }
+        }
+    }
+}
+
+impl Debug for Gate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.inner.lock().unwrap();
+        f.debug_struct("Gate")
+            .field("closed", &state.closed)
+            .field("in_flight", &state.in_flight)
+            .finish()
+    }
+}
+
+/// A send that is in progress, which leaves the gate when dropped.
+struct Entered<'a>(&'a Gate);
+
+impl Drop for Entered<'_> {
+    fn drop(&mut self) {
+        let waker = {
+            let mut state = self.0.inner.lock().unwrap();
+            state.in_flight -= 1;
+            state.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake()
+        }
+    }
+}
+
+/// A wrapper around a sender, which fails to send once its [`Gate`] is closed.
+///
+/// Created with [`IsSenderExt::gated`]. Clones share the same gate.
+#[derive(Debug, Clone)]
+pub struct GatedSender<T> {
+    sender: T,
+    gate: Gate,
+}
+
+impl<T> GatedSender<T> {
+    pub fn new(sender: T, gate: &Gate) -> Self {
+        Self {
+            sender,
+            gate: gate.clone(),
+        }
+    }
+
+    pub fn gate(&self) -> &Gate {
+        &self.gate
+    }
+
+    pub fn into_inner(self) -> (T, Gate) {
+        (self.sender, self.gate)
+    }
+
+    pub fn inner_ref(&self) -> &T {
+        &self.sender
+    }
+}
+
+forward_broadcast_sender_impl!(<T> GatedSender<T>);
+
+impl<T: IsSender> IsSender for GatedSender<T> {
+    type With = T::With;
+
+    fn is_closed(&self) -> bool {
+        self.gate.is_closed() || self.sender.is_closed()
+    }
+
+    forward_sender_methods!(
+        capacity,
+        len,
+        receiver_count,
+        sender_count,
+        is_full,
+        remaining,
+        stats
+    );
+}
+
+impl<T> IsStaticSender for GatedSender<T>
+where
+    T: IsStaticSender + Sync,
+    T::Protocol: Send,
+    T::With: Send,
+{
+    type Protocol = T::Protocol;
+
+    async fn send_protocol_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        with: Self::With,
+    ) -> Result<(), SendError<(Self::Protocol, Self::With)>> {
+        let Some(_entered) = this.gate.enter() else {
+            return Err(SendError((protocol, with)));
+        };
+        T::send_protocol_with(&this.sender, protocol, with).await
+    }
+
+    fn try_send_protocol_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        with: Self::With,
+    ) -> Result<(), TrySendError<(Self::Protocol, Self::With)>> {
+        let Some(_entered) = this.gate.enter() else {
+            return Err(TrySendError::Closed((protocol, with)));
+        };
+        T::try_send_protocol_with(&this.sender, protocol, with)
+    }
+
+    fn wait_for_space(this: &Self) -> Option<impl Future<Output = bool> + Send + '_> {
+        T::wait_for_space(&this.sender)
+    }
+
+    #[cfg(blocking)]
+    fn send_protocol_blocking_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        with: Self::With,
+    ) -> Result<(), SendError<(Self::Protocol, Self::With)>> {
+        let Some(_entered) = this.gate.enter() else {
+            return Err(SendError((protocol, with)));
+        };
+        T::send_protocol_blocking_with(&this.sender, protocol, with)
+    }
+}
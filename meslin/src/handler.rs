@@ -0,0 +1,130 @@
+use crate::*;
+use std::future::Future;
+
+/// Trait for state that handles messages of type `M`, the receiving counterpart of [`Sends<M>`].
+///
+/// Handlers of all messages of a protocol can be called using [`Dispatch`].
+///
+/// [`Sends<M>`]: crate::Sends
+pub trait Handler<M> {
+    fn handle(&mut self, msg: M) -> impl Future<Output = ()> + Send;
+}
+
+/// Trait implemented by protocols that can dispatch their messages to a [`Handler`].
+///
+/// This is usually derived on an enum using [`macro@Dispatch`](crate::Dispatch), which requires
+/// the state to implement [`Handler<M>`] for every message `M` of the protocol.
+///
+/// ```
+/// # use meslin::*;
+/// #[derive(Debug, From, TryInto, Dispatch)]
+/// enum Protocol {
+///     Add(u32),
+///     Reset(()),
+/// }
+///
+/// #[derive(Default)]
+/// struct Counter(u32);
+///
+/// impl Handler<u32> for Counter {
+///     async fn handle(&mut self, n: u32) {
+///         self.0 += n;
+///     }
+/// }
+///
+/// impl Handler<()> for Counter {
+///     async fn handle(&mut self, _: ()) {
+///         self.0 = 0;
+///     }
+/// }
+///
+/// # futures::executor::block_on(async {
+/// let (sender, mut receiver) = mpmc::unbounded::<Protocol>();
+/// sender.send::<u32>(3u32).await.unwrap();
+/// sender.send::<u32>(4u32).await.unwrap();
+///
+/// let mut counter = Counter::default();
+/// receiver.recv_dispatch(&mut counter).await.unwrap();
+/// receiver.recv_dispatch(&mut counter).await.unwrap();
+/// assert_eq!(counter.0, 7);
+/// # });
+/// ```
+pub trait Dispatch<S>: Sized {
+    /// Call the handler of the state for the message of this protocol.
+    fn dispatch(self, state: &mut S) -> impl Future<Output = ()> + Send;
+}
+
+/// Run the mailbox-loop of an actor: receive protocols and [`Dispatch`] them to the handlers of
+/// the state, until the channel is closed. Returns the final state.
+///
+/// See [`run_mailbox_until`] to stop the loop early, for example on a shutdown message.
+pub async fn run_mailbox<R, S>(receiver: R, state: S) -> S
+where
+    R: IsReceiver,
+    R::Protocol: Dispatch<S>,
+    S: Send,
+{
+    run_mailbox_until(receiver, state, |_| false).await
+}
+
+/// Like [`run_mailbox`], but also stops once `stop` returns `true` for the state, which is
+/// checked before every message is received.
+///
+/// ```
+/// # use meslin::*;
+/// #[derive(Debug, From, TryInto, Dispatch)]
+/// enum Protocol {
+///     Add(u32),
+///     Stop(Shutdown),
+/// }
+///
+/// #[derive(Default)]
+/// struct Counter {
+///     total: u32,
+///     shutdown: Option<Shutdown>,
+/// }
+///
+/// impl Handler<u32> for Counter {
+///     async fn handle(&mut self, n: u32) {
+///         self.total += n;
+///     }
+/// }
+///
+/// impl Handler<Shutdown> for Counter {
+///     async fn handle(&mut self, shutdown: Shutdown) {
+///         self.shutdown = Some(shutdown);
+///     }
+/// }
+///
+/// # futures::executor::block_on(async {
+/// let (sender, receiver) = mpmc::unbounded::<Protocol>();
+/// let actor = async {
+///     let counter = run_mailbox_until(receiver, Counter::default(), |c| c.shutdown.is_some()).await;
+///     counter.shutdown.unwrap().complete();
+///     counter.total
+/// };
+/// let client = async {
+///     sender.send::<u32>(3u32).await.unwrap();
+///     sender.request::<Shutdown>(()).await.unwrap();
+/// };
+/// let (total, ()) = futures::join!(actor, client);
+/// assert_eq!(total, 3);
+/// # });
+/// ```
+pub async fn run_mailbox_until<R, S>(
+    mut receiver: R,
+    mut state: S,
+    mut stop: impl FnMut(&S) -> bool,
+) -> S
+where
+    R: IsReceiver,
+    R::Protocol: Dispatch<S>,
+    S: Send,
+{
+    while !stop(&state) {
+        if receiver.recv_dispatch(&mut state).await.is_err() {
+            break;
+        }
+    }
+    state
+}
@@ -0,0 +1,145 @@
+use crate::*;
+use std::collections::VecDeque;
+
+/// A wrapper around a receiver, which buffers protocols locally so they can be peeked at and
+/// requeued.
+///
+/// Protocols that are requeued are received again before any new protocols. This allows actors
+/// to defer messages while waiting for a state transition. The local buffer is bounded by the
+/// capacity given to [`Inbox::with_capacity`].
+///
+/// ```
+/// # use meslin::*;
+/// # futures::executor::block_on(async {
+/// let (sender, receiver) = mpmc::unbounded::<u32>();
+/// let mut inbox = receiver.inbox();
+/// sender.send::<u32>(1u32).await.unwrap();
+/// sender.send::<u32>(2u32).await.unwrap();
+///
+/// assert_eq!(inbox.peek().await.unwrap(), &(1, ()));
+/// let (deferred, ()) = inbox.recv_protocol_with().await.unwrap();
+/// assert_eq!(inbox.recv_protocol().await.unwrap(), 2);
+/// inbox.requeue(deferred, ()).unwrap();
+/// assert_eq!(inbox.recv_protocol().await.unwrap(), 1);
+/// # });
+/// ```
+#[derive(Debug)]
+pub struct Inbox<R: IsReceiver> {
+    receiver: R,
+    buffer: VecDeque<(R::Protocol, R::With)>,
+    capacity: Option<usize>,
+}
+
+impl<R: IsReceiver> Inbox<R> {
+    /// Create an inbox with an unbounded local buffer.
+    pub fn new(receiver: R) -> Self {
+        Self {
+            receiver,
+            buffer: VecDeque::new(),
+            capacity: None,
+        }
+    }
+
+    /// Create an inbox of which the local buffer holds at most `capacity` protocols.
+    pub fn with_capacity(receiver: R, capacity: usize) -> Self {
+        Self {
+            receiver,
+            buffer: VecDeque::with_capacity(capacity),
+            capacity: Some(capacity),
+        }
+    }
+
+    pub fn into_inner(self) -> (R, VecDeque<(R::Protocol, R::With)>) {
+        (self.receiver, self.buffer)
+    }
+
+    pub fn inner_ref(&self) -> &R {
+        &self.receiver
+    }
+
+    pub fn inner_mut(&mut self) -> &mut R {
+        &mut self.receiver
+    }
+
+    /// The protocols that are buffered locally, in the order they will be received.
+    pub fn buffered(&self) -> &VecDeque<(R::Protocol, R::With)> {
+        &self.buffer
+    }
+
+    /// Whether the local buffer is full, in which case protocols can not be requeued.
+    pub fn is_full(&self) -> bool {
+        self.capacity
+            .is_some_and(|capacity| self.buffer.len() >= capacity)
+    }
+
+    /// Push the protocol to the front of the inbox, so that it is received next.
+    ///
+    /// Fails if the local buffer is full.
+    pub fn requeue(
+        &mut self,
+        protocol: R::Protocol,
+        with: R::With,
+    ) -> Result<(), (R::Protocol, R::With)> {
+        match self.is_full() {
+            true => Err((protocol, with)),
+            false => {
+                self.buffer.push_front((protocol, with));
+                Ok(())
+            }
+        }
+    }
+
+    /// Look at the next protocol and its `with`-value without removing it, waiting
+    /// asynchronously until a message becomes available.
+    ///
+    /// A protocol received for peeking is buffered locally, even if the buffer is full.
+    pub async fn peek(&mut self) -> Result<&(R::Protocol, R::With), RecvError> {
+        if self.buffer.is_empty() {
+            let received = R::recv_protocol_with(&mut self.receiver).await?;
+            self.buffer.push_back(received);
+        }
+        Ok(self.buffer.front().unwrap())
+    }
+
+    /// Look at the next protocol and its `with`-value without removing it, returning an error
+    /// if no message is available.
+    pub fn try_peek(&mut self) -> Result<&(R::Protocol, R::With), TryRecvError> {
+        if self.buffer.is_empty() {
+            let received = R::try_recv_protocol_with(&mut self.receiver)?;
+            self.buffer.push_back(received);
+        }
+        Ok(self.buffer.front().unwrap())
+    }
+}
+
+impl<R> IsReceiver for Inbox<R>
+where
+    R: IsReceiver + Send,
+    R::Protocol: Send,
+    R::With: Send,
+{
+    type Protocol = R::Protocol;
+    type With = R::With;
+
+    async fn recv_protocol_with(this: &mut Self) -> Result<(R::Protocol, R::With), RecvError> {
+        match this.buffer.pop_front() {
+            Some(received) => Ok(received),
+            None => R::recv_protocol_with(&mut this.receiver).await,
+        }
+    }
+
+    fn try_recv_protocol_with(this: &mut Self) -> Result<(R::Protocol, R::With), TryRecvError> {
+        match this.buffer.pop_front() {
+            Some(received) => Ok(received),
+            None => R::try_recv_protocol_with(&mut this.receiver),
+        }
+    }
+
+    #[cfg(blocking)]
+    fn recv_protocol_blocking_with(this: &mut Self) -> Result<(R::Protocol, R::With), RecvError> {
+        match this.buffer.pop_front() {
+            Some(received) => Ok(received),
+            None => R::recv_protocol_blocking_with(&mut this.receiver),
+        }
+    }
+}
@@ -20,7 +20,9 @@
 //! When sending a message to an actor, you only need to provide the input type and if the message
 //! is sent succesfully, the output type is returned.
 //!
-//! [`Message`] is implemented for a lot of common types, like `i32`, `String`, `Vec<T>`, etc.
+//! [`Message`] is implemented for a lot of common types, like `i32`, `String`, `Vec<T>`, etc.,
+//! and for `bytes::Bytes` and `bytes::BytesMut` when the `bytes` feature is enabled. With the
+//! `json` feature, `serde_json::Value` and `Box<serde_json::value::RawValue>` can be sent as well.
 //! Furthermore, it is implemented for [`Msg<M>`] and [`Request<A, B>`]. The first is a simple
 //! wrapper that allows sending any type that does not implement [`trait@Message`]. The second is a
 //! message that requires a response, i.e. the output is actually a [`oneshot::Receiver`].
@@ -52,10 +54,17 @@
 //! - `{...}_with`: Instead of using the default [`IsSender::With`] value, a custom value is given.
 //! - `try_{...}`:  Sends a message, returning an error if space is not available.
 //! - `{...}_blocking`: Sends a message, blocking the current thread until space becomes available.
+//!   Blocking methods are not available on `wasm32` targets, where they would deadlock, and can
+//!   be removed entirely with the `forbid-blocking` feature. Where blocking would deadlock at
+//!   runtime, like within a current-thread Tokio runtime, they still succeed if they don't have
+//!   to wait, but panic if they do. Use [`can_block`] to check this first.
 //! - `{...}_msg`: Instead of giving the [`Message::Input`], the message itself is given.
 //! - `dyn_{...}`: Attempts to send a message, when it can not be statically verified that the actor will
 //!   accept the message.
 //!
+//! These modifiers can be combined, e.g. `try_request`, `request_blocking_with` or
+//! `dyn_request`, so every way of sending is available through the same methods.
+//!
 //! ### Dynamic senders
 //! A unique feature of Meslin is the transformation of senders into dynamic senders,
 //! converting any sender into a [`dyn DynSends<W>`](DynSends). This allows for storage
@@ -75,10 +84,17 @@
 //! - `mpmc::Sender<ProtocolA>` can be converted into `DynSender<Set![Msg1, ...]>` as long as
 //!   `ProtocolA` implements [`DynFromInto`] and `Contains<Msg1> + Contains<...> + ...`.
 //!
+//! On single-threaded `wasm32` targets, senders only have to be [`MaybeSend`] and [`MaybeSync`]
+//! to be converted into a dynamic sender, so that a [`struct@DynSender`] does not require them to be
+//! `Send` there. The [`struct@DynSender`] is then not `Send` itself, and can't be sent as part of a
+//! message like [`ReplyTo`]. Its futures are `Send` on all targets, like those of all other
+//! senders. On other targets, senders that are not `Send` or `Sync` can be used dynamically
+//! through a [`struct@LocalDynSender`] instead.
+//!
 //! ## Cargo features
 //! The following features are available:
-//! - Default features: `["derive", "request", "mpmc", "broadcast", "priority"]`
-//! - Additional features: `["watch"]""
+//! - Default features: `["derive", "request", "mpmc", "broadcast", "priority", "dynamic"]`
+//! - Additional features: `["mpsc", "watch", "conflate", "journal", "leveled", "local", "otel", "time", "testing", "serde", "postcard", "remote", "remote-ws", "persist", "bytes", "json", "tokio", "smol", "test-strategies"]`
 //!
 //! ## Basic example
 //! ```
@@ -102,14 +118,91 @@ pub use message::*;
 mod send_traits;
 pub use send_traits::*;
 
+mod receive_traits;
+pub use receive_traits::*;
+
+mod handler;
+pub use handler::*;
+
+mod select;
+pub use select::*;
+
+mod merge;
+pub use merge::*;
+
+mod race;
+pub use race::*;
+
+mod inbox;
+pub use inbox::*;
+
+mod correlation;
+pub use correlation::*;
+
 mod sender_wrappers;
 pub use sender_wrappers::*;
 
+mod backpressure;
+pub use backpressure::*;
+
+mod stats;
+pub use stats::*;
+
+mod backend;
+pub use backend::*;
+
+mod address;
+pub use address::*;
+
+mod cancel;
+pub use cancel::*;
+
+mod gate;
+pub use gate::*;
+
+mod monitor;
+pub use monitor::*;
+
+mod wakers;
+
+mod reserve;
+pub use reserve::*;
+
+#[cfg(blocking)]
+mod blocking;
+#[cfg(blocking)]
+pub use blocking::*;
+
 #[cfg(feature = "dynamic")]
 mod dynamic;
 #[cfg(feature = "dynamic")]
 pub use dynamic::*;
 
+pub mod prelude;
+
+pub mod mux;
+
+#[cfg(feature = "otel")]
+pub mod otel;
+
+#[cfg(feature = "time")]
+pub mod time;
+
+#[cfg(feature = "testing")]
+pub mod testing;
+
+#[cfg(feature = "remote")]
+pub mod remote;
+
+#[cfg(feature = "persist")]
+pub mod persist;
+
+#[cfg(feature = "test-strategies")]
+pub mod test_strategies;
+
+#[cfg(any(feature = "tokio", feature = "smol"))]
+pub mod task;
+
 #[cfg(feature = "derive")]
 mod derive {
     #[allow(unused_imports)]
@@ -124,8 +217,94 @@ mod derive {
     /// Derive macro for [`trait@DynProtocol`].
     ///
     /// This derives [`trait@DynProtocol`] and [`AsSet`](type_sets::AsSet).
+    ///
+    /// A variant that wraps another protocol can be flattened using
+    /// `#[meslin(flatten(Msg1, Msg2, ...))]`, listing the messages of the inner protocol. These
+    /// messages are then accepted by the outer protocol, which also implements [`From`] and
+    /// [`TryFrom`] for them.
+    ///
+    /// All messages must implement [`trait@Message`], since they are described by the
+    /// [`ProtocolInfo`] of [`DynProtocol::protocol_info`].
     pub use meslin_derive::DynProtocol;
 
+    /// Derive macro for [`trait@Dispatch`].
+    ///
+    /// This dispatches every variant to the [`Handler`] of its message. Variants that are
+    /// flattened using `#[meslin(flatten(..))]` are dispatched by the inner protocol.
+    ///
+    /// Marking the protocol with `#[meslin(client)]` generates a `<Protocol>Client<S>`, which wraps
+    /// a sender and has one async method per message, named after its variant in snake-case. The
+    /// method sends the message and returns its output, so callers do not have to name the
+    /// message type. Flattened variants get a method that sends any message of the inner protocol,
+    /// which is named when calling it:
+    ///
+    /// ```
+    /// # use meslin::*;
+    /// #[derive(Debug, From, TryInto, Dispatch)]
+    /// #[meslin(client)]
+    /// enum Greeter {
+    ///     SayHello(String),
+    ///     GetAge(Request<String, u32>),
+    /// }
+    ///
+    /// # futures::executor::block_on(async {
+    /// let (sender, receiver) = mpmc::unbounded::<Greeter>();
+    /// let client = GreeterClient::new(sender);
+    /// client.say_hello("hello").await.unwrap();
+    ///
+    /// let reply = async { client.get_age("alice").await.unwrap().await.unwrap() };
+    /// let serve = async {
+    ///     receiver.recv_async().await.unwrap();
+    ///     let Greeter::GetAge(request) = receiver.recv_async().await.unwrap() else {
+    ///         unreachable!()
+    ///     };
+    ///     request.reply(42).unwrap();
+    /// };
+    /// let (age, ()) = futures::join!(reply, serve);
+    /// assert_eq!(age, 42);
+    /// # });
+    /// ```
+    ///
+    /// Marking the protocol with `#[meslin(service)]` instead dispatches it to a generated
+    /// `<Protocol>Service` trait, with one async method per variant, named after the variant in
+    /// snake-case. Flattened variants receive the inner protocol. Implementing an actor is then a
+    /// matter of implementing the trait, after which it is run with the provided `serve`. Both
+    /// options can be combined as `#[meslin(client, service)]`:
+    ///
+    /// ```
+    /// # use meslin::*;
+    /// #[derive(Debug, From, TryInto, Dispatch)]
+    /// #[meslin(service)]
+    /// enum Counter {
+    ///     Add(u32),
+    ///     Get(Request<(), u32>),
+    /// }
+    ///
+    /// #[derive(Default)]
+    /// struct State(u32);
+    ///
+    /// impl CounterService for State {
+    ///     async fn add(&mut self, n: u32) {
+    ///         self.0 += n;
+    ///     }
+    ///
+    ///     async fn get(&mut self, request: Request<(), u32>) {
+    ///         let _ = request.reply(self.0);
+    ///     }
+    /// }
+    ///
+    /// # futures::executor::block_on(async {
+    /// let (sender, receiver) = mpmc::unbounded::<Counter>();
+    /// let client = async move {
+    ///     sender.send::<u32>(2u32).await.unwrap();
+    ///     sender.request::<Request<(), u32>>(()).await.unwrap()
+    /// };
+    /// let (total, _) = futures::join!(client, CounterService::serve(receiver, State::default()));
+    /// assert_eq!(total, 2);
+    /// # });
+    /// ```
+    pub use meslin_derive::Dispatch;
+
     /// Re-export of [`derive_more::From`].
     pub use derive_more::From;
 
@@ -150,5 +329,13 @@ mod util {
             }
         }
     }
+
+    /// Converts a returned protocol back into the message it was created from.
+    ///
+    /// Used by all send-paths of the blanket [`Sends`](crate::Sends) implementation, so that
+    /// the conversion is only instantiated once per (protocol, message) pair.
+    pub(crate) fn protocol_into_msg<P: TryInto<M>, M, W>((protocol, with): (P, W)) -> (M, W) {
+        (protocol.try_into().unwrap_silent(), with)
+    }
 }
 use util::*;
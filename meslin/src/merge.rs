@@ -0,0 +1,120 @@
+use crate::*;
+use futures::{future::BoxFuture, Stream};
+use std::{
+    fmt::Debug,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Merge multiple receivers of the same protocol into a single [`Stream`], for example the
+/// shards of a keyed channel.
+///
+/// The stream yields the protocol and its `with`-value of every receiver. The receivers are
+/// polled fairly, starting with the next receiver every time a protocol is received. Receivers
+/// that are closed are removed, and the stream ends once all receivers are closed.
+///
+/// ```
+/// # use meslin::*;
+/// # use futures::StreamExt;
+/// # futures::executor::block_on(async {
+/// let (sender1, receiver1) = mpmc::unbounded::<u32>();
+/// let (sender2, receiver2) = mpmc::unbounded::<u32>();
+/// sender1.send::<u32>(1u32).await.unwrap();
+/// sender2.send::<u32>(2u32).await.unwrap();
+/// drop((sender1, sender2));
+///
+/// let mut received = merge([receiver1, receiver2])
+///     .map(|(protocol, ())| protocol)
+///     .collect::<Vec<_>>()
+///     .await;
+/// received.sort();
+/// assert_eq!(received, [1, 2]);
+/// # });
+/// ```
+pub fn merge<R>(receivers: impl IntoIterator<Item = R>) -> Merge<R>
+where
+    R: IsReceiver + Send + 'static,
+{
+    Merge {
+        receivers: receivers.into_iter().map(Merge::recv).collect(),
+        next: 0,
+    }
+}
+
+/// A [`Stream`] over multiple receivers of the same protocol, created with [`merge`].
+pub struct Merge<R: IsReceiver> {
+    receivers: Vec<BoxFuture<'static, Received<R>>>,
+    next: usize,
+}
+
+type Received<R> = (
+    R,
+    Result<(<R as IsReceiver>::Protocol, <R as IsReceiver>::With), RecvError>,
+);
+
+impl<R> Merge<R>
+where
+    R: IsReceiver + Send + 'static,
+{
+    /// Add another receiver to the stream.
+    pub fn push(&mut self, receiver: R) {
+        self.receivers.push(Self::recv(receiver));
+    }
+
+    /// The amount of receivers that are not closed.
+    pub fn len(&self) -> usize {
+        self.receivers.len()
+    }
+
+    /// Whether all receivers are closed.
+    pub fn is_empty(&self) -> bool {
+        self.receivers.is_empty()
+    }
+
+    fn recv(mut receiver: R) -> BoxFuture<'static, Received<R>> {
+        Box::pin(async move {
+            let received = R::recv_protocol_with(&mut receiver).await;
+            (receiver, received)
+        })
+    }
+}
+
+impl<R> Stream for Merge<R>
+where
+    R: IsReceiver + Send + 'static,
+{
+    type Item = (R::Protocol, R::With);
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut polled = 0;
+        while polled < self.receivers.len() {
+            let index = (self.next + polled) % self.receivers.len();
+            match self.receivers[index].as_mut().poll(cx) {
+                Poll::Ready((receiver, Ok(received))) => {
+                    self.receivers[index] = Self::recv(receiver);
+                    self.next = index + 1;
+                    return Poll::Ready(Some(received));
+                }
+                Poll::Ready((_receiver, Err(RecvError))) => {
+                    drop(self.receivers.remove(index));
+                    // Start over, since the indices have shifted.
+                    self.next = index;
+                    polled = 0;
+                }
+                Poll::Pending => polled += 1,
+            }
+        }
+        match self.receivers.is_empty() {
+            true => Poll::Ready(None),
+            false => Poll::Pending,
+        }
+    }
+}
+
+impl<R: IsReceiver> Debug for Merge<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Merge")
+            .field("receivers", &self.receivers.len())
+            .finish()
+    }
+}
@@ -1,8 +1,14 @@
 use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell},
     collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, LinkedList, VecDeque},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6},
     num::*,
+    ops::{Deref, DerefMut},
+    path::PathBuf,
     rc::Rc,
     sync::Arc,
+    time::{Duration, Instant, SystemTime},
 };
 
 /// Trait that defines how a message is created and canceled.
@@ -40,9 +46,49 @@ pub trait Message: Sized {
 /// This is useful for sending types that are not owned by the sender, since 
 /// [`Msg<T>`] implements [`Message`] for any type `T`.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Msg<T>(pub T);
 
-impl<T: Send + 'static> Message for Msg<T> {
+impl<T> Msg<T> {
+    /// Unwrap the inner value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for Msg<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T> Deref for Msg<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Msg<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T> AsRef<T> for Msg<T> {
+    fn as_ref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> AsMut<T> for Msg<T> {
+    fn as_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: 'static> Message for Msg<T> {
     type Input = T;
     type Output = ();
 
@@ -55,6 +101,63 @@ impl<T: Send + 'static> Message for Msg<T> {
     }
 }
 
+/// A message of which the payload is created by a closure, which is only evaluated when the
+/// receiver consumes it.
+///
+/// This avoids expensive construction of the payload, such as serialization or snapshots, when
+/// the message is never consumed, because it was dropped or expired.
+///
+/// ```
+/// # use meslin::*;
+/// # futures::executor::block_on(async {
+/// let (sender, receiver) = mpmc::unbounded::<Lazy<String>>();
+/// sender.send::<Lazy<String>>(|| "expensive".to_string()).await.unwrap();
+/// assert_eq!(receiver.recv_async().await.unwrap().evaluate(), "expensive");
+/// # });
+/// ```
+pub struct Lazy<T>(Box<dyn FnOnce() -> T + Send>);
+
+impl<T> Lazy<T> {
+    pub fn new(f: impl FnOnce() -> T + Send + 'static) -> Self {
+        Self(Box::new(f))
+    }
+
+    /// Evaluate the closure, creating the payload.
+    pub fn evaluate(self) -> T {
+        (self.0)()
+    }
+}
+
+impl<T, F> From<F> for Lazy<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+{
+    fn from(f: F) -> Self {
+        Self::new(f)
+    }
+}
+
+impl<T> std::fmt::Debug for Lazy<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Lazy")
+            .field(&std::any::type_name::<T>())
+            .finish()
+    }
+}
+
+impl<T: 'static> Message for Lazy<T> {
+    type Input = Self;
+    type Output = ();
+
+    fn create(from: Self::Input) -> (Self, Self::Output) {
+        (from, ())
+    }
+
+    fn cancel(self, _: Self::Output) -> Self::Input {
+        self
+    }
+}
+
 macro_rules! common_messages {
     (0;
         $($ty:ty),* $(,)?
@@ -68,7 +171,7 @@ macro_rules! common_messages {
     (
         impl<$($gen:ident),*> $ty:ty
     ) => {
-        impl<$($gen: Send + Sync + 'static),*> Message for $ty {
+        impl<$($gen: 'static),*> Message for $ty {
             type Input = $ty;
             type Output = ();
 
@@ -84,12 +187,22 @@ macro_rules! common_messages {
 }
 
 common_messages!(0;
-    char, String, bool, &'static str,
-    usize, u8, u16, u32, u64,
-    isize, i8, i16, i32, i64,
+    char, String, bool, &'static str, Box<str>, Arc<str>, Rc<str>, Cow<'static, str>,
+    usize, u8, u16, u32, u64, u128,
+    isize, i8, i16, i32, i64, i128,
     f32, f64,
-    NonZeroUsize, NonZeroU8, NonZeroU16, NonZeroU32, NonZeroU64,
-    NonZeroI8, NonZeroI16, NonZeroI32, NonZeroI64,
+    NonZeroUsize, NonZeroU8, NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU128,
+    NonZeroI8, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI128,
+    Duration, Instant, SystemTime, PathBuf,
+    IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6,
+);
+#[cfg(feature = "bytes")]
+common_messages!(0;
+    bytes::Bytes, bytes::BytesMut,
+);
+#[cfg(feature = "json")]
+common_messages!(0;
+    serde_json::Value, Box<serde_json::value::RawValue>,
 );
 common_messages!(1;
     Option<T1>,
@@ -97,6 +210,7 @@ common_messages!(1;
     Box<T1>,
     Arc<T1>,
     Rc<T1>,
+    Cell<T1>, RefCell<T1>,
     &'static [T1],
 );
 common_messages!(2;
@@ -105,12 +219,25 @@ common_messages!(2;
 
 );
 
+impl<T: 'static, const N: usize> Message for [T; N] {
+    type Input = Self;
+    type Output = ();
+
+    fn create(from: Self::Input) -> (Self, Self::Output) {
+        (from, ())
+    }
+
+    fn cancel(self, _: Self::Output) -> Self::Input {
+        self
+    }
+}
+
 macro_rules! tuple_messages {
     ($(
         ($($t:ident),* $(,)?)
     ),* $(,)?) => {
         $(
-            impl<$($t: Send + 'static,)*> Message for ($($t,)*) {
+            impl<$($t: 'static,)*> Message for ($($t,)*) {
                 type Input = Self;
                 type Output = ();
 
@@ -0,0 +1,377 @@
+use crate::{wakers::*, *};
+use std::{
+    fmt::Debug,
+    future::{poll_fn, Future},
+    sync::{Arc, Mutex},
+    task::Poll,
+};
+
+/// A watermark of the queue-depth, see [`Monitor::on_watermark`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Watermark {
+    /// The number of protocols rose to the high watermark.
+    High,
+    /// The number of protocols fell back to the low watermark.
+    Low,
+}
+
+pub(crate) struct Watermarks {
+    high: usize,
+    low: usize,
+    above: bool,
+    callback: Arc<dyn Fn(Watermark) + Send + Sync>,
+}
+
+impl Watermarks {
+    /// # Panics
+    /// Panics if `low` is not lower than `high`.
+    pub(crate) fn new(
+        high: usize,
+        low: usize,
+        len: usize,
+        callback: impl Fn(Watermark) + Send + Sync + 'static,
+    ) -> Self {
+        assert!(
+            low < high,
+            "the low watermark must be lower than the high watermark"
+        );
+        Self {
+            high,
+            low,
+            above: len >= high,
+            callback: Arc::new(callback),
+        }
+    }
+
+    /// Returns the callback and watermark to notify, if `len` crossed a watermark.
+    pub(crate) fn crossed(
+        &mut self,
+        len: usize,
+    ) -> Option<(Arc<dyn Fn(Watermark) + Send + Sync>, Watermark)> {
+        let crossed = match self.above {
+            false if len >= self.high => Watermark::High,
+            true if len <= self.low => Watermark::Low,
+            _ => return None,
+        };
+        self.above = crossed == Watermark::High;
+        Some((self.callback.clone(), crossed))
+    }
+}
+
+/// A monitor of the queue-depth of a channel, which allows senders to wait for capacity and to
+/// be notified of watermarks, for channels that don't support this themselves, like the
+/// [`mpmc`](crate::mpmc) and [`priority`](crate::priority) channels.
+///
+/// The monitor observes the channel through the senders that were created with
+/// [`IsSenderExt::monitored`] and the receivers that were created with
+/// [`IsReceiverExt::monitored`]. The depth is counted: every protocol that is sent through a
+/// [`MonitoredSender`] increments it, and every protocol that is received through a
+/// [`MonitoredReceiver`] decrements it. All senders and receivers of the channel should
+/// therefore be monitored, and protocols that are dropped by the channel, for example by
+/// [`Backpressure::DropOldest`], are not accounted for. Clones of the monitor share the same
+/// state.
+///
+/// ```
+/// # use meslin::*;
+/// # futures::executor::block_on(async {
+/// let monitor = Monitor::new();
+/// let (sender, receiver) = mpmc::bounded::<u32>(2);
+/// let (sender, mut receiver) = (sender.monitored(&monitor), receiver.monitored(&monitor));
+/// sender.send::<u32>(1u32).await.unwrap();
+/// sender.send::<u32>(2u32).await.unwrap();
+/// assert_eq!(monitor.depth(), 2);
+///
+/// let (has_space, _) = futures::join!(sender.wait_for_capacity(2), async {
+///     receiver.recv_protocol().await.unwrap();
+///     receiver.recv_protocol().await.unwrap();
+/// });
+/// assert!(has_space);
+/// # });
+/// ```
+#[derive(Clone, Default)]
+pub struct Monitor {
+    inner: Arc<Mutex<State>>,
+}
+
+#[derive(Default)]
+struct State {
+    /// Can be negative for a moment, since a protocol can be received before its send returns.
+    depth: isize,
+    receiver_count: usize,
+    watermarks: Option<Watermarks>,
+    send_wakers: Wakers,
+}
+
+impl State {
+    fn depth(&self) -> usize {
+        self.depth.max(0) as usize
+    }
+}
+
+impl Monitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of protocols that were sent through the monitored senders, and not
+    /// yet received through the monitored receivers.
+    pub fn depth(&self) -> usize {
+        self.inner.lock().unwrap().depth()
+    }
+
+    /// Call `callback` whenever the depth rises to `high`, or afterwards falls back to `low`.
+    /// This allows producers to adapt to the load of the channel without polling its length.
+    ///
+    /// The callback is called by the monitored sender or receiver that crossed the watermark,
+    /// and replaces any previously set callback.
+    ///
+    /// # Panics
+    /// Panics if `low` is not lower than `high`.
+    pub fn on_watermark(
+        &self,
+        high: usize,
+        low: usize,
+        callback: impl Fn(Watermark) + Send + Sync + 'static,
+    ) {
+        let mut state = self.inner.lock().unwrap();
+        state.watermarks = Some(Watermarks::new(high, low, state.depth(), callback));
+    }
+
+    fn sent(&self) {
+        let crossed = {
+            let mut state = self.inner.lock().unwrap();
+            state.depth += 1;
+            let depth = state.depth();
+            state.watermarks.as_mut().and_then(|w| w.crossed(depth))
+        };
+        if let Some((callback, watermark)) = crossed {
+            callback(watermark);
+        }
+    }
+
+    fn received(&self) {
+        let (wakers, crossed) = {
+            let mut state = self.inner.lock().unwrap();
+            state.depth -= 1;
+            let depth = state.depth();
+            (
+                state.send_wakers.take(),
+                state.watermarks.as_mut().and_then(|w| w.crossed(depth)),
+            )
+        };
+        wakers.into_iter().for_each(|waker| waker.wake());
+        if let Some((callback, watermark)) = crossed {
+            callback(watermark);
+        }
+    }
+}
+
+impl Debug for Monitor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.inner.lock().unwrap();
+        f.debug_struct("Monitor")
+            .field("depth", &state.depth())
+            .field("receiver_count", &state.receiver_count)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A wrapper around a sender, which reports its sends to a [`Monitor`].
+///
+/// Created with [`IsSenderExt::monitored`]. Clones share the same monitor.
+#[derive(Debug, Clone)]
+pub struct MonitoredSender<T> {
+    sender: T,
+    monitor: Monitor,
+}
+
+impl<T> MonitoredSender<T> {
+    pub fn new(sender: T, monitor: &Monitor) -> Self {
+        Self {
+            sender,
+            monitor: monitor.clone(),
+        }
+    }
+
+    pub fn monitor(&self) -> &Monitor {
+        &self.monitor
+    }
+
+    pub fn into_inner(self) -> (T, Monitor) {
+        (self.sender, self.monitor)
+    }
+
+    pub fn inner_ref(&self) -> &T {
+        &self.sender
+    }
+
+    fn sent<E>(&self, result: Result<(), E>) -> Result<(), E> {
+        if result.is_ok() {
+            self.monitor.sent();
+        }
+        result
+    }
+
+    /// Wait until the channel has space for at least `n` protocols, without sending anything.
+    ///
+    /// This allows producers to pace themselves before building a batch. Unbounded channels
+    /// always have space. Returns `false` if the channel is closed, or if none of its receivers
+    /// are monitored, since then no receive would wake this future.
+    ///
+    /// # Panics
+    /// Panics if `n` is larger than the capacity of the channel.
+    pub fn wait_for_capacity(&self, n: usize) -> impl Future<Output = bool> + Send + '_
+    where
+        T: IsSender + Sync,
+    {
+        let mut slot = WakerSlot::new(&*self.monitor.inner, |state: &mut State| {
+            &mut state.send_wakers
+        });
+        poll_fn(move |cx| {
+            // The space is checked while holding the lock, so that a receive in the meantime
+            // wakes this future.
+            let mut state = self.monitor.inner.lock().unwrap();
+            if self.sender.is_closed() || state.receiver_count == 0 {
+                return Poll::Ready(false);
+            }
+            match self.sender.capacity() {
+                Some(capacity) if self.sender.remaining().unwrap_or(capacity) < n => {
+                    assert!(
+                        n <= capacity,
+                        "n is larger than the capacity of the channel"
+                    );
+                    slot.register(&mut state, cx);
+                    Poll::Pending
+                }
+                _ => Poll::Ready(true),
+            }
+        })
+    }
+}
+
+forward_broadcast_sender_impl!(<T> MonitoredSender<T>);
+
+impl<T: IsSender> IsSender for MonitoredSender<T> {
+    type With = T::With;
+
+    forward_sender_methods!();
+}
+
+impl<T> IsStaticSender for MonitoredSender<T>
+where
+    T: IsStaticSender + Sync,
+    T::Protocol: Send,
+    T::With: Send,
+{
+    type Protocol = T::Protocol;
+
+    async fn send_protocol_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        with: Self::With,
+    ) -> Result<(), SendError<(Self::Protocol, Self::With)>> {
+        this.sent(T::send_protocol_with(&this.sender, protocol, with).await)
+    }
+
+    fn try_send_protocol_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        with: Self::With,
+    ) -> Result<(), TrySendError<(Self::Protocol, Self::With)>> {
+        this.sent(T::try_send_protocol_with(&this.sender, protocol, with))
+    }
+
+    fn wait_for_space(this: &Self) -> Option<impl Future<Output = bool> + Send + '_> {
+        (this.sender.capacity() != Some(0)).then(|| this.wait_for_capacity(1))
+    }
+
+    #[cfg(blocking)]
+    fn send_protocol_blocking_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        with: Self::With,
+    ) -> Result<(), SendError<(Self::Protocol, Self::With)>> {
+        this.sent(T::send_protocol_blocking_with(&this.sender, protocol, with))
+    }
+}
+
+/// A wrapper around a receiver, which reports its receives to a [`Monitor`].
+///
+/// Created with [`IsReceiverExt::monitored`]. Only receives through [`IsReceiver`] are
+/// reported, so the inherent methods of the inner receiver should not be used.
+#[derive(Debug)]
+pub struct MonitoredReceiver<R> {
+    receiver: R,
+    monitor: Monitor,
+}
+
+impl<R> MonitoredReceiver<R> {
+    pub fn new(receiver: R, monitor: &Monitor) -> Self {
+        monitor.inner.lock().unwrap().receiver_count += 1;
+        Self {
+            receiver,
+            monitor: monitor.clone(),
+        }
+    }
+
+    pub fn monitor(&self) -> &Monitor {
+        &self.monitor
+    }
+
+    pub fn inner_ref(&self) -> &R {
+        &self.receiver
+    }
+
+    fn received<T, E>(&self, result: Result<T, E>) -> Result<T, E> {
+        if result.is_ok() {
+            self.monitor.received();
+        }
+        result
+    }
+}
+
+impl<R: Clone> Clone for MonitoredReceiver<R> {
+    fn clone(&self) -> Self {
+        Self::new(self.receiver.clone(), &self.monitor)
+    }
+}
+
+impl<R> Drop for MonitoredReceiver<R> {
+    fn drop(&mut self) {
+        let wakers = {
+            let mut state = self.monitor.inner.lock().unwrap();
+            state.receiver_count -= 1;
+            match state.receiver_count {
+                0 => state.send_wakers.take(),
+                _ => Vec::new(),
+            }
+        };
+        wakers.into_iter().for_each(|waker| waker.wake());
+    }
+}
+
+impl<R> IsReceiver for MonitoredReceiver<R>
+where
+    R: IsReceiver + Send,
+    R::Protocol: Send,
+    R::With: Send,
+{
+    type Protocol = R::Protocol;
+    type With = R::With;
+
+    async fn recv_protocol_with(this: &mut Self) -> Result<(R::Protocol, R::With), RecvError> {
+        let received = R::recv_protocol_with(&mut this.receiver).await;
+        this.received(received)
+    }
+
+    fn try_recv_protocol_with(this: &mut Self) -> Result<(R::Protocol, R::With), TryRecvError> {
+        let received = R::try_recv_protocol_with(&mut this.receiver);
+        this.received(received)
+    }
+
+    #[cfg(blocking)]
+    fn recv_protocol_blocking_with(this: &mut Self) -> Result<(R::Protocol, R::With), RecvError> {
+        let received = R::recv_protocol_blocking_with(&mut this.receiver);
+        this.received(received)
+    }
+}
@@ -0,0 +1,325 @@
+//! Multiplexing of many logical channels over a single physical channel.
+//!
+//! The physical channel carries envelopes of `(id, protocol)`. A [`Mux`] wraps the physical
+//! sender and creates a [`MuxSender`] per id, which puts every protocol in an envelope with its
+//! id. A [`Demux`] wraps the physical receiver and creates a [`MuxReceiver`] per id, which only
+//! receives the protocols sent with that id. This is useful when only one pipe exists between
+//! two parties, like a remote link.
+//!
+//! Unlike the [`Demux`](crate::Demux) of dynamic protocols, which splits a receiver per message
+//! type, this splits a receiver per id. Protocols of which no receiver exists are dropped, so
+//! all receivers should be created before any of them is polled.
+//!
+//! ```
+//! # use meslin::{*, mux::{Demux, Mux}};
+//! # futures::executor::block_on(async {
+//! let (sender, receiver) = mpmc::unbounded::<(u8, u32)>();
+//! let mux = Mux::new(sender);
+//! let demux = Demux::new(receiver);
+//! let (sender1, mut receiver1) = (mux.channel(1), demux.channel(1));
+//! let (sender2, mut receiver2) = (mux.channel(2), demux.channel(2));
+//!
+//! sender2.send::<u32>(20u32).await.unwrap();
+//! sender1.send::<u32>(10u32).await.unwrap();
+//! assert_eq!(receiver1.recv_protocol().await.unwrap(), 10);
+//! assert_eq!(receiver2.recv_protocol().await.unwrap(), 20);
+//! # });
+//! ```
+use crate::{wakers::*, *};
+use futures::{lock::Mutex as AsyncMutex, ready};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Debug,
+    future::{poll_fn, Future},
+    hash::Hash,
+    sync::{Arc, Mutex},
+    task::{Poll, Waker},
+};
+
+/// The sending side of a [multiplexed channel](self), which creates a [`MuxSender`] per id.
+#[derive(Debug, Clone)]
+pub struct Mux<S> {
+    sender: S,
+}
+
+impl<S> Mux<S> {
+    pub fn new(sender: S) -> Self {
+        Self { sender }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.sender
+    }
+
+    pub fn inner_ref(&self) -> &S {
+        &self.sender
+    }
+
+    /// Create a sender of the logical channel with the given id.
+    pub fn channel<I>(&self, id: I) -> MuxSender<S, I>
+    where
+        S: Clone,
+    {
+        MuxSender {
+            sender: self.sender.clone(),
+            id,
+        }
+    }
+}
+
+/// A sender of one logical channel of a [`Mux`], which sends every protocol in an envelope with
+/// its id.
+#[derive(Debug, Clone)]
+pub struct MuxSender<S, I> {
+    sender: S,
+    id: I,
+}
+
+impl<S, I> MuxSender<S, I> {
+    pub fn id(&self) -> &I {
+        &self.id
+    }
+
+    pub fn into_inner(self) -> (S, I) {
+        (self.sender, self.id)
+    }
+
+    pub fn inner_ref(&self) -> &S {
+        &self.sender
+    }
+}
+
+impl<S: IsSender, I> IsSender for MuxSender<S, I> {
+    type With = S::With;
+
+    forward_sender_methods!();
+}
+
+impl<S, I, P> IsStaticSender for MuxSender<S, I>
+where
+    S: IsStaticSender<Protocol = (I, P)> + Sync,
+    I: Clone + Sync,
+{
+    type Protocol = P;
+
+    fn send_protocol_with(
+        this: &Self,
+        protocol: P,
+        with: Self::With,
+    ) -> impl Future<Output = Result<(), SendError<(P, Self::With)>>> + Send {
+        let fut = S::send_protocol_with(&this.sender, (this.id.clone(), protocol), with);
+        async {
+            fut.await
+                .map_err(|e| e.map(|((_, protocol), with)| (protocol, with)))
+        }
+    }
+
+    fn try_send_protocol_with(
+        this: &Self,
+        protocol: P,
+        with: Self::With,
+    ) -> Result<(), TrySendError<(P, Self::With)>> {
+        S::try_send_protocol_with(&this.sender, (this.id.clone(), protocol), with)
+            .map_err(|e| e.map(|((_, protocol), with)| (protocol, with)))
+    }
+
+    fn wait_for_space(this: &Self) -> Option<impl Future<Output = bool> + Send + '_> {
+        S::wait_for_space(&this.sender)
+    }
+
+    #[cfg(blocking)]
+    fn send_protocol_blocking_with(
+        this: &Self,
+        protocol: P,
+        with: Self::With,
+    ) -> Result<(), SendError<(P, Self::With)>> {
+        S::send_protocol_blocking_with(&this.sender, (this.id.clone(), protocol), with)
+            .map_err(|e| e.map(|((_, protocol), with)| (protocol, with)))
+    }
+}
+
+/// The receiving side of a [multiplexed channel](self), which creates a [`MuxReceiver`] per id.
+pub struct Demux<R: IsReceiver, I> {
+    receiver: Arc<AsyncMutex<R>>,
+    shared: Arc<Mutex<Channels<R, I>>>,
+}
+
+/// A receiver of one logical channel of a [`Demux`].
+///
+/// Multiple receivers of the same id can be created, in which case every protocol is received
+/// by only one of them. Receiving is done by whichever receiver is polled, so the physical
+/// receiver must be cancel-safe, like all receivers of this crate.
+pub struct MuxReceiver<R: IsReceiver, I: Hash + Eq> {
+    id: I,
+    receiver: Arc<AsyncMutex<R>>,
+    shared: Arc<Mutex<Channels<R, I>>>,
+}
+
+struct Channels<R: IsReceiver, I> {
+    buffers: HashMap<I, Buffer<R>>,
+    closed: bool,
+}
+
+struct Buffer<R: IsReceiver> {
+    received: VecDeque<(R::Protocol, R::With)>,
+    receivers: usize,
+    wakers: Wakers,
+}
+
+impl<R: IsReceiver, I> Demux<R, I> {
+    pub fn new(receiver: R) -> Self {
+        Self {
+            receiver: Arc::new(AsyncMutex::new(receiver)),
+            shared: Arc::new(Mutex::new(Channels {
+                buffers: HashMap::new(),
+                closed: false,
+            })),
+        }
+    }
+
+    /// Create a receiver of the logical channel with the given id.
+    pub fn channel(&self, id: I) -> MuxReceiver<R, I>
+    where
+        I: Hash + Eq + Clone,
+    {
+        let mut shared = self.shared.lock().unwrap();
+        shared
+            .buffers
+            .entry(id.clone())
+            .or_insert_with(|| Buffer {
+                received: VecDeque::new(),
+                receivers: 0,
+                wakers: Wakers::default(),
+            })
+            .receivers += 1;
+        MuxReceiver {
+            id,
+            receiver: self.receiver.clone(),
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<R, I, P> MuxReceiver<R, I>
+where
+    R: IsReceiver<Protocol = (I, P)>,
+    I: Hash + Eq,
+{
+    pub fn id(&self) -> &I {
+        &self.id
+    }
+
+    /// Take the next protocol that was buffered for this receiver.
+    fn take_buffered(&self) -> Result<Option<(P, R::With)>, RecvError> {
+        let mut shared = self.shared.lock().unwrap();
+        let closed = shared.closed;
+        let buffer = shared.buffers.get_mut(&self.id).unwrap();
+        match buffer.received.pop_front() {
+            Some(((_, protocol), with)) => Ok(Some((protocol, with))),
+            None if closed => Err(RecvError),
+            None => Ok(None),
+        }
+    }
+
+    /// Buffer the protocol for the receivers of its id, returning the wakers to wake.
+    fn buffer(&self, received: Result<(R::Protocol, R::With), RecvError>) -> Vec<Waker> {
+        let mut shared = self.shared.lock().unwrap();
+        match received {
+            Ok(((id, protocol), with)) => match shared.buffers.get_mut(&id) {
+                Some(buffer) => {
+                    buffer.received.push_back(((id, protocol), with));
+                    buffer.wakers.take()
+                }
+                None => Vec::new(),
+            },
+            Err(RecvError) => {
+                shared.closed = true;
+                shared
+                    .buffers
+                    .values_mut()
+                    .flat_map(|buffer| buffer.wakers.take())
+                    .collect()
+            }
+        }
+    }
+}
+
+impl<R, I, P> IsReceiver for MuxReceiver<R, I>
+where
+    R: IsReceiver<Protocol = (I, P)> + Send + 'static,
+    R::With: Send,
+    I: Hash + Eq + Send + Sync,
+    P: Send,
+{
+    type Protocol = P;
+    type With = R::With;
+
+    async fn recv_protocol_with(this: &mut Self) -> Result<(P, R::With), RecvError> {
+        let this = &*this;
+        let id = &this.id;
+        let mut slot = WakerSlot::new(&*this.shared, |shared: &mut Channels<R, I>| {
+            &mut shared.buffers.get_mut(id).unwrap().wakers
+        });
+        // The physical receiver is locked while receiving, and released when this future is
+        // dropped.
+        let mut recv = None;
+        poll_fn(|cx| loop {
+            if let Some(received) = this.take_buffered()? {
+                return Poll::Ready(Ok(received));
+            }
+            slot.register(&mut this.shared.lock().unwrap(), cx);
+
+            let fut = recv.get_or_insert_with(|| {
+                let receiver = this.receiver.lock();
+                Box::pin(async move { R::recv_protocol_with(&mut *receiver.await).await })
+            });
+            let received = ready!(fut.as_mut().poll(cx));
+            recv = None;
+            this.buffer(received).into_iter().for_each(Waker::wake);
+        })
+        .await
+    }
+
+    fn try_recv_protocol_with(this: &mut Self) -> Result<(P, R::With), TryRecvError> {
+        loop {
+            if let Some(received) = this.take_buffered().map_err(|_| TryRecvError::Closed)? {
+                return Ok(received);
+            }
+            let received = match this.receiver.try_lock() {
+                Some(mut receiver) => match R::try_recv_protocol_with(&mut *receiver) {
+                    Ok(received) => Ok(received),
+                    Err(TryRecvError::Closed) => Err(RecvError),
+                    Err(TryRecvError::Empty) => return Err(TryRecvError::Empty),
+                },
+                // Another receiver is receiving.
+                None => return Err(TryRecvError::Empty),
+            };
+            this.buffer(received).into_iter().for_each(Waker::wake);
+        }
+    }
+}
+
+impl<R: IsReceiver, I: Hash + Eq> Drop for MuxReceiver<R, I> {
+    fn drop(&mut self) {
+        let mut shared = self.shared.lock().unwrap();
+        let buffer = shared.buffers.get_mut(&self.id).unwrap();
+        buffer.receivers -= 1;
+        if buffer.receivers == 0 {
+            shared.buffers.remove(&self.id);
+        }
+    }
+}
+
+impl<R: IsReceiver, I> Debug for Demux<R, I> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Demux").finish_non_exhaustive()
+    }
+}
+
+impl<R: IsReceiver, I: Hash + Eq + Debug> Debug for MuxReceiver<R, I> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MuxReceiver")
+            .field("id", &self.id)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,108 @@
+use crate::*;
+use opentelemetry::{global, Context, ContextGuard};
+use std::collections::HashMap;
+
+/// A W3C trace-context carrier, that can be sent along with a message.
+///
+/// The carrier is filled in using the globally registered
+/// [`TextMapPropagator`](opentelemetry::propagation::TextMapPropagator), which means that
+/// nothing is propagated unless a propagator has been set with
+/// [`global::set_text_map_propagator`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TraceContext {
+    fields: HashMap<String, String>,
+}
+
+impl TraceContext {
+    /// Inject the given [`Context`] into a new carrier.
+    pub fn inject(cx: &Context) -> Self {
+        let mut fields = HashMap::new();
+        global::get_text_map_propagator(|propagator| propagator.inject_context(cx, &mut fields));
+        Self { fields }
+    }
+
+    /// Inject the current [`Context`] into a new carrier.
+    pub fn current() -> Self {
+        Self::inject(&Context::current())
+    }
+
+    /// Extract the [`Context`] from the carrier, using the current context as parent.
+    pub fn extract(&self) -> Context {
+        global::get_text_map_propagator(|propagator| propagator.extract(&self.fields))
+    }
+
+    /// Extract the [`Context`] and attach it, making it the current context until the
+    /// guard is dropped.
+    pub fn attach(&self) -> ContextGuard {
+        self.extract().attach()
+    }
+
+    /// Returns `true` if nothing was injected into the carrier.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    pub fn fields(&self) -> &HashMap<String, String> {
+        &self.fields
+    }
+
+    pub fn into_fields(self) -> HashMap<String, String> {
+        self.fields
+    }
+
+    pub fn from_fields(fields: HashMap<String, String>) -> Self {
+        Self { fields }
+    }
+}
+
+/// A message envelope that carries the [`TraceContext`] of the sender.
+///
+/// The input and output of [`Traced<M>`] are the same as those of `M`, so a message can be
+/// sent as `sender.send::<Traced<M>>(input)`. When the message is created, the current
+/// context is injected, so the receiver can continue the trace using [`Traced::attach`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Traced<M> {
+    pub msg: M,
+    pub cx: TraceContext,
+}
+
+impl<M> Traced<M> {
+    /// Wrap the message, injecting the current [`Context`].
+    pub fn new(msg: M) -> Self {
+        Self {
+            msg,
+            cx: TraceContext::current(),
+        }
+    }
+
+    /// Attach the propagated context, returning the message and a guard which keeps the
+    /// context active until it is dropped.
+    pub fn attach(self) -> (M, ContextGuard) {
+        let guard = self.cx.attach();
+        (self.msg, guard)
+    }
+
+    pub fn into_parts(self) -> (M, TraceContext) {
+        (self.msg, self.cx)
+    }
+
+    pub fn from_parts(msg: M, cx: TraceContext) -> Self {
+        Self { msg, cx }
+    }
+}
+
+impl<M: Message> Message for Traced<M> {
+    type Input = M::Input;
+    type Output = M::Output;
+
+    fn create(input: Self::Input) -> (Self, Self::Output) {
+        let (msg, output) = M::create(input);
+        (Self::new(msg), output)
+    }
+
+    fn cancel(self, output: Self::Output) -> Self::Input {
+        self.msg.cancel(output)
+    }
+}
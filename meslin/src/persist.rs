@@ -0,0 +1,374 @@
+//! Persisting messages, so that they can be recovered after a crash.
+//!
+//! A [`PersistSender`] records every protocol in a [`Persist`]-log before sending it, wrapped
+//! in a [`Persisted`] that carries its sequence number. Once the consumer has processed the
+//! protocol, it marks it as completed with [`Persist::complete`]. After a restart, all
+//! protocols that were not completed can be sent again with [`PersistSender::recover`].
+//!
+//! The [`FileLog`] is a reference implementation, backed by an append-only file.
+//!
+//! ```
+//! # use meslin::{*, persist::*};
+//! # use std::sync::Arc;
+//! # let path = std::env::temp_dir().join(format!("meslin-doc-{}.log", std::process::id()));
+//! # let _ = std::fs::remove_file(&path);
+//! let log = Arc::new(FileLog::<u32>::open(&path).unwrap());
+//! let (sender, receiver) = mpmc::unbounded::<Persisted<u32>>();
+//! let sender = PersistSender::new(sender, log.clone());
+//!
+//! sender.try_send::<u32>(10u32).unwrap();
+//! let Persisted { seq, protocol } = receiver.recv().unwrap();
+//! assert_eq!(protocol, 10);
+//! log.complete(seq).unwrap();
+//! # std::fs::remove_file(&path).unwrap();
+//! ```
+use crate::*;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    fs::{File, OpenOptions},
+    future::Future,
+    io::{self, BufReader, Read, Write},
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+/// A log in which protocols are recorded before they are delivered.
+pub trait Persist<P>: Send + Sync {
+    /// Record the protocol, returning its sequence number.
+    fn record(&self, protocol: &P) -> io::Result<u64>;
+
+    /// Mark the protocol with the given sequence number as completed.
+    fn complete(&self, seq: u64) -> io::Result<()>;
+
+    /// Returns all protocols that have not been completed, ordered by sequence number.
+    fn pending(&self) -> io::Result<Vec<(u64, P)>>;
+}
+
+/// A protocol together with its sequence number in a [`Persist`]-log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Persisted<P> {
+    pub seq: u64,
+    pub protocol: P,
+}
+
+/// A wrapper around a sender, which records every protocol in a [`Persist`]-log before
+/// sending it.
+///
+/// If recording fails, the protocol is not sent, and is returned as if the channel were closed.
+/// If sending fails, the protocol is marked as completed, since it is returned to the caller.
+#[derive(Debug)]
+pub struct PersistSender<T, L> {
+    sender: T,
+    log: Arc<L>,
+}
+
+impl<T, L> PersistSender<T, L> {
+    pub fn new(sender: T, log: Arc<L>) -> Self {
+        Self { sender, log }
+    }
+
+    pub fn into_inner(self) -> (T, Arc<L>) {
+        (self.sender, self.log)
+    }
+
+    pub fn inner_ref(&self) -> (&T, &Arc<L>) {
+        (&self.sender, &self.log)
+    }
+
+    /// Send all pending protocols of the log again, in order, returning the amount sent.
+    ///
+    /// This should be called after a restart, before any new protocols are sent.
+    pub async fn recover<P>(&self) -> io::Result<usize>
+    where
+        T: IsStaticSender<Protocol = Persisted<P>>,
+        T::With: Default,
+        L: Persist<P>,
+    {
+        let pending = self.log.pending()?;
+        let amount = pending.len();
+        for (seq, protocol) in pending {
+            T::send_protocol_with(
+                &self.sender,
+                Persisted { seq, protocol },
+                T::With::default(),
+            )
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "channel is closed"))?;
+        }
+        Ok(amount)
+    }
+}
+
+impl<T: Clone, L> Clone for PersistSender<T, L> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            log: self.log.clone(),
+        }
+    }
+}
+
+impl<T: IsSender, L> IsSender for PersistSender<T, L> {
+    type With = T::With;
+
+    forward_sender_methods!();
+}
+
+impl<T, L, P> IsStaticSender for PersistSender<T, L>
+where
+    T: IsStaticSender<Protocol = Persisted<P>> + Send + Sync,
+    T::With: Send,
+    L: Persist<P>,
+    P: Send,
+{
+    type Protocol = P;
+
+    fn send_protocol_with(
+        this: &Self,
+        protocol: P,
+        with: Self::With,
+    ) -> impl Future<Output = Result<(), SendError<(P, Self::With)>>> + Send {
+        let seq = this.log.record(&protocol);
+        async move {
+            let Ok(seq) = seq else {
+                return Err(SendError((protocol, with)));
+            };
+            let result = T::send_protocol_with(&this.sender, Persisted { seq, protocol }, with);
+            result.await.map_err(|e| {
+                let _ = this.log.complete(seq);
+                e.map(|(persisted, with)| (persisted.protocol, with))
+            })
+        }
+    }
+
+    fn try_send_protocol_with(
+        this: &Self,
+        protocol: P,
+        with: Self::With,
+    ) -> Result<(), TrySendError<(P, Self::With)>> {
+        let Ok(seq) = this.log.record(&protocol) else {
+            return Err(TrySendError::Closed((protocol, with)));
+        };
+        T::try_send_protocol_with(&this.sender, Persisted { seq, protocol }, with).map_err(|e| {
+            let _ = this.log.complete(seq);
+            e.map(|(persisted, with)| (persisted.protocol, with))
+        })
+    }
+
+    fn wait_for_space(this: &Self) -> Option<impl Future<Output = bool> + Send + '_> {
+        T::wait_for_space(&this.sender)
+    }
+
+    #[cfg(blocking)]
+    fn send_protocol_blocking_with(
+        this: &Self,
+        protocol: P,
+        with: Self::With,
+    ) -> Result<(), SendError<(P, Self::With)>> {
+        let Ok(seq) = this.log.record(&protocol) else {
+            return Err(SendError((protocol, with)));
+        };
+        T::send_protocol_blocking_with(&this.sender, Persisted { seq, protocol }, with).map_err(
+            |e| {
+                let _ = this.log.complete(seq);
+                e.map(|(persisted, with)| (persisted.protocol, with))
+            },
+        )
+    }
+}
+
+/// A [`Persist`]-log backed by an append-only file.
+///
+/// Protocols are serialized with the [`WireFormat`] `F`. Completed protocols are only removed
+/// from the file by [`FileLog::compact`]. A record that was only partially written, for
+/// example because of a crash, is ignored when the file is opened.
+pub struct FileLog<P, F = Bincode> {
+    path: PathBuf,
+    sync: bool,
+    state: Mutex<FileLogState>,
+    _marker: PhantomData<fn() -> (P, F)>,
+}
+
+struct FileLogState {
+    file: File,
+    next_seq: u64,
+    pending: BTreeMap<u64, Vec<u8>>,
+}
+
+#[derive(Serialize, Deserialize)]
+enum Record {
+    Append { seq: u64, protocol: Vec<u8> },
+    Complete { seq: u64 },
+}
+
+impl<P> FileLog<P> {
+    /// Open the log at the given path using the [`Bincode`] format, creating it if it does not
+    /// exist.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::open_with_format(path, Bincode)
+    }
+}
+
+impl<P, F: WireFormat> FileLog<P, F> {
+    /// Open the log at the given path using the given [`WireFormat`], creating it if it does
+    /// not exist.
+    pub fn open_with_format(path: impl AsRef<Path>, _format: F) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut next_seq = 0;
+        let mut pending = BTreeMap::new();
+        if path.exists() {
+            let mut reader = BufReader::new(File::open(&path)?);
+            while let Some(record) = read_record::<F>(&mut reader)? {
+                match record {
+                    Record::Append { seq, protocol } => {
+                        next_seq = next_seq.max(seq + 1);
+                        pending.insert(seq, protocol);
+                    }
+                    Record::Complete { seq } => {
+                        pending.remove(&seq);
+                    }
+                }
+            }
+        }
+
+        // Rewrite the file, which also drops a partially written record at the end.
+        let file = write_compacted::<F>(&path, &pending)?;
+        Ok(Self {
+            path,
+            sync: true,
+            state: Mutex::new(FileLogState {
+                file,
+                next_seq,
+                pending,
+            }),
+            _marker: PhantomData,
+        })
+    }
+
+    /// Whether to flush every record to disk before returning, `true` by default.
+    ///
+    /// Disabling this is faster, but records might be lost when the system crashes.
+    pub fn with_sync(mut self, sync: bool) -> Self {
+        self.sync = sync;
+        self
+    }
+
+    /// Rewrite the file, removing all completed protocols.
+    pub fn compact(&self) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.file = write_compacted::<F>(&self.path, &state.pending)?;
+        Ok(())
+    }
+
+    /// Returns the number of protocols that have not been completed.
+    pub fn pending_count(&self) -> usize {
+        self.state.lock().unwrap().pending.len()
+    }
+
+    fn append(&self, state: &mut FileLogState, record: &Record) -> io::Result<()> {
+        write_record::<F>(&mut state.file, record)?;
+        if self.sync {
+            state.file.sync_data()?;
+        }
+        Ok(())
+    }
+}
+
+impl<P, F> Persist<P> for FileLog<P, F>
+where
+    P: Serialize + DeserializeOwned,
+    F: WireFormat,
+{
+    fn record(&self, protocol: &P) -> io::Result<u64> {
+        let protocol = F::serialize(protocol).map_err(io::Error::other)?;
+        let mut state = self.state.lock().unwrap();
+        let seq = state.next_seq;
+        self.append(
+            &mut state,
+            &Record::Append {
+                seq,
+                protocol: protocol.clone(),
+            },
+        )?;
+        state.next_seq += 1;
+        state.pending.insert(seq, protocol);
+        Ok(seq)
+    }
+
+    fn complete(&self, seq: u64) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.pending.contains_key(&seq) {
+            self.append(&mut state, &Record::Complete { seq })?;
+            state.pending.remove(&seq);
+        }
+        Ok(())
+    }
+
+    fn pending(&self) -> io::Result<Vec<(u64, P)>> {
+        let state = self.state.lock().unwrap();
+        state
+            .pending
+            .iter()
+            .map(|(seq, protocol)| Ok((*seq, F::deserialize(protocol).map_err(io::Error::other)?)))
+            .collect()
+    }
+}
+
+impl<P, F> Debug for FileLog<P, F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FileLog")
+            .field("path", &self.path)
+            .field("sync", &self.sync)
+            .field("pending", &self.state.lock().unwrap().pending.len())
+            .finish()
+    }
+}
+
+/// Write the pending records to a temporary file, and atomically replace the log with it.
+fn write_compacted<F: WireFormat>(
+    path: &Path,
+    pending: &BTreeMap<u64, Vec<u8>>,
+) -> io::Result<File> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let mut file = File::create(&tmp)?;
+    for (seq, protocol) in pending {
+        let record = Record::Append {
+            seq: *seq,
+            protocol: protocol.clone(),
+        };
+        write_record::<F>(&mut file, &record)?;
+    }
+    file.sync_all()?;
+    std::fs::rename(&tmp, path)?;
+    OpenOptions::new().append(true).open(path)
+}
+
+/// Write a length-prefixed record.
+fn write_record<F: WireFormat>(file: &mut File, record: &Record) -> io::Result<()> {
+    let bytes = F::serialize(record).map_err(io::Error::other)?;
+    let mut buf = Vec::with_capacity(bytes.len() + 4);
+    buf.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    buf.extend_from_slice(&bytes);
+    file.write_all(&buf)
+}
+
+/// Read a length-prefixed record, returning `None` at the end of the file or at a partially
+/// written record.
+fn read_record<F: WireFormat>(reader: &mut impl Read) -> io::Result<Option<Record>> {
+    let mut len = [0; 4];
+    match reader.read_exact(&mut len) {
+        Ok(()) => (),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let mut bytes = vec![0; u32::from_le_bytes(len) as usize];
+    match reader.read_exact(&mut bytes) {
+        Ok(()) => Ok(F::deserialize(&bytes).ok()),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+        Err(e) => Err(e),
+    }
+}
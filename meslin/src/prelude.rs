@@ -0,0 +1,33 @@
+//! The prelude, which re-exports the traits, macros and derive-macros that are needed to send
+//! and receive messages.
+//!
+//! ```
+//! use meslin::prelude::*;
+//!
+//! #[derive(Debug, From, TryInto, DynProtocol)]
+//! enum Protocol {
+//!     Number(u32),
+//!     Text(String),
+//! }
+//!
+//! # futures::executor::block_on(async {
+//! let (sender, mut receiver) = meslin::mpmc::unbounded::<Protocol>();
+//! let sender: DynSender![u32] = sender.into_dyn_sender();
+//! sender.send::<u32>(1u32).await.unwrap();
+//! sender.dyn_send::<String>("hi").await.unwrap();
+//! assert!(matches!(receiver.recv_protocol().await.unwrap(), Protocol::Number(1)));
+//! # });
+//! ```
+
+pub use crate::{
+    select, ChannelBackend, Dispatch, Handler, IsReceiver, IsReceiverExt, IsSender, IsSenderExt,
+    IsStaticSender, Message, SelectReceivers, Sends,
+};
+
+#[cfg(feature = "dynamic")]
+pub use crate::{
+    DynProtocol, DynSender, IntoDynSender, IsDynSender, IsDynSenderExt, Set, TryIntoDynSender,
+};
+
+#[cfg(feature = "derive")]
+pub use crate::{From, TryInto};
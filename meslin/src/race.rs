@@ -0,0 +1,56 @@
+use crate::*;
+use futures::{stream::FuturesUnordered, StreamExt};
+
+/// Send the same request to all senders, and return the first reply that is received
+/// successfully.
+///
+/// All other requests are cancelled by dropping them, including their reply slots. This is
+/// useful for replicated actors, of which any answer will do. If no reply is received, the error
+/// of the last request that failed is returned, or the input if there are no senders.
+///
+/// ```
+/// # use meslin::*;
+/// # futures::executor::block_on(async {
+/// let (sender1, receiver1) = mpmc::unbounded::<Request<u32, &str>>();
+/// let (sender2, _receiver2) = mpmc::unbounded::<Request<u32, &str>>();
+///
+/// let reply = async {
+///     let request = receiver1.recv_async().await.unwrap();
+///     request.reply("first").unwrap();
+/// };
+/// let (result, ()) = futures::join!(
+///     race_request::<_, Request<u32, &str>>([&sender1, &sender2], 1u32),
+///     reply,
+/// );
+/// assert_eq!(result.unwrap(), "first");
+/// # });
+/// ```
+pub async fn race_request<'a, S, M>(
+    senders: impl IntoIterator<Item = &'a S>,
+    msg: impl Into<M::Input>,
+) -> Result<
+    <M::Output as ResultFuture>::Ok,
+    RequestError<M::Input, <M::Output as ResultFuture>::Error>,
+>
+where
+    S: Sends<M> + 'a,
+    S::With: Default,
+    M: Message,
+    M::Input: Clone,
+    M::Output: ResultFuture,
+{
+    let msg = msg.into();
+    let mut requests = senders
+        .into_iter()
+        .map(|sender| sender.request::<M>(msg.clone()))
+        .collect::<FuturesUnordered<_>>();
+
+    let mut error = RequestError::Full(msg);
+    while let Some(result) = requests.next().await {
+        match result {
+            Ok(reply) => return Ok(reply),
+            Err(e) => error = e,
+        }
+    }
+    Err(error)
+}
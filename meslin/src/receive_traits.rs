@@ -0,0 +1,243 @@
+use crate::*;
+use std::future::Future;
+
+/// Trait that is implemented by all receivers, the counterpart of [`IsStaticSender`].
+///
+/// Receivers return the protocol together with the `with`-value it was sent with. For most
+/// receivers [`IsReceiver::With`] is `()`, but for example the [`priority::Receiver`] returns
+/// the priority of the message.
+pub trait IsReceiver {
+    /// The protocol that can be received from this receiver.
+    type Protocol;
+
+    /// The value that is received along with the protocol.
+    type With;
+
+    fn recv_protocol_with(
+        this: &mut Self,
+    ) -> impl Future<Output = Result<(Self::Protocol, Self::With), RecvError>> + Send;
+
+    fn try_recv_protocol_with(
+        this: &mut Self,
+    ) -> Result<(Self::Protocol, Self::With), TryRecvError>;
+
+    #[cfg(blocking)]
+    fn recv_protocol_blocking_with(
+        this: &mut Self,
+    ) -> Result<(Self::Protocol, Self::With), RecvError> {
+        crate::block_on(Self::recv_protocol_with(this))
+    }
+}
+
+/// Trait implemented by the error of [`TryInto<M>`] for a protocol, which returns the protocol if it
+/// is not the message `M`.
+///
+/// This is implemented for the protocol itself, and for the error of the
+/// [`TryInto`](macro@crate::TryInto) derive-macro.
+pub trait RecoverProtocol<P> {
+    fn recover_protocol(self) -> P;
+}
+
+impl<P> RecoverProtocol<P> for P {
+    fn recover_protocol(self) -> P {
+        self
+    }
+}
+
+#[cfg(feature = "derive")]
+impl<P> RecoverProtocol<P> for derive_more::TryIntoError<P> {
+    fn recover_protocol(self) -> P {
+        self.input
+    }
+}
+
+/// Extension methods for [`IsReceiver`].
+pub trait IsReceiverExt: IsReceiver + Sized {
+    /// Receive the protocol and its `with`-value, waiting asynchronously until a message
+    /// becomes available.
+    fn recv_protocol_with(
+        &mut self,
+    ) -> impl Future<Output = Result<(Self::Protocol, Self::With), RecvError>> + Send {
+        <Self as IsReceiver>::recv_protocol_with(self)
+    }
+
+    /// Receive the protocol and its `with`-value, blocking the current thread until a message
+    /// becomes available.
+    #[cfg(blocking)]
+    fn recv_protocol_blocking_with(&mut self) -> Result<(Self::Protocol, Self::With), RecvError> {
+        <Self as IsReceiver>::recv_protocol_blocking_with(self)
+    }
+
+    /// Receive the protocol and its `with`-value, returning an error if no message is available.
+    fn try_recv_protocol_with(&mut self) -> Result<(Self::Protocol, Self::With), TryRecvError> {
+        <Self as IsReceiver>::try_recv_protocol_with(self)
+    }
+
+    /// Receive the protocol, waiting asynchronously until a message becomes available.
+    fn recv_protocol(&mut self) -> impl Future<Output = Result<Self::Protocol, RecvError>> + Send {
+        let fut = <Self as IsReceiver>::recv_protocol_with(self);
+        async { fut.await.map(|(protocol, _)| protocol) }
+    }
+
+    /// Receive the protocol, blocking the current thread until a message becomes available.
+    #[cfg(blocking)]
+    fn recv_protocol_blocking(&mut self) -> Result<Self::Protocol, RecvError> {
+        <Self as IsReceiver>::recv_protocol_blocking_with(self).map(|(protocol, _)| protocol)
+    }
+
+    /// Receive the protocol, returning an error if no message is available.
+    fn try_recv_protocol(&mut self) -> Result<Self::Protocol, TryRecvError> {
+        <Self as IsReceiver>::try_recv_protocol_with(self).map(|(protocol, _)| protocol)
+    }
+
+    /// Receive the protocol and its `with`-value, waiting asynchronously until a message
+    /// becomes available, unless the [`CancellationToken`] is cancelled first.
+    fn recv_protocol_with_cancellable(
+        &mut self,
+        token: Option<&CancellationToken>,
+    ) -> impl Future<Output = Result<(Self::Protocol, Self::With), CancelRecvError>> + Send {
+        let fut = <Self as IsReceiver>::recv_protocol_with(self);
+        async move {
+            match crate::cancel::until_cancelled(token, fut).await {
+                Some(received) => received.map_err(|RecvError| CancelRecvError::Closed),
+                None => Err(CancelRecvError::Cancelled),
+            }
+        }
+    }
+
+    /// Receive the protocol, waiting asynchronously until a message becomes available, unless
+    /// the [`CancellationToken`] is cancelled first.
+    fn recv_protocol_cancellable(
+        &mut self,
+        token: Option<&CancellationToken>,
+    ) -> impl Future<Output = Result<Self::Protocol, CancelRecvError>> + Send {
+        let fut = self.recv_protocol_with_cancellable(token);
+        async { fut.await.map(|(protocol, _)| protocol) }
+    }
+
+    /// Receive the protocol, waiting asynchronously until a message becomes available, and
+    /// convert it into the message `M`.
+    ///
+    /// If the protocol is a different message, it is returned as [`RecvAsError::Unexpected`].
+    fn recv_as<M>(
+        &mut self,
+    ) -> impl Future<Output = Result<M, RecvAsError<(Self::Protocol, Self::With)>>> + Send + '_
+    where
+        Self::Protocol: TryInto<M>,
+        <Self::Protocol as TryInto<M>>::Error: RecoverProtocol<Self::Protocol>,
+    {
+        let fut = <Self as IsReceiver>::recv_protocol_with(self);
+        async {
+            let (protocol, with) = fut.await?;
+            protocol
+                .try_into()
+                .map_err(|e| RecvAsError::Unexpected((e.recover_protocol(), with)))
+        }
+    }
+
+    /// Receive protocols until one is the message `M`, waiting asynchronously until it becomes
+    /// available.
+    ///
+    /// Every protocol that is a different message is passed to the fallback, which can for
+    /// example forward it to a side channel, or requeue it by sending it to the channel again.
+    fn recv_as_or_else<'a, M, F>(
+        &'a mut self,
+        mut fallback: F,
+    ) -> impl Future<Output = Result<M, RecvError>> + Send + 'a
+    where
+        Self: Send,
+        Self::Protocol: TryInto<M>,
+        <Self::Protocol as TryInto<M>>::Error: RecoverProtocol<Self::Protocol>,
+        F: FnMut(Self::Protocol, Self::With) + Send + 'a,
+    {
+        async move {
+            loop {
+                let (protocol, with) = <Self as IsReceiver>::recv_protocol_with(self).await?;
+                match protocol.try_into() {
+                    Ok(msg) => break Ok(msg),
+                    Err(e) => fallback(e.recover_protocol(), with),
+                }
+            }
+        }
+    }
+
+    /// Wrap the receiver in an [`Inbox`], allowing protocols to be peeked at and requeued.
+    fn inbox(self) -> Inbox<Self> {
+        Inbox::new(self)
+    }
+
+    /// Wrap the receiver in an [`Inbox`] with a one-slot buffer, allowing the next protocol to be
+    /// peeked at before it is received.
+    ///
+    /// A peeked protocol is held by this receiver, so other receivers of the same channel will
+    /// not receive it.
+    fn peekable(self) -> Inbox<Self> {
+        Inbox::with_capacity(self, 1)
+    }
+
+    /// Report every protocol that is received to the [`Monitor`], which wakes the senders that
+    /// wait for capacity, see [`MonitoredReceiver`].
+    fn monitored(self, monitor: &Monitor) -> MonitoredReceiver<Self> {
+        MonitoredReceiver::new(self, monitor)
+    }
+
+    /// Split the receiver into one stream per message type, see [`Demux`].
+    #[cfg(feature = "dynamic")]
+    fn demux(self) -> Demux<Self> {
+        Demux::new(self)
+    }
+
+    /// Skip messages of which the [`Expires`](time::Expires) deadline has passed, see
+    /// [`SkipExpired`](time::SkipExpired).
+    #[cfg(feature = "time")]
+    fn skip_expired(self) -> time::SkipExpired<Self> {
+        time::SkipExpired::new(self)
+    }
+
+    /// Like [`IsReceiverExt::skip_expired`], but measures the deadline using the given
+    /// [`Clock`](time::Clock).
+    #[cfg(feature = "time")]
+    fn skip_expired_with_clock(self, clock: impl time::Clock) -> time::SkipExpired<Self> {
+        time::SkipExpired::with_clock(self, clock)
+    }
+
+    /// Receive the protocol, waiting asynchronously until a message becomes available, and
+    /// [`Dispatch`] it to the handlers of the state.
+    fn recv_dispatch<'a, S>(
+        &'a mut self,
+        state: &'a mut S,
+    ) -> impl Future<Output = Result<(), RecvError>> + Send + 'a
+    where
+        Self::Protocol: Dispatch<S>,
+        S: Send,
+    {
+        let fut = <Self as IsReceiver>::recv_protocol_with(self);
+        async move {
+            let (protocol, _) = fut.await?;
+            protocol.dispatch(state).await;
+            Ok(())
+        }
+    }
+
+    /// Receive all protocols that are queued, without waiting, until the receiver is empty or
+    /// closed. This is used to drain the backlog during a [`Shutdown`].
+    fn drain_queued(&mut self) -> Drain<'_, Self> {
+        Drain { receiver: self }
+    }
+}
+impl<T> IsReceiverExt for T where T: IsReceiver {}
+
+/// An iterator over all protocols that are queued in a receiver, created with
+/// [`IsReceiverExt::drain_queued`].
+#[derive(Debug)]
+pub struct Drain<'a, R> {
+    receiver: &'a mut R,
+}
+
+impl<R: IsReceiver> Iterator for Drain<'_, R> {
+    type Item = (R::Protocol, R::With);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        R::try_recv_protocol_with(self.receiver).ok()
+    }
+}
@@ -0,0 +1,431 @@
+use super::frame::{self, Frame, FrameSink, FrameStream, PROTOCOL_VERSION};
+use crate::*;
+use futures::{future::BoxFuture, SinkExt, StreamExt};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    any::{Any, TypeId},
+    collections::{HashMap, HashSet},
+    fmt::Debug,
+    io,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::{TcpStream, ToSocketAddrs},
+    sync::mpsc,
+};
+
+/// A sender that sends messages over a connection to a remote [`serve`](super::serve)r.
+///
+/// See the [module-level documentation](super) for more information.
+pub struct RemoteSender<W = (), F = Bincode> {
+    inner: Arc<Inner<W, F>>,
+}
+
+struct Inner<W, F> {
+    frames: mpsc::UnboundedSender<Frame>,
+    shared: Arc<Shared<W, F>>,
+}
+
+pub(super) struct Shared<W, F> {
+    registry: Arc<MessageRegistry<W, F>>,
+    next_id: AtomicU64,
+    state: Mutex<State<W>>,
+}
+
+struct State<W> {
+    closed: bool,
+    accepted: HashSet<&'static str>,
+    members: &'static [TypeId],
+    pending: HashMap<u64, Pending<W>>,
+    replies: HashMap<u64, ReplyHandler>,
+}
+
+struct Pending<W> {
+    msg: BoxedMsg<W>,
+    notify: Option<::oneshot::Sender<Result<(), DynSendError<BoxedMsg<W>>>>>,
+}
+
+impl<W, F> RemoteSender<W, F>
+where
+    W: Serialize + DeserializeOwned + Send + 'static,
+    F: WireFormat,
+{
+    /// Connect to a remote server over TCP.
+    pub async fn connect(
+        addr: impl ToSocketAddrs,
+        registry: Arc<MessageRegistry<W, F>>,
+    ) -> io::Result<Self> {
+        let stream = TcpStream::connect(addr).await?;
+        stream.set_nodelay(true)?;
+        Self::from_stream(stream, registry).await
+    }
+
+    /// Create a remote sender from an established connection, performing the handshake.
+    ///
+    /// This spawns the task that drives the connection on the current tokio runtime.
+    pub async fn from_stream<S>(stream: S, registry: Arc<MessageRegistry<W, F>>) -> io::Result<Self>
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let (mut incoming, mut outgoing) = frame::split::<F, _>(stream);
+        let (sender, shared, mut frames_rx) = Self::unconnected(registry);
+        shared.handshake(&mut incoming, &mut outgoing).await?;
+        tokio::spawn(async move {
+            let dropped = AtomicBool::new(false);
+            shared
+                .run(incoming, outgoing, &mut frames_rx, &dropped)
+                .await;
+            shared.close();
+        });
+        Ok(sender)
+    }
+
+    /// Create a remote sender without a connection, returning the frames it sends.
+    ///
+    /// The frames must be driven by a transport with [`Shared::run`].
+    pub(super) fn unconnected(
+        registry: Arc<MessageRegistry<W, F>>,
+    ) -> (Self, Arc<Shared<W, F>>, mpsc::UnboundedReceiver<Frame>) {
+        let (frames, frames_rx) = mpsc::unbounded_channel::<Frame>();
+        let shared = Arc::new(Shared {
+            registry,
+            next_id: AtomicU64::new(0),
+            state: Mutex::new(State {
+                closed: false,
+                accepted: HashSet::new(),
+                members: &[],
+                pending: HashMap::new(),
+                replies: HashMap::new(),
+            }),
+        });
+        let sender = Self {
+            inner: Arc::new(Inner {
+                frames,
+                shared: shared.clone(),
+            }),
+        };
+        (sender, shared, frames_rx)
+    }
+
+    /// The registry used to serialize messages.
+    pub fn registry(&self) -> &Arc<MessageRegistry<W, F>> {
+        &self.inner.shared.registry
+    }
+
+    /// Serialize the message and send it to the server, returning a receiver for the
+    /// acknowledgement if `wait` is `true`.
+    fn send_frame(
+        &self,
+        msg: BoxedMsg<W>,
+        wait: bool,
+    ) -> Result<
+        Option<::oneshot::Receiver<Result<(), DynSendError<BoxedMsg<W>>>>>,
+        DynTrySendError<BoxedMsg<W>>,
+    > {
+        let shared = &self.inner.shared;
+        let bytes = match shared.registry.serialize(&msg) {
+            Ok(bytes) => bytes,
+            Err(_) => return Err(DynTrySendError::NotAccepted(msg)),
+        };
+
+        let mut state = shared.state.lock().unwrap();
+        if state.closed {
+            return Err(DynTrySendError::Closed(msg));
+        }
+        match shared.registry.name_of(&msg) {
+            Some(name) if state.accepted.contains(name) => (),
+            _ => return Err(DynTrySendError::NotAccepted(msg)),
+        }
+        let id = shared.next_id.fetch_add(1, Ordering::Relaxed);
+        let (notify, ack) = match wait {
+            true => {
+                let (tx, rx) = ::oneshot::channel();
+                (Some(tx), Some(rx))
+            }
+            false => (None, None),
+        };
+        if self
+            .inner
+            .frames
+            .send(Frame::Send { id, msg: bytes })
+            .is_err()
+        {
+            return Err(DynTrySendError::Closed(msg));
+        }
+        state.pending.insert(id, Pending { msg, notify });
+        Ok(ack)
+    }
+}
+
+impl<W, F> Shared<W, F>
+where
+    W: Serialize + DeserializeOwned + Send + 'static,
+    F: WireFormat,
+{
+    fn handle_frame(&self, frame: Frame) {
+        let mut state = self.state.lock().unwrap();
+        let (id, error): (_, Option<fn(_) -> _>) = match frame {
+            Frame::Delivered { id } => (id, None),
+            Frame::NotAccepted { id } => (id, Some(DynSendError::NotAccepted)),
+            Frame::Closed { id } => (id, Some(DynSendError::Closed)),
+            Frame::Reply { id, reply } => {
+                if let Some(handler) = state.replies.remove(&id) {
+                    handler(&reply);
+                }
+                return;
+            }
+            Frame::Send { .. } | Frame::Hello { .. } => return,
+        };
+        let Some(Pending { msg, notify }) = state.pending.remove(&id) else {
+            return;
+        };
+        match error {
+            None => {
+                if let Some(handler) = self.registry.take_reply(msg) {
+                    state.replies.insert(id, handler);
+                }
+                if let Some(notify) = notify {
+                    let _ = notify.send(Ok(()));
+                }
+            }
+            Some(error) => {
+                if let Some(notify) = notify {
+                    let _ = notify.send(Err(error(msg)));
+                }
+            }
+        }
+    }
+
+    /// Exchange [`Frame::Hello`]s with the server, and store the messages it accepts.
+    ///
+    /// The server only accepts messages that are accepted by its sender, and that are
+    /// registered with the same version on both sides.
+    pub(super) async fn handshake(
+        &self,
+        incoming: &mut FrameStream,
+        outgoing: &mut FrameSink,
+    ) -> io::Result<()> {
+        let messages = self
+            .registry
+            .versions()
+            .map(|(name, version)| (name.to_string(), version))
+            .collect();
+        outgoing
+            .send(Frame::Hello {
+                protocol: PROTOCOL_VERSION,
+                messages,
+            })
+            .await?;
+
+        let Some(Frame::Hello { protocol, messages }) = incoming.next().await.transpose()? else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "expected handshake",
+            ));
+        };
+        if protocol != PROTOCOL_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported protocol version {protocol}"),
+            ));
+        }
+
+        let accepted = self
+            .registry
+            .names()
+            .filter(|name| messages.iter().any(|(accepted, _)| accepted == name))
+            .collect::<HashSet<_>>();
+        let mut state = self.state.lock().unwrap();
+        if accepted != state.accepted {
+            state.members = self.registry.interned_members(accepted.iter().copied());
+            state.accepted = accepted;
+        }
+        Ok(())
+    }
+
+    /// Drive a connection until it is lost, setting `dropped` once all senders have been dropped.
+    ///
+    /// Once all senders are dropped, the outgoing frames are closed, and the connection is kept
+    /// open until the server closes it, so that outstanding replies can still be received.
+    pub(super) async fn run(
+        &self,
+        mut incoming: FrameStream,
+        mut outgoing: FrameSink,
+        frames_rx: &mut mpsc::UnboundedReceiver<Frame>,
+        dropped: &AtomicBool,
+    ) {
+        let write = async {
+            while let Some(frame) = frames_rx.recv().await {
+                if outgoing.send(frame).await.is_err() {
+                    return;
+                }
+            }
+            dropped.store(true, Ordering::Relaxed);
+            if outgoing.close().await.is_ok() {
+                futures::future::pending().await
+            }
+        };
+        let read = async {
+            while let Some(Ok(frame)) = incoming.next().await {
+                self.handle_frame(frame);
+            }
+        };
+        futures::pin_mut!(write, read);
+        futures::future::select(write, read).await;
+    }
+
+    /// Fail all messages that have not been acknowledged, and discard the frames that have not
+    /// been written yet.
+    pub(super) fn disconnect(&self, frames_rx: &mut mpsc::UnboundedReceiver<Frame>) {
+        let mut state = self.state.lock().unwrap();
+        while frames_rx.try_recv().is_ok() {}
+        state.fail_pending();
+    }
+
+    /// Close the sender, failing all messages that have not been acknowledged.
+    pub(super) fn close(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.closed = true;
+        state.fail_pending();
+    }
+}
+
+impl<W> State<W> {
+    fn fail_pending(&mut self) {
+        self.replies.clear();
+        for (_, Pending { msg, notify }) in self.pending.drain() {
+            if let Some(notify) = notify {
+                let _ = notify.send(Err(DynSendError::Closed(msg)));
+            }
+        }
+    }
+}
+
+impl<W, F> IsSender for RemoteSender<W, F> {
+    type With = W;
+
+    fn is_closed(&self) -> bool {
+        self.inner.shared.state.lock().unwrap().closed
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        None
+    }
+
+    fn len(&self) -> usize {
+        self.inner.shared.state.lock().unwrap().pending.len()
+    }
+
+    fn receiver_count(&self) -> usize {
+        match self.is_closed() {
+            true => 0,
+            false => 1,
+        }
+    }
+
+    fn sender_count(&self) -> usize {
+        Arc::strong_count(&self.inner)
+    }
+}
+
+impl<W, F> IsDynSender for RemoteSender<W, F>
+where
+    W: Serialize + DeserializeOwned + Send + 'static,
+    F: WireFormat,
+{
+    fn dyn_send_boxed_msg_with(
+        &self,
+        msg: BoxedMsg<Self::With>,
+    ) -> BoxFuture<'_, Result<(), DynSendError<BoxedMsg<Self::With>>>> {
+        let ack = self.send_frame(msg, true);
+        Box::pin(async move {
+            let ack = ack
+                .map_err(|e| match e {
+                    DynTrySendError::NotAccepted(msg) => DynSendError::NotAccepted(msg),
+                    DynTrySendError::Closed(msg) | DynTrySendError::Full(msg) => {
+                        DynSendError::Closed(msg)
+                    }
+                })?
+                .expect("waiting for acknowledgement");
+            // The connection closes all pending messages before dropping the notifier.
+            ack.await.expect("acknowledgement was dropped")
+        })
+    }
+
+    #[cfg(blocking)]
+    fn dyn_send_boxed_msg_blocking_with(
+        &self,
+        msg: BoxedMsg<Self::With>,
+    ) -> Result<(), DynSendError<BoxedMsg<Self::With>>> {
+        crate::block_on(self.dyn_send_boxed_msg_with(msg))
+    }
+
+    /// Send the message without waiting for the acknowledgement of the server.
+    ///
+    /// Only errors that can be detected locally are returned: if the message is not accepted by
+    /// the server, it is dropped silently.
+    fn dyn_try_send_boxed_msg_with(
+        &self,
+        msg: BoxedMsg<Self::With>,
+    ) -> Result<(), DynTrySendError<BoxedMsg<Self::With>>> {
+        self.send_frame(msg, false).map(|_| ())
+    }
+
+    /// The messages that were accepted by the server during the handshake.
+    ///
+    /// Only their [`TypeId`]s are known, so [`IsDynSender::protocol_info`] returns `None`.
+    fn members(&self) -> &'static [TypeId] {
+        self.inner.shared.state.lock().unwrap().members
+    }
+
+    fn clone_boxed(&self) -> Box<dyn IsDynSender<With = Self::With>> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+impl<W, F, R> TryIntoDynSender<R, W> for RemoteSender<W, F>
+where
+    W: Serialize + DeserializeOwned + Send + 'static,
+    F: WireFormat,
+    R: type_sets::Members + 'static,
+{
+    fn try_into_dyn_sender(self) -> Result<DynSender<R, W>, Self> {
+        if R::members().iter().all(|t| self.members().contains(t)) {
+            Ok(DynSender::new_unchecked(self))
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl<W, F> Clone for RemoteSender<W, F> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<W, F: WireFormat> Debug for RemoteSender<W, F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RemoteSender")
+            .field("registry", &self.inner.shared.registry)
+            .finish()
+    }
+}
@@ -0,0 +1,224 @@
+use crate::WireFormat;
+use futures::{future::BoxFuture, ready, stream::BoxStream, FutureExt, Sink};
+use serde::{Deserialize, Serialize};
+use std::{
+    future::Future,
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// The maximum size of a single frame, to protect against malicious length-prefixes.
+const MAX_FRAME_LEN: u32 = 64 * 1024 * 1024;
+
+/// The version of the frames, which is checked during the handshake.
+pub(crate) const PROTOCOL_VERSION: u32 = 1;
+
+/// A frame that is sent between a [`RemoteSender`](super::RemoteSender) and a server.
+///
+/// Every connection starts with an exchange of [`Frame::Hello`]s. After that, every frame
+/// carries the correlation id of the message it belongs to.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) enum Frame {
+    /// Client to server: the names and versions of the messages in the registry.
+    ///
+    /// Server to client: the subset of those messages that is accepted by the server.
+    Hello {
+        protocol: u32,
+        messages: Vec<(String, u32)>,
+    },
+    /// Client to server: a message serialized by the registry.
+    Send { id: u64, msg: Vec<u8> },
+    /// Server to client: the message was delivered to the local sender.
+    Delivered { id: u64 },
+    /// Server to client: the message was not accepted.
+    NotAccepted { id: u64 },
+    /// Server to client: the local channel is closed.
+    Closed { id: u64 },
+    /// Server to client: the reply to a delivered request.
+    Reply { id: u64, reply: Vec<u8> },
+}
+
+impl Frame {
+    pub(crate) fn encode<F: WireFormat>(&self) -> io::Result<Vec<u8>> {
+        F::serialize(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub(crate) fn decode<F: WireFormat>(bytes: &[u8]) -> io::Result<Self> {
+        F::deserialize(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// The incoming frames of a connection, ending when the connection is closed.
+pub(crate) type FrameStream = BoxStream<'static, io::Result<Frame>>;
+
+/// The outgoing frames of a connection.
+pub(crate) type FrameSink = Pin<Box<dyn Sink<Frame, Error = io::Error> + Send>>;
+
+/// Split a byte-stream into length-prefixed frames.
+pub(crate) fn split<F: WireFormat, S>(stream: S) -> (FrameStream, FrameSink)
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let (reader, writer) = tokio::io::split(stream);
+    let incoming = futures::stream::try_unfold(reader, |mut reader| async move {
+        Ok(read_frame::<F, _>(&mut reader)
+            .await?
+            .map(|frame| (frame, reader)))
+    });
+    let outgoing = unfold_closing(
+        writer,
+        |mut writer, frame| async move {
+            write_frame::<F, _>(&mut writer, &frame).await?;
+            Ok(writer)
+        },
+        |mut writer| async move { writer.shutdown().await },
+    );
+    (Box::pin(incoming), outgoing)
+}
+
+/// Like [`futures::sink::unfold`], but the state is closed with `close` when the sink is closed,
+/// so that the other side sees the end of the connection.
+pub(crate) fn unfold_closing<T, Wr, WrFut, Cl, ClFut>(
+    state: T,
+    mut write: Wr,
+    close: Cl,
+) -> FrameSink
+where
+    T: Send + 'static,
+    Wr: FnMut(T, Frame) -> WrFut + Send + 'static,
+    WrFut: Future<Output = io::Result<T>> + Send + 'static,
+    Cl: FnOnce(T) -> ClFut + Send + 'static,
+    ClFut: Future<Output = io::Result<()>> + Send + 'static,
+{
+    Box::pin(UnfoldClosing {
+        state: Unfold::Idle(state),
+        write: Box::new(move |state, frame| write(state, frame).boxed()),
+        close: Some(Box::new(move |state| close(state).boxed())),
+    })
+}
+
+struct UnfoldClosing<T> {
+    state: Unfold<T>,
+    write: Box<dyn FnMut(T, Frame) -> BoxFuture<'static, io::Result<T>> + Send>,
+    close: Option<Box<dyn FnOnce(T) -> BoxFuture<'static, io::Result<()>> + Send>>,
+}
+
+enum Unfold<T> {
+    Idle(T),
+    Writing(BoxFuture<'static, io::Result<T>>),
+    Closing(BoxFuture<'static, io::Result<()>>),
+    Closed,
+}
+
+impl<T> UnfoldClosing<T> {
+    /// Wait until the frame that is being written has been written.
+    fn poll_written(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match &mut self.state {
+            Unfold::Idle(_) => Poll::Ready(Ok(())),
+            Unfold::Writing(fut) => {
+                let written = ready!(fut.as_mut().poll(cx));
+                match written {
+                    Ok(state) => {
+                        self.state = Unfold::Idle(state);
+                        Poll::Ready(Ok(()))
+                    }
+                    Err(e) => {
+                        self.state = Unfold::Closed;
+                        Poll::Ready(Err(e))
+                    }
+                }
+            }
+            Unfold::Closing(_) | Unfold::Closed => {
+                Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()))
+            }
+        }
+    }
+}
+
+impl<T> Unpin for UnfoldClosing<T> {}
+
+impl<T> Sink<Frame> for UnfoldClosing<T> {
+    type Error = io::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().poll_written(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, frame: Frame) -> io::Result<()> {
+        let this = self.get_mut();
+        match std::mem::replace(&mut this.state, Unfold::Closed) {
+            Unfold::Idle(state) => {
+                this.state = Unfold::Writing((this.write)(state, frame));
+                Ok(())
+            }
+            _ => panic!("start_send called without poll_ready"),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().poll_written(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            match std::mem::replace(&mut this.state, Unfold::Closed) {
+                Unfold::Idle(state) => match this.close.take() {
+                    Some(close) => this.state = Unfold::Closing(close(state)),
+                    None => return Poll::Ready(Ok(())),
+                },
+                Unfold::Writing(fut) => {
+                    this.state = Unfold::Writing(fut);
+                    ready!(this.poll_written(cx))?;
+                }
+                Unfold::Closing(mut fut) => {
+                    let closed = fut.as_mut().poll(cx);
+                    if closed.is_pending() {
+                        this.state = Unfold::Closing(fut);
+                    }
+                    return closed;
+                }
+                Unfold::Closed => return Poll::Ready(Ok(())),
+            }
+        }
+    }
+}
+
+/// Write a length-prefixed frame.
+pub(crate) async fn write_frame<F: WireFormat, S: AsyncWrite + Unpin>(
+    stream: &mut S,
+    frame: &Frame,
+) -> io::Result<()> {
+    let bytes = frame.encode::<F>()?;
+    let len = u32::try_from(bytes.len())
+        .ok()
+        .filter(|len| *len <= MAX_FRAME_LEN)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "frame too large"))?;
+    stream.write_all(&len.to_be_bytes()).await?;
+    stream.write_all(&bytes).await?;
+    stream.flush().await
+}
+
+/// Read a length-prefixed frame, returning `None` if the stream was closed cleanly.
+pub(crate) async fn read_frame<F: WireFormat, S: AsyncRead + Unpin>(
+    stream: &mut S,
+) -> io::Result<Option<Frame>> {
+    let mut len = [0; 4];
+    match stream.read_exact(&mut len).await {
+        Ok(_) => (),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let len = u32::from_be_bytes(len);
+    if len > MAX_FRAME_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "frame too large",
+        ));
+    }
+    let mut bytes = vec![0; len as usize];
+    stream.read_exact(&mut bytes).await?;
+    Frame::decode::<F>(&bytes).map(Some)
+}
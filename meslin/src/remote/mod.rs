@@ -0,0 +1,63 @@
+//! Sending messages to senders in other processes.
+//!
+//! A local sender can be exposed with [`serve`], after which a [`RemoteSender`] can connect to
+//! it. Messages are serialized using a [`MessageRegistry`](crate::MessageRegistry), and both
+//! sides must use the same [`WireFormat`](crate::WireFormat). The [`RemoteSender`] implements
+//! [`IsDynSender`], and can therefore be converted into a [`struct@DynSender`] with
+//! [`TryIntoDynSender`].
+//!
+//! When connecting, the registries of both sides are compared in a handshake. Only messages that
+//! are registered with the same [`SerializableMessage::VERSION`](crate::SerializableMessage)
+//! on both sides, and that are accepted by the served sender, can be sent; all other messages
+//! are `NotAccepted`. The [`IsDynSender::members`] of a [`RemoteSender`] are the messages agreed
+//! upon in the handshake.
+//!
+//! Besides TCP, the following transports are available:
+//! - On Unix, [`serve_unix`] and [`RemoteSender::connect_unix`] communicate between processes
+//!   on the same host.
+//! - With the `remote-ws` feature, [`serve_ws`] and [`RemoteSender::connect_ws`] communicate
+//!   over WebSockets, with keepalive pings and reconnecting configured through [`WsConfig`].
+//!
+//! Every message is acknowledged by the server, so the errors returned by the [`RemoteSender`]
+//! are the same as those of the local sender. A lost connection is reported as `Closed`.
+//! Replies to a [`Request`] are sent back using the correlation id of the request.
+//!
+//! ```no_run
+//! # use meslin::{*, remote::*};
+//! # use std::sync::Arc;
+//! # #[derive(Debug, From, TryInto, DynProtocol)]
+//! # enum MyProtocol { A(Request<u32, String>) }
+//! # async fn example() -> std::io::Result<()> {
+//! let registry = Arc::new(MessageRegistry::new().with_request::<u32, String>("my-request"));
+//!
+//! // Expose the local sender on the server
+//! let (sender, receiver) = mpmc::unbounded::<MyProtocol>();
+//! let listener = tokio::net::TcpListener::bind("127.0.0.1:8000").await?;
+//! tokio::spawn(serve(listener, sender, registry.clone()));
+//!
+//! // Connect to it from the client
+//! let remote = RemoteSender::connect("127.0.0.1:8000", registry).await?;
+//! let remote: DynSender![Request<u32, String>] = remote.try_into_dyn_sender().unwrap();
+//! let reply = remote.request::<Request<u32, String>>(10u32).await.unwrap();
+//! # Ok(()) }
+//! ```
+#[allow(unused_imports)]
+use crate::*;
+
+mod frame;
+
+mod client;
+pub use client::*;
+
+mod server;
+pub use server::*;
+
+#[cfg(unix)]
+mod unix;
+#[cfg(unix)]
+pub use unix::*;
+
+#[cfg(feature = "remote-ws")]
+mod ws;
+#[cfg(feature = "remote-ws")]
+pub use ws::*;
@@ -0,0 +1,154 @@
+use super::frame::{self, Frame, FrameSink, FrameStream, PROTOCOL_VERSION};
+use crate::*;
+use futures::{SinkExt, StreamExt};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{any::TypeId, io, sync::Arc};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpListener,
+    sync::mpsc,
+};
+
+/// Expose the sender to [`RemoteSender`](super::RemoteSender)s connecting to the listener.
+///
+/// Every connection is handled on a separate task using [`serve_connection`]. This only
+/// returns when accepting a connection fails.
+pub async fn serve<S, W, F>(
+    listener: TcpListener,
+    sender: S,
+    registry: Arc<MessageRegistry<W, F>>,
+) -> io::Result<()>
+where
+    S: IsDynSender<With = W> + Clone + Send,
+    W: Serialize + DeserializeOwned + Send + 'static,
+    F: WireFormat,
+{
+    loop {
+        let (stream, _addr) = listener.accept().await?;
+        stream.set_nodelay(true)?;
+        tokio::spawn(serve_connection(stream, sender.clone(), registry.clone()));
+    }
+}
+
+/// Expose the sender to a single [`RemoteSender`](super::RemoteSender) connected through the
+/// stream.
+///
+/// Messages are delivered to the sender in the order they are received. This returns once the
+/// connection is closed and all replies have been sent.
+pub async fn serve_connection<C, S, W, F>(
+    stream: C,
+    sender: S,
+    registry: Arc<MessageRegistry<W, F>>,
+) -> io::Result<()>
+where
+    C: AsyncRead + AsyncWrite + Send + 'static,
+    S: IsDynSender<With = W>,
+    W: Serialize + DeserializeOwned + Send + 'static,
+    F: WireFormat,
+{
+    let (incoming, outgoing) = frame::split::<F, _>(stream);
+    serve_frames(incoming, outgoing, sender, registry).await
+}
+
+/// Serve a single connection of any transport.
+pub(super) async fn serve_frames<S, W, F>(
+    mut incoming: FrameStream,
+    mut outgoing: FrameSink,
+    sender: S,
+    registry: Arc<MessageRegistry<W, F>>,
+) -> io::Result<()>
+where
+    S: IsDynSender<With = W>,
+    W: Serialize + DeserializeOwned + Send + 'static,
+    F: WireFormat,
+{
+    handshake(&mut incoming, &mut outgoing, sender.members(), &registry).await?;
+    let (frames, mut frames_rx) = mpsc::unbounded_channel::<Frame>();
+
+    let writer = tokio::spawn(async move {
+        while let Some(frame) = frames_rx.recv().await {
+            outgoing.send(frame).await?;
+        }
+        outgoing.close().await
+    });
+
+    let result = async move {
+        while let Some(frame) = incoming.next().await.transpose()? {
+            let Frame::Send { id, msg } = frame else {
+                continue;
+            };
+            let (msg, reply) = match registry.deserialize_with_reply(&msg) {
+                Ok(msg) => msg,
+                Err(_) => {
+                    let _ = frames.send(Frame::NotAccepted { id });
+                    continue;
+                }
+            };
+            let delivery = sender.dyn_send_boxed_msg_with(msg);
+            let ack = match delivery.await {
+                Ok(()) => Frame::Delivered { id },
+                Err(DynSendError::NotAccepted(_)) => Frame::NotAccepted { id },
+                Err(DynSendError::Closed(_)) => Frame::Closed { id },
+            };
+            let delivered = matches!(ack, Frame::Delivered { .. });
+            let _ = frames.send(ack);
+
+            if let (true, Some(reply)) = (delivered, reply) {
+                let frames = frames.clone();
+                tokio::spawn(async move {
+                    if let Some(Ok(reply)) = reply.await {
+                        let _ = frames.send(Frame::Reply { id, reply });
+                    }
+                });
+            }
+        }
+        Ok::<_, io::Error>(())
+    }
+    .await;
+
+    let written = writer.await.map_err(io::Error::other)?;
+    result.and(written)
+}
+
+/// Reply to the [`Frame::Hello`] of the client with the messages that are accepted: those that
+/// are accepted by the sender, and registered with the same version on both sides.
+async fn handshake<W, F>(
+    incoming: &mut FrameStream,
+    outgoing: &mut FrameSink,
+    members: &[TypeId],
+    registry: &MessageRegistry<W, F>,
+) -> io::Result<()>
+where
+    W: Serialize + DeserializeOwned + Send + 'static,
+    F: WireFormat,
+{
+    let Some(Frame::Hello { protocol, messages }) = incoming.next().await.transpose()? else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "expected handshake",
+        ));
+    };
+    let messages = messages
+        .into_iter()
+        .filter(|(name, version)| {
+            registry.version_of(name) == Some(*version)
+                && registry
+                    .type_id_of(name)
+                    .is_some_and(|type_id| members.contains(&type_id))
+        })
+        .collect();
+    outgoing
+        .send(Frame::Hello {
+            protocol: PROTOCOL_VERSION,
+            messages,
+        })
+        .await?;
+
+    match protocol == PROTOCOL_VERSION {
+        true => Ok(()),
+        false => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unsupported protocol version {protocol}"),
+        )),
+    }
+}
@@ -0,0 +1,36 @@
+use super::*;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{io, path::Path, sync::Arc};
+use tokio::net::{UnixListener, UnixStream};
+
+impl<W, F> RemoteSender<W, F>
+where
+    W: Serialize + DeserializeOwned + Send + 'static,
+    F: WireFormat,
+{
+    /// Connect to a remote server over a Unix domain socket.
+    pub async fn connect_unix(
+        path: impl AsRef<Path>,
+        registry: Arc<MessageRegistry<W, F>>,
+    ) -> io::Result<Self> {
+        let stream = UnixStream::connect(path).await?;
+        Self::from_stream(stream, registry).await
+    }
+}
+
+/// Like [`serve`], but accepts connections on a Unix domain socket.
+pub async fn serve_unix<S, W, F>(
+    listener: UnixListener,
+    sender: S,
+    registry: Arc<MessageRegistry<W, F>>,
+) -> io::Result<()>
+where
+    S: IsDynSender<With = W> + Clone + Send,
+    W: Serialize + DeserializeOwned + Send + 'static,
+    F: WireFormat,
+{
+    loop {
+        let (stream, _addr) = listener.accept().await?;
+        tokio::spawn(serve_connection(stream, sender.clone(), registry.clone()));
+    }
+}
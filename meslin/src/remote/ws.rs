@@ -0,0 +1,348 @@
+use super::{
+    client::Shared,
+    frame::{unfold_closing, Frame, FrameSink, FrameStream},
+    server::serve_frames,
+    RemoteSender,
+};
+use crate::*;
+use futures::{
+    future::{self, Either},
+    lock::Mutex as AsyncMutex,
+    stream::SplitSink,
+    Sink, SinkExt, Stream, StreamExt, TryStreamExt,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fmt::Debug,
+    io,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+};
+use tokio_tungstenite::{
+    tungstenite::{self, Message},
+    MaybeTlsStream, WebSocketStream,
+};
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Configuration of a WebSocket connection, used by [`RemoteSender::connect_ws_with`].
+///
+/// By default, a ping is sent every 30 seconds and the sender is closed as soon as the
+/// connection is lost.
+pub struct WsConfig {
+    keepalive: Option<Duration>,
+    reconnect: Option<Box<dyn FnMut(u32) -> Option<Duration> + Send>>,
+    on_connect: Option<Box<dyn FnMut() + Send>>,
+}
+
+impl WsConfig {
+    pub fn new() -> Self {
+        Self {
+            keepalive: Some(Duration::from_secs(30)),
+            reconnect: None,
+            on_connect: None,
+        }
+    }
+
+    /// Set the interval at which pings are sent, or disable them with `None`.
+    ///
+    /// If nothing is received from the server for two intervals, the connection is considered
+    /// lost.
+    pub fn keepalive(mut self, interval: Option<Duration>) -> Self {
+        self.keepalive = interval;
+        self
+    }
+
+    /// Reconnect when the connection is lost.
+    ///
+    /// The hook is called with the number of failed attempts so far, and returns the delay before
+    /// the next attempt, or `None` to give up and close the sender. Messages that were not
+    /// acknowledged when the connection was lost fail as `Closed`; messages sent while
+    /// reconnecting are sent once the connection is restored.
+    pub fn reconnect(mut self, hook: impl FnMut(u32) -> Option<Duration> + Send + 'static) -> Self {
+        self.reconnect = Some(Box::new(hook));
+        self
+    }
+
+    /// Call the hook whenever the connection has been restored.
+    pub fn on_connect(mut self, hook: impl FnMut() + Send + 'static) -> Self {
+        self.on_connect = Some(Box::new(hook));
+        self
+    }
+}
+
+impl Default for WsConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for WsConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WsConfig")
+            .field("keepalive", &self.keepalive)
+            .field("reconnect", &self.reconnect.is_some())
+            .field("on_connect", &self.on_connect.is_some())
+            .finish()
+    }
+}
+
+impl<W, F> RemoteSender<W, F>
+where
+    W: Serialize + DeserializeOwned + Send + 'static,
+    F: WireFormat,
+{
+    /// Connect to a remote server over a WebSocket, using the default [`WsConfig`].
+    pub async fn connect_ws(
+        url: impl AsRef<str>,
+        registry: Arc<MessageRegistry<W, F>>,
+    ) -> io::Result<Self> {
+        Self::connect_ws_with(url, registry, WsConfig::new()).await
+    }
+
+    /// Connect to a remote server over a WebSocket.
+    ///
+    /// This spawns the task that drives the connection on the current tokio runtime. Only
+    /// the first connection attempt is reported as an error; later attempts are handled by
+    /// [`WsConfig::reconnect`].
+    pub async fn connect_ws_with(
+        url: impl AsRef<str>,
+        registry: Arc<MessageRegistry<W, F>>,
+        mut config: WsConfig,
+    ) -> io::Result<Self> {
+        let url = url.as_ref().to_string();
+        let (sender, shared, mut frames_rx) = Self::unconnected(registry);
+        let mut connection = connect(&url, &shared).await?;
+
+        tokio::spawn(async move {
+            loop {
+                let dropped = drive(&shared, connection, config.keepalive, &mut frames_rx).await;
+                shared.disconnect(&mut frames_rx);
+                if dropped {
+                    break;
+                }
+                match reconnect(&url, &shared, &mut config).await {
+                    Some(reconnected) => connection = reconnected,
+                    None => break,
+                }
+            }
+            shared.close();
+        });
+
+        Ok(sender)
+    }
+}
+
+/// Like [`serve`](super::serve), but accepts WebSocket connections on the listener.
+///
+/// The WebSocket protocol carries the same frames as the other transports, one per binary
+/// message.
+pub async fn serve_ws<S, W, F>(
+    listener: TcpListener,
+    sender: S,
+    registry: Arc<MessageRegistry<W, F>>,
+) -> io::Result<()>
+where
+    S: IsDynSender<With = W> + Clone + Send,
+    W: Serialize + DeserializeOwned + Send + 'static,
+    F: WireFormat,
+{
+    loop {
+        let (stream, _addr) = listener.accept().await?;
+        stream.set_nodelay(true)?;
+        tokio::spawn(serve_ws_connection(
+            stream,
+            sender.clone(),
+            registry.clone(),
+        ));
+    }
+}
+
+/// Like [`serve_connection`](super::serve_connection), but performs a WebSocket handshake on
+/// the stream first.
+pub async fn serve_ws_connection<C, S, W, F>(
+    stream: C,
+    sender: S,
+    registry: Arc<MessageRegistry<W, F>>,
+) -> io::Result<()>
+where
+    C: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    S: IsDynSender<With = W>,
+    W: Serialize + DeserializeOwned + Send + 'static,
+    F: WireFormat,
+{
+    let ws = tokio_tungstenite::accept_async(stream)
+        .await
+        .map_err(io::Error::other)?;
+    let (sink, stream) = ws.split();
+    let outgoing = sink
+        .sink_map_err(io::Error::other)
+        .with(|frame: Frame| future::ready(frame.encode::<F>().map(Message::Binary)));
+    serve_frames(
+        incoming::<F, _>(stream),
+        Box::pin(outgoing),
+        sender,
+        registry,
+    )
+    .await
+}
+
+/// An established connection, of which the handshake has been completed.
+struct Connection {
+    incoming: FrameStream,
+    outgoing: FrameSink,
+    sink: Arc<AsyncMutex<SplitSink<WsStream, Message>>>,
+    last_seen: Arc<Mutex<Instant>>,
+}
+
+/// Connect and perform the handshake.
+async fn connect<W, F>(url: &str, shared: &Shared<W, F>) -> io::Result<Connection>
+where
+    W: Serialize + DeserializeOwned + Send + 'static,
+    F: WireFormat,
+{
+    let (ws, _response) = tokio_tungstenite::connect_async(url)
+        .await
+        .map_err(io::Error::other)?;
+    let (sink, stream) = ws.split();
+    let sink = Arc::new(AsyncMutex::new(sink));
+    let last_seen = Arc::new(Mutex::new(Instant::now()));
+
+    let mut incoming = incoming::<F, _>(stream.inspect({
+        let last_seen = last_seen.clone();
+        move |_| *last_seen.lock().unwrap() = Instant::now()
+    }));
+    let mut outgoing = unfold_closing(
+        sink.clone(),
+        |sink, frame| async move {
+            let msg = Message::Binary(frame.encode::<F>()?);
+            sink.lock()
+                .await
+                .send(msg)
+                .await
+                .map_err(io::Error::other)?;
+            Ok(sink)
+        },
+        |sink| async move { sink.lock().await.close().await.map_err(io::Error::other) },
+    );
+    shared.handshake(&mut incoming, &mut outgoing).await?;
+
+    Ok(Connection {
+        incoming,
+        outgoing,
+        sink,
+        last_seen,
+    })
+}
+
+/// Wait for the reconnect-hook and reconnect, returning `None` if the hook gave up.
+async fn reconnect<W, F>(
+    url: &str,
+    shared: &Shared<W, F>,
+    config: &mut WsConfig,
+) -> Option<Connection>
+where
+    W: Serialize + DeserializeOwned + Send + 'static,
+    F: WireFormat,
+{
+    let hook = config.reconnect.as_mut()?;
+    let mut attempt = 0;
+    loop {
+        tokio::time::sleep(hook(attempt)?).await;
+        if let Ok(connection) = connect(url, shared).await {
+            if let Some(on_connect) = &mut config.on_connect {
+                on_connect();
+            }
+            return Some(connection);
+        }
+        attempt += 1;
+    }
+}
+
+/// Drive the connection until it is lost, returning `true` if all senders have been dropped.
+async fn drive<W, F>(
+    shared: &Shared<W, F>,
+    connection: Connection,
+    keepalive: Option<Duration>,
+    frames_rx: &mut mpsc::UnboundedReceiver<Frame>,
+) -> bool
+where
+    W: Serialize + DeserializeOwned + Send + 'static,
+    F: WireFormat,
+{
+    let Connection {
+        incoming,
+        outgoing,
+        sink,
+        last_seen,
+    } = connection;
+    let dropped = AtomicBool::new(false);
+    let run = shared.run(incoming, outgoing, frames_rx, &dropped);
+
+    if let Some(interval) = keepalive {
+        let ping = ping(sink, last_seen, interval, &dropped);
+        futures::pin_mut!(run, ping);
+        // Once all senders are dropped, the pings stop and the server closes the connection.
+        if let Either::Right(((), run)) = future::select(run, ping).await {
+            if dropped.load(Ordering::Relaxed) {
+                run.await;
+            }
+        }
+    } else {
+        run.await;
+    }
+    dropped.load(Ordering::Relaxed)
+}
+
+/// Send pings until the connection is lost, nothing was received for two intervals, or all
+/// senders have been dropped.
+async fn ping<S>(
+    sink: Arc<AsyncMutex<S>>,
+    last_seen: Arc<Mutex<Instant>>,
+    interval: Duration,
+    dropped: &AtomicBool,
+) where
+    S: Sink<Message> + Unpin,
+{
+    loop {
+        tokio::time::sleep(interval).await;
+        if dropped.load(Ordering::Relaxed) || last_seen.lock().unwrap().elapsed() > interval * 2 {
+            return;
+        }
+        if sink
+            .lock()
+            .await
+            .send(Message::Ping(Vec::new()))
+            .await
+            .is_err()
+        {
+            return;
+        }
+    }
+}
+
+/// The frames received as binary messages, ending when the connection is closed.
+fn incoming<F: WireFormat, S>(stream: S) -> FrameStream
+where
+    S: Stream<Item = Result<Message, tungstenite::Error>> + Send + 'static,
+{
+    Box::pin(
+        stream
+            .map_err(io::Error::other)
+            .try_take_while(|msg| future::ready(Ok(!msg.is_close())))
+            .try_filter_map(|msg| async move {
+                match msg {
+                    Message::Binary(bytes) => Frame::decode::<F>(&bytes).map(Some),
+                    _ => Ok(None),
+                }
+            }),
+    )
+}
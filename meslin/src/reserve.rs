@@ -0,0 +1,472 @@
+use crate::{wakers::*, *};
+use std::{
+    fmt::Debug,
+    future::poll_fn,
+    sync::{Arc, Mutex},
+    task::Poll,
+};
+
+/// Trait for senders of which space in the channel can be reserved ahead of sending, with a
+/// [`Permit`].
+///
+/// A reserved slot counts towards the capacity of the channel until the permit is used or
+/// dropped. This makes it possible to only send when space is guaranteed, as [`try_send_all`]
+/// does to deliver a message to either all or none of multiple channels.
+///
+/// The [`leveled`](crate::leveled) and [`conflate`](crate::conflate) channels reserve space
+/// themselves. Any other sender can reserve space when it is wrapped in a [`ReservableSender`].
+///
+/// Like [`IsStaticSender`], the required methods take `this: &Self`, so that they do not clash
+/// with the methods of the sender.
+pub trait Reserve: IsStaticSender {
+    /// Reserve a slot in the channel, failing if the channel is closed or full.
+    fn try_reserve_slot(this: &Self) -> Result<(), TrySendError<()>>;
+
+    /// Send the protocol into a slot that was reserved with [`Reserve::try_reserve_slot`],
+    /// releasing the reservation. Fails if the channel was closed in the meantime, or if the slot
+    /// was taken by a send that does not observe the reservation, see [`ReservableSender`].
+    fn send_reserved_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        with: Self::With,
+    ) -> Result<(), TrySendError<(Self::Protocol, Self::With)>>;
+
+    /// Release a slot that was reserved with [`Reserve::try_reserve_slot`] without sending.
+    fn release_slot(this: &Self);
+
+    /// Reserve a slot in the channel, returning a [`Permit`] that sends into it.
+    fn try_reserve(&self) -> Result<Permit<'_, Self>, TrySendError<()>>
+    where
+        Self: Sized,
+    {
+        Self::try_reserve_slot(self)?;
+        Ok(Permit {
+            sender: self,
+            used: false,
+        })
+    }
+}
+
+/// A slot that is reserved in a channel with [`Reserve::try_reserve`]. The slot is released when
+/// the permit is dropped without sending.
+pub struct Permit<'a, S: Reserve> {
+    sender: &'a S,
+    used: bool,
+}
+
+impl<S: Reserve> Permit<'_, S> {
+    /// Send the protocol with a custom value into the reserved slot.
+    pub fn send_protocol_with(
+        mut self,
+        protocol: S::Protocol,
+        with: S::With,
+    ) -> Result<(), TrySendError<(S::Protocol, S::With)>> {
+        self.used = true;
+        S::send_reserved_with(self.sender, protocol, with)
+    }
+
+    /// Send the message with a custom value into the reserved slot.
+    pub fn send_msg_with<M>(self, msg: M, with: S::With) -> Result<(), TrySendError<(M, S::With)>>
+    where
+        S::Protocol: From<M> + TryInto<M>,
+    {
+        self.send_protocol_with(S::Protocol::from(msg), with)
+            .map_err(|e| e.map(protocol_into_msg))
+    }
+
+    /// Send the message using a default value into the reserved slot.
+    pub fn send_msg<M>(self, msg: M) -> Result<(), TrySendError<M>>
+    where
+        S::Protocol: From<M> + TryInto<M>,
+        S::With: Default,
+    {
+        self.send_msg_with(msg, Default::default())
+            .map_err(|e| e.map(|(msg, _)| msg))
+    }
+}
+
+impl<S: Reserve> Drop for Permit<'_, S> {
+    fn drop(&mut self) {
+        if !self.used {
+            S::release_slot(self.sender);
+        }
+    }
+}
+
+impl<S: Reserve> Debug for Permit<'_, S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Permit").finish_non_exhaustive()
+    }
+}
+
+/// Trait for senders that can reserve a slot for the message `M`, which is sent with the default
+/// `with`-value.
+///
+/// It is implemented for all senders that implement [`Reserve`], and can be used as a trait
+/// object, so that [`try_send_all`] accepts senders of different types.
+pub trait ReserveMsg<M> {
+    /// Reserve a slot in the channel, returning a [`MsgPermit`] that sends `M` into it.
+    fn try_reserve_msg(&self) -> Result<MsgPermit<'_, M>, TrySendError<()>>;
+}
+
+impl<S, M> ReserveMsg<M> for S
+where
+    S: Reserve,
+    S::Protocol: From<M> + TryInto<M>,
+    S::With: Default,
+{
+    fn try_reserve_msg(&self) -> Result<MsgPermit<'_, M>, TrySendError<()>> {
+        let permit = self.try_reserve()?;
+        Ok(MsgPermit {
+            send: Box::new(move |msg| permit.send_msg(msg)),
+        })
+    }
+}
+
+/// A [`Permit`] for the message `M`, returned by [`ReserveMsg::try_reserve_msg`], of which the
+/// type of the sender is erased. The slot is released when the permit is dropped without sending.
+pub struct MsgPermit<'a, M> {
+    send: Box<dyn FnOnce(M) -> Result<(), TrySendError<M>> + 'a>,
+}
+
+impl<M> MsgPermit<'_, M> {
+    /// Send the message using a default value into the reserved slot.
+    pub fn send(self, msg: M) -> Result<(), TrySendError<M>> {
+        (self.send)(msg)
+    }
+}
+
+impl<M> Debug for MsgPermit<'_, M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MsgPermit").finish_non_exhaustive()
+    }
+}
+
+/// Send a clone of the message to all senders, but only if all of their channels currently have
+/// space; otherwise it is sent to none of them.
+///
+/// This is needed when a message must be mirrored consistently to multiple consumers. A slot is
+/// first reserved in every channel, and only sent into once all reservations succeeded. If one
+/// fails, its error is returned and all reservations are released. The senders can be of
+/// different types, as long as they implement [`ReserveMsg<M>`].
+///
+/// A channel can still close after its slot was reserved, in which case the message is delivered
+/// to all other channels and [`TrySendError::Closed`] is returned.
+///
+/// ```
+/// # use meslin::*;
+/// # #[cfg(feature = "leveled")] {
+/// let (sender1, mut receiver1) = leveled::bounded::<u32>(1);
+/// let (sender2, receiver2) = mpmc::bounded::<u32>(2);
+/// let sender2 = sender2.reservable();
+/// try_send_all(&[&sender1, &sender2], 1u32).unwrap();
+///
+/// // The first channel is full, so the message is sent to neither.
+/// assert_eq!(try_send_all(&[&sender1, &sender2], 2u32), Err(TrySendError::Full(2)));
+/// assert_eq!(receiver1.try_recv().unwrap().0, 1);
+/// assert_eq!(receiver2.try_recv().unwrap(), 1);
+/// assert!(receiver2.try_recv().is_err());
+/// # }
+/// ```
+pub fn try_send_all<M: Clone>(
+    senders: &[&dyn ReserveMsg<M>],
+    msg: M,
+) -> Result<(), TrySendError<M>> {
+    let mut permits = Vec::with_capacity(senders.len());
+    for sender in senders {
+        match sender.try_reserve_msg() {
+            Ok(permit) => permits.push(permit),
+            Err(e) => return Err(e.map(|()| msg)),
+        }
+    }
+
+    let mut failed = None;
+    for permit in permits {
+        if let Err(e) = permit.send(msg.clone()) {
+            failed = failed.or(Some(e.map(|_| ())));
+        }
+    }
+    match failed {
+        Some(e) => Err(e.map(|()| msg)),
+        None => Ok(()),
+    }
+}
+
+/// Like [`try_send_all`], but sends the message with a custom value, to senders of the same type.
+pub fn try_send_all_with<S, M>(
+    senders: &[&S],
+    msg: M,
+    with: S::With,
+) -> Result<(), TrySendError<(M, S::With)>>
+where
+    S: Reserve,
+    S::Protocol: From<M> + TryInto<M>,
+    S::With: Clone,
+    M: Clone,
+{
+    let mut permits = Vec::with_capacity(senders.len());
+    for sender in senders {
+        match sender.try_reserve() {
+            Ok(permit) => permits.push(permit),
+            Err(e) => return Err(e.map(|()| (msg, with))),
+        }
+    }
+
+    let mut failed = None;
+    for permit in permits {
+        if let Err(e) = permit.send_msg_with(msg.clone(), with.clone()) {
+            failed = failed.or(Some(e.map(|_| ())));
+        }
+    }
+    match failed {
+        Some(e) => Err(e.map(|()| (msg, with))),
+        None => Ok(()),
+    }
+}
+
+/// A wrapper around a sender, which implements [`Reserve`] for channels that can not reserve
+/// space themselves, like the [`mpmc`](crate::mpmc), [`broadcast`](crate::broadcast) and
+/// [`priority`](crate::priority) channels.
+///
+/// Created with [`IsSenderExt::reservable`]. The reservations are tracked by the wrapper, and
+/// shared by its clones. Sends through the wrapper leave the reserved slots free, and if the
+/// channel is otherwise full, they wait until the reservations are used or released. Sends that
+/// do not go through the wrapper can take a reserved slot, after which sending into it fails with
+/// [`TrySendError::Full`], so all senders of the channel should be reservable.
+///
+/// ```
+/// # use meslin::*;
+/// let (sender, receiver) = mpmc::bounded::<u32>(2);
+/// let sender = sender.reservable();
+/// let permit = sender.try_reserve().unwrap();
+/// sender.try_send::<u32>(1u32).unwrap();
+/// assert!(sender.try_send::<u32>(2u32).is_err());
+///
+/// permit.send_msg(3u32).unwrap();
+/// assert_eq!(receiver.drain().collect::<Vec<_>>(), [1, 3]);
+/// ```
+pub struct ReservableSender<T> {
+    sender: T,
+    reservations: Arc<Mutex<Reservations>>,
+}
+
+#[derive(Default)]
+struct Reservations {
+    /// The number of slots that are reserved.
+    reserved: usize,
+    /// The number of sends that wait for space in the channel itself. No slots can be reserved
+    /// meanwhile, since the channel would hand them to these sends.
+    waiting: usize,
+    /// The sends that wait until a reservation is used or released.
+    wakers: Wakers,
+}
+
+impl<T> ReservableSender<T> {
+    pub fn new(sender: T) -> Self {
+        Self {
+            sender,
+            reservations: Arc::default(),
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.sender
+    }
+
+    pub fn inner_ref(&self) -> &T {
+        &self.sender
+    }
+
+    /// The number of slots that are reserved.
+    pub fn reserved(&self) -> usize {
+        self.reservations.lock().unwrap().reserved
+    }
+
+    /// Returns `true` if the channel is full once the reserved slots are taken into account.
+    fn is_reserved_full(&self, reservations: &Reservations) -> bool
+    where
+        T: IsSender,
+    {
+        match self.sender.remaining() {
+            // Channels that drop protocols when they are full are never full.
+            Some(0) if !self.sender.is_full() => false,
+            remaining => remaining.is_some_and(|remaining| remaining <= reservations.reserved),
+        }
+    }
+
+    /// Release a reservation, waking the sends that wait for it.
+    fn release(&self, reservations: &mut Reservations) -> Vec<std::task::Waker> {
+        reservations.reserved -= 1;
+        reservations.wakers.take()
+    }
+}
+
+impl<T: Clone> Clone for ReservableSender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            reservations: self.reservations.clone(),
+        }
+    }
+}
+
+impl<T: Debug> Debug for ReservableSender<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reservations = self.reservations.lock().unwrap();
+        f.debug_struct("ReservableSender")
+            .field("sender", &self.sender)
+            .field("reserved", &reservations.reserved)
+            .field("waiting", &reservations.waiting)
+            .finish()
+    }
+}
+
+/// A send that waits for space in the channel itself, which blocks new reservations until it
+/// is dropped.
+struct Waiting<'a>(&'a Mutex<Reservations>);
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.0.lock().unwrap().waiting -= 1;
+    }
+}
+
+forward_broadcast_sender_impl!(<T> ReservableSender<T>);
+
+impl<T: IsSender> IsSender for ReservableSender<T> {
+    type With = T::With;
+
+    forward_sender_methods!(
+        is_closed,
+        capacity,
+        len,
+        receiver_count,
+        sender_count,
+        stats
+    );
+
+    fn is_full(&self) -> bool {
+        self.sender.is_full() || self.is_reserved_full(&self.reservations.lock().unwrap())
+    }
+
+    fn remaining(&self) -> Option<usize> {
+        let reserved = self.reservations.lock().unwrap().reserved;
+        self.sender
+            .remaining()
+            .map(|remaining| remaining.saturating_sub(reserved))
+    }
+}
+
+impl<T> IsStaticSender for ReservableSender<T>
+where
+    T: IsStaticSender + Sync,
+    T::Protocol: Send,
+    T::With: Send,
+{
+    type Protocol = T::Protocol;
+
+    async fn send_protocol_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        with: Self::With,
+    ) -> Result<(), SendError<(Self::Protocol, Self::With)>> {
+        let mut item = Some((protocol, with));
+        let mut slot = WakerSlot::new(&*this.reservations, |reservations: &mut Reservations| {
+            &mut reservations.wakers
+        });
+        let waited = poll_fn(|cx| {
+            let mut reservations = this.reservations.lock().unwrap();
+            let (protocol, with) = item.take().unwrap();
+            if reservations.reserved == 0 {
+                reservations.waiting += 1;
+                return Poll::Ready(Ok((protocol, with)));
+            }
+            match Self::try_send_protocol_locked(this, &reservations, protocol, with) {
+                Ok(()) => Poll::Ready(Err(Ok(()))),
+                Err(TrySendError::Closed(item)) => Poll::Ready(Err(Err(SendError(item)))),
+                Err(TrySendError::Full(full)) => {
+                    slot.register(&mut reservations, cx);
+                    item = Some(full);
+                    Poll::Pending
+                }
+            }
+        })
+        .await;
+        let (protocol, with) = match waited {
+            Ok(item) => item,
+            Err(sent) => return sent,
+        };
+        // Without reservations, the send can wait in the channel itself, until it is done.
+        let _waiting = Waiting(&this.reservations);
+        T::send_protocol_with(&this.sender, protocol, with).await
+    }
+
+    fn try_send_protocol_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        with: Self::With,
+    ) -> Result<(), TrySendError<(Self::Protocol, Self::With)>> {
+        let reservations = this.reservations.lock().unwrap();
+        Self::try_send_protocol_locked(this, &reservations, protocol, with)
+    }
+}
+
+impl<T> ReservableSender<T>
+where
+    T: IsStaticSender,
+{
+    /// Try to send the protocol into a slot that is not reserved.
+    fn try_send_protocol_locked(
+        this: &Self,
+        reservations: &Reservations,
+        protocol: T::Protocol,
+        with: T::With,
+    ) -> Result<(), TrySendError<(T::Protocol, T::With)>> {
+        if !this.sender.is_closed() && this.is_reserved_full(reservations) {
+            return Err(TrySendError::Full((protocol, with)));
+        }
+        T::try_send_protocol_with(&this.sender, protocol, with)
+    }
+}
+
+impl<T> Reserve for ReservableSender<T>
+where
+    T: IsStaticSender + Sync,
+    T::Protocol: Send,
+    T::With: Send,
+{
+    fn try_reserve_slot(this: &Self) -> Result<(), TrySendError<()>> {
+        let mut reservations = this.reservations.lock().unwrap();
+        if this.sender.is_closed() {
+            return Err(TrySendError::Closed(()));
+        }
+        if reservations.waiting > 0 || this.sender.is_full() || this.is_reserved_full(&reservations)
+        {
+            return Err(TrySendError::Full(()));
+        }
+        reservations.reserved += 1;
+        Ok(())
+    }
+
+    fn send_reserved_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        with: Self::With,
+    ) -> Result<(), TrySendError<(Self::Protocol, Self::With)>> {
+        let (sent, wakers) = {
+            let mut reservations = this.reservations.lock().unwrap();
+            let wakers = this.release(&mut reservations);
+            (
+                T::try_send_protocol_with(&this.sender, protocol, with),
+                wakers,
+            )
+        };
+        wakers.into_iter().for_each(|waker| waker.wake());
+        sent
+    }
+
+    fn release_slot(this: &Self) {
+        let wakers = this.release(&mut this.reservations.lock().unwrap());
+        wakers.into_iter().for_each(|waker| waker.wake());
+    }
+}
@@ -0,0 +1,188 @@
+use crate::*;
+use std::{
+    future::{poll_fn, Future},
+    pin::pin,
+    task::Poll,
+};
+
+/// A macro that receives from the first of multiple receivers with a message available, see
+/// [`SelectReceivers`].
+///
+/// Example:
+/// - `select!(rx1, rx2).await` == `SelectReceivers::recv_select((&mut rx1, &mut rx2)).await`
+///
+/// ```
+/// # use meslin::*;
+/// # futures::executor::block_on(async {
+/// let (_sender1, mut receiver1) = mpmc::unbounded::<u32>();
+/// let (sender2, mut receiver2) = priority::unbounded::<String, u8>();
+/// sender2.send_with::<String>("hi", 1).await.unwrap();
+///
+/// match select!(receiver1, receiver2).await {
+///     Select2::A(received) => panic!("unexpected {received:?}"),
+///     Select2::B(received) => assert_eq!(received.unwrap(), ("hi".to_string(), 1)),
+/// }
+/// # });
+/// ```
+#[macro_export]
+macro_rules! select {
+    ($($receiver:expr),+ $(,)?) => {
+        $crate::SelectReceivers::recv_select(($(&mut $receiver,)+))
+    };
+}
+
+/// A macro that receives from the receiver with the highest priority that has a message
+/// available, see [`PrioritySelectReceivers`]. Receivers are given from high to low priority.
+///
+/// Example:
+/// - `priority_select!(rx1, rx2).await` ==
+///   `PrioritySelectReceivers::recv_priority_select((&mut rx1, &mut rx2)).await`
+///
+/// ```
+/// # use meslin::*;
+/// # futures::executor::block_on(async {
+/// let (control, mut control_rx) = mpmc::unbounded::<&str>();
+/// let (data, mut data_rx) = mpmc::unbounded::<u32>();
+/// data.send::<u32>(1u32).await.unwrap();
+/// control.send::<&str>("pause").await.unwrap();
+///
+/// let Ok(Select2::A(("pause", ()))) = priority_select!(control_rx, data_rx).await else {
+///     panic!("expected the control message first")
+/// };
+/// drop(control);
+/// let Ok(Select2::B((1, ()))) = priority_select!(control_rx, data_rx).await else {
+///     panic!("expected the data message")
+/// };
+/// # });
+/// ```
+#[macro_export]
+macro_rules! priority_select {
+    ($($receiver:expr),+ $(,)?) => {
+        $crate::PrioritySelectReceivers::recv_priority_select(($(&mut $receiver,)+))
+    };
+}
+
+/// Receive from `high` if it has a message available, and otherwise from `low`, see
+/// [`PrioritySelectReceivers`].
+///
+/// This is the common pattern of an actor with a control channel and a data channel. Use
+/// [`priority_select!`] to select from more receivers.
+pub fn priority_select<'a, R1, R2>(
+    high: &'a mut R1,
+    low: &'a mut R2,
+) -> impl Future<
+    Output = Result<Select2<(R1::Protocol, R1::With), (R2::Protocol, R2::With)>, RecvError>,
+> + Send
+       + 'a
+where
+    R1: IsReceiver + Send,
+    R2: IsReceiver + Send,
+{
+    (high, low).recv_priority_select()
+}
+
+/// Trait implemented for tuples of (up to 4) mutable references to receivers, which receives
+/// from the first receiver with a message available.
+///
+/// The result is returned as a [`Select2`], [`Select3`] or [`Select4`], of which the variant
+/// indicates the receiver. The receivers are polled in order, so earlier receivers take priority.
+/// When a receiver is closed, its [`RecvError`] is returned immediately.
+pub trait SelectReceivers {
+    type Output;
+
+    /// Receive the protocol and its `with`-value from the first receiver with a message
+    /// available.
+    fn recv_select(self) -> impl Future<Output = Self::Output> + Send;
+}
+
+/// Trait implemented for tuples of (up to 4) mutable references to receivers, ordered from high
+/// to low priority, which receives from the receiver with the highest priority that has a
+/// message available.
+///
+/// Unlike [`SelectReceivers`], closed receivers are skipped, so that a lower-priority receiver
+/// can still be drained after a higher-priority one closed. A [`RecvError`] is only returned
+/// once all receivers are closed.
+pub trait PrioritySelectReceivers {
+    type Output;
+
+    /// Receive the protocol and its `with`-value from the receiver with the highest priority
+    /// that has a message available.
+    fn recv_priority_select(self) -> impl Future<Output = Result<Self::Output, RecvError>> + Send;
+}
+
+macro_rules! select_receivers {
+    ($(
+        $select:ident { $($variant:ident $receiver:ident $index:tt),* };
+    )*) => {$(
+        /// The result of [`SelectReceivers::recv_select`], of which the variant indicates the
+        /// receiver.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum $select<$($variant),*> {
+            $($variant($variant),)*
+        }
+
+        impl<'a, $($receiver),*> SelectReceivers for ($(&'a mut $receiver,)*)
+        where
+            $($receiver: IsReceiver + Send,)*
+        {
+            type Output = $select<$(
+                Result<($receiver::Protocol, $receiver::With), RecvError>
+            ),*>;
+
+            fn recv_select(self) -> impl Future<Output = Self::Output> + Send {
+                async move {
+                    let mut futs = ($(pin!($receiver::recv_protocol_with(self.$index)),)*);
+                    poll_fn(|cx| {
+                        $(
+                            if let Poll::Ready(received) = futs.$index.as_mut().poll(cx) {
+                                return Poll::Ready($select::$variant(received));
+                            }
+                        )*
+                        Poll::Pending
+                    })
+                    .await
+                }
+            }
+        }
+
+        impl<'a, $($receiver),*> PrioritySelectReceivers for ($(&'a mut $receiver,)*)
+        where
+            $($receiver: IsReceiver + Send,)*
+        {
+            type Output = $select<$(($receiver::Protocol, $receiver::With)),*>;
+
+            fn recv_priority_select(
+                self,
+            ) -> impl Future<Output = Result<Self::Output, RecvError>> + Send {
+                async move {
+                    let mut futs = ($(pin!($receiver::recv_protocol_with(self.$index)),)*);
+                    let mut closed = [$({ let _ = $index; false }),*];
+                    poll_fn(|cx| {
+                        $(
+                            if !closed[$index] {
+                                match futs.$index.as_mut().poll(cx) {
+                                    Poll::Ready(Ok(received)) => {
+                                        return Poll::Ready(Ok($select::$variant(received)))
+                                    }
+                                    Poll::Ready(Err(RecvError)) => closed[$index] = true,
+                                    Poll::Pending => (),
+                                }
+                            }
+                        )*
+                        match $(closed[$index])&&* {
+                            true => Poll::Ready(Err(RecvError)),
+                            false => Poll::Pending,
+                        }
+                    })
+                    .await
+                }
+            }
+        }
+    )*};
+}
+
+select_receivers! {
+    Select2 { A R1 0, B R2 1 };
+    Select3 { A R1 0, B R2 1, C R3 2 };
+    Select4 { A R1 0, B R2 1, C R3 2, D R4 3 };
+}
@@ -21,6 +21,46 @@ pub trait IsSender {
 
     /// Returns the number of senders in the channel.
     fn sender_count(&self) -> usize;
+
+    /// Returns `true` if the channel is full, meaning that sending would have to wait.
+    ///
+    /// Channels that never wait, like unbounded channels or channels that evict old messages, are
+    /// never full.
+    fn is_full(&self) -> bool {
+        self.capacity()
+            .is_some_and(|capacity| self.len() >= capacity)
+    }
+
+    /// Returns the number of messages that can be sent before the channel is full, if it is
+    /// bounded.
+    fn remaining(&self) -> Option<usize> {
+        self.capacity()
+            .map(|capacity| capacity.saturating_sub(self.len()))
+    }
+
+    /// Returns a snapshot of the health of the channel.
+    fn stats(&self) -> ChannelStats {
+        ChannelStats::without_counters(self)
+    }
+}
+
+/// Trait for senders of which every message is delivered to all receivers (fan-out), instead of
+/// to only one of them (point-to-point), like the [`broadcast`](crate::broadcast) and
+/// [`watch`](crate::watch) senders.
+///
+/// Generic code can require this trait to reason about whether a send reaches one or many
+/// receivers.
+pub trait IsBroadcastSender: IsSender {
+    /// Returns the number of receivers that are subscribed to the channel, including those that
+    /// currently do not receive messages.
+    fn subscriber_count(&self) -> usize {
+        self.receiver_count()
+    }
+
+    /// Returns the number of receivers that a message sent now is delivered to.
+    fn delivery_count(&self) -> usize {
+        self.receiver_count()
+    }
 }
 
 /// A supertrait of [`IsSender`], that defines how a protocol can be sent to the sender.
@@ -43,12 +83,24 @@ pub trait IsStaticSender: IsSender {
         with: Self::With,
     ) -> Result<(), TrySendError<(Self::Protocol, Self::With)>>;
 
+    /// Returns a future that waits until the channel has space for a protocol without sending
+    /// anything, and resolves to `false` if the channel is closed instead. Returns `None` if the
+    /// sender can not wait for space without sending, which is the default.
+    ///
+    /// This allows sends that can be given up, like [`IsSenderExt::send_cancellable`], to keep
+    /// the protocol while the channel is full, so that it is returned when they are given up.
+    fn wait_for_space(this: &Self) -> Option<impl Future<Output = bool> + Send + '_> {
+        let _ = this;
+        None::<std::future::Ready<bool>>
+    }
+
+    #[cfg(blocking)]
     fn send_protocol_blocking_with(
         this: &Self,
         protocol: Self::Protocol,
         with: Self::With,
     ) -> Result<(), SendError<(Self::Protocol, Self::With)>> {
-        futures::executor::block_on(Self::send_protocol_with(this, protocol, with))
+        crate::block_on(Self::send_protocol_with(this, protocol, with))
     }
 }
 
@@ -65,12 +117,13 @@ pub trait Sends<M>: IsSender {
         with: Self::With,
     ) -> impl Future<Output = Result<(), SendError<(M, Self::With)>>> + Send;
 
+    #[cfg(blocking)]
     fn send_msg_blocking_with(
         this: &Self,
         msg: M,
         with: Self::With,
     ) -> Result<(), SendError<(M, Self::With)>> {
-        futures::executor::block_on(Self::send_msg_with(this, msg, with))
+        crate::block_on(Self::send_msg_with(this, msg, with))
     }
 
     fn try_send_msg_with(
@@ -78,6 +131,12 @@ pub trait Sends<M>: IsSender {
         msg: M,
         with: Self::With,
     ) -> Result<(), TrySendError<(M, Self::With)>>;
+
+    /// See [`IsStaticSender::wait_for_space`].
+    fn wait_for_space(this: &Self) -> Option<impl Future<Output = bool> + Send + '_> {
+        let _ = this;
+        None::<std::future::Ready<bool>>
+    }
 }
 
 impl<M, T> Sends<M> for T
@@ -94,18 +153,19 @@ where
         async {
             match fut.await {
                 Ok(()) => Ok(()),
-                Err(e) => Err(e.map(|(t, w)| (t.try_into().unwrap_silent(), w))),
+                Err(e) => Err(e.map(protocol_into_msg)),
             }
         }
     }
 
+    #[cfg(blocking)]
     fn send_msg_blocking_with(
         this: &Self,
         msg: M,
         with: Self::With,
     ) -> Result<(), SendError<(M, Self::With)>> {
         T::send_protocol_blocking_with(this, T::Protocol::from(msg), with)
-            .map_err(|e| e.map(|(t, w)| (t.try_into().unwrap_silent(), w)))
+            .map_err(|e| e.map(protocol_into_msg))
     }
 
     fn try_send_msg_with(
@@ -114,7 +174,202 @@ where
         with: Self::With,
     ) -> Result<(), TrySendError<(M, Self::With)>> {
         T::try_send_protocol_with(this, T::Protocol::from(msg), with)
-            .map_err(|e| e.map(|(t, w)| (t.try_into().unwrap_silent(), w)))
+            .map_err(|e| e.map(protocol_into_msg))
+    }
+
+    fn wait_for_space(this: &Self) -> Option<impl Future<Output = bool> + Send + '_> {
+        <T as IsStaticSender>::wait_for_space(this)
+    }
+}
+
+/// Implements [`IsSender`] and [`IsStaticSender`] for pointer types by forwarding to the
+/// sender they point to. [`Sends<M>`] then follows from the blanket implementation.
+macro_rules! forward_sender_impls {
+    ($($ty:ty),*) => {$(
+        impl<T: IsSender + ?Sized> IsSender for $ty {
+            type With = T::With;
+
+            fn is_closed(&self) -> bool {
+                (**self).is_closed()
+            }
+
+            fn capacity(&self) -> Option<usize> {
+                (**self).capacity()
+            }
+
+            fn len(&self) -> usize {
+                (**self).len()
+            }
+
+            fn receiver_count(&self) -> usize {
+                (**self).receiver_count()
+            }
+
+            fn sender_count(&self) -> usize {
+                (**self).sender_count()
+            }
+
+            fn is_full(&self) -> bool {
+                (**self).is_full()
+            }
+
+            fn remaining(&self) -> Option<usize> {
+                (**self).remaining()
+            }
+
+            fn stats(&self) -> ChannelStats {
+                (**self).stats()
+            }
+        }
+
+        impl<T: IsStaticSender + ?Sized> IsStaticSender for $ty {
+            type Protocol = T::Protocol;
+
+            fn send_protocol_with(
+                this: &Self,
+                protocol: Self::Protocol,
+                with: Self::With,
+            ) -> impl Future<Output = Result<(), SendError<(Self::Protocol, Self::With)>>> + Send
+            {
+                T::send_protocol_with(&**this, protocol, with)
+            }
+
+            fn try_send_protocol_with(
+                this: &Self,
+                protocol: Self::Protocol,
+                with: Self::With,
+            ) -> Result<(), TrySendError<(Self::Protocol, Self::With)>> {
+                T::try_send_protocol_with(&**this, protocol, with)
+            }
+
+            fn wait_for_space(this: &Self) -> Option<impl Future<Output = bool> + Send + '_> {
+                T::wait_for_space(&**this)
+            }
+
+            #[cfg(blocking)]
+            fn send_protocol_blocking_with(
+                this: &Self,
+                protocol: Self::Protocol,
+                with: Self::With,
+            ) -> Result<(), SendError<(Self::Protocol, Self::With)>> {
+                T::send_protocol_blocking_with(&**this, protocol, with)
+            }
+        }
+    )*};
+}
+
+forward_sender_impls!(&T, Box<T>, std::sync::Arc<T>);
+
+/// Implements the methods of [`IsSender`] that inspect the channel for a wrapper, by forwarding
+/// them to the inner sender in its `sender` field. Without a list, all of them are forwarded.
+macro_rules! forward_sender_methods {
+    () => {
+        forward_sender_methods!(
+            is_closed,
+            capacity,
+            len,
+            receiver_count,
+            sender_count,
+            is_full,
+            remaining,
+            stats
+        );
+    };
+    ($($method:ident),*) => {$(
+        forward_sender_methods!(@$method);
+    )*};
+    (@is_closed) => {
+        fn is_closed(&self) -> bool {
+            self.sender.is_closed()
+        }
+    };
+    (@capacity) => {
+        fn capacity(&self) -> Option<usize> {
+            self.sender.capacity()
+        }
+    };
+    (@len) => {
+        fn len(&self) -> usize {
+            self.sender.len()
+        }
+    };
+    (@receiver_count) => {
+        fn receiver_count(&self) -> usize {
+            self.sender.receiver_count()
+        }
+    };
+    (@sender_count) => {
+        fn sender_count(&self) -> usize {
+            self.sender.sender_count()
+        }
+    };
+    (@is_full) => {
+        fn is_full(&self) -> bool {
+            self.sender.is_full()
+        }
+    };
+    (@remaining) => {
+        fn remaining(&self) -> Option<usize> {
+            self.sender.remaining()
+        }
+    };
+    (@stats) => {
+        fn stats(&self) -> ChannelStats {
+            self.sender.stats()
+        }
+    };
+}
+pub(crate) use forward_sender_methods;
+
+/// Implements [`IsBroadcastSender`] for a wrapper, by forwarding to the inner sender in its
+/// `sender` field, of which the type is the first generic parameter.
+macro_rules! forward_broadcast_sender_impl {
+    (<$inner:ident $(, $generic:ident)*> $ty:ty) => {
+        impl<$inner: IsBroadcastSender $(, $generic)*> IsBroadcastSender for $ty
+        where
+            Self: IsSender,
+        {
+            fn subscriber_count(&self) -> usize {
+                self.sender.subscriber_count()
+            }
+
+            fn delivery_count(&self) -> usize {
+                self.sender.delivery_count()
+            }
+        }
+    };
+}
+pub(crate) use forward_broadcast_sender_impl;
+
+/// Provides the `with`-value to use when a message `M` is sent without one.
+///
+/// This can be implemented by any type, for example a configuration object or a zero-sized
+/// type per protocol, and is used by a [`DefaultWithSender`]. This allows the ergonomic `send`
+/// methods to be used on senders of which the `with`-value has no sensible global default.
+pub trait WithDefault<M> {
+    type With;
+
+    fn with_default(&self, msg: &M) -> Self::With;
+}
+
+/// Conversion of a priority into the `with`-value of a sender, used by
+/// [`IsSenderExt::priority`].
+///
+/// This is implemented for every [`Ord`] type itself, and for [`Reverse`](std::cmp::Reverse) so
+/// that a sender of which the lowest priority is received first can be given a plain priority.
+pub trait FromPriority<O> {
+    fn from_priority(priority: O) -> Self;
+}
+
+impl<O: Ord> FromPriority<O> for O {
+    fn from_priority(priority: O) -> Self {
+        priority
+    }
+}
+
+impl<O: Ord> FromPriority<O> for std::cmp::Reverse<O> {
+    fn from_priority(priority: O) -> Self {
+        std::cmp::Reverse(priority)
     }
 }
 
@@ -129,6 +384,163 @@ pub trait IsSenderExt: IsSender + Sized {
         WithValueSender::new(self, with)
     }
 
+    /// Map the `with` value of the sender to `()`, by providing the priority to send with.
+    ///
+    /// This is an alias of [`IsSenderExt::with`] for senders of which the `with` value is a
+    /// priority, like the [`priority::Sender`](crate::priority::Sender).
+    fn priority<O>(self, priority: O) -> WithValueSender<Self>
+    where
+        Self: IsStaticSender,
+        Self::With: FromPriority<O> + Clone,
+    {
+        self.with(FromPriority::from_priority(priority))
+    }
+
+    /// Give the sender approximate priority semantics, with a reorder buffer of at most `depth`
+    /// protocols, see [`PriorityAdapter`]. Buffered protocols are not forwarded by themselves, so
+    /// the adapter must be flushed when the producer stops sending, see [`PriorityAdapter::new`].
+    fn priority_adapter<O>(self, depth: usize) -> PriorityAdapter<Self, O>
+    where
+        Self: IsStaticSender,
+        Self::With: Default,
+        O: Ord,
+    {
+        PriorityAdapter::new(self, depth)
+    }
+
+    /// Map the `with` value of the sender to `()`, by providing a function that computes the
+    /// `with` value of every protocol.
+    fn with_fn<F>(self, f: F) -> WithFnSender<Self, F>
+    where
+        Self: IsStaticSender,
+        F: Fn(&Self::Protocol) -> Self::With,
+    {
+        WithFnSender::new(self, f)
+    }
+
+    /// Map the `with` value of the sender to `()`, by providing a [`WithDefault`] that determines
+    /// the `with` value of every message.
+    fn with_defaults<D>(self, defaults: D) -> DefaultWithSender<Self, D> {
+        DefaultWithSender::new(self, defaults)
+    }
+
+    /// Drop every protocol for which the filter returns `false`, instead of sending it.
+    fn filter<F>(self, f: F) -> FilterSender<Self, F>
+    where
+        Self: IsStaticSender,
+        F: Fn(&Self::Protocol) -> bool,
+    {
+        FilterSender::new(self, f)
+    }
+
+    /// Drop protocols that are sent within `interval` of the last protocol, see
+    /// [`Throttle`](time::Throttle).
+    #[cfg(feature = "time")]
+    fn throttle(self, interval: std::time::Duration) -> time::Throttle<Self>
+    where
+        Self: IsStaticSender,
+    {
+        time::Throttle::new(self, interval)
+    }
+
+    /// Like [`IsSenderExt::throttle`], but measures the interval using the given
+    /// [`Clock`](time::Clock).
+    #[cfg(feature = "time")]
+    fn throttle_with_clock(
+        self,
+        interval: std::time::Duration,
+        clock: impl time::Clock,
+    ) -> time::Throttle<Self>
+    where
+        Self: IsStaticSender,
+    {
+        time::Throttle::with_clock(self, interval, clock)
+    }
+
+    /// Only forward a protocol once no newer protocol was sent for `delay`, see
+    /// [`Debounce`](time::Debounce).
+    #[cfg(feature = "time")]
+    fn debounce(self, delay: std::time::Duration) -> time::Debounce<Self>
+    where
+        Self: IsStaticSender,
+    {
+        time::Debounce::new(self, delay)
+    }
+
+    /// Like [`IsSenderExt::debounce`], but measures the delay using the given
+    /// [`Clock`](time::Clock).
+    #[cfg(feature = "time")]
+    fn debounce_with_clock(
+        self,
+        delay: std::time::Duration,
+        clock: impl time::Clock,
+    ) -> time::Debounce<Self>
+    where
+        Self: IsStaticSender,
+    {
+        time::Debounce::with_clock(self, delay, clock)
+    }
+
+    /// Collect messages into batches of at most `size` messages, which are sent as a single
+    /// `Vec<M>` message, see [`Batch`](time::Batch).
+    #[cfg(feature = "time")]
+    fn batch<M>(self, size: usize, interval: std::time::Duration) -> time::Batch<Self, M>
+    where
+        Self: IsStaticSender + Sends<Vec<M>>,
+        Self::With: Default,
+    {
+        time::Batch::new(self, size, interval)
+    }
+
+    /// Like [`IsSenderExt::batch`], but measures the interval using the given
+    /// [`Clock`](time::Clock).
+    #[cfg(feature = "time")]
+    fn batch_with_clock<M>(
+        self,
+        size: usize,
+        interval: std::time::Duration,
+        clock: impl time::Clock,
+    ) -> time::Batch<Self, M>
+    where
+        Self: IsStaticSender + Sends<Vec<M>>,
+        Self::With: Default,
+    {
+        time::Batch::with_clock(self, size, interval, clock)
+    }
+
+    /// Fail to send once the [`Gate`] is closed, so that the receiver can drain its backlog
+    /// without new protocols arriving, see [`GatedSender`].
+    fn gated(self, gate: &Gate) -> GatedSender<Self> {
+        GatedSender::new(self, gate)
+    }
+
+    /// Report every protocol that is sent to the [`Monitor`], which allows waiting for capacity
+    /// and observing watermarks, see [`MonitoredSender`].
+    fn monitored(self, monitor: &Monitor) -> MonitoredSender<Self> {
+        MonitoredSender::new(self, monitor)
+    }
+
+    /// Track reservations of space in the channel, which implements [`Reserve`] for any sender,
+    /// see [`ReservableSender`].
+    fn reservable(self) -> ReservableSender<Self> {
+        ReservableSender::new(self)
+    }
+
+    /// Count the messages that are sent, which are reported by [`IsSender::stats`], see
+    /// [`Counted`].
+    fn counted(self) -> Counted<Self> {
+        Counted::new(self)
+    }
+
+    /// Transform every protocol before it is sent.
+    fn map_msg<F>(self, f: F) -> MapMsgSender<Self, F>
+    where
+        Self: IsStaticSender,
+        F: Fn(Self::Protocol) -> Self::Protocol,
+    {
+        MapMsgSender::new(self, f)
+    }
+
     /// Map the `with` value of the sender to `W`, by providing conversion functions.
     fn map_with<W>(
         self,
@@ -141,6 +553,17 @@ pub trait IsSenderExt: IsSender + Sized {
         MappedWithSender::new(self, f1, f2)
     }
 
+    /// Like [`IsSenderExt::map_with`], but the conversion functions can be closures that capture
+    /// their environment.
+    fn map_with_closures<W, F1, F2>(self, f1: F1, f2: F2) -> MappedWithSender<Self, W, F1, F2>
+    where
+        Self: IsStaticSender + Send + Sync,
+        F1: Fn(W) -> Self::With,
+        F2: Fn(Self::With) -> W,
+    {
+        MappedWithSender::new(self, f1, f2)
+    }
+
     /// Send a message with a custom value, waiting asynchronously until space becomes available.
     ///
     /// See the crate [docs](crate) under `#Send methods` for more information.
@@ -158,6 +581,7 @@ pub trait IsSenderExt: IsSender + Sized {
     /// Send a message with a custom value, blocking the current thread until space becomes available.
     ///
     /// See the crate [docs](crate) under `#Send methods` for more information.
+    #[cfg(blocking)]
     fn send_msg_blocking_with<M>(
         &self,
         msg: M,
@@ -198,6 +622,7 @@ pub trait IsSenderExt: IsSender + Sized {
     /// Send a message using a default value, blocking the current thread until space becomes available.
     ///
     /// See the crate [docs](crate) under `#Send methods` for more information.
+    #[cfg(blocking)]
     fn send_msg_blocking<M: Message>(&self, msg: M) -> Result<(), SendError<M>>
     where
         Self: Sends<M>,
@@ -244,6 +669,7 @@ pub trait IsSenderExt: IsSender + Sized {
     /// Send a message with a custom value, blocking the current thread until space becomes available.
     ///
     /// See the crate [docs](crate) under `#Send methods` for more information.
+    #[cfg(blocking)]
     fn send_blocking_with<M: Message>(
         &self,
         msg: impl Into<M::Input>,
@@ -295,6 +721,7 @@ pub trait IsSenderExt: IsSender + Sized {
     /// Send a message using a default value, blocking the current thread until space becomes available.
     ///
     /// See the crate [docs](crate) under `#Send methods` for more information.
+    #[cfg(blocking)]
     fn send_blocking<M: Message>(
         &self,
         msg: impl Into<M::Input>,
@@ -373,8 +800,199 @@ pub trait IsSenderExt: IsSender + Sized {
             })
         }
     }
+
+    /// Send a message with a custom value, blocking the current thread until space becomes
+    /// available, and then block until the [`Message::Output`] resolves.
+    ///
+    /// Waiting for the reply always blocks, so if the current thread
+    /// [can not block](crate::can_block), this panics before the message is sent.
+    ///
+    /// See the crate [docs](crate) under `#Send methods` for more information.
+    #[cfg(blocking)]
+    fn request_blocking_with<M: Message>(
+        &self,
+        msg: impl Into<M::Input>,
+        with: Self::With,
+    ) -> Result<
+        <M::Output as ResultFuture>::Ok,
+        RequestError<(M::Input, Self::With), <M::Output as ResultFuture>::Error>,
+    >
+    where
+        Self: Sends<M>,
+        M::Output: ResultFuture,
+    {
+        crate::assert_can_block();
+        let rx = self.send_blocking_with(msg, with)?;
+        crate::block_on(rx).map_err(RequestError::NoReply)
+    }
+
+    /// Send a message using a default value, blocking the current thread until space becomes
+    /// available, and then block until the [`Message::Output`] resolves.
+    ///
+    /// See the crate [docs](crate) under `#Send methods` for more information.
+    #[cfg(blocking)]
+    fn request_blocking<M: Message>(
+        &self,
+        msg: impl Into<M::Input>,
+    ) -> Result<
+        <M::Output as ResultFuture>::Ok,
+        RequestError<M::Input, <M::Output as ResultFuture>::Error>,
+    >
+    where
+        Self: Sends<M>,
+        Self::With: Default,
+        M::Output: ResultFuture,
+    {
+        self.request_blocking_with(msg, Default::default())
+            .map_err(|e| match e {
+                RequestError::Full(e) => RequestError::Full(e.0),
+                RequestError::NoReply(e) => RequestError::NoReply(e),
+            })
+    }
+
+    /// Send a message with a custom value, returning an error if space is not available, and
+    /// then await the [`Message::Output`].
+    ///
+    /// See the crate [docs](crate) under `#Send methods` for more information.
+    fn try_request_with<M: Message>(
+        &self,
+        msg: impl Into<M::Input>,
+        with: Self::With,
+    ) -> impl std::future::Future<
+        Output = Result<
+            <M::Output as ResultFuture>::Ok,
+            TryRequestError<(M::Input, Self::With), <M::Output as ResultFuture>::Error>,
+        >,
+    > + Send
+    where
+        Self: Sends<M>,
+        Self::With: Send,
+        M::Input: Send,
+        M::Output: ResultFuture,
+    {
+        let sent = self.try_send_with(msg, with);
+        async {
+            let rx = sent?;
+            rx.await.map_err(TryRequestError::NoReply)
+        }
+    }
+
+    /// Send a message using a default value, returning an error if space is not available, and
+    /// then await the [`Message::Output`].
+    ///
+    /// See the crate [docs](crate) under `#Send methods` for more information.
+    fn try_request<M: Message>(
+        &self,
+        msg: impl Into<M::Input>,
+    ) -> impl std::future::Future<
+        Output = Result<
+            <M::Output as ResultFuture>::Ok,
+            TryRequestError<M::Input, <M::Output as ResultFuture>::Error>,
+        >,
+    > + Send
+    where
+        Self: Sends<M>,
+        Self::With: Default + Send,
+        M::Input: Send,
+        M::Output: ResultFuture,
+    {
+        let fut = self.try_request_with(msg, Default::default());
+        async {
+            fut.await.map_err(|e| match e {
+                TryRequestError::Closed(e) => TryRequestError::Closed(e.0),
+                TryRequestError::Full(e) => TryRequestError::Full(e.0),
+                TryRequestError::NoReply(e) => TryRequestError::NoReply(e),
+            })
+        }
+    }
+
+    /// Send a message using a default value, waiting asynchronously until space becomes
+    /// available, unless the [`CancellationToken`] is cancelled first.
+    ///
+    /// The message is returned if the token is cancelled before it was sent, also while waiting
+    /// for space in the channel. Only senders that do not support
+    /// [`IsStaticSender::wait_for_space`] have to be handed the message to wait for space, in
+    /// which case it is lost if the token is cancelled meanwhile.
+    fn send_cancellable<M: Message>(
+        &self,
+        msg: impl Into<M::Input>,
+        token: Option<&CancellationToken>,
+    ) -> impl Future<Output = Result<M::Output, CancelSendError<M::Input>>> + Send
+    where
+        Self: Sends<M> + Sync,
+        Self::With: Default,
+        M::Input: Send,
+    {
+        let msg = msg.into();
+        async move {
+            if token.is_some_and(CancellationToken::is_cancelled) {
+                return Err(CancelSendError::Cancelled(Some(msg)));
+            }
+            let cancelled = crate::cancel::cancelled(token);
+            crate::cancel::send_until::<Self, M>(self, msg, cancelled)
+                .await
+                .map_err(|e| match e {
+                    crate::cancel::Unsent::Closed(msg) => CancelSendError::Closed(msg),
+                    crate::cancel::Unsent::Stopped(msg) => CancelSendError::Cancelled(msg),
+                })
+        }
+    }
+
+    /// Like [`IsSenderExt::send_cancellable`], and then await the [`Message::Output`], unless the
+    /// [`CancellationToken`] is cancelled first.
+    fn request_cancellable<M: Message>(
+        &self,
+        msg: impl Into<M::Input>,
+        token: Option<&CancellationToken>,
+    ) -> impl Future<
+        Output = Result<
+            <M::Output as ResultFuture>::Ok,
+            CancelRequestError<M::Input, <M::Output as ResultFuture>::Error>,
+        >,
+    > + Send
+    where
+        Self: Sends<M> + Sync,
+        Self::With: Default,
+        M::Input: Send,
+        M::Output: ResultFuture,
+    {
+        let sent = self.send_cancellable::<M>(msg, token);
+        async move {
+            let rx = sent.await?;
+            match crate::cancel::until_cancelled(token, rx).await {
+                Some(reply) => reply.map_err(CancelRequestError::NoReply),
+                None => Err(CancelRequestError::Cancelled(None)),
+            }
+        }
+    }
+
+    /// Send a [`Barrier`] and wait until the receiver has released it, which happens after it
+    /// processed all messages that were sent before.
+    #[cfg(feature = "request")]
+    fn barrier(
+        &self,
+    ) -> impl std::future::Future<Output = Result<(), RequestError<(), RecvError>>> + Send
+    where
+        Self: Sends<Barrier>,
+        Self::With: Default,
+    {
+        self.request::<Barrier>(())
+    }
+
+    /// Send a [`Shutdown`] and wait until the receiver has drained its backlog and completed
+    /// the shutdown.
+    #[cfg(feature = "request")]
+    fn shutdown_and_drain(
+        &self,
+    ) -> impl std::future::Future<Output = Result<(), RequestError<(), RecvError>>> + Send
+    where
+        Self: Sends<Shutdown>,
+        Self::With: Default,
+    {
+        self.request::<Shutdown>(())
+    }
 }
-impl<T: ?Sized> IsSenderExt for T where T: IsSender + Sized {}
+impl<T> IsSenderExt for T where T: IsSender {}
 
 //-------------------------------------
 // ResultFuture
@@ -1,6 +1,6 @@
 use crate::*;
 use core::future::Future;
-use std::marker::PhantomData;
+use std::{fmt::Debug, marker::PhantomData};
 
 /// A wrapper around a sender, which provides a default `with`-value.
 #[derive(Debug)]
@@ -40,37 +40,113 @@ impl<T: IsSender> WithValueSender<T> {
     }
 }
 
+forward_broadcast_sender_impl!(<T> WithValueSender<T>);
+
 impl<T> IsSender for WithValueSender<T>
 where
     T: IsSender,
 {
     type With = ();
 
-    fn is_closed(&self) -> bool {
-        self.sender.is_closed()
+    forward_sender_methods!();
+}
+
+impl<T> IsStaticSender for WithValueSender<T>
+where
+    T: IsStaticSender,
+    T::With: Clone,
+{
+    type Protocol = T::Protocol;
+
+    fn send_protocol_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        with: (),
+    ) -> impl Future<Output = Result<(), SendError<(Self::Protocol, Self::With)>>> + Send {
+        let fut = T::send_protocol_with(&this.sender, protocol, this.with.clone());
+        async move {
+            match fut.await {
+                Ok(()) => Ok(()),
+                Err(e) => Err(e.map(|(protocol, _)| (protocol, with))),
+            }
+        }
     }
 
-    fn capacity(&self) -> Option<usize> {
-        self.sender.capacity()
+    fn try_send_protocol_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        with: (),
+    ) -> Result<(), TrySendError<(Self::Protocol, Self::With)>> {
+        match T::try_send_protocol_with(&this.sender, protocol, this.with.clone()) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(e.map(|(protocol, _)| (protocol, with))),
+        }
     }
 
-    fn len(&self) -> usize {
-        self.sender.len()
+    fn wait_for_space(this: &Self) -> Option<impl Future<Output = bool> + Send + '_> {
+        T::wait_for_space(&this.sender)
+    }
+
+    #[cfg(blocking)]
+    fn send_protocol_blocking_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        with: Self::With,
+    ) -> Result<(), SendError<(Self::Protocol, Self::With)>> {
+        match T::send_protocol_blocking_with(&this.sender, protocol, this.with.clone()) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(e.map(|(protocol, _)| (protocol, with))),
+        }
+    }
+}
+
+/// A wrapper around a sender, which computes the `with`-value for every protocol that is sent.
+///
+/// This can be used for `with`-values that change over time, like timestamps or trace ids.
+#[derive(Clone)]
+pub struct WithFnSender<T, F> {
+    sender: T,
+    f: F,
+}
+
+impl<T, F> WithFnSender<T, F> {
+    pub fn new(sender: T, f: F) -> Self {
+        Self { sender, f }
     }
 
-    fn receiver_count(&self) -> usize {
-        self.sender.receiver_count()
+    pub fn into_inner(self) -> (T, F) {
+        (self.sender, self.f)
     }
 
-    fn sender_count(&self) -> usize {
-        self.sender.sender_count()
+    pub fn inner_ref(&self) -> (&T, &F) {
+        (&self.sender, &self.f)
+    }
+
+    pub fn inner_mut(&mut self) -> (&mut T, &mut F) {
+        (&mut self.sender, &mut self.f)
     }
 }
 
-impl<T> IsStaticSender for WithValueSender<T>
+impl<T: Debug, F> Debug for WithFnSender<T, F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WithFnSender")
+            .field("sender", &self.sender)
+            .finish_non_exhaustive()
+    }
+}
+
+forward_broadcast_sender_impl!(<T, F> WithFnSender<T, F>);
+
+impl<T: IsSender, F> IsSender for WithFnSender<T, F> {
+    type With = ();
+
+    forward_sender_methods!();
+}
+
+impl<T, F> IsStaticSender for WithFnSender<T, F>
 where
     T: IsStaticSender,
-    T::With: Clone,
+    F: Fn(&T::Protocol) -> T::With,
 {
     type Protocol = T::Protocol;
 
@@ -79,7 +155,8 @@ where
         protocol: Self::Protocol,
         with: (),
     ) -> impl Future<Output = Result<(), SendError<(Self::Protocol, Self::With)>>> + Send {
-        let fut = T::send_protocol_with(&this.sender, protocol, this.with.clone());
+        let computed = (this.f)(&protocol);
+        let fut = T::send_protocol_with(&this.sender, protocol, computed);
         async move {
             match fut.await {
                 Ok(()) => Ok(()),
@@ -93,47 +170,249 @@ where
         protocol: Self::Protocol,
         with: (),
     ) -> Result<(), TrySendError<(Self::Protocol, Self::With)>> {
-        match T::try_send_protocol_with(&this.sender, protocol, this.with.clone()) {
+        let computed = (this.f)(&protocol);
+        match T::try_send_protocol_with(&this.sender, protocol, computed) {
             Ok(()) => Ok(()),
             Err(e) => Err(e.map(|(protocol, _)| (protocol, with))),
         }
     }
 
+    fn wait_for_space(this: &Self) -> Option<impl Future<Output = bool> + Send + '_> {
+        T::wait_for_space(&this.sender)
+    }
+
+    #[cfg(blocking)]
     fn send_protocol_blocking_with(
         this: &Self,
         protocol: Self::Protocol,
-        with: Self::With,
+        with: (),
     ) -> Result<(), SendError<(Self::Protocol, Self::With)>> {
-        match T::send_protocol_blocking_with(&this.sender, protocol, this.with.clone()) {
+        let computed = (this.f)(&protocol);
+        match T::send_protocol_blocking_with(&this.sender, protocol, computed) {
             Ok(()) => Ok(()),
             Err(e) => Err(e.map(|(protocol, _)| (protocol, with))),
         }
     }
 }
 
+/// A wrapper around a sender, which drops every protocol that does not pass the filter.
+///
+/// Protocols that are dropped are still reported as sent. This can be used to mute messages,
+/// for example during shutdown.
+#[derive(Clone)]
+pub struct FilterSender<T, F> {
+    sender: T,
+    f: F,
+}
+
+impl<T, F> FilterSender<T, F> {
+    pub fn new(sender: T, f: F) -> Self {
+        Self { sender, f }
+    }
+
+    pub fn into_inner(self) -> (T, F) {
+        (self.sender, self.f)
+    }
+
+    pub fn inner_ref(&self) -> (&T, &F) {
+        (&self.sender, &self.f)
+    }
+
+    pub fn inner_mut(&mut self) -> (&mut T, &mut F) {
+        (&mut self.sender, &mut self.f)
+    }
+}
+
+impl<T: Debug, F> Debug for FilterSender<T, F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FilterSender")
+            .field("sender", &self.sender)
+            .finish_non_exhaustive()
+    }
+}
+
+forward_broadcast_sender_impl!(<T, F> FilterSender<T, F>);
+
+impl<T: IsSender, F> IsSender for FilterSender<T, F> {
+    type With = T::With;
+
+    forward_sender_methods!();
+}
+
+impl<T, F> IsStaticSender for FilterSender<T, F>
+where
+    T: IsStaticSender,
+    F: Fn(&T::Protocol) -> bool,
+{
+    type Protocol = T::Protocol;
+
+    fn send_protocol_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        with: Self::With,
+    ) -> impl Future<Output = Result<(), SendError<(Self::Protocol, Self::With)>>> + Send {
+        let fut = match (this.f)(&protocol) {
+            true => Some(T::send_protocol_with(&this.sender, protocol, with)),
+            false => None,
+        };
+        async move {
+            match fut {
+                Some(fut) => fut.await,
+                None => Ok(()),
+            }
+        }
+    }
+
+    fn try_send_protocol_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        with: Self::With,
+    ) -> Result<(), TrySendError<(Self::Protocol, Self::With)>> {
+        match (this.f)(&protocol) {
+            true => T::try_send_protocol_with(&this.sender, protocol, with),
+            false => Ok(()),
+        }
+    }
+
+    fn wait_for_space(this: &Self) -> Option<impl Future<Output = bool> + Send + '_> {
+        T::wait_for_space(&this.sender)
+    }
+
+    #[cfg(blocking)]
+    fn send_protocol_blocking_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        with: Self::With,
+    ) -> Result<(), SendError<(Self::Protocol, Self::With)>> {
+        match (this.f)(&protocol) {
+            true => T::send_protocol_blocking_with(&this.sender, protocol, with),
+            false => Ok(()),
+        }
+    }
+}
+
+/// A wrapper around a sender, which transforms every protocol before it is sent.
+///
+/// This can be used to redact fields or fill in defaults. When sending fails, the transformed
+/// protocol is returned.
+#[derive(Clone)]
+pub struct MapMsgSender<T, F> {
+    sender: T,
+    f: F,
+}
+
+impl<T, F> MapMsgSender<T, F> {
+    pub fn new(sender: T, f: F) -> Self {
+        Self { sender, f }
+    }
+
+    pub fn into_inner(self) -> (T, F) {
+        (self.sender, self.f)
+    }
+
+    pub fn inner_ref(&self) -> (&T, &F) {
+        (&self.sender, &self.f)
+    }
+
+    pub fn inner_mut(&mut self) -> (&mut T, &mut F) {
+        (&mut self.sender, &mut self.f)
+    }
+}
+
+impl<T: Debug, F> Debug for MapMsgSender<T, F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MapMsgSender")
+            .field("sender", &self.sender)
+            .finish_non_exhaustive()
+    }
+}
+
+forward_broadcast_sender_impl!(<T, F> MapMsgSender<T, F>);
+
+impl<T: IsSender, F> IsSender for MapMsgSender<T, F> {
+    type With = T::With;
+
+    forward_sender_methods!();
+}
+
+impl<T, F> IsStaticSender for MapMsgSender<T, F>
+where
+    T: IsStaticSender,
+    F: Fn(T::Protocol) -> T::Protocol,
+{
+    type Protocol = T::Protocol;
+
+    fn send_protocol_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        with: Self::With,
+    ) -> impl Future<Output = Result<(), SendError<(Self::Protocol, Self::With)>>> + Send {
+        T::send_protocol_with(&this.sender, (this.f)(protocol), with)
+    }
+
+    fn try_send_protocol_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        with: Self::With,
+    ) -> Result<(), TrySendError<(Self::Protocol, Self::With)>> {
+        T::try_send_protocol_with(&this.sender, (this.f)(protocol), with)
+    }
+
+    fn wait_for_space(this: &Self) -> Option<impl Future<Output = bool> + Send + '_> {
+        T::wait_for_space(&this.sender)
+    }
+
+    #[cfg(blocking)]
+    fn send_protocol_blocking_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        with: Self::With,
+    ) -> Result<(), SendError<(Self::Protocol, Self::With)>> {
+        T::send_protocol_blocking_with(&this.sender, (this.f)(protocol), with)
+    }
+}
+
 /// A wrapper around a sender, which provides a mapping between the `with`-value of the sender and
 /// a custom `with`-value.
-#[derive(Debug)]
-pub struct MappedWithSender<T: IsSender, W> {
+///
+/// The mapping functions can be closures; by default they are function pointers.
+pub struct MappedWithSender<
+    T: IsSender,
+    W,
+    F1 = fn(W) -> <T as IsSender>::With,
+    F2 = fn(<T as IsSender>::With) -> W,
+> {
     sender: T,
-    f1: fn(W) -> T::With,
-    f2: fn(T::With) -> W,
+    f1: F1,
+    f2: F2,
     _marker: PhantomData<fn() -> W>,
 }
 
-impl<T: IsSender + Clone, W> Clone for MappedWithSender<T, W> {
+impl<T: IsSender + Clone, W, F1: Clone, F2: Clone> Clone for MappedWithSender<T, W, F1, F2> {
     fn clone(&self) -> Self {
         Self {
             sender: self.sender.clone(),
-            f1: self.f1,
-            f2: self.f2,
+            f1: self.f1.clone(),
+            f2: self.f2.clone(),
             _marker: PhantomData,
         }
     }
 }
 
-impl<T: IsSender, W> MappedWithSender<T, W> {
-    pub fn new(sender: T, f1: fn(W) -> T::With, f2: fn(T::With) -> W) -> Self {
+impl<T: IsSender + Debug, W, F1, F2> Debug for MappedWithSender<T, W, F1, F2> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MappedWithSender")
+            .field("sender", &self.sender)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: IsSender, W, F1, F2> MappedWithSender<T, W, F1, F2>
+where
+    F1: Fn(W) -> T::With,
+    F2: Fn(T::With) -> W,
+{
+    pub fn new(sender: T, f1: F1, f2: F2) -> Self {
         Self {
             sender,
             f1,
@@ -142,46 +421,32 @@ impl<T: IsSender, W> MappedWithSender<T, W> {
         }
     }
 
-    pub fn into_inner(self) -> (T, fn(W) -> T::With, fn(T::With) -> W) {
+    pub fn into_inner(self) -> (T, F1, F2) {
         (self.sender, self.f1, self.f2)
     }
 
-    pub fn inner_ref(&self) -> (&T, &fn(W) -> T::With, &fn(T::With) -> W) {
+    pub fn inner_ref(&self) -> (&T, &F1, &F2) {
         (&self.sender, &self.f1, &self.f2)
     }
 
-    pub fn inner_mut(&mut self) -> (&mut T, &mut fn(W) -> T::With, &mut fn(T::With) -> W) {
+    pub fn inner_mut(&mut self) -> (&mut T, &mut F1, &mut F2) {
         (&mut self.sender, &mut self.f1, &mut self.f2)
     }
 }
 
-impl<T: IsSender, W> IsSender for MappedWithSender<T, W> {
-    type With = W;
-
-    fn is_closed(&self) -> bool {
-        self.sender.is_closed()
-    }
-
-    fn capacity(&self) -> Option<usize> {
-        self.sender.capacity()
-    }
-
-    fn len(&self) -> usize {
-        self.sender.len()
-    }
+forward_broadcast_sender_impl!(<T, W, F1, F2> MappedWithSender<T, W, F1, F2>);
 
-    fn receiver_count(&self) -> usize {
-        self.sender.receiver_count()
-    }
+impl<T: IsSender, W, F1, F2> IsSender for MappedWithSender<T, W, F1, F2> {
+    type With = W;
 
-    fn sender_count(&self) -> usize {
-        self.sender.sender_count()
-    }
+    forward_sender_methods!();
 }
 
-impl<T, W> IsStaticSender for MappedWithSender<T, W>
+impl<T, W, F1, F2> IsStaticSender for MappedWithSender<T, W, F1, F2>
 where
     T: IsStaticSender + Send + Sync,
+    F1: Fn(W) -> T::With + Sync,
+    F2: Fn(T::With) -> W + Sync,
 {
     type Protocol = T::Protocol;
 
@@ -210,6 +475,11 @@ where
         }
     }
 
+    fn wait_for_space(this: &Self) -> Option<impl Future<Output = bool> + Send + '_> {
+        T::wait_for_space(&this.sender)
+    }
+
+    #[cfg(blocking)]
     fn send_protocol_blocking_with(
         this: &Self,
         protocol: Self::Protocol,
@@ -221,3 +491,370 @@ where
         }
     }
 }
+
+/// A wrapper around a sender, which determines the `with`-value of every message with a
+/// [`WithDefault`].
+///
+/// Unlike the other wrappers, this only implements [`Sends<M>`] for messages that have a
+/// [`WithDefault<M>`] implementation.
+#[derive(Debug, Clone)]
+pub struct DefaultWithSender<T, D> {
+    sender: T,
+    defaults: D,
+}
+
+impl<T, D> DefaultWithSender<T, D> {
+    pub fn new(sender: T, defaults: D) -> Self {
+        Self { sender, defaults }
+    }
+
+    pub fn into_inner(self) -> (T, D) {
+        (self.sender, self.defaults)
+    }
+
+    pub fn inner_ref(&self) -> (&T, &D) {
+        (&self.sender, &self.defaults)
+    }
+
+    pub fn inner_mut(&mut self) -> (&mut T, &mut D) {
+        (&mut self.sender, &mut self.defaults)
+    }
+}
+
+forward_broadcast_sender_impl!(<T, D> DefaultWithSender<T, D>);
+
+impl<T: IsSender, D> IsSender for DefaultWithSender<T, D> {
+    type With = ();
+
+    forward_sender_methods!();
+}
+
+impl<M, T, D> Sends<M> for DefaultWithSender<T, D>
+where
+    T: Sends<M>,
+    D: WithDefault<M, With = T::With>,
+{
+    fn send_msg_with(
+        this: &Self,
+        msg: M,
+        with: (),
+    ) -> impl Future<Output = Result<(), SendError<(M, ())>>> + Send {
+        let default = this.defaults.with_default(&msg);
+        let fut = T::send_msg_with(&this.sender, msg, default);
+        async move { fut.await.map_err(|e| e.map(|(msg, _)| (msg, with))) }
+    }
+
+    #[cfg(blocking)]
+    fn send_msg_blocking_with(this: &Self, msg: M, with: ()) -> Result<(), SendError<(M, ())>> {
+        let default = this.defaults.with_default(&msg);
+        T::send_msg_blocking_with(&this.sender, msg, default)
+            .map_err(|e| e.map(|(msg, _)| (msg, with)))
+    }
+
+    fn try_send_msg_with(this: &Self, msg: M, with: ()) -> Result<(), TrySendError<(M, ())>> {
+        let default = this.defaults.with_default(&msg);
+        T::try_send_msg_with(&this.sender, msg, default).map_err(|e| e.map(|(msg, _)| (msg, with)))
+    }
+
+    fn wait_for_space(this: &Self) -> Option<impl Future<Output = bool> + Send + '_> {
+        T::wait_for_space(&this.sender)
+    }
+}
+
+/// A wrapper around a sender, which gives any channel approximate priority semantics.
+///
+/// The `with`-value of the adapter is the priority. Protocols that can not be sent because the
+/// channel is full are kept in a reorder buffer of at most `depth` protocols, from which the
+/// protocol with the highest priority is forwarded first when space becomes available. Protocols
+/// with equal priority are forwarded in the order they were sent. The buffer is shared between
+/// clones of the adapter.
+///
+/// Buffered protocols are forwarded whenever the adapter sends, or when it is flushed with
+/// [`PriorityAdapter::flush`] or [`PriorityAdapter::try_flush`]. Once the buffer is full, sending
+/// waits for space using [`IsStaticSender::wait_for_space`] of the sender. Senders that can not
+/// wait for space without sending are instead handed the protocol with the highest priority to
+/// wait with, in which case that protocol is lost if the send is dropped, and if the channel
+/// closes meanwhile, the protocol that is returned may be a buffered one instead of the one that
+/// was sent.
+///
+/// ```
+/// # use meslin::*;
+/// # futures::executor::block_on(async {
+/// let (sender, receiver) = mpmc::bounded::<u32>(1);
+/// let sender = sender.priority_adapter::<u8>(2);
+///
+/// sender.send_with::<u32>(1u32, 0).await.unwrap();
+/// sender.send_with::<u32>(2u32, 1).await.unwrap();
+/// sender.send_with::<u32>(3u32, 2).await.unwrap();
+/// assert_eq!(sender.buffered(), 2);
+///
+/// assert_eq!(receiver.recv_async().await.unwrap(), 1);
+/// sender.try_flush();
+/// assert_eq!(receiver.recv_async().await.unwrap(), 3);
+/// sender.flush().await.unwrap();
+/// assert_eq!(receiver.recv_async().await.unwrap(), 2);
+/// # });
+/// ```
+pub struct PriorityAdapter<T: IsStaticSender, O> {
+    sender: T,
+    depth: usize,
+    buffer: std::sync::Arc<std::sync::Mutex<ReorderBuffer<T::Protocol, O>>>,
+}
+
+struct ReorderBuffer<P, O> {
+    heap: std::collections::BinaryHeap<Buffered<P, O>>,
+    next_seq: u64,
+}
+
+struct Buffered<P, O> {
+    priority: O,
+    seq: u64,
+    protocol: P,
+}
+
+impl<P, O: Ord> PartialEq for Buffered<P, O> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl<P, O: Ord> Eq for Buffered<P, O> {}
+
+impl<P, O: Ord> PartialOrd for Buffered<P, O> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<P, O: Ord> Ord for Buffered<P, O> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl<T, O> PriorityAdapter<T, O>
+where
+    T: IsStaticSender,
+    T::With: Default,
+    O: Ord,
+{
+    /// Create an adapter with a reorder buffer of at most `depth` protocols.
+    ///
+    /// # Flushing
+    /// The adapter does not forward buffered protocols by itself: they are only forwarded when
+    /// one of its clones sends, or is flushed. A producer that stops sending must therefore call
+    /// [`PriorityAdapter::flush`] when it is done, since its buffered protocols are otherwise never
+    /// forwarded, and are dropped together with the last clone of the adapter.
+    pub fn new(sender: T, depth: usize) -> Self {
+        Self {
+            sender,
+            depth,
+            buffer: std::sync::Arc::new(std::sync::Mutex::new(ReorderBuffer {
+                heap: std::collections::BinaryHeap::new(),
+                next_seq: 0,
+            })),
+        }
+    }
+
+    pub fn inner_ref(&self) -> &T {
+        &self.sender
+    }
+
+    /// The maximum amount of protocols that are buffered.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// The amount of protocols that are buffered.
+    pub fn buffered(&self) -> usize {
+        self.buffer.lock().unwrap().heap.len()
+    }
+
+    /// Forward as many buffered protocols as fit in the channel, without waiting.
+    pub fn try_flush(&self) {
+        self.forward(&mut self.buffer.lock().unwrap());
+    }
+
+    /// Forward all buffered protocols, waiting asynchronously until space becomes available.
+    ///
+    /// Fails if the channel is closed, returning the protocol that could not be sent.
+    pub async fn flush(&self) -> Result<(), SendError<(T::Protocol, O)>> {
+        loop {
+            {
+                let mut buffer = self.buffer.lock().unwrap();
+                self.forward(&mut buffer);
+                if buffer.heap.is_empty() {
+                    return Ok(());
+                }
+            }
+            // Protocols are only taken out of the buffer once there is space, if the sender can
+            // wait for it, so that they are not lost if this future is dropped.
+            let has_space = match T::wait_for_space(&self.sender) {
+                Some(space) => space.await,
+                None => false,
+            };
+            if !has_space {
+                let Some(buffered) = self.buffer.lock().unwrap().heap.pop() else {
+                    return Ok(());
+                };
+                T::send_protocol_with(&self.sender, buffered.protocol, T::With::default())
+                    .await
+                    .map_err(|e| e.map(|(protocol, _)| (protocol, buffered.priority)))?;
+            }
+        }
+    }
+
+    /// Forward buffered protocols, in order of priority, until the channel is full.
+    fn forward(&self, buffer: &mut ReorderBuffer<T::Protocol, O>) {
+        while let Some(buffered) = buffer.heap.pop() {
+            let Buffered {
+                priority,
+                seq,
+                protocol,
+            } = buffered;
+            match T::try_send_protocol_with(&self.sender, protocol, T::With::default()) {
+                Ok(()) => (),
+                Err(TrySendError::Full((protocol, _)) | TrySendError::Closed((protocol, _))) => {
+                    buffer.heap.push(Buffered {
+                        priority,
+                        seq,
+                        protocol,
+                    });
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Push the protocol to the buffer, even if it is full.
+    fn push(buffer: &mut ReorderBuffer<T::Protocol, O>, protocol: T::Protocol, priority: O) {
+        let seq = buffer.next_seq;
+        buffer.next_seq += 1;
+        buffer.heap.push(Buffered {
+            priority,
+            seq,
+            protocol,
+        });
+    }
+}
+
+impl<T: IsStaticSender + Clone, O> Clone for PriorityAdapter<T, O> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            depth: self.depth,
+            buffer: self.buffer.clone(),
+        }
+    }
+}
+
+impl<T: IsStaticSender + Debug, O> Debug for PriorityAdapter<T, O> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PriorityAdapter")
+            .field("sender", &self.sender)
+            .field("depth", &self.depth)
+            .field("buffered", &self.buffer.lock().unwrap().heap.len())
+            .finish()
+    }
+}
+
+impl<T: IsStaticSender, O> IsSender for PriorityAdapter<T, O> {
+    type With = O;
+
+    fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        self.sender.capacity().map(|capacity| capacity + self.depth)
+    }
+
+    fn len(&self) -> usize {
+        self.sender.len() + self.buffer.lock().unwrap().heap.len()
+    }
+
+    forward_sender_methods!(receiver_count, sender_count);
+
+    fn is_full(&self) -> bool {
+        self.buffer.lock().unwrap().heap.len() >= self.depth && self.sender.is_full()
+    }
+
+    fn remaining(&self) -> Option<usize> {
+        let buffered = self.buffer.lock().unwrap().heap.len();
+        self.sender
+            .remaining()
+            .map(|remaining| remaining + self.depth.saturating_sub(buffered))
+    }
+
+    fn stats(&self) -> ChannelStats {
+        ChannelStats {
+            len: self.len(),
+            capacity: self.capacity(),
+            ..self.sender.stats()
+        }
+    }
+}
+
+impl<T, O> IsStaticSender for PriorityAdapter<T, O>
+where
+    T: IsStaticSender + Sync,
+    T::Protocol: Send,
+    T::With: Default,
+    O: Ord + Send,
+{
+    type Protocol = T::Protocol;
+
+    async fn send_protocol_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        with: O,
+    ) -> Result<(), SendError<(Self::Protocol, O)>> {
+        let (mut protocol, mut with) = match Self::try_send_protocol_with(this, protocol, with) {
+            Ok(()) => return Ok(()),
+            Err(TrySendError::Closed(protocol)) => return Err(SendError(protocol)),
+            Err(TrySendError::Full(protocol)) => protocol,
+        };
+        // Wait for space without taking a protocol out of the buffer, if the sender can, so that
+        // nothing is lost if this future is dropped.
+        while let Some(space) = T::wait_for_space(&this.sender) {
+            if !space.await {
+                return Err(SendError((protocol, with)));
+            }
+            (protocol, with) = match Self::try_send_protocol_with(this, protocol, with) {
+                Ok(()) => return Ok(()),
+                Err(TrySendError::Closed(protocol)) => return Err(SendError(protocol)),
+                Err(TrySendError::Full(protocol)) => protocol,
+            };
+        }
+        // Otherwise make space by taking the protocol with the highest priority out of the
+        // buffer, which may be the protocol that is being sent.
+        let buffered = {
+            let mut buffer = this.buffer.lock().unwrap();
+            Self::push(&mut buffer, protocol, with);
+            buffer.heap.pop().unwrap()
+        };
+        T::send_protocol_with(&this.sender, buffered.protocol, T::With::default())
+            .await
+            .map_err(|e| e.map(|(protocol, _)| (protocol, buffered.priority)))
+    }
+
+    fn try_send_protocol_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        with: O,
+    ) -> Result<(), TrySendError<(Self::Protocol, O)>> {
+        let mut buffer = this.buffer.lock().unwrap();
+        if this.sender.is_closed() {
+            return Err(TrySendError::Closed((protocol, with)));
+        }
+        this.forward(&mut buffer);
+        if buffer.heap.len() >= this.depth && this.sender.is_full() {
+            return Err(TrySendError::Full((protocol, with)));
+        }
+        Self::push(&mut buffer, protocol, with);
+        this.forward(&mut buffer);
+        Ok(())
+    }
+}
@@ -0,0 +1,164 @@
+use crate::*;
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+/// A snapshot of the health of a channel, returned by [`IsSender::stats`].
+///
+/// Stats are reported by senders only: to observe a channel from the receiving side, keep a
+/// sender of the same channel. The number of sent messages and the creation time are only
+/// available for senders that are [`Counted`], and the number of dropped messages only for
+/// channels that track it, like the [`mpmc`](crate::mpmc) channel; otherwise they are `None`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChannelStats {
+    /// The number of messages in the channel.
+    pub len: usize,
+    /// The capacity of the channel, if it is bounded.
+    pub capacity: Option<usize>,
+    /// The number of senders of the channel.
+    pub sender_count: usize,
+    /// The number of receivers of the channel.
+    pub receiver_count: usize,
+    /// The total number of messages that were sent through a [`Counted`] sender.
+    pub sent: Option<u64>,
+    /// The total number of messages that were dropped, e.g. by a [`Backpressure`] strategy.
+    pub dropped: Option<u64>,
+    /// When the [`Counted`] sender was created. This is not available on `wasm32` targets.
+    pub created_at: Option<Instant>,
+}
+
+impl ChannelStats {
+    /// The stats of the sender, without counters.
+    pub(crate) fn without_counters<S: IsSender + ?Sized>(sender: &S) -> Self {
+        Self {
+            len: sender.len(),
+            capacity: sender.capacity(),
+            sender_count: sender.sender_count(),
+            receiver_count: sender.receiver_count(),
+            sent: None,
+            dropped: None,
+            created_at: None,
+        }
+    }
+}
+
+/// A wrapper around a sender, which counts the messages that are sent successfully.
+///
+/// Created with [`IsSenderExt::counted`]. Counting is opt-in, so that senders that don't need
+/// it don't pay for a shared counter on every send. Clones share the same counter, and the
+/// count is reported by [`IsSender::stats`]. Messages that are accepted but then dropped by a
+/// [`Backpressure`] strategy are counted as sent, and also as dropped by the channel.
+///
+/// ```
+/// # use meslin::*;
+/// let (sender, _receiver) = mpmc::unbounded::<u32>();
+/// let sender = sender.counted();
+/// sender.clone().try_send::<u32>(1u32).unwrap();
+/// sender.try_send::<u32>(2u32).unwrap();
+/// assert_eq!(sender.stats().sent, Some(2));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Counted<T> {
+    sender: T,
+    counters: Arc<Counters>,
+}
+
+#[derive(Debug)]
+struct Counters {
+    sent: AtomicU64,
+    created_at: Option<Instant>,
+}
+
+impl<T> Counted<T> {
+    pub fn new(sender: T) -> Self {
+        Self {
+            sender,
+            counters: Arc::new(Counters {
+                sent: AtomicU64::new(0),
+                created_at: (!cfg!(target_arch = "wasm32")).then(Instant::now),
+            }),
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.sender
+    }
+
+    pub fn inner_ref(&self) -> &T {
+        &self.sender
+    }
+
+    fn sent<E>(&self, result: Result<(), E>) -> Result<(), E> {
+        if result.is_ok() {
+            self.counters.sent.fetch_add(1, Ordering::Relaxed);
+        }
+        result
+    }
+}
+
+forward_broadcast_sender_impl!(<T> Counted<T>);
+
+impl<T: IsSender> IsSender for Counted<T> {
+    type With = T::With;
+
+    forward_sender_methods!(
+        is_closed,
+        capacity,
+        len,
+        receiver_count,
+        sender_count,
+        is_full,
+        remaining
+    );
+
+    fn stats(&self) -> ChannelStats {
+        ChannelStats {
+            sent: Some(self.counters.sent.load(Ordering::Relaxed)),
+            created_at: self.counters.created_at,
+            ..self.sender.stats()
+        }
+    }
+}
+
+impl<T> IsStaticSender for Counted<T>
+where
+    T: IsStaticSender + Sync,
+    T::Protocol: Send,
+    T::With: Send,
+{
+    type Protocol = T::Protocol;
+
+    async fn send_protocol_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        with: Self::With,
+    ) -> Result<(), SendError<(Self::Protocol, Self::With)>> {
+        this.sent(T::send_protocol_with(&this.sender, protocol, with).await)
+    }
+
+    fn try_send_protocol_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        with: Self::With,
+    ) -> Result<(), TrySendError<(Self::Protocol, Self::With)>> {
+        this.sent(T::try_send_protocol_with(&this.sender, protocol, with))
+    }
+
+    fn wait_for_space(this: &Self) -> Option<impl Future<Output = bool> + Send + '_> {
+        T::wait_for_space(&this.sender)
+    }
+
+    #[cfg(blocking)]
+    fn send_protocol_blocking_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        with: Self::With,
+    ) -> Result<(), SendError<(Self::Protocol, Self::With)>> {
+        this.sent(T::send_protocol_blocking_with(&this.sender, protocol, with))
+    }
+}
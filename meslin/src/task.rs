@@ -0,0 +1,142 @@
+//! Helpers to create a channel and spawn the task that receives from it, in one go.
+//!
+//! These do not supervise the spawned task in any way: they only remove the boilerplate of
+//! creating a channel, moving the receiver into a future and spawning it.
+//!
+//! ```
+//! # use meslin::*;
+//! # #[tokio::main]
+//! # async fn main() {
+//! let (sender, handle) = task::spawn::<mpmc::Mpmc, u32, _, _>(
+//!     mpmc::Config::unbounded(),
+//!     |receiver| async move { receiver.recv_async().await.unwrap() * 2 },
+//! );
+//! sender.send::<u32>(21u32).await.unwrap();
+//! assert_eq!(handle.await.unwrap(), 42);
+//! # }
+//! ```
+
+use crate::*;
+#[cfg(feature = "mpmc")]
+use futures::future::{join_all, JoinAll};
+use std::future::Future;
+
+/// Create a channel using backend `B` and spawn the future returned by `f` on the current
+/// `tokio` runtime, returning the sender and the [`JoinHandle`](tokio::task::JoinHandle).
+///
+/// # Panics
+/// Panics if called outside of a `tokio` runtime.
+#[cfg(feature = "tokio")]
+pub fn spawn<B, P, F, Fut>(
+    config: B::Config,
+    f: F,
+) -> (B::Sender, tokio::task::JoinHandle<Fut::Output>)
+where
+    B: ChannelBackend<P>,
+    F: FnOnce(B::Receiver) -> Fut,
+    Fut: Future + Send + 'static,
+    Fut::Output: Send + 'static,
+{
+    let (sender, receiver) = channel::<P, B>(config);
+    (sender, tokio::spawn(f(receiver)))
+}
+
+/// Create a channel using backend `B` and spawn the future returned by `f` on the global `smol`
+/// executor, returning the sender and the [`Task`](smol::Task).
+///
+/// The task is canceled when the [`Task`](smol::Task) is dropped, unless it is detached.
+#[cfg(feature = "smol")]
+pub fn spawn_smol<B, P, F, Fut>(config: B::Config, f: F) -> (B::Sender, smol::Task<Fut::Output>)
+where
+    B: ChannelBackend<P>,
+    F: FnOnce(B::Receiver) -> Fut,
+    Fut: Future + Send + 'static,
+    Fut::Output: Send + 'static,
+{
+    let (sender, receiver) = channel::<P, B>(config);
+    (sender, smol::spawn(f(receiver)))
+}
+
+/// Spawn a pool of `workers` competing consumers on the current `tokio` runtime, which all
+/// receive from the same mpmc-channel.
+///
+/// The future of every worker is created by calling `f` with the index of the worker and a clone
+/// of the receiver. Returns the shared sender and a future that resolves once all workers have
+/// finished, with their outputs in order of index.
+///
+/// ```
+/// # use meslin::*;
+/// # #[tokio::main]
+/// # async fn main() {
+/// let (sender, workers) = task::spawn_pool::<u32, _, _>(4, mpmc::Config::bounded(8), |_, receiver| {
+///     async move {
+///         let mut handled = 0;
+///         while receiver.recv_async().await.is_ok() {
+///             handled += 1;
+///         }
+///         handled
+///     }
+/// });
+/// for i in 0..10u32 {
+///     sender.send::<u32>(i).await.unwrap();
+/// }
+/// drop(sender);
+/// let handled: u32 = workers.await.into_iter().map(Result::unwrap).sum();
+/// assert_eq!(handled, 10);
+/// # }
+/// ```
+///
+/// # Panics
+/// Panics if called outside of a `tokio` runtime.
+#[cfg(all(feature = "tokio", feature = "mpmc"))]
+pub fn spawn_pool<P, F, Fut>(
+    workers: usize,
+    config: mpmc::Config,
+    f: F,
+) -> (
+    mpmc::Sender<P>,
+    JoinAll<tokio::task::JoinHandle<Fut::Output>>,
+)
+where
+    P: Send,
+    F: FnMut(usize, mpmc::Receiver<P>) -> Fut,
+    Fut: Future + Send + 'static,
+    Fut::Output: Send + 'static,
+{
+    spawn_pool_with(workers, config, f, tokio::spawn)
+}
+
+/// Like [`spawn_pool`], but spawns the workers on the global `smol` executor.
+///
+/// The workers are canceled when the returned future is dropped.
+#[cfg(all(feature = "smol", feature = "mpmc"))]
+pub fn spawn_pool_smol<P, F, Fut>(
+    workers: usize,
+    config: mpmc::Config,
+    f: F,
+) -> (mpmc::Sender<P>, JoinAll<smol::Task<Fut::Output>>)
+where
+    P: Send,
+    F: FnMut(usize, mpmc::Receiver<P>) -> Fut,
+    Fut: Future + Send + 'static,
+    Fut::Output: Send + 'static,
+{
+    spawn_pool_with(workers, config, f, smol::spawn)
+}
+
+#[cfg(feature = "mpmc")]
+fn spawn_pool_with<P, F, Fut, H>(
+    workers: usize,
+    config: mpmc::Config,
+    mut f: F,
+    spawn: impl Fn(Fut) -> H,
+) -> (mpmc::Sender<P>, JoinAll<H>)
+where
+    P: Send,
+    F: FnMut(usize, mpmc::Receiver<P>) -> Fut,
+    H: Future,
+{
+    let (sender, receiver) = channel::<P, mpmc::Mpmc>(config);
+    let handles = join_all((0..workers).map(|i| spawn(f(i, receiver.clone()))));
+    (sender, handles)
+}
@@ -0,0 +1,115 @@
+//! Support for property testing with [`proptest`] and [`arbitrary`].
+//!
+//! [`Msg<T>`] and [`Request<A, B>`] implement both [`proptest::arbitrary::Arbitrary`] and
+//! [`arbitrary::Arbitrary`]; tuples and most other messages are already supported by the crates
+//! themselves. The [`protocol_strategy!`](crate::protocol_strategy) macro creates a strategy
+//! that generates random protocol traffic from the messages of a protocol.
+//!
+//! Generated requests are created without anyone awaiting their reply.
+//!
+//! ```
+//! # use meslin::{*, test_strategies::proptest::prelude::*};
+//! #[derive(Debug, Message, From, TryInto)]
+//! enum Protocol {
+//!     Number(u32),
+//!     Text(String),
+//!     Request(Request<u32, u32>),
+//! }
+//!
+//! proptest!(|(protocols in prop::collection::vec(
+//!     protocol_strategy![Protocol: u32, String, Request<u32, u32>],
+//!     0..10,
+//! ))| {
+//!     let (sender, receiver) = mpmc::unbounded::<Protocol>();
+//!     let len = protocols.len();
+//!     for protocol in protocols {
+//!         sender.try_send::<Protocol>(protocol).unwrap();
+//!     }
+//!     prop_assert_eq!(receiver.len(), len);
+//! });
+//! ```
+use crate::*;
+use ::proptest::{
+    arbitrary::{any_with, Arbitrary as PropArbitrary},
+    strategy::{BoxedStrategy, Strategy},
+};
+
+/// Re-export of [`proptest`](::proptest).
+pub use ::proptest;
+
+/// Re-export of [`arbitrary`](::arbitrary).
+pub use ::arbitrary;
+
+/// Create a [`Strategy`] that generates a protocol from one of the given messages.
+///
+/// Every message must implement [`proptest::arbitrary::Arbitrary`], and the protocol must
+/// implement `From` for every message.
+///
+/// Example:
+/// - `protocol_strategy![Protocol: u32, String]`
+#[macro_export]
+macro_rules! protocol_strategy {
+    ($protocol:ty: $($msg:ty),+ $(,)?) => {
+        $crate::test_strategies::proptest::prop_oneof![
+            $($crate::test_strategies::proptest::strategy::Strategy::prop_map(
+                $crate::test_strategies::proptest::arbitrary::any::<$msg>(),
+                <$protocol as ::core::convert::From<$msg>>::from,
+            )),+
+        ]
+    };
+}
+
+impl<T> PropArbitrary for Msg<T>
+where
+    T: PropArbitrary + 'static,
+{
+    type Parameters = T::Parameters;
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(args: Self::Parameters) -> Self::Strategy {
+        any_with::<T>(args).prop_map(Msg).boxed()
+    }
+}
+
+impl<'a, T> arbitrary::Arbitrary<'a> for Msg<T>
+where
+    T: arbitrary::Arbitrary<'a>,
+{
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        T::arbitrary(u).map(Msg)
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        T::size_hint(depth)
+    }
+}
+
+#[cfg(feature = "request")]
+impl<A, B> PropArbitrary for Request<A, B>
+where
+    A: PropArbitrary + 'static,
+    B: std::fmt::Debug + 'static,
+{
+    type Parameters = A::Parameters;
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(args: Self::Parameters) -> Self::Strategy {
+        any_with::<A>(args)
+            .prop_map(|msg| Request::new(msg).0)
+            .boxed()
+    }
+}
+
+#[cfg(feature = "request")]
+impl<'a, A, B> arbitrary::Arbitrary<'a> for Request<A, B>
+where
+    A: arbitrary::Arbitrary<'a>,
+{
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        A::arbitrary(u).map(|msg| Request::new(msg).0)
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        A::size_hint(depth)
+    }
+}
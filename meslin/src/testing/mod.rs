@@ -0,0 +1,6 @@
+//! Utilities for testing actors and protocols.
+
+mod test_receiver;
+pub use test_receiver::*;
+
+pub mod pump;
@@ -0,0 +1,199 @@
+//! A channel whose delivery is driven manually.
+//!
+//! Messages sent through a [`pump::Sender`](Sender) are staged, and only become available to
+//! the [`Receiver`] once they are released with [`Pump::pump`]. This allows tests to
+//! deterministically interleave sending and receiving, without relying on the scheduling of
+//! the executor.
+//!
+//! ```
+//! # use meslin::{testing::pump, IsSenderExt};
+//! let (sender, pump, receiver) = pump::channel::<u32>();
+//!
+//! sender.try_send::<u32>(1u32).unwrap();
+//! sender.try_send::<u32>(2u32).unwrap();
+//! assert!(receiver.try_recv().is_err());
+//!
+//! assert_eq!(pump.pump(1), 1);
+//! assert_eq!(receiver.try_recv().unwrap(), 1);
+//! assert!(receiver.try_recv().is_err());
+//! ```
+use crate::*;
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    sync::{Arc, Mutex},
+};
+
+/// Re-export of [`flume::Receiver`].
+pub use flume::Receiver;
+
+struct State<P> {
+    staged: VecDeque<P>,
+    sender_count: usize,
+    tx: Option<flume::Sender<P>>,
+}
+
+impl<P> State<P> {
+    fn is_closed(&self) -> bool {
+        match &self.tx {
+            Some(tx) => tx.is_disconnected(),
+            None => true,
+        }
+    }
+
+    /// Drop the inner sender once no more messages can be released.
+    fn close_if_done(&mut self) {
+        if self.sender_count == 0 && self.staged.is_empty() {
+            self.tx = None;
+        }
+    }
+}
+
+/// The sending half of a [pump-channel](self).
+pub struct Sender<P> {
+    shared: Arc<Mutex<State<P>>>,
+}
+
+/// The handle used to release staged messages of a [pump-channel](self) to the receiver.
+pub struct Pump<P> {
+    shared: Arc<Mutex<State<P>>>,
+}
+
+impl<P> Pump<P> {
+    /// Release up to `n` staged messages to the receiver, returning the amount released.
+    pub fn pump(&self, n: usize) -> usize {
+        let mut state = self.shared.lock().unwrap();
+        let mut released = 0;
+        while released < n {
+            let Some(protocol) = state.staged.pop_front() else {
+                break;
+            };
+            if let Some(tx) = &state.tx {
+                // The receiver might have been dropped, in which case the message is lost.
+                let _ = tx.send(protocol);
+            }
+            released += 1;
+        }
+        state.close_if_done();
+        released
+    }
+
+    /// Release all staged messages to the receiver, returning the amount released.
+    pub fn pump_all(&self) -> usize {
+        self.pump(usize::MAX)
+    }
+
+    /// Returns the number of staged messages that have not been released yet.
+    pub fn staged(&self) -> usize {
+        self.shared.lock().unwrap().staged.len()
+    }
+}
+
+impl<P> IsSender for Sender<P> {
+    type With = ();
+
+    fn is_closed(&self) -> bool {
+        self.shared.lock().unwrap().is_closed()
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        None
+    }
+
+    fn len(&self) -> usize {
+        let state = self.shared.lock().unwrap();
+        state.staged.len() + state.tx.as_ref().map(|tx| tx.len()).unwrap_or(0)
+    }
+
+    fn receiver_count(&self) -> usize {
+        let state = self.shared.lock().unwrap();
+        state.tx.as_ref().map(|tx| tx.receiver_count()).unwrap_or(0)
+    }
+
+    fn sender_count(&self) -> usize {
+        self.shared.lock().unwrap().sender_count
+    }
+}
+
+impl<P: Send> IsStaticSender for Sender<P> {
+    type Protocol = P;
+
+    async fn send_protocol_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        _with: (),
+    ) -> Result<(), SendError<(Self::Protocol, ())>> {
+        Self::try_send_protocol_with(this, protocol, ()).map_err(|e| SendError(e.into_inner()))
+    }
+
+    #[cfg(blocking)]
+    fn send_protocol_blocking_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        _with: (),
+    ) -> Result<(), SendError<(Self::Protocol, ())>> {
+        Self::try_send_protocol_with(this, protocol, ()).map_err(|e| SendError(e.into_inner()))
+    }
+
+    fn try_send_protocol_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        _with: (),
+    ) -> Result<(), TrySendError<(Self::Protocol, ())>> {
+        let mut state = this.shared.lock().unwrap();
+        if state.is_closed() {
+            return Err(TrySendError::Closed((protocol, ())));
+        }
+        state.staged.push_back(protocol);
+        Ok(())
+    }
+}
+
+impl<P> Clone for Sender<P> {
+    fn clone(&self) -> Self {
+        self.shared.lock().unwrap().sender_count += 1;
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<P> Drop for Sender<P> {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock().unwrap();
+        state.sender_count -= 1;
+        state.close_if_done();
+    }
+}
+
+impl<P> Debug for Sender<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Sender")
+            .field("staged", &self.shared.lock().unwrap().staged.len())
+            .finish()
+    }
+}
+
+impl<P> Debug for Pump<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Pump")
+            .field("staged", &self.staged())
+            .finish()
+    }
+}
+
+pub fn channel<P>() -> (Sender<P>, Pump<P>, flume::Receiver<P>) {
+    let (tx, receiver) = flume::unbounded();
+    let shared = Arc::new(Mutex::new(State {
+        staged: VecDeque::new(),
+        sender_count: 1,
+        tx: Some(tx),
+    }));
+    (
+        Sender {
+            shared: shared.clone(),
+        },
+        Pump { shared },
+        receiver,
+    )
+}
@@ -0,0 +1,136 @@
+use crate::{
+    time::{timeout, Clock, SystemClock},
+    *,
+};
+use std::{any::type_name, sync::Arc, time::Duration};
+
+/// A wrapper around a receiver, that provides helpers for asserting on received messages.
+///
+/// All `expect_{...}` methods panic if the expectation is not met. Waiting for a message
+/// panics after the timeout has elapsed, which is 1 second by default. Time is measured
+/// using the [`SystemClock`], unless a different [`Clock`] is set with [`TestReceiver::with_clock`].
+///
+/// ```
+/// # use meslin::{mpmc, testing::TestReceiver, IsSenderExt};
+/// # futures::executor::block_on(async {
+/// let (sender, receiver) = mpmc::unbounded::<u32>();
+/// let mut receiver = TestReceiver::new(receiver);
+///
+/// sender.send::<u32>(10u32).await.unwrap();
+/// assert_eq!(receiver.expect_next::<u32>().await, 10);
+/// receiver.expect_no_message(std::time::Duration::from_millis(10)).await;
+/// # });
+/// ```
+#[derive(Debug)]
+pub struct TestReceiver<R> {
+    receiver: R,
+    timeout: Duration,
+    clock: Arc<dyn Clock>,
+}
+
+impl<R: IsReceiver> TestReceiver<R> {
+    pub fn new(receiver: R) -> Self {
+        Self {
+            receiver,
+            timeout: Duration::from_secs(1),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Set the maximum time to wait for an expected message.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the [`Clock`] used to measure timeouts.
+    pub fn with_clock(mut self, clock: impl Clock) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    pub fn inner(&self) -> &R {
+        &self.receiver
+    }
+
+    pub fn inner_mut(&mut self) -> &mut R {
+        &mut self.receiver
+    }
+
+    pub fn into_inner(self) -> R {
+        self.receiver
+    }
+
+    /// Expect the next protocol and its `with`-value.
+    pub async fn expect_protocol_with(&mut self) -> (R::Protocol, R::With) {
+        let fut = R::recv_protocol_with(&mut self.receiver);
+        match timeout(&*self.clock, self.timeout, fut).await {
+            Ok(Ok(protocol)) => protocol,
+            Ok(Err(RecvError)) => panic!(
+                "Expected protocol {}, but the channel is closed",
+                type_name::<R::Protocol>()
+            ),
+            Err(_) => panic!(
+                "Expected protocol {}, but none was received within {:?}",
+                type_name::<R::Protocol>(),
+                self.timeout
+            ),
+        }
+    }
+
+    /// Expect the next protocol.
+    pub async fn expect_protocol(&mut self) -> R::Protocol {
+        self.expect_protocol_with().await.0
+    }
+
+    /// Expect the next message to be of type `M`.
+    pub async fn expect_next<M>(&mut self) -> M
+    where
+        R::Protocol: TryInto<M>,
+    {
+        match self.expect_protocol().await.try_into() {
+            Ok(msg) => msg,
+            Err(_) => panic!(
+                "Expected message {}, but received a different message",
+                type_name::<M>()
+            ),
+        }
+    }
+
+    /// Expect the next message to be a [`Request<A, B>`], which can then be replied to
+    /// using [`Request::reply`].
+    #[cfg(feature = "request")]
+    pub async fn expect_request<A, B>(&mut self) -> Request<A, B>
+    where
+        R::Protocol: TryInto<Request<A, B>>,
+    {
+        self.expect_next::<Request<A, B>>().await
+    }
+
+    /// Expect that no message is received within the given duration.
+    pub async fn expect_no_message(&mut self, duration: Duration) {
+        let fut = R::recv_protocol_with(&mut self.receiver);
+        if let Ok(Ok(_)) = timeout(&*self.clock, duration, fut).await {
+            panic!(
+                "Expected no message, but received protocol {}",
+                type_name::<R::Protocol>()
+            )
+        }
+    }
+
+    /// Expect that the channel is closed and empty.
+    pub async fn expect_closed(&mut self) {
+        let fut = R::recv_protocol_with(&mut self.receiver);
+        match timeout(&*self.clock, self.timeout, fut).await {
+            Ok(Err(RecvError)) => (),
+            Ok(Ok(_)) => panic!(
+                "Expected the channel to be closed, but received protocol {}",
+                type_name::<R::Protocol>()
+            ),
+            Err(_) => panic!(
+                "Expected the channel to be closed, but it was still open after {:?}",
+                self.timeout
+            ),
+        }
+    }
+}
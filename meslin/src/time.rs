@@ -0,0 +1,1169 @@
+//! Clocks used by all timeout-based APIs.
+//!
+//! Every API that waits for a duration does so through a [`Clock`], which defaults to the
+//! [`SystemClock`]. In tests, a [`ManualClock`] can be used instead, which only moves forward
+//! when it is advanced explicitly.
+use crate::{
+    cancel::{send_until, until, Unsent},
+    forward_sender_methods, ChannelStats, IsReceiver, IsSender, IsStaticSender, Message, RecvError,
+    ResultFuture, SendError, Sends, TryRecvError, TrySendError,
+};
+use futures::{
+    future::{BoxFuture, Either},
+    pin_mut, Future,
+};
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    mem::Discriminant,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+use thiserror::Error;
+
+// The system time is not available through `std` on wasm targets.
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+/// A source of time, used for all timeout-based APIs.
+pub trait Clock: Send + Sync + Debug + 'static {
+    /// Returns the current time.
+    fn now(&self) -> Instant;
+
+    /// Returns a future that completes after the given duration.
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+}
+
+/// The default [`Clock`], backed by the system time and [`futures_timer`].
+///
+/// On `wasm32` targets, the time is read with `web-time`, and [`futures_timer`] sleeps using the
+/// timers of the browser, since neither the system time nor a timer thread are available there.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        Box::pin(futures_timer::Delay::new(duration))
+    }
+}
+
+/// A [`Clock`] that only moves forward when [`ManualClock::advance`] is called.
+///
+/// Cloning the clock returns a handle to the same clock.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    inner: Arc<Mutex<ManualClockInner>>,
+}
+
+#[derive(Debug)]
+struct ManualClockInner {
+    now: Instant,
+    next_id: u64,
+    sleepers: HashMap<u64, (Instant, Waker)>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(ManualClockInner {
+                now: Instant::now(),
+                next_id: 0,
+                sleepers: HashMap::new(),
+            })),
+        }
+    }
+
+    /// Advance the clock, waking all sleeps that have elapsed.
+    pub fn advance(&self, duration: Duration) {
+        let wakers = {
+            let mut inner = self.inner.lock().unwrap();
+            inner.now += duration;
+            let now = inner.now;
+            let elapsed = inner
+                .sleepers
+                .iter()
+                .filter(|(_, (deadline, _))| *deadline <= now)
+                .map(|(id, _)| *id)
+                .collect::<Vec<_>>();
+            elapsed
+                .into_iter()
+                .filter_map(|id| inner.sleepers.remove(&id))
+                .map(|(_, waker)| waker)
+                .collect::<Vec<_>>()
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+
+    /// Returns the number of sleeps that are currently waiting on this clock.
+    pub fn sleepers(&self) -> usize {
+        self.inner.lock().unwrap().sleepers.len()
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.inner.lock().unwrap().now
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        let mut inner = self.inner.lock().unwrap();
+        let id = inner.next_id;
+        inner.next_id += 1;
+        Box::pin(ManualSleep {
+            clock: self.inner.clone(),
+            deadline: inner.now + duration,
+            id,
+        })
+    }
+}
+
+struct ManualSleep {
+    clock: Arc<Mutex<ManualClockInner>>,
+    deadline: Instant,
+    id: u64,
+}
+
+impl Future for ManualSleep {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut inner = self.clock.lock().unwrap();
+        if inner.now >= self.deadline {
+            inner.sleepers.remove(&self.id);
+            Poll::Ready(())
+        } else {
+            inner
+                .sleepers
+                .insert(self.id, (self.deadline, cx.waker().clone()));
+            Poll::Pending
+        }
+    }
+}
+
+impl Drop for ManualSleep {
+    fn drop(&mut self) {
+        self.clock.lock().unwrap().sleepers.remove(&self.id);
+    }
+}
+
+/// Error that is returned when a timeout elapsed.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Error)]
+#[error("Timeout elapsed after {0:?}.")]
+pub struct Elapsed(pub Duration);
+
+/// Await the future, failing if it does not complete within the given duration.
+pub async fn timeout<F: Future>(
+    clock: &dyn Clock,
+    duration: Duration,
+    fut: F,
+) -> Result<F::Output, Elapsed> {
+    let sleep = clock.sleep(duration);
+    pin_mut!(fut);
+    match futures::future::select(fut, sleep).await {
+        Either::Left((output, _)) => Ok(output),
+        Either::Right(((), _)) => Err(Elapsed(duration)),
+    }
+}
+
+/// Extension trait for futures, adding a `.timeout(duration)` modifier.
+///
+/// When the duration elapses, the inner future is dropped. To get the message back when a send
+/// times out, use [`send_timeout`] instead of bounding the future of a send.
+///
+/// ```
+/// # use meslin::{*, time::{Elapsed, TimeoutExt}};
+/// # use std::time::Duration;
+/// # futures::executor::block_on(async {
+/// let (sender, _receiver) = mpmc::bounded::<u32>(1);
+/// sender.send::<u32>(1u32).timeout(Duration::from_secs(1)).await.unwrap().unwrap();
+///
+/// let result = sender.send::<u32>(2u32).timeout(Duration::from_millis(10)).await;
+/// assert_eq!(result.unwrap_err(), Elapsed(Duration::from_millis(10)));
+/// # });
+/// ```
+pub trait TimeoutExt: Future + Sized {
+    /// Bound the future by the given duration, measured by the [`SystemClock`].
+    fn timeout(self, duration: Duration) -> Timeout<Self> {
+        self.timeout_with_clock(&SystemClock, duration)
+    }
+
+    /// Like [`TimeoutExt::timeout`], but measures the duration using the given [`Clock`].
+    fn timeout_with_clock(self, clock: &dyn Clock, duration: Duration) -> Timeout<Self> {
+        Timeout {
+            fut: self,
+            sleep: clock.sleep(duration),
+            duration,
+        }
+    }
+}
+
+impl<F: Future> TimeoutExt for F {}
+
+pin_project_lite::pin_project! {
+    /// Future returned by [`TimeoutExt::timeout`].
+    pub struct Timeout<F> {
+        #[pin]
+        fut: F,
+        sleep: BoxFuture<'static, ()>,
+        duration: Duration,
+    }
+}
+
+impl<F: Future> Future for Timeout<F> {
+    type Output = Result<F::Output, Elapsed>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        if let Poll::Ready(output) = this.fut.poll(cx) {
+            return Poll::Ready(Ok(output));
+        }
+        match this.sleep.as_mut().poll(cx) {
+            Poll::Ready(()) => Poll::Ready(Err(Elapsed(*this.duration))),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<F> Debug for Timeout<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Timeout")
+            .field("duration", &self.duration)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Error that is returned by [`send_timeout`].
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Error)]
+pub enum SendTimeoutError<T> {
+    #[error("Channel is closed: Failed to send message {0:?}.")]
+    Closed(T),
+    /// The message is returned if it was not sent yet, see
+    /// [`CancelSendError::Cancelled`](crate::CancelSendError::Cancelled).
+    #[error("Timeout elapsed: Failed to send message {0:?}.")]
+    Elapsed(Option<T>),
+}
+
+/// Send a message, failing if it can not be sent before the deadline.
+///
+/// This is [`IsSenderExt::send`](crate::IsSenderExt::send) with a timeout, measured by the
+/// [`SystemClock`]. Like [`IsSenderExt::send_cancellable`](crate::IsSenderExt::send_cancellable),
+/// the message is returned when the deadline elapses while waiting for space in the channel.
+///
+/// ```
+/// # use meslin::{*, time::{send_timeout, SendTimeoutError}};
+/// # use std::time::Duration;
+/// # futures::executor::block_on(async {
+/// let monitor = Monitor::new();
+/// let (sender, receiver) = mpmc::bounded::<u32>(1);
+/// let (sender, _receiver) = (sender.monitored(&monitor), receiver.monitored(&monitor));
+/// send_timeout::<_, u32>(&sender, 1u32, Duration::from_secs(1)).await.unwrap();
+///
+/// let result = send_timeout::<_, u32>(&sender, 2u32, Duration::from_millis(10)).await;
+/// assert_eq!(result, Err(SendTimeoutError::Elapsed(Some(2))));
+/// # });
+/// ```
+pub async fn send_timeout<S, M>(
+    sender: &S,
+    msg: impl Into<M::Input>,
+    deadline: Duration,
+) -> Result<M::Output, SendTimeoutError<M::Input>>
+where
+    S: Sends<M>,
+    S::With: Default,
+    M: Message,
+{
+    send_timeout_with_clock(&SystemClock, sender, msg, deadline).await
+}
+
+/// Like [`send_timeout`], but measures the deadline using the given [`Clock`].
+pub async fn send_timeout_with_clock<S, M>(
+    clock: &dyn Clock,
+    sender: &S,
+    msg: impl Into<M::Input>,
+    deadline: Duration,
+) -> Result<M::Output, SendTimeoutError<M::Input>>
+where
+    S: Sends<M>,
+    S::With: Default,
+    M: Message,
+{
+    send_until::<S, M>(sender, msg.into(), clock.sleep(deadline))
+        .await
+        .map_err(|e| match e {
+            Unsent::Closed(msg) => SendTimeoutError::Closed(msg),
+            Unsent::Stopped(msg) => SendTimeoutError::Elapsed(msg),
+        })
+}
+
+/// Error that is returned by [`ask`].
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Error)]
+pub enum AskError<M, E> {
+    #[error("Channel is closed: Failed to send message {0:?}.")]
+    Closed(M),
+    #[error("No reply received: {0}")]
+    NoReply(#[source] E),
+    /// The message is returned if it was not sent yet, see [`SendTimeoutError::Elapsed`]. It
+    /// is lost if the deadline elapsed while waiting for the reply.
+    #[error("Timeout elapsed: Failed to send message {0:?}.")]
+    Elapsed(Option<M>),
+}
+
+impl<M, E> From<crate::RequestError<M, E>> for AskError<M, E> {
+    fn from(e: crate::RequestError<M, E>) -> Self {
+        match e {
+            crate::RequestError::Full(msg) => Self::Closed(msg),
+            crate::RequestError::NoReply(e) => Self::NoReply(e),
+        }
+    }
+}
+
+impl<M, E> From<SendTimeoutError<M>> for AskError<M, E> {
+    fn from(e: SendTimeoutError<M>) -> Self {
+        match e {
+            SendTimeoutError::Closed(msg) => Self::Closed(msg),
+            SendTimeoutError::Elapsed(msg) => Self::Elapsed(msg),
+        }
+    }
+}
+
+/// Send a request and await its reply, failing if both do not complete before the deadline.
+///
+/// This is [`IsSenderExt::request`](crate::IsSenderExt::request) with a timeout, measured by the
+/// [`SystemClock`]. When the deadline elapses while waiting for space in the channel, the
+/// message is returned like with [`send_timeout`]. When it elapses while waiting for the reply,
+/// the reply slot is dropped, so the receiver can observe that the reply is no longer awaited.
+///
+/// ```
+/// # use meslin::{*, time::{ask, AskError}};
+/// # use std::time::Duration;
+/// # futures::executor::block_on(async {
+/// let (sender, receiver) = mpmc::unbounded::<Request<u32, u32>>();
+///
+/// let reply = async {
+///     let request = receiver.recv_async().await.unwrap();
+///     let msg = request.msg;
+///     request.reply(msg + 1).unwrap();
+/// };
+/// let (result, ()) = futures::join!(
+///     ask::<_, Request<u32, u32>>(&sender, 1u32, Duration::from_secs(1)),
+///     reply,
+/// );
+/// assert_eq!(result.unwrap(), 2);
+///
+/// let result = ask::<_, Request<u32, u32>>(&sender, 1u32, Duration::from_millis(10)).await;
+/// assert!(matches!(result, Err(AskError::Elapsed(None))));
+/// # });
+/// ```
+pub async fn ask<S, M>(
+    sender: &S,
+    msg: impl Into<M::Input>,
+    deadline: Duration,
+) -> Result<<M::Output as ResultFuture>::Ok, AskError<M::Input, <M::Output as ResultFuture>::Error>>
+where
+    S: Sends<M>,
+    S::With: Default,
+    M: Message,
+    M::Output: ResultFuture,
+{
+    ask_with_clock(&SystemClock, sender, msg, deadline).await
+}
+
+/// Like [`ask`], but measures the deadline using the given [`Clock`].
+pub async fn ask_with_clock<S, M>(
+    clock: &dyn Clock,
+    sender: &S,
+    msg: impl Into<M::Input>,
+    deadline: Duration,
+) -> Result<<M::Output as ResultFuture>::Ok, AskError<M::Input, <M::Output as ResultFuture>::Error>>
+where
+    S: Sends<M>,
+    S::With: Default,
+    M: Message,
+    M::Output: ResultFuture,
+{
+    // The send and the reply share the same deadline.
+    let mut sleep = clock.sleep(deadline);
+    let reply = match send_until::<S, M>(sender, msg.into(), &mut sleep).await {
+        Ok(reply) => reply,
+        Err(Unsent::Closed(msg)) => return Err(AskError::Closed(msg)),
+        Err(Unsent::Stopped(msg)) => return Err(AskError::Elapsed(msg)),
+    };
+    match until(Pin::new(&mut sleep), reply).await {
+        Some(reply) => reply.map_err(AskError::NoReply),
+        None => Err(AskError::Elapsed(None)),
+    }
+}
+
+/// A `with`-value that gives a message a deadline, wrapping the original `with`-value `W`.
+///
+/// Messages that are received after their deadline are skipped by a [`SkipExpired`] receiver,
+/// which prevents stale commands from being executed after a long backlog. Expiring messages
+/// are ordered by their deadline first, so that a
+/// [`priority::unbounded_min`](crate::priority::unbounded_min) channel receives the message
+/// that expires first.
+///
+/// A sender can stamp every message with a new deadline using
+/// [`IsSenderExt::map_with_closures`](crate::IsSenderExt::map_with_closures):
+///
+/// ```
+/// # use meslin::{*, time::{Expires, ManualClock}};
+/// # use std::time::Duration;
+/// # futures::executor::block_on(async {
+/// let clock = ManualClock::new();
+/// let (sender, receiver) = priority::unbounded_min::<u32, Expires>();
+/// let mut receiver = receiver.skip_expired_with_clock(clock.clone());
+///
+/// let ttl = Duration::from_secs(1);
+/// let stamp = clock.clone();
+/// let sender =
+///     sender.map_with_closures(move |()| Expires::after_with_clock(&stamp, ttl, ()), |_| ());
+///
+/// sender.send::<u32>(1u32).await.unwrap();
+/// clock.advance(Duration::from_secs(2));
+/// sender.send::<u32>(2u32).await.unwrap();
+/// assert_eq!(receiver.recv_protocol().await.unwrap(), 2);
+/// # });
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Expires<W = ()> {
+    pub deadline: Instant,
+    pub with: W,
+}
+
+impl<W> Expires<W> {
+    /// Expire the message at the given deadline.
+    pub fn at(deadline: Instant, with: W) -> Self {
+        Self { deadline, with }
+    }
+
+    /// Expire the message after the given duration, measured by the [`SystemClock`].
+    pub fn after(ttl: Duration, with: W) -> Self {
+        Self::after_with_clock(&SystemClock, ttl, with)
+    }
+
+    /// Like [`Expires::after`], but measures the duration using the given [`Clock`].
+    pub fn after_with_clock(clock: &dyn Clock, ttl: Duration, with: W) -> Self {
+        Self::at(clock.now() + ttl, with)
+    }
+
+    /// Whether the deadline has passed according to the given [`Clock`].
+    pub fn is_expired(&self, clock: &dyn Clock) -> bool {
+        clock.now() >= self.deadline
+    }
+
+    pub fn into_inner(self) -> (Instant, W) {
+        (self.deadline, self.with)
+    }
+}
+
+/// A wrapper around a receiver, which skips messages of which the [`Expires`] deadline has
+/// passed.
+///
+/// The deadline is checked when the message is received, using the [`Clock`] given to
+/// [`SkipExpired::with_clock`]. Expired messages are dropped, unless a hook is set with
+/// [`SkipExpired::on_expired`], in which case it receives them instead. This can be used to
+/// route them to a dead-letter channel.
+pub struct SkipExpired<R: IsReceiver> {
+    receiver: R,
+    clock: Box<dyn Clock>,
+    on_expired: Option<Box<dyn FnMut(R::Protocol, R::With) + Send>>,
+}
+
+impl<R: IsReceiver> SkipExpired<R> {
+    /// Skip expired messages, measured by the [`SystemClock`].
+    pub fn new(receiver: R) -> Self {
+        Self::with_clock(receiver, SystemClock)
+    }
+
+    /// Skip expired messages, measured by the given [`Clock`].
+    pub fn with_clock(receiver: R, clock: impl Clock) -> Self {
+        Self {
+            receiver,
+            clock: Box::new(clock),
+            on_expired: None,
+        }
+    }
+
+    /// Call the hook with every message that is skipped because it expired.
+    pub fn on_expired(mut self, hook: impl FnMut(R::Protocol, R::With) + Send + 'static) -> Self {
+        self.on_expired = Some(Box::new(hook));
+        self
+    }
+
+    pub fn into_inner(self) -> R {
+        self.receiver
+    }
+
+    pub fn inner_ref(&self) -> &R {
+        &self.receiver
+    }
+
+    pub fn inner_mut(&mut self) -> &mut R {
+        &mut self.receiver
+    }
+}
+
+impl<R, W> SkipExpired<R>
+where
+    R: IsReceiver<With = Expires<W>>,
+{
+    /// Returns the received protocol if it has not expired, and otherwise passes it to the hook.
+    fn filter(&mut self, protocol: R::Protocol, with: Expires<W>) -> Option<(R::Protocol, W)> {
+        if !with.is_expired(&*self.clock) {
+            return Some((protocol, with.with));
+        }
+        if let Some(on_expired) = &mut self.on_expired {
+            on_expired(protocol, with);
+        }
+        None
+    }
+}
+
+impl<R, W> IsReceiver for SkipExpired<R>
+where
+    R: IsReceiver<With = Expires<W>> + Send,
+    R::Protocol: Send,
+    W: Send,
+{
+    type Protocol = R::Protocol;
+    type With = W;
+
+    async fn recv_protocol_with(this: &mut Self) -> Result<(R::Protocol, W), RecvError> {
+        loop {
+            let (protocol, with) = R::recv_protocol_with(&mut this.receiver).await?;
+            if let Some(received) = this.filter(protocol, with) {
+                return Ok(received);
+            }
+        }
+    }
+
+    fn try_recv_protocol_with(this: &mut Self) -> Result<(R::Protocol, W), TryRecvError> {
+        loop {
+            let (protocol, with) = R::try_recv_protocol_with(&mut this.receiver)?;
+            if let Some(received) = this.filter(protocol, with) {
+                return Ok(received);
+            }
+        }
+    }
+
+    #[cfg(blocking)]
+    fn recv_protocol_blocking_with(this: &mut Self) -> Result<(R::Protocol, W), RecvError> {
+        loop {
+            let (protocol, with) = R::recv_protocol_blocking_with(&mut this.receiver)?;
+            if let Some(received) = this.filter(protocol, with) {
+                return Ok(received);
+            }
+        }
+    }
+}
+
+impl<R: IsReceiver + Debug> Debug for SkipExpired<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SkipExpired")
+            .field("receiver", &self.receiver)
+            .field("clock", &self.clock)
+            .field("on_expired", &self.on_expired.is_some())
+            .finish()
+    }
+}
+
+/// The key by which [`Throttle`] and [`Debounce`] group protocols: either all protocols together,
+/// or one group per enum variant.
+type VariantKey<P> = Option<Discriminant<P>>;
+
+fn variant_key<P>(per_variant: bool, protocol: &P) -> VariantKey<P> {
+    per_variant.then(|| std::mem::discriminant(protocol))
+}
+
+/// A wrapper around a sender, which drops protocols that are sent within `interval` of the last
+/// protocol that was forwarded.
+///
+/// Protocols that are dropped are still reported as sent. By default all protocols are throttled
+/// together; with [`Throttle::per_variant`], every variant of an enum protocol is throttled
+/// separately. Clones of the wrapper share their throttle.
+///
+/// ```
+/// # use meslin::{*, time::ManualClock};
+/// # use std::time::Duration;
+/// let clock = ManualClock::new();
+/// let (sender, receiver) = mpmc::unbounded::<u32>();
+/// let sender = sender.throttle_with_clock(Duration::from_secs(1), clock.clone());
+///
+/// sender.try_send::<u32>(1u32).unwrap();
+/// sender.try_send::<u32>(2u32).unwrap();
+/// clock.advance(Duration::from_secs(1));
+/// sender.try_send::<u32>(3u32).unwrap();
+/// assert_eq!(receiver.drain().collect::<Vec<_>>(), [1, 3]);
+/// ```
+pub struct Throttle<T: IsStaticSender> {
+    sender: T,
+    interval: Duration,
+    per_variant: bool,
+    clock: Arc<dyn Clock>,
+    last: Arc<Mutex<HashMap<VariantKey<T::Protocol>, Instant>>>,
+}
+
+impl<T: IsStaticSender> Throttle<T> {
+    /// Throttle the sender, measured by the [`SystemClock`].
+    pub fn new(sender: T, interval: Duration) -> Self {
+        Self::with_clock(sender, interval, SystemClock)
+    }
+
+    /// Throttle the sender, measured by the given [`Clock`].
+    pub fn with_clock(sender: T, interval: Duration, clock: impl Clock) -> Self {
+        Self {
+            sender,
+            interval,
+            per_variant: false,
+            clock: Arc::new(clock),
+            last: Default::default(),
+        }
+    }
+
+    /// Throttle every variant of the protocol separately.
+    pub fn per_variant(mut self) -> Self {
+        self.per_variant = true;
+        self
+    }
+
+    pub fn into_inner(self) -> T {
+        self.sender
+    }
+
+    pub fn inner_ref(&self) -> &T {
+        &self.sender
+    }
+
+    /// Returns whether the protocol should be forwarded, marking it as the last one if so.
+    fn admit(&self, protocol: &T::Protocol) -> bool {
+        let now = self.clock.now();
+        let mut last = self.last.lock().unwrap();
+        match last.entry(variant_key(self.per_variant, protocol)) {
+            std::collections::hash_map::Entry::Occupied(entry)
+                if now < *entry.get() + self.interval =>
+            {
+                false
+            }
+            entry => {
+                entry.insert_entry(now);
+                true
+            }
+        }
+    }
+}
+
+impl<T: IsStaticSender + Clone> Clone for Throttle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            interval: self.interval,
+            per_variant: self.per_variant,
+            clock: self.clock.clone(),
+            last: self.last.clone(),
+        }
+    }
+}
+
+impl<T: IsStaticSender + Debug> Debug for Throttle<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Throttle")
+            .field("sender", &self.sender)
+            .field("interval", &self.interval)
+            .field("per_variant", &self.per_variant)
+            .finish()
+    }
+}
+
+impl<T: IsStaticSender> IsSender for Throttle<T> {
+    type With = T::With;
+
+    forward_sender_methods!();
+}
+
+impl<T> IsStaticSender for Throttle<T>
+where
+    T: IsStaticSender + Sync,
+    T::Protocol: Send,
+    T::With: Send,
+{
+    type Protocol = T::Protocol;
+
+    async fn send_protocol_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        with: Self::With,
+    ) -> Result<(), SendError<(Self::Protocol, Self::With)>> {
+        match this.admit(&protocol) {
+            true => T::send_protocol_with(&this.sender, protocol, with).await,
+            false => Ok(()),
+        }
+    }
+
+    fn try_send_protocol_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        with: Self::With,
+    ) -> Result<(), TrySendError<(Self::Protocol, Self::With)>> {
+        match this.admit(&protocol) {
+            true => T::try_send_protocol_with(&this.sender, protocol, with),
+            false => Ok(()),
+        }
+    }
+}
+
+/// A wrapper around a sender, which only forwards a protocol once no newer protocol was sent
+/// for `delay`.
+///
+/// A send waits until the sender has been quiet for `delay`, after which the latest protocol
+/// is forwarded. Protocols that are superseded by a newer one are dropped, but still reported
+/// as sent. If forwarding fails, the protocol that is returned may be a newer one than the
+/// protocol that was sent. By default all protocols are debounced together; with
+/// [`Debounce::per_variant`], every variant of an enum protocol is debounced separately.
+///
+/// Since a debounce can only be completed by waiting, `try_send` only replaces the protocol of a
+/// send that is already waiting, and otherwise forwards the protocol immediately. If all sends
+/// that wait for a protocol are dropped, the protocol is dropped as well.
+///
+/// ```
+/// # use meslin::{*, time::ManualClock};
+/// # use std::time::Duration;
+/// # futures::executor::block_on(async {
+/// let clock = ManualClock::new();
+/// let (sender, receiver) = mpmc::unbounded::<u32>();
+/// let sender = sender.debounce_with_clock(Duration::from_secs(1), clock.clone());
+///
+/// let advance = async {
+///     sender.try_send::<u32>(2u32).unwrap();
+///     clock.advance(Duration::from_secs(1));
+/// };
+/// let (result, ()) = futures::join!(sender.send::<u32>(1u32), advance);
+/// result.unwrap();
+/// assert_eq!(receiver.drain().collect::<Vec<_>>(), [2]);
+/// # });
+/// ```
+pub struct Debounce<T: IsStaticSender> {
+    sender: T,
+    delay: Duration,
+    per_variant: bool,
+    clock: Arc<dyn Clock>,
+    pending: Arc<Mutex<HashMap<VariantKey<T::Protocol>, Pending<T::Protocol, T::With>>>>,
+}
+
+/// The latest protocol of a [`Debounce`], waiting to be forwarded.
+struct Pending<P, W> {
+    protocol: P,
+    with: W,
+    sent_at: Instant,
+    /// Shared by the sends that wait to forward this protocol.
+    waiting: Arc<()>,
+}
+
+/// A send of a [`Debounce`] that waits to forward the latest protocol of its key.
+///
+/// If the last waiting send is dropped before the protocol is forwarded, the protocol is
+/// removed, so that later sends are not merged into a protocol that nobody forwards.
+struct Waiting<'a, P, W> {
+    pending: &'a Mutex<HashMap<VariantKey<P>, Pending<P, W>>>,
+    key: VariantKey<P>,
+    waiting: Option<Arc<()>>,
+}
+
+impl<P, W> Drop for Waiting<'_, P, W> {
+    fn drop(&mut self) {
+        let mut pending = self.pending.lock().unwrap();
+        // The count is only changed while locked, so the last waiting send is always noticed.
+        let waiting = self.waiting.take().unwrap();
+        if let Some(latest) = pending.get(&self.key) {
+            if Arc::ptr_eq(&latest.waiting, &waiting) && Arc::strong_count(&waiting) == 2 {
+                pending.remove(&self.key);
+            }
+        }
+    }
+}
+
+impl<T: IsStaticSender> Debounce<T> {
+    /// Debounce the sender, measured by the [`SystemClock`].
+    pub fn new(sender: T, delay: Duration) -> Self {
+        Self::with_clock(sender, delay, SystemClock)
+    }
+
+    /// Debounce the sender, measured by the given [`Clock`].
+    pub fn with_clock(sender: T, delay: Duration, clock: impl Clock) -> Self {
+        Self {
+            sender,
+            delay,
+            per_variant: false,
+            clock: Arc::new(clock),
+            pending: Default::default(),
+        }
+    }
+
+    /// Debounce every variant of the protocol separately.
+    pub fn per_variant(mut self) -> Self {
+        self.per_variant = true;
+        self
+    }
+
+    pub fn into_inner(self) -> T {
+        self.sender
+    }
+
+    pub fn inner_ref(&self) -> &T {
+        &self.sender
+    }
+
+    /// The amount of protocols that are waiting to be forwarded.
+    pub fn pending(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+}
+
+impl<T: IsStaticSender + Clone> Clone for Debounce<T> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            delay: self.delay,
+            per_variant: self.per_variant,
+            clock: self.clock.clone(),
+            pending: self.pending.clone(),
+        }
+    }
+}
+
+impl<T: IsStaticSender + Debug> Debug for Debounce<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Debounce")
+            .field("sender", &self.sender)
+            .field("delay", &self.delay)
+            .field("per_variant", &self.per_variant)
+            .field("pending", &self.pending())
+            .finish()
+    }
+}
+
+impl<T: IsStaticSender> IsSender for Debounce<T> {
+    type With = T::With;
+
+    forward_sender_methods!();
+}
+
+impl<T> IsStaticSender for Debounce<T>
+where
+    T: IsStaticSender + Sync,
+    T::Protocol: Send,
+    T::With: Send,
+{
+    type Protocol = T::Protocol;
+
+    async fn send_protocol_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        with: Self::With,
+    ) -> Result<(), SendError<(Self::Protocol, Self::With)>> {
+        let key = variant_key(this.per_variant, &protocol);
+        let sent_at = this.clock.now();
+        let waiting = {
+            let mut pending = this.pending.lock().unwrap();
+            let waiting = match pending.get(&key) {
+                Some(latest) => latest.waiting.clone(),
+                None => Arc::new(()),
+            };
+            pending.insert(
+                key,
+                Pending {
+                    protocol,
+                    with,
+                    sent_at,
+                    waiting: waiting.clone(),
+                },
+            );
+            waiting
+        };
+        let _waiting = Waiting {
+            pending: &this.pending,
+            key,
+            waiting: Some(waiting),
+        };
+
+        loop {
+            // Every waiting send sleeps until the latest protocol has been quiet for the delay,
+            // after which the first one to wake up forwards it.
+            let remaining = {
+                let mut pending = this.pending.lock().unwrap();
+                let Some(latest) = pending.get(&key) else {
+                    return Ok(());
+                };
+                let quiet_at = latest.sent_at + this.delay;
+                match quiet_at.checked_duration_since(this.clock.now()) {
+                    Some(remaining) if !remaining.is_zero() => Ok(remaining),
+                    _ => Err(pending.remove(&key).unwrap()),
+                }
+            };
+            match remaining {
+                Ok(remaining) => this.clock.sleep(remaining).await,
+                Err(latest) => {
+                    return T::send_protocol_with(&this.sender, latest.protocol, latest.with).await
+                }
+            }
+        }
+    }
+
+    fn try_send_protocol_with(
+        this: &Self,
+        protocol: Self::Protocol,
+        with: Self::With,
+    ) -> Result<(), TrySendError<(Self::Protocol, Self::With)>> {
+        let key = variant_key(this.per_variant, &protocol);
+        if let Some(latest) = this.pending.lock().unwrap().get_mut(&key) {
+            latest.protocol = protocol;
+            latest.with = with;
+            latest.sent_at = this.clock.now();
+            return Ok(());
+        }
+        T::try_send_protocol_with(&this.sender, protocol, with)
+    }
+}
+
+/// A wrapper around a sender, which collects messages into batches that are sent as a single
+/// `Vec<M>` message.
+///
+/// A batch is sent as soon as it holds `size` messages, or once `interval` has passed since its
+/// first message was sent. The first `send` of a batch waits for the interval to pass, and if it
+/// is dropped before that, the next `send` takes over. This means that `try_send` only sends
+/// full batches; the rest is sent with [`Batch::flush`] or [`Batch::try_flush`]. Clones of the
+/// wrapper share their batch.
+///
+/// If sending a batch fails, the message that was sent is returned, and the other messages of
+/// the batch are lost.
+///
+/// ```
+/// # use meslin::{*, time::ManualClock};
+/// # use std::time::Duration;
+/// # futures::executor::block_on(async {
+/// let clock = ManualClock::new();
+/// let (sender, receiver) = mpmc::unbounded::<Vec<u32>>();
+/// let sender = sender.batch_with_clock::<u32>(2, Duration::from_secs(1), clock.clone());
+///
+/// sender.try_send::<u32>(1u32).unwrap();
+/// sender.try_send::<u32>(2u32).unwrap();
+/// assert_eq!(receiver.try_recv().unwrap(), [1, 2]);
+///
+/// let advance = async { clock.advance(Duration::from_secs(1)) };
+/// let (result, ()) = futures::join!(sender.send::<u32>(3u32), advance);
+/// result.unwrap();
+/// assert_eq!(receiver.try_recv().unwrap(), [3]);
+/// # });
+/// ```
+pub struct Batch<T, M> {
+    sender: T,
+    size: usize,
+    interval: Duration,
+    clock: Arc<dyn Clock>,
+    buffer: Arc<Mutex<BatchBuffer<M>>>,
+}
+
+struct BatchBuffer<M> {
+    msgs: Vec<M>,
+    /// Incremented whenever a batch is taken out of the buffer.
+    generation: u64,
+    /// Whether a send waits for the interval of the current batch.
+    timed: bool,
+}
+
+impl<M> BatchBuffer<M> {
+    fn take(&mut self) -> Vec<M> {
+        self.generation += 1;
+        self.timed = false;
+        std::mem::take(&mut self.msgs)
+    }
+}
+
+/// The send that waits for the interval of a batch, which hands this over to the next send if
+/// it is dropped before the batch was sent.
+struct BatchTimer<'a, M> {
+    buffer: &'a Mutex<BatchBuffer<M>>,
+    generation: u64,
+}
+
+impl<M> Drop for BatchTimer<'_, M> {
+    fn drop(&mut self) {
+        let mut buffer = self.buffer.lock().unwrap();
+        if buffer.generation == self.generation {
+            buffer.timed = false;
+        }
+    }
+}
+
+impl<T, M> Batch<T, M>
+where
+    T: IsStaticSender + Sends<Vec<M>>,
+    T::With: Default,
+{
+    /// Collect batches of at most `size` messages, measuring the interval by the
+    /// [`SystemClock`].
+    ///
+    /// # Panics
+    /// Panics if `size` is zero.
+    pub fn new(sender: T, size: usize, interval: Duration) -> Self {
+        Self::with_clock(sender, size, interval, SystemClock)
+    }
+
+    /// Like [`Batch::new`], but measures the interval using the given [`Clock`].
+    ///
+    /// # Panics
+    /// Panics if `size` is zero.
+    pub fn with_clock(sender: T, size: usize, interval: Duration, clock: impl Clock) -> Self {
+        assert!(size > 0, "the size of a batch must be larger than zero");
+        Self {
+            sender,
+            size,
+            interval,
+            clock: Arc::new(clock),
+            buffer: Arc::new(Mutex::new(BatchBuffer {
+                msgs: Vec::with_capacity(size),
+                generation: 0,
+                timed: false,
+            })),
+        }
+    }
+
+    pub fn inner_ref(&self) -> &T {
+        &self.sender
+    }
+
+    /// The amount of messages in the current batch.
+    pub fn buffered(&self) -> usize {
+        self.buffer.lock().unwrap().msgs.len()
+    }
+
+    /// Send the current batch, waiting asynchronously until space becomes available.
+    ///
+    /// Does nothing if the batch is empty.
+    pub async fn flush(&self) -> Result<(), SendError<Vec<M>>> {
+        let batch = self.buffer.lock().unwrap().take();
+        if batch.is_empty() {
+            return Ok(());
+        }
+        T::send_msg_with(&self.sender, batch, T::With::default())
+            .await
+            .map_err(|e| e.map(|(batch, _)| batch))
+    }
+
+    /// Send the current batch, failing if the channel is full.
+    ///
+    /// Does nothing if the batch is empty.
+    pub fn try_flush(&self) -> Result<(), TrySendError<Vec<M>>> {
+        let batch = self.buffer.lock().unwrap().take();
+        if batch.is_empty() {
+            return Ok(());
+        }
+        T::try_send_msg_with(&self.sender, batch, T::With::default())
+            .map_err(|e| e.map(|(batch, _)| batch))
+    }
+}
+
+impl<T: Clone, M> Clone for Batch<T, M> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            size: self.size,
+            interval: self.interval,
+            clock: self.clock.clone(),
+            buffer: self.buffer.clone(),
+        }
+    }
+}
+
+impl<T: Debug, M> Debug for Batch<T, M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Batch")
+            .field("sender", &self.sender)
+            .field("size", &self.size)
+            .field("interval", &self.interval)
+            .field("buffered", &self.buffer.lock().unwrap().msgs.len())
+            .finish()
+    }
+}
+
+impl<T: IsSender, M> IsSender for Batch<T, M> {
+    type With = ();
+
+    forward_sender_methods!();
+}
+
+impl<T, M> IsStaticSender for Batch<T, M>
+where
+    T: IsStaticSender + Sends<Vec<M>> + Sync,
+    T::With: Default + Send,
+    M: Send,
+{
+    type Protocol = M;
+
+    async fn send_protocol_with(this: &Self, msg: M, (): ()) -> Result<(), SendError<(M, ())>> {
+        let (batch, generation) = {
+            let mut buffer = this.buffer.lock().unwrap();
+            buffer.msgs.push(msg);
+            if buffer.msgs.len() >= this.size {
+                (buffer.take(), None)
+            } else if !buffer.timed {
+                buffer.timed = true;
+                (Vec::new(), Some((buffer.generation, buffer.msgs.len() - 1)))
+            } else {
+                return Ok(());
+            }
+        };
+
+        // The first send of a batch waits for the interval, after which it sends the batch
+        // unless it was already sent.
+        let batch = match generation {
+            None => batch,
+            Some((generation, _)) => {
+                let _timer = BatchTimer {
+                    buffer: &this.buffer,
+                    generation,
+                };
+                this.clock.sleep(this.interval).await;
+                let mut buffer = this.buffer.lock().unwrap();
+                if buffer.generation != generation {
+                    return Ok(());
+                }
+                buffer.take()
+            }
+        };
+        T::send_msg_with(&this.sender, batch, T::With::default())
+            .await
+            .map_err(|e| {
+                e.map(|(mut batch, _)| match generation {
+                    None => (batch.pop().unwrap(), ()),
+                    Some((_, index)) => (batch.swap_remove(index), ()),
+                })
+            })
+    }
+
+    fn try_send_protocol_with(this: &Self, msg: M, (): ()) -> Result<(), TrySendError<(M, ())>> {
+        let batch = {
+            let mut buffer = this.buffer.lock().unwrap();
+            buffer.msgs.push(msg);
+            if buffer.msgs.len() < this.size {
+                return Ok(());
+            }
+            buffer.take()
+        };
+        T::try_send_msg_with(&this.sender, batch, T::With::default())
+            .map_err(|e| e.map(|(mut batch, _)| (batch.pop().unwrap(), ())))
+    }
+}
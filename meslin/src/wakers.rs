@@ -0,0 +1,172 @@
+use std::{
+    cell::RefCell,
+    marker::PhantomData,
+    sync::Mutex,
+    task::{Context, Waker},
+};
+#[cfg(feature = "broadcast")]
+use std::{fmt::Debug, sync::Arc};
+#[cfg(any(feature = "broadcast", feature = "watch"))]
+use std::{
+    future::{poll_fn, Future},
+    task::Poll,
+};
+
+/// The wakers of the futures that wait on a shared state, with one slot per future.
+///
+/// A future that is polled again updates its slot instead of adding another waker, and removes
+/// it when it is dropped, see [`WakerSlot`].
+#[derive(Default)]
+pub(crate) struct Wakers {
+    slots: Vec<(usize, Waker)>,
+    next_id: usize,
+}
+
+impl Wakers {
+    /// Take all wakers, after which the futures have to register again if they still wait.
+    pub(crate) fn take(&mut self) -> Vec<Waker> {
+        std::mem::take(&mut self.slots)
+            .into_iter()
+            .map(|(_, waker)| waker)
+            .collect()
+    }
+
+    fn register(&mut self, id: &mut Option<usize>, waker: &Waker) {
+        if let Some(slot) = id.and_then(|id| self.slots.iter_mut().find(|(i, _)| *i == id)) {
+            if !slot.1.will_wake(waker) {
+                slot.1 = waker.clone();
+            }
+            return;
+        }
+        *id = Some(self.next_id);
+        self.slots.push((self.next_id, waker.clone()));
+        self.next_id = self.next_id.wrapping_add(1);
+    }
+
+    fn remove(&mut self, id: usize) {
+        self.slots.retain(|(i, _)| *i != id);
+    }
+}
+
+/// A shared state that holds [`Wakers`], either a [`Mutex`] or, for local channels, a
+/// [`RefCell`].
+pub(crate) trait SharedState<S> {
+    /// Run `f` with the state, unless it is poisoned or already borrowed.
+    fn with_state(&self, f: impl FnOnce(&mut S));
+}
+
+impl<S> SharedState<S> for Mutex<S> {
+    fn with_state(&self, f: impl FnOnce(&mut S)) {
+        if let Ok(mut state) = self.lock() {
+            f(&mut state)
+        }
+    }
+}
+
+impl<S> SharedState<S> for RefCell<S> {
+    fn with_state(&self, f: impl FnOnce(&mut S)) {
+        if let Ok(mut state) = self.try_borrow_mut() {
+            f(&mut state)
+        }
+    }
+}
+
+/// The slot of a waiting future in the [`Wakers`] of a shared state, which is removed when the
+/// future is dropped.
+pub(crate) struct WakerSlot<'a, S, F = fn(&mut S) -> &mut Wakers, L = Mutex<S>>
+where
+    F: Fn(&mut S) -> &mut Wakers,
+    L: SharedState<S>,
+{
+    shared: &'a L,
+    wakers: F,
+    id: Option<usize>,
+    _state: PhantomData<fn(&mut S)>,
+}
+
+impl<'a, S, F, L> WakerSlot<'a, S, F, L>
+where
+    F: Fn(&mut S) -> &mut Wakers,
+    L: SharedState<S>,
+{
+    pub(crate) fn new(shared: &'a L, wakers: F) -> Self {
+        Self {
+            shared,
+            wakers,
+            id: None,
+            _state: PhantomData,
+        }
+    }
+
+    /// Register the waker of `cx`, given the locked state of `shared`.
+    pub(crate) fn register(&mut self, state: &mut S, cx: &Context<'_>) {
+        (self.wakers)(state).register(&mut self.id, cx.waker());
+    }
+}
+
+impl<S, F, L> Drop for WakerSlot<'_, S, F, L>
+where
+    F: Fn(&mut S) -> &mut Wakers,
+    L: SharedState<S>,
+{
+    fn drop(&mut self) {
+        let Some(id) = self.id else { return };
+        self.shared
+            .with_state(|state| (self.wakers)(state).remove(id));
+    }
+}
+
+/// A count of changes, which futures can wait on.
+#[cfg(any(feature = "broadcast", feature = "watch"))]
+#[derive(Default)]
+pub(crate) struct Changes {
+    count: u64,
+    wakers: Wakers,
+}
+
+#[cfg(any(feature = "broadcast", feature = "watch"))]
+impl Changes {
+    /// Count a change, waking the futures that wait for it.
+    pub(crate) fn notify(shared: &Mutex<Self>) {
+        let wakers = {
+            let mut changes = shared.lock().unwrap();
+            changes.count += 1;
+            changes.wakers.take()
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+
+    /// Wait until a change is counted, from the moment this method is called.
+    pub(crate) fn wait(shared: &Mutex<Self>) -> impl Future<Output = ()> + Send + '_ {
+        let count = shared.lock().unwrap().count;
+        let mut slot = WakerSlot::new(shared, |changes: &mut Self| &mut changes.wakers);
+        poll_fn(move |cx| {
+            let mut changes = shared.lock().unwrap();
+            match changes.count != count {
+                true => Poll::Ready(()),
+                false => {
+                    slot.register(&mut changes, cx);
+                    Poll::Pending
+                }
+            }
+        })
+    }
+}
+
+/// Counts a change of the shared [`Changes`] when dropped.
+#[cfg(feature = "broadcast")]
+pub(crate) struct NotifyOnDrop(pub(crate) Arc<Mutex<Changes>>);
+
+#[cfg(feature = "broadcast")]
+impl Drop for NotifyOnDrop {
+    fn drop(&mut self) {
+        Changes::notify(&self.0)
+    }
+}
+
+#[cfg(feature = "broadcast")]
+impl Debug for NotifyOnDrop {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NotifyOnDrop").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,40 @@
+#![cfg(blocking)]
+
+use meslin::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+#[test]
+fn blocking_methods_use_the_block_on_hook() {
+    set_block_on(|fut| {
+        CALLS.fetch_add(1, Ordering::SeqCst);
+        futures::executor::block_on(fut)
+    });
+
+    // Sends and receives that don't have to wait don't block.
+    let (sender, mut receiver) = priority::unbounded::<u32, u8>();
+    sender.send_blocking_with::<u32>(1u32, 0).unwrap();
+    assert_eq!(receiver.recv_protocol_blocking_with().unwrap(), (1, 0));
+    assert_eq!(CALLS.load(Ordering::SeqCst), 0);
+
+    let handle = std::thread::spawn({
+        let sender = sender.clone();
+        move || {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            sender.send_blocking_with::<u32>(2u32, 0).unwrap();
+        }
+    });
+    assert_eq!(receiver.recv_protocol_blocking_with().unwrap(), (2, 0));
+    assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+    handle.join().unwrap();
+
+    reset_block_on();
+    let handle = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        sender.send_blocking_with::<u32>(3u32, 0).unwrap();
+    });
+    assert_eq!(receiver.recv_protocol_blocking_with().unwrap(), (3, 0));
+    assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+    handle.join().unwrap();
+}
@@ -0,0 +1,30 @@
+use bytes::{Bytes, BytesMut};
+use meslin::*;
+
+#[derive(Debug, From, TryInto)]
+enum BinaryProtocol {
+    Frozen(Bytes),
+    Buffer(BytesMut),
+}
+
+#[tokio::test]
+async fn test_bytes_messages() {
+    let (sender, receiver) = mpmc::unbounded::<BinaryProtocol>();
+    sender
+        .send::<Bytes>(Bytes::from_static(b"ping"))
+        .await
+        .unwrap();
+    sender
+        .send::<BytesMut>(BytesMut::from(&b"pong"[..]))
+        .await
+        .unwrap();
+
+    let BinaryProtocol::Frozen(frozen) = receiver.recv_async().await.unwrap() else {
+        panic!("expected Bytes");
+    };
+    assert_eq!(frozen, "ping");
+    let BinaryProtocol::Buffer(buffer) = receiver.recv_async().await.unwrap() else {
+        panic!("expected BytesMut");
+    };
+    assert_eq!(buffer, "pong");
+}
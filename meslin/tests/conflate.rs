@@ -0,0 +1,76 @@
+use meslin::*;
+
+#[tokio::test]
+async fn conflate_keeps_latest_per_key() {
+    let (sender, mut receiver) = conflate::unbounded::<u32, char>();
+    for (value, key) in [(1u32, 'a'), (2, 'b'), (3, 'a'), (4, 'c'), (5, 'b')] {
+        sender.try_send_with::<u32>(value, key).unwrap();
+    }
+    assert_eq!(sender.len(), 3);
+    assert_eq!(sender.conflated(), 2);
+
+    let mut received = Vec::new();
+    while let Ok(item) = receiver.try_recv() {
+        received.push(item);
+    }
+    assert_eq!(received, [(3, 'a'), (5, 'b'), (4, 'c')]);
+}
+
+#[tokio::test]
+async fn conflate_bounded_by_keys() {
+    let (sender, mut receiver) = conflate::bounded::<u32, u8>(2);
+    sender.try_send_with::<u32>(1u32, 0).unwrap();
+    sender.try_send_with::<u32>(2u32, 1).unwrap();
+    assert!(matches!(
+        sender.try_send_with::<u32>(3u32, 2),
+        Err(TrySendError::Full((3, 2)))
+    ));
+    // Overwriting a queued key never waits.
+    sender.send_with::<u32>(4u32, 0).await.unwrap();
+
+    let (result, received) = futures::join!(sender.send_with::<u32>(3u32, 2), async {
+        receiver.recv().await.unwrap()
+    });
+    result.unwrap();
+    assert_eq!(received, (4, 0));
+    assert_eq!(receiver.len(), 2);
+
+    drop(sender);
+    assert_eq!(receiver.recv().await.unwrap(), (2, 1));
+    assert_eq!(receiver.recv().await.unwrap(), (3, 2));
+    assert_eq!(receiver.recv().await, Err(RecvError));
+}
+
+#[tokio::test]
+async fn conflate_key_from_protocol() {
+    #[derive(Debug, Clone, PartialEq)]
+    struct Tick {
+        symbol: &'static str,
+        price: u32,
+    }
+
+    let (sender, mut receiver) = conflate::unbounded::<Msg<Tick>, &'static str>();
+    let sender = sender.with_fn(|tick: &Msg<Tick>| tick.symbol);
+    for (symbol, price) in [("ABC", 1), ("XYZ", 2), ("ABC", 3)] {
+        sender
+            .send::<Msg<Tick>>(Tick { symbol, price })
+            .await
+            .unwrap();
+    }
+    let (tick, symbol) = receiver.recv().await.unwrap();
+    assert_eq!((tick.price, symbol), (3, "ABC"));
+}
+
+#[tokio::test]
+async fn conflate_reserved_slot_is_released_on_overwrite() {
+    let (sender, mut receiver) = conflate::bounded::<u32, &str>(2);
+    sender.try_send_with::<u32>(1u32, "a").unwrap();
+    let permit = sender.try_reserve().unwrap();
+    assert!(sender.try_send_with::<u32>(2u32, "b").is_err());
+
+    // The key is already queued, so the reserved slot is released.
+    permit.send_msg_with(3u32, "a").unwrap();
+    sender.try_send_with::<u32>(4u32, "b").unwrap();
+    assert_eq!(receiver.recv().await.unwrap(), (3, "a"));
+    assert_eq!(receiver.recv().await.unwrap(), (4, "b"));
+}
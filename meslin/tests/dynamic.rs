@@ -16,7 +16,7 @@ pub struct HelloWorld(pub String);
 async fn test() {
     let (sender, _receiver) = mpmc::unbounded::<MyProtocol>();
 
-    let _ = sender.clone().into_dyn::<Set![u32]>();
+    let _: DynSender![u32] = sender.clone().into_dyn_sender();
 
     let boxed_sender = sender.clone().boxed();
     boxed_sender
@@ -36,3 +36,601 @@ async fn test() {
     let dyn_sender = dyn_sender.try_transform::<Set![HelloWorld]>().unwrap();
     dyn_sender.try_transform::<Set![u64, u32]>().unwrap_err();
 }
+
+#[tokio::test]
+async fn test_filter() {
+    let (sender, receiver) = mpmc::unbounded::<MyProtocol>();
+    let sender = sender.filter(|protocol| !matches!(protocol, MyProtocol::A(0)));
+
+    sender.send::<u32>(0u32).await.unwrap();
+    sender.try_send::<u32>(1u32).unwrap();
+
+    let dyn_sender: DynSender![u32] = sender.counted().into_dyn_sender();
+    dyn_sender.dyn_send::<u32>(0u32).await.unwrap();
+    dyn_sender.dyn_send::<u32>(2u32).await.unwrap();
+
+    assert!(matches!(receiver.try_recv().unwrap(), MyProtocol::A(1)));
+    assert!(matches!(receiver.try_recv().unwrap(), MyProtocol::A(2)));
+    assert!(receiver.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_map_msg() {
+    let (sender, receiver) = mpmc::unbounded::<MyProtocol>();
+    let sender = sender.map_msg(|protocol| match protocol {
+        MyProtocol::B(_) => MyProtocol::B(HelloWorld("<redacted>".to_string())),
+        protocol => protocol,
+    });
+
+    let dyn_sender: DynSender![HelloWorld, u32] = sender.into_dyn_sender();
+    dyn_sender.dyn_send::<HelloWorld>("secret").await.unwrap();
+    dyn_sender.dyn_send::<u32>(1u32).await.unwrap();
+
+    let MyProtocol::B(HelloWorld(text)) = receiver.try_recv().unwrap() else {
+        panic!("expected HelloWorld")
+    };
+    assert_eq!(text, "<redacted>");
+    assert!(matches!(receiver.try_recv().unwrap(), MyProtocol::A(1)));
+}
+
+#[derive(Debug, From, TryInto, DynProtocol)]
+pub enum OtherProtocol {
+    D(u64),
+}
+
+union_protocol! {
+    #[derive(Debug)]
+    pub enum UnionProtocol {
+        Mine(MyProtocol) { u32, HelloWorld, Request<u32, String> },
+        Other(OtherProtocol) { u64 },
+    }
+}
+
+#[tokio::test]
+async fn test_union_protocol() {
+    let (sender, receiver) = mpmc::unbounded::<UnionProtocol>();
+    sender.send::<u32>(1u32).await.unwrap();
+    sender.send::<u64>(2u64).await.unwrap();
+
+    let dyn_sender: DynSender![HelloWorld, u64] = sender.clone().into_dyn_sender();
+    dyn_sender.dyn_send::<HelloWorld>("hi").await.unwrap();
+
+    assert!(matches!(
+        receiver.try_recv().unwrap(),
+        UnionProtocol::Mine(MyProtocol::A(1))
+    ));
+    assert!(matches!(
+        receiver.try_recv().unwrap(),
+        UnionProtocol::Other(OtherProtocol::D(2))
+    ));
+    assert!(matches!(
+        receiver.try_recv().unwrap(),
+        UnionProtocol::Mine(MyProtocol::B(_))
+    ));
+
+    drop(receiver);
+    let err = sender.try_send::<u32>(3u32).unwrap_err();
+    assert_eq!(err.into_inner(), 3);
+    let protocol = UnionProtocol::from(4u64);
+    assert!(matches!(
+        u32::try_from(protocol),
+        Err(UnionProtocol::Other(_))
+    ));
+}
+
+#[derive(Debug, From, TryInto, DynProtocol)]
+pub enum FlattenedProtocol {
+    #[meslin(flatten(u32, HelloWorld, Request<u32, String>))]
+    Mine(MyProtocol),
+    E(i8),
+}
+
+#[tokio::test]
+async fn test_flatten() {
+    let (sender, receiver) = mpmc::unbounded::<FlattenedProtocol>();
+    sender.send::<u32>(1u32).await.unwrap();
+    sender.send::<i8>(2i8).await.unwrap();
+    IsStaticSender::send_protocol_with(&sender, MyProtocol::A(3).into(), ())
+        .await
+        .unwrap();
+
+    let dyn_sender: DynSender![HelloWorld, i8] = sender.into_dyn_sender();
+    dyn_sender.dyn_send::<HelloWorld>("hi").await.unwrap();
+
+    assert!(matches!(
+        receiver.try_recv().unwrap(),
+        FlattenedProtocol::Mine(MyProtocol::A(1))
+    ));
+    assert!(matches!(
+        receiver.try_recv().unwrap(),
+        FlattenedProtocol::E(2)
+    ));
+    assert!(matches!(
+        receiver.try_recv().unwrap(),
+        FlattenedProtocol::Mine(MyProtocol::A(3))
+    ));
+    assert!(matches!(
+        receiver.try_recv().unwrap(),
+        FlattenedProtocol::Mine(MyProtocol::B(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_demux() {
+    use futures::StreamExt;
+
+    let (sender, receiver) = mpmc::unbounded::<MyProtocol>();
+    let demux = receiver.demux();
+    let numbers = demux.stream::<u32>();
+    let greetings = demux.stream::<HelloWorld>();
+
+    let numbers = tokio::spawn(numbers.collect::<Vec<_>>());
+    let greetings = tokio::spawn(greetings.map(|HelloWorld(text)| text).collect::<Vec<_>>());
+
+    for i in 0..100u32 {
+        sender.send::<u32>(i).await.unwrap();
+        sender.send::<HelloWorld>(i.to_string()).await.unwrap();
+    }
+    // Messages without a stream are dropped.
+    let (request, _rx) = Request::new(1);
+    sender
+        .send_msg::<Request<u32, String>>(request)
+        .await
+        .unwrap();
+    drop(sender);
+
+    assert_eq!(numbers.await.unwrap(), (0..100).collect::<Vec<_>>());
+    assert_eq!(
+        greetings.await.unwrap(),
+        (0..100).map(|i| i.to_string()).collect::<Vec<_>>()
+    );
+}
+
+#[tokio::test]
+async fn test_reply_to() {
+    #[derive(Debug, From, TryInto)]
+    enum Server {
+        Ping(ReplyTo<u32, u32>),
+    }
+
+    let (server, server_rx) = mpmc::unbounded::<Server>();
+    let (client, client_rx) = mpmc::unbounded::<MyProtocol>();
+
+    let handle = tokio::spawn(async move {
+        while let Ok(Server::Ping(ping)) = server_rx.recv_async().await {
+            ping.reply(ping.msg + 1).await.unwrap();
+        }
+    });
+
+    for i in 0..10u32 {
+        server
+            .send::<ReplyTo<u32, u32>>((i, client.clone().into_dyn_sender()))
+            .await
+            .unwrap();
+        match client_rx.recv_async().await.unwrap() {
+            MyProtocol::A(reply) => assert_eq!(reply, i + 1),
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+
+    // The input is returned when sending fails.
+    handle.abort();
+    let _ = handle.await;
+    let (msg, _reply_to) = server
+        .send::<ReplyTo<u32, u32>>((5u32, client.into_dyn_sender()))
+        .await
+        .unwrap_err()
+        .into_inner();
+    assert_eq!(msg, 5);
+}
+
+#[tokio::test]
+async fn test_dyn_backpressure() {
+    let (sender, receiver) = mpmc::bounded_with::<MyProtocol>(1, Backpressure::DropOldest);
+    let sender: DynSender![u32] = sender.into_dyn_sender();
+    sender.send::<u32>(1u32).await.unwrap();
+    sender.send::<u32>(2u32).await.unwrap();
+    assert!(matches!(
+        receiver.recv_async().await.unwrap(),
+        MyProtocol::A(2)
+    ));
+    assert!(receiver.is_empty());
+}
+
+#[tokio::test]
+async fn test_dyn_request() {
+    let (sender, receiver) = mpmc::unbounded::<MyProtocol>();
+    let sender: DynSender![u32] = sender.into_dyn_sender();
+    let handle = tokio::spawn(async move {
+        let MyProtocol::C(request) = receiver.recv_async().await.unwrap() else {
+            panic!("expected request")
+        };
+        let msg = request.msg;
+        request.reply(msg.to_string()).unwrap();
+    });
+
+    let reply = sender.dyn_request::<Request<u32, String>>(1u32).await;
+    assert_eq!(reply.unwrap(), "1");
+    assert!(matches!(
+        sender.dyn_request::<Request<String, u32>>("hi").await,
+        Err(DynRequestError::NotAccepted(_))
+    ));
+    handle.await.unwrap();
+    assert!(matches!(
+        sender.dyn_request::<Request<u32, String>>(2u32).await,
+        Err(DynRequestError::Closed(2))
+    ));
+}
+
+#[test]
+fn test_accepts() {
+    use std::any::TypeId;
+
+    let (sender, _receiver) = mpmc::unbounded::<MyProtocol>();
+    assert!(sender.accepts_msg::<u32>());
+    assert!(!sender.accepts_msg::<u64>());
+    assert!(sender.accepts_all(&[TypeId::of::<u32>(), TypeId::of::<HelloWorld>()]));
+    assert!(!sender.accepts_all(&[TypeId::of::<u32>(), TypeId::of::<u64>()]));
+
+    assert!(accepts_type_id::<MyProtocol>(TypeId::of::<HelloWorld>()));
+    assert!(accepts_all_type_ids::<MyProtocol>(sender.members()));
+    assert!(!accepts_all_type_ids::<Set![u32]>(sender.members()));
+}
+
+#[tokio::test]
+async fn test_downcast() {
+    let (sender, receiver) = mpmc::bounded::<MyProtocol>(1);
+    let mut dyn_sender: DynSender![u32] = sender.into_dyn_sender();
+
+    assert!(dyn_sender
+        .downcast_ref::<mpmc::Sender<MyProtocol>>()
+        .is_some());
+    assert!(dyn_sender
+        .downcast_mut::<broadcast::Sender<MyProtocol>>()
+        .is_none());
+
+    // Reconfigure the sender after it has been type-erased.
+    let inner = dyn_sender
+        .downcast_mut::<mpmc::Sender<MyProtocol>>()
+        .unwrap();
+    *inner = inner.clone().on_overflow(|_| ());
+
+    let dyn_sender = dyn_sender
+        .downcast::<broadcast::Sender<MyProtocol>>()
+        .unwrap_err();
+    let sender = dyn_sender.downcast::<mpmc::Sender<MyProtocol>>().unwrap();
+    sender.send::<u32>(1u32).await.unwrap();
+    assert!(matches!(
+        receiver.recv_async().await.unwrap(),
+        MyProtocol::A(1)
+    ));
+}
+
+#[tokio::test]
+async fn test_boxed_msg_inspection() {
+    let (sender, _receiver) = mpmc::unbounded::<MyProtocol>();
+
+    let Err(DynTrySendError::NotAccepted(msg)) =
+        sender.dyn_try_send_boxed_msg_with(BoxedMsg::new(10u64, ()))
+    else {
+        panic!("message should not be accepted");
+    };
+    assert_eq!(msg.type_id(), std::any::TypeId::of::<u64>());
+    assert_eq!(msg.type_name(), "u64");
+    assert_eq!(format!("{msg:?}"), "BoxedMsg(\"u64\")");
+    assert!(msg.is::<u64>());
+    assert_eq!(msg.downcast_ref::<u64>(), Some((&10, &())));
+
+    let msg = msg.downcast::<u32>().unwrap_err();
+    assert_eq!(msg.downcast::<u64>().unwrap(), (10, ()));
+}
+
+#[tokio::test]
+async fn test_address() {
+    let (sender, receiver) = mpmc::unbounded::<MyProtocol>();
+    let address = Address::new(sender).with_name("worker");
+    let id = address.id();
+    assert_eq!(address.clone().id(), id);
+    assert_ne!(Address::new(address.sender().clone()).id(), id);
+
+    address.send::<u32>(1u32).await.unwrap();
+    let dyn_sender: DynSender![u32] = address.into_dyn();
+    dyn_sender.send::<u32>(2u32).await.unwrap();
+
+    let address = dyn_sender
+        .downcast_ref::<Address<mpmc::Sender<MyProtocol>>>()
+        .unwrap();
+    assert_eq!((address.id(), address.name()), (id, Some("worker")));
+    assert!(matches!(
+        receiver.recv_async().await.unwrap(),
+        MyProtocol::A(1)
+    ));
+    assert!(matches!(
+        receiver.recv_async().await.unwrap(),
+        MyProtocol::A(2)
+    ));
+}
+
+#[tokio::test]
+async fn test_dyn_stats() {
+    let (sender, _receiver) = mpmc::unbounded::<MyProtocol>();
+    let dyn_sender: DynSender![u32] = sender.counted().into_dyn_sender();
+    dyn_sender.send::<u32>(1u32).await.unwrap();
+    assert_eq!(dyn_sender.stats().sent, Some(1));
+    assert_eq!(dyn_sender.boxed().stats().len, 1);
+}
+
+#[tokio::test]
+async fn test_erased_sender() {
+    let (mpmc, mpmc_rx) = mpmc::unbounded::<MyProtocol>();
+    let (priority, priority_rx) = priority::unbounded::<MyProtocol, u8>();
+
+    let mpmc: DynSender![u32] = mpmc.into_dyn_sender();
+    let priority: DynSender![u32; u8] = priority.into_dyn_sender();
+    let senders: Vec<ErasedSender![u32]> = vec![mpmc.erase_with(), priority.erase_with()];
+    for sender in &senders {
+        sender.send::<u32>(1u32).await.unwrap();
+        sender
+            .clone()
+            .boxed()
+            .dyn_try_send::<u64>(2u64)
+            .unwrap_err();
+    }
+
+    assert!(matches!(
+        mpmc_rx.recv_async().await.unwrap(),
+        MyProtocol::A(1)
+    ));
+    assert!(matches!(
+        priority_rx.recv().await.unwrap(),
+        (MyProtocol::A(1), 0)
+    ));
+    assert!(senders[0]
+        .downcast_ref::<mpmc::Sender<MyProtocol>>()
+        .is_some());
+
+    drop(priority_rx);
+    assert!(senders[1].try_send::<u32>(3u32).is_err());
+}
+
+#[tokio::test]
+async fn test_dyn_with() {
+    let (sender, receiver) = priority::unbounded::<MyProtocol, u8>();
+    let dyn_sender: DynSender![u32; u8] = sender.into_dyn_sender();
+
+    let with_sender: DynSender![u32] = dyn_sender.clone().with(5);
+    with_sender.send::<u32>(1u32).await.unwrap();
+    assert!(matches!(
+        receiver.recv().await.unwrap(),
+        (MyProtocol::A(1), 5)
+    ));
+
+    let mapped: DynSender![u32; u16] = dyn_sender.map_with(|w: u16| w as u8, u16::from);
+    mapped.send_msg_with(2u32, 7u16).await.unwrap();
+    assert!(matches!(
+        receiver.recv().await.unwrap(),
+        (MyProtocol::A(2), 7)
+    ));
+
+    drop(receiver);
+    let Err(TrySendError::Closed((2, 9))) = mapped.try_send_msg_with(2u32, 9u16) else {
+        panic!("expected closed error");
+    };
+}
+
+#[tokio::test]
+async fn test_dyn_from_with_fn_closure() {
+    let (sender, receiver) = priority::unbounded::<MyProtocol, u8>();
+    let priority = 3u8;
+    let sender = sender.with_fn(move |_: &MyProtocol| priority);
+    assert!(format!("{sender:?}").starts_with("WithFnSender"));
+
+    let dyn_sender: DynSender![u32] = sender.counted().into_dyn_sender();
+    dyn_sender.send::<u32>(1u32).await.unwrap();
+    assert!(matches!(
+        receiver.recv().await.unwrap(),
+        (MyProtocol::A(1), 3)
+    ));
+}
+
+#[tokio::test]
+async fn test_dyn_from_map_with_closures() {
+    let (sender, receiver) = priority::unbounded::<MyProtocol, u8>();
+    let offset = 2u8;
+    let sender = sender.map_with_closures(
+        move |with: u16| with as u8 + offset,
+        move |with: u8| u16::from(with - offset),
+    );
+    assert!(format!("{sender:?}").starts_with("MappedWithSender"));
+
+    let dyn_sender: DynSender![u32; u16] = sender.into_dyn_sender();
+    dyn_sender.send_with::<u32>(1u32, 5).await.unwrap();
+    assert!(matches!(
+        receiver.recv().await.unwrap(),
+        (MyProtocol::A(1), 7)
+    ));
+}
+
+#[tokio::test]
+async fn test_dyn_trait_object() {
+    let (sender, receiver) = mpmc::unbounded::<MyProtocol>();
+    let boxed = sender.boxed();
+    let sender: &dyn IsDynSender<With = ()> = &*boxed;
+
+    assert!(sender.accepts_msg::<u32>());
+    sender.dyn_send_msg(1u32).await.unwrap();
+    sender.dyn_try_send::<HelloWorld>("hi").unwrap();
+    sender.dyn_try_send_msg(2u64).unwrap_err();
+
+    assert!(matches!(
+        receiver.recv_async().await.unwrap(),
+        MyProtocol::A(1)
+    ));
+    assert!(matches!(
+        receiver.recv_async().await.unwrap(),
+        MyProtocol::B(_)
+    ));
+}
+
+/// A sender that is `!Send`, since it holds an `Rc`.
+#[derive(Debug, Clone)]
+struct RcSender {
+    sender: mpmc::Sender<MyProtocol>,
+    _rc: std::rc::Rc<()>,
+}
+
+impl IsSender for RcSender {
+    type With = ();
+
+    fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        self.sender.capacity()
+    }
+
+    fn len(&self) -> usize {
+        self.sender.len()
+    }
+
+    fn receiver_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+
+    fn sender_count(&self) -> usize {
+        self.sender.sender_count()
+    }
+}
+
+impl IsStaticSender for RcSender {
+    type Protocol = MyProtocol;
+
+    fn send_protocol_with(
+        this: &Self,
+        protocol: MyProtocol,
+        with: (),
+    ) -> impl std::future::Future<Output = Result<(), SendError<(MyProtocol, ())>>> + Send {
+        mpmc::Sender::send_protocol_with(&this.sender, protocol, with)
+    }
+
+    fn try_send_protocol_with(
+        this: &Self,
+        protocol: MyProtocol,
+        with: (),
+    ) -> Result<(), TrySendError<(MyProtocol, ())>> {
+        mpmc::Sender::try_send_protocol_with(&this.sender, protocol, with)
+    }
+}
+
+#[test]
+fn test_local_dyn_sender() {
+    let (sender, receiver) = mpmc::unbounded::<MyProtocol>();
+    let rc_sender = RcSender {
+        sender: sender.clone(),
+        _rc: std::rc::Rc::new(()),
+    };
+
+    let local: LocalDynSender![u32, HelloWorld] = LocalDynSender::new(rc_sender);
+    let dyn_sender: DynSender![u32] = sender.counted().into_dyn_sender();
+    let from_dyn = LocalDynSender::from(dyn_sender);
+
+    futures::executor::block_on(async {
+        local.send::<u32>(1u32).await.unwrap();
+        local.clone().send::<HelloWorld>("hi").await.unwrap();
+        from_dyn.try_send::<u32>(2u32).unwrap();
+        local.local_send_msg(3u64).await.unwrap_err();
+        assert!(!from_dyn.local_accepts_msg::<u64>());
+    });
+
+    assert!(matches!(receiver.try_recv().unwrap(), MyProtocol::A(1)));
+    assert!(matches!(receiver.try_recv().unwrap(), MyProtocol::B(_)));
+    assert!(matches!(receiver.try_recv().unwrap(), MyProtocol::A(2)));
+    assert!(local.downcast_ref::<RcSender>().is_some());
+}
+
+#[derive(Debug, From, TryInto, DynProtocol, Dispatch)]
+#[meslin(client)]
+pub enum GreeterProtocol {
+    SayHello(HelloWorld),
+    GetName(Request<u32, String>),
+    GetHTTPStatus(Request<(), u16>),
+    #[meslin(flatten(u32))]
+    Inner(InnerProtocol),
+}
+
+#[derive(Debug, From, TryInto, DynProtocol)]
+pub enum InnerProtocol {
+    Number(u32),
+}
+
+#[tokio::test]
+async fn test_protocol_client() {
+    let (sender, receiver) = mpmc::unbounded::<GreeterProtocol>();
+    let client = GreeterProtocolClient::new(sender);
+
+    client.say_hello("hi").await.unwrap();
+    let GreeterProtocol::SayHello(HelloWorld(text)) = receiver.recv_async().await.unwrap() else {
+        panic!("expected SayHello")
+    };
+    assert_eq!(text, "hi");
+
+    let (reply, request) = futures::join!(
+        async { client.get_name(1u32).await.unwrap().await.unwrap() },
+        async {
+            let GreeterProtocol::GetName(request) = receiver.recv_async().await.unwrap() else {
+                panic!("expected GetName")
+            };
+            request.reply("one".to_string()).unwrap();
+        }
+    );
+    assert_eq!((reply.as_str(), request), ("one", ()));
+
+    // Acronyms are kept together in the method names.
+    let _status = client.get_http_status(()).await.unwrap();
+    assert!(matches!(
+        receiver.recv_async().await.unwrap(),
+        GreeterProtocol::GetHTTPStatus(_)
+    ));
+
+    // Flattened variants send the messages of the inner protocol.
+    client.inner::<u32>(5u32).await.unwrap();
+    assert!(matches!(
+        receiver.recv_async().await.unwrap(),
+        GreeterProtocol::Inner(InnerProtocol::Number(5))
+    ));
+
+    drop(receiver);
+    assert_eq!(client.say_hello("bye").await.unwrap_err().0 .0, "bye");
+}
+
+#[test]
+fn test_protocol_info() {
+    let info = FlattenedProtocol::protocol_info();
+    assert!(info.name.ends_with("FlattenedProtocol"));
+    let names = info.messages.iter().map(|msg| msg.name).collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "i8",
+            "u32",
+            "dynamic::HelloWorld",
+            std::any::type_name::<Request<u32, String>>()
+        ]
+    );
+
+    let request = info.message::<Request<u32, String>>().unwrap();
+    assert!(request.request);
+    assert_eq!(request.input, "u32");
+    let hello = info.message::<HelloWorld>().unwrap();
+    assert!(!hello.request);
+    assert_eq!(hello.output, "()");
+    assert!(info.message::<u64>().is_none());
+
+    let (sender, _receiver) = mpmc::unbounded::<FlattenedProtocol>();
+    let dyn_sender: DynSender![u32] = sender.counted().into_dyn_sender();
+    assert_eq!(dyn_sender.protocol_info(), Some(info));
+}
+
+assert_accepts!(MyProtocol, u32, HelloWorld, Request<u32, String>);
+assert_subset!(Set![HelloWorld, u32], MyProtocol);
@@ -0,0 +1,82 @@
+use meslin::*;
+
+#[tokio::test]
+async fn journal_replays_before_live_protocols() {
+    let (sender, mut receiver) = journal::channel::<u32>(3);
+    for i in 0..5u32 {
+        sender.send::<u32>(i).await.unwrap();
+    }
+    assert_eq!(sender.first_seq(), 2);
+    assert_eq!(sender.next_seq(), 5);
+
+    // The first receiver fell behind, and skips to the oldest protocol in the journal.
+    assert_eq!(receiver.recv().await.unwrap(), (2, 2));
+
+    let mut late = sender.replay_from(3);
+    let handle = tokio::spawn(async move {
+        let mut received = Vec::new();
+        while let Ok((protocol, seq)) = late.recv_protocol_with().await {
+            received.push((seq, protocol));
+        }
+        received
+    });
+    sender.send::<u32>(5u32).await.unwrap();
+    drop(sender);
+
+    assert_eq!(handle.await.unwrap(), vec![(3, 3), (4, 4), (5, 5)]);
+    assert_eq!(receiver.try_recv().unwrap(), (3, 3));
+}
+
+#[test]
+fn journal_subscribe_only_receives_new_protocols() {
+    let (sender, receiver) = journal::channel::<u32>(8);
+    sender.try_send::<u32>(1u32).unwrap();
+
+    let mut subscriber = sender.subscribe();
+    assert!(matches!(subscriber.try_recv(), Err(TryRecvError::Empty)));
+    sender.try_send::<u32>(2u32).unwrap();
+    assert_eq!(subscriber.try_recv().unwrap(), (1, 2));
+    assert_eq!(sender.receiver_count(), 2);
+
+    drop(receiver);
+    drop(sender);
+    assert_eq!(subscriber.try_recv_protocol(), Err(TryRecvError::Closed));
+}
+
+#[tokio::test]
+async fn journal_notifies_receiver_count_changes() {
+    let (sender, receiver) = journal::channel::<u32>(4);
+    let changed = sender.receiver_count_changed();
+    drop(receiver);
+    assert_eq!(changed.await, 0);
+
+    let sender2 = sender.clone();
+    let producer = tokio::spawn(async move {
+        // Pause until somebody is listening. The future is created before checking the count,
+        // so that no change is missed.
+        loop {
+            let changed = sender2.receiver_count_changed();
+            if sender2.receiver_count() > 0 {
+                break;
+            }
+            changed.await;
+        }
+        sender2.send::<u32>(1u32).await.unwrap();
+    });
+    tokio::task::yield_now().await;
+    let mut receiver = sender.subscribe();
+    producer.await.unwrap();
+    assert_eq!(receiver.recv().await.unwrap(), (0, 1));
+}
+
+#[test]
+fn journal_is_a_broadcast_sender() {
+    fn delivery_count<S: IsBroadcastSender>(sender: &S) -> usize {
+        sender.delivery_count()
+    }
+
+    let (sender, _receiver) = journal::channel::<u32>(4);
+    let _late = sender.subscribe();
+    assert_eq!(delivery_count(&sender), 2);
+    assert_eq!(delivery_count(&sender.clone().counted()), 2);
+}
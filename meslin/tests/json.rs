@@ -0,0 +1,25 @@
+use meslin::*;
+use serde_json::{json, value::RawValue, Value};
+
+#[derive(Debug, From, TryInto)]
+enum GatewayProtocol {
+    Value(Value),
+    Raw(Box<RawValue>),
+}
+
+#[tokio::test]
+async fn test_json_messages() {
+    let (sender, receiver) = mpmc::unbounded::<GatewayProtocol>();
+    sender.send::<Value>(json!({ "id": 1 })).await.unwrap();
+    let raw = RawValue::from_string("[1,2,3]".to_string()).unwrap();
+    sender.send::<Box<RawValue>>(raw).await.unwrap();
+
+    let GatewayProtocol::Value(value) = receiver.recv_async().await.unwrap() else {
+        panic!("expected Value");
+    };
+    assert_eq!(value["id"], 1);
+    let GatewayProtocol::Raw(raw) = receiver.recv_async().await.unwrap() else {
+        panic!("expected RawValue");
+    };
+    assert_eq!(raw.get(), "[1,2,3]");
+}
@@ -0,0 +1,186 @@
+use meslin::{leveled::Level, *};
+
+#[tokio::test]
+async fn leveled_receives_highest_level_first() {
+    let (sender, mut receiver) = leveled::unbounded::<u32>();
+    for (i, level) in [
+        Level::Low,
+        Level::Normal,
+        Level::High,
+        Level::Low,
+        Level::High,
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        sender.try_send_with::<u32>(i as u32, level).unwrap();
+    }
+    assert_eq!(sender.len_at(Level::Low), 2);
+    assert_eq!(sender.len(), 5);
+
+    let mut received = Vec::new();
+    while let Ok(item) = receiver.try_recv() {
+        received.push(item);
+    }
+    assert_eq!(
+        received,
+        [
+            (2, Level::High),
+            (4, Level::High),
+            (1, Level::Normal),
+            (0, Level::Low),
+            (3, Level::Low)
+        ]
+    );
+}
+
+#[tokio::test]
+async fn leveled_bounded_waits_for_capacity() {
+    let (sender, mut receiver) = leveled::bounded::<u32>(1);
+    sender.send::<u32>(1u32).await.unwrap();
+    assert!(matches!(
+        sender.try_send_with::<u32>(2u32, Level::High),
+        Err(TrySendError::Full((2, Level::High)))
+    ));
+
+    let send = tokio::spawn({
+        let sender = sender.clone();
+        async move { sender.send_with::<u32>(2u32, Level::High).await }
+    });
+    assert_eq!(receiver.recv().await.unwrap(), (1, Level::Normal));
+    send.await.unwrap().unwrap();
+    assert_eq!(receiver.recv().await.unwrap(), (2, Level::High));
+
+    drop(sender);
+    assert!(receiver.recv().await.is_err());
+}
+
+#[tokio::test]
+async fn leveled_closes_when_receivers_are_dropped() {
+    let (sender, receiver) = leveled::bounded::<u32>(1);
+    sender.send::<u32>(1u32).await.unwrap();
+    let send = tokio::spawn({
+        let sender = sender.clone();
+        async move { sender.send::<u32>(2u32).await }
+    });
+    tokio::task::yield_now().await;
+    drop(receiver);
+    assert!(send.await.unwrap().is_err());
+    assert!(sender.is_closed());
+}
+
+#[tokio::test]
+async fn leveled_watermarks_notify_on_crossing() {
+    use leveled::Watermark;
+    use std::sync::{Arc, Mutex};
+
+    let crossed = Arc::new(Mutex::new(Vec::new()));
+    let (sender, mut receiver) = leveled::bounded::<u32>(8);
+    sender.on_watermark(3, 1, {
+        let crossed = crossed.clone();
+        move |watermark| crossed.lock().unwrap().push(watermark)
+    });
+
+    // Oscillating between the watermarks does not notify again.
+    for i in 0..3u32 {
+        sender.send::<u32>(i).await.unwrap();
+    }
+    receiver.recv().await.unwrap();
+    sender.send::<u32>(3u32).await.unwrap();
+    assert_eq!(*crossed.lock().unwrap(), [Watermark::High]);
+
+    receiver.recv().await.unwrap();
+    receiver.recv().await.unwrap();
+    assert_eq!(*crossed.lock().unwrap(), [Watermark::High, Watermark::Low]);
+}
+
+#[tokio::test]
+async fn leveled_wait_for_capacity() {
+    let (sender, mut receiver) = leveled::bounded::<u32>(3);
+    for i in 0..3u32 {
+        sender.send::<u32>(i).await.unwrap();
+    }
+
+    let waiter = tokio::spawn({
+        let sender = sender.clone();
+        async move { sender.wait_for_capacity(2).await }
+    });
+    receiver.recv().await.unwrap();
+    tokio::task::yield_now().await;
+    assert!(!waiter.is_finished());
+    receiver.recv().await.unwrap();
+    assert!(waiter.await.unwrap());
+    assert_eq!(sender.len(), 1);
+
+    drop(receiver);
+    assert!(!sender.wait_for_capacity(3).await);
+}
+
+#[tokio::test]
+async fn leveled_reserved_slots_count_towards_capacity() {
+    let (sender, mut receiver) = leveled::bounded::<u32>(2);
+    let permit = sender.try_reserve().unwrap();
+    sender.try_send::<u32>(1u32).unwrap();
+    assert!(matches!(sender.try_reserve(), Err(TrySendError::Full(()))));
+    assert!(sender.try_send::<u32>(2u32).is_err());
+
+    permit.send_msg_with(3u32, Level::High).unwrap();
+    assert_eq!(receiver.recv().await.unwrap(), (3, Level::High));
+    assert_eq!(receiver.recv().await.unwrap(), (1, Level::Normal));
+
+    // Dropping a permit releases its slot.
+    let permit = sender.try_reserve().unwrap();
+    let _permit2 = sender.try_reserve().unwrap();
+    drop(permit);
+    sender.try_send::<u32>(4u32).unwrap();
+    assert!(sender.try_send::<u32>(5u32).is_err());
+}
+
+#[tokio::test]
+async fn leveled_try_send_all_is_all_or_nothing() {
+    let (sender1, mut receiver1) = leveled::bounded::<u32>(1);
+    let (sender2, mut receiver2) = leveled::bounded::<u32>(1);
+    let (sender3, receiver3) = leveled::unbounded::<u32>();
+
+    try_send_all(&[&sender1, &sender2, &sender3], 1u32).unwrap();
+    receiver1.recv().await.unwrap();
+    assert_eq!(
+        try_send_all(&[&sender1, &sender2, &sender3], 2u32),
+        Err(TrySendError::Full(2))
+    );
+    assert!(receiver1.try_recv().is_err());
+    assert_eq!(sender3.len(), 1);
+
+    // The reservations of the failed send were released.
+    receiver2.recv().await.unwrap();
+    try_send_all_with(&[&sender1, &sender2], 3u32, Level::Low).unwrap();
+    assert_eq!(receiver1.recv().await.unwrap(), (3, Level::Low));
+    assert_eq!(receiver2.recv().await.unwrap(), (3, Level::Low));
+
+    drop(receiver3);
+    assert_eq!(
+        try_send_all(&[&sender1, &sender3], 4u32),
+        Err(TrySendError::Closed(4))
+    );
+    assert!(receiver1.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn leveled_cancelled_send_returns_the_message() {
+    let (sender, mut receiver) = leveled::bounded::<u32>(1);
+    sender.send::<u32>(1u32).await.unwrap();
+
+    let token = CancellationToken::new();
+    let cancel = async {
+        tokio::task::yield_now().await;
+        token.cancel();
+    };
+    let (sent, ()) = tokio::join!(sender.send_cancellable::<u32>(2u32, Some(&token)), cancel);
+    assert_eq!(sent, Err(CancelSendError::Cancelled(Some(2))));
+
+    let send = sender.send_cancellable::<u32>(3u32, None);
+    let (sent, received) = tokio::join!(send, receiver.recv());
+    sent.unwrap();
+    assert_eq!(received.unwrap(), (1, Level::Normal));
+    assert_eq!(receiver.recv().await.unwrap(), (3, Level::Normal));
+}
@@ -0,0 +1,62 @@
+use meslin::*;
+use std::{cell::RefCell, rc::Rc};
+
+#[derive(Debug, From, TryInto)]
+enum Protocol {
+    Shared(Rc<RefCell<Vec<u32>>>),
+    Number(u32),
+}
+
+#[test]
+fn local_sends_non_send_protocols() {
+    let (sender, mut receiver) = local::unbounded::<Protocol>();
+    let shared = Rc::new(RefCell::new(Vec::new()));
+
+    sender
+        .try_send::<Rc<RefCell<Vec<u32>>>>(shared.clone())
+        .unwrap();
+    sender.try_send::<u32>(1u32).unwrap();
+    assert_eq!(sender.len(), 2);
+
+    let Protocol::Shared(received) = receiver.try_recv().unwrap() else {
+        panic!("expected shared");
+    };
+    received.borrow_mut().push(1);
+    assert_eq!(*shared.borrow(), [1]);
+    assert!(matches!(receiver.try_recv().unwrap(), Protocol::Number(1)));
+    assert_eq!(receiver.try_recv().unwrap_err(), TryRecvError::Empty);
+}
+
+#[test]
+fn local_bounded_waits_for_space() {
+    let (sender, mut receiver) = local::bounded::<Protocol>(1);
+    sender.try_send::<u32>(1u32).unwrap();
+    assert_eq!(sender.try_send::<u32>(2u32), Err(TrySendError::Full(2)));
+
+    futures::executor::block_on(async {
+        let (sent, first) = futures::join!(sender.send::<u32>(2u32), receiver.recv());
+        sent.unwrap();
+        assert!(matches!(first.unwrap(), Protocol::Number(1)));
+        assert!(matches!(
+            receiver.recv().await.unwrap(),
+            Protocol::Number(2)
+        ));
+    });
+}
+
+#[test]
+fn local_closes_when_halves_drop() {
+    let (sender, mut receiver) = local::unbounded::<Protocol>();
+    let sender2 = sender.clone();
+    assert_eq!(sender.sender_count(), 2);
+    drop(sender);
+    drop(sender2);
+    futures::executor::block_on(async {
+        assert_eq!(receiver.recv().await.unwrap_err(), RecvError);
+    });
+
+    let (sender, receiver) = local::unbounded::<Protocol>();
+    drop(receiver);
+    assert!(sender.is_closed());
+    assert_eq!(sender.try_send::<u32>(1u32), Err(TrySendError::Closed(1)));
+}
@@ -120,3 +120,1308 @@ async fn priority() {
     assert!(matches!(rx.recv().await.unwrap(), (MyProtocol::A(1), _)));
     assert!(matches!(rx.recv().await.unwrap(), (MyProtocol::A(0), _)));
 }
+
+#[derive(Debug, From, TryInto, Dispatch)]
+enum Inner {
+    Add(u32),
+}
+
+#[derive(Debug, From, TryInto, Dispatch)]
+enum Outer {
+    #[meslin(flatten(u32))]
+    Inner(Inner),
+    Greet(HelloWorld),
+}
+
+#[derive(Default)]
+struct State {
+    total: u32,
+    greetings: Vec<String>,
+}
+
+impl Handler<u32> for State {
+    async fn handle(&mut self, n: u32) {
+        self.total += n;
+    }
+}
+
+impl Handler<HelloWorld> for State {
+    async fn handle(&mut self, HelloWorld(greeting): HelloWorld) {
+        self.greetings.push(greeting);
+    }
+}
+
+#[tokio::test]
+async fn test_dispatch() {
+    let (sender, mut receiver) = mpmc::unbounded::<Outer>();
+    IsStaticSender::send_protocol_with(&sender, Inner::Add(2).into(), ())
+        .await
+        .unwrap();
+    sender.send::<HelloWorld>("hello").await.unwrap();
+    drop(sender);
+
+    let mut state = State::default();
+    while receiver.recv_dispatch(&mut state).await.is_ok() {}
+    assert_eq!(state.total, 2);
+    assert_eq!(state.greetings, ["hello"]);
+}
+
+#[derive(Debug, From, TryInto, Dispatch)]
+#[meslin(service)]
+enum Counter {
+    Add(u32),
+    Greet(HelloWorld),
+    #[meslin(flatten(u32))]
+    Inner(Inner),
+}
+
+impl CounterService for State {
+    async fn add(&mut self, n: u32) {
+        self.total += n;
+    }
+
+    async fn greet(&mut self, HelloWorld(greeting): HelloWorld) {
+        self.greetings.push(greeting);
+    }
+
+    async fn inner(&mut self, inner: Inner) {
+        inner.dispatch(self).await
+    }
+}
+
+#[tokio::test]
+async fn test_service() {
+    let (sender, receiver) = mpmc::unbounded::<Counter>();
+    sender.send::<u32>(1u32).await.unwrap();
+    sender.send::<HelloWorld>("hello").await.unwrap();
+    IsStaticSender::send_protocol_with(&sender, Counter::Inner(Inner::Add(2)), ())
+        .await
+        .unwrap();
+    drop(sender);
+
+    let state = CounterService::serve(receiver, State::default()).await;
+    assert_eq!(state.total, 3);
+    assert_eq!(state.greetings, ["hello"]);
+}
+
+#[tokio::test]
+async fn test_select() {
+    let (sender1, mut receiver1) = mpmc::unbounded::<MyProtocol>();
+    let (sender2, mut receiver2) = broadcast::channel::<u64>(10);
+    let (sender3, mut receiver3) = priority::unbounded::<u8, u8>();
+    let _senders = (sender1.clone(), sender2.clone());
+
+    tokio::spawn(async move {
+        sender2.send::<u64>(2u64).await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        sender1.send::<u32>(1u32).await.unwrap();
+        drop(sender3);
+    });
+
+    let Select3::B(Ok((2, ()))) = select!(receiver1, receiver2, receiver3).await else {
+        panic!("expected message from receiver2")
+    };
+    let Select3::A(Ok((MyProtocol::A(1), ()))) = select!(receiver1, receiver2, receiver3).await
+    else {
+        panic!("expected message from receiver1")
+    };
+    let Select3::C(Err(RecvError)) = select!(receiver1, receiver2, receiver3).await else {
+        panic!("expected receiver3 to be closed")
+    };
+}
+
+#[tokio::test]
+async fn test_priority_select() {
+    let (control, mut control_rx) = mpmc::unbounded::<&str>();
+    let (data, mut data_rx) = mpmc::unbounded::<u32>();
+    let (low, mut low_rx) = mpmc::unbounded::<u8>();
+    for n in 0..3u32 {
+        data.send::<u32>(n).await.unwrap();
+    }
+    low.send::<u8>(0u8).await.unwrap();
+    control.send::<&str>("first").await.unwrap();
+    control.send::<&str>("second").await.unwrap();
+
+    let mut received = Vec::new();
+    while let Ok(msg) = priority_select!(control_rx, data_rx, low_rx).await {
+        received.push(msg);
+        if received.len() == 5 {
+            break;
+        }
+    }
+    assert_eq!(
+        received,
+        [
+            Select3::A(("first", ())),
+            Select3::A(("second", ())),
+            Select3::B((0, ())),
+            Select3::B((1, ())),
+            Select3::B((2, ())),
+        ]
+    );
+
+    // Closed receivers are skipped, until all receivers are closed.
+    drop((control, data));
+    tokio::spawn(async move { low.send::<u8>(1u8).await.unwrap() });
+    assert_eq!(
+        priority_select(&mut control_rx, &mut data_rx).await,
+        Err(RecvError)
+    );
+    assert_eq!(
+        priority_select!(control_rx, data_rx, low_rx).await,
+        Ok(Select3::C((0, ())))
+    );
+    assert_eq!(
+        priority_select!(control_rx, data_rx, low_rx).await,
+        Ok(Select3::C((1, ())))
+    );
+    assert_eq!(
+        priority_select!(control_rx, data_rx, low_rx).await,
+        Err(RecvError)
+    );
+}
+
+#[tokio::test]
+async fn test_cancellation() {
+    // A monitored sender can wait for space without sending, so it keeps the message.
+    let monitor = Monitor::new();
+    let (sender, receiver) = mpmc::bounded::<MyProtocol>(1);
+    let (sender, mut receiver) = (sender.monitored(&monitor), receiver.monitored(&monitor));
+    let token = CancellationToken::new();
+    let child = token.child_token();
+
+    sender
+        .send_cancellable::<u32>(1u32, Some(&child))
+        .await
+        .unwrap();
+    let cancel = async {
+        tokio::task::yield_now().await;
+        token.cancel();
+    };
+    let (sent, ()) = tokio::join!(sender.send_cancellable::<u32>(2u32, Some(&child)), cancel);
+    assert_eq!(sent, Err(CancelSendError::Cancelled(Some(2))));
+    assert!(child.is_cancelled());
+    assert_eq!(
+        sender.send_cancellable::<u32>(3u32, Some(&child)).await,
+        Err(CancelSendError::Cancelled(Some(3)))
+    );
+
+    assert!(matches!(
+        receiver.recv_protocol_cancellable(None).await,
+        Ok(MyProtocol::A(1))
+    ));
+    assert_eq!(
+        receiver
+            .recv_protocol_cancellable(Some(&token))
+            .await
+            .unwrap_err(),
+        CancelRecvError::Cancelled
+    );
+
+    let token = CancellationToken::new();
+    let serve = async {
+        let MyProtocol::C(_request) = receiver.recv_protocol().await.unwrap() else {
+            panic!("expected a request")
+        };
+        token.cancel();
+    };
+    let (reply, ()) = tokio::join!(
+        sender.request_cancellable::<Request<u32, String>>(5u32, Some(&token)),
+        serve
+    );
+    assert!(matches!(reply, Err(CancelRequestError::Cancelled(None))));
+
+    drop(receiver);
+    assert_eq!(
+        sender.send_cancellable::<u32>(6u32, None).await,
+        Err(CancelSendError::Closed(6))
+    );
+}
+
+#[tokio::test]
+async fn test_merge_is_fair() {
+    use futures::StreamExt;
+
+    let (sender1, receiver1) = mpmc::unbounded::<u32>();
+    let (sender2, receiver2) = mpmc::unbounded::<u32>();
+    for i in 0..3u32 {
+        sender1.send::<u32>(i).await.unwrap();
+        sender2.send::<u32>(i + 10).await.unwrap();
+    }
+    drop(sender2);
+
+    let mut merged = merge([receiver1, receiver2]);
+    let mut received = Vec::new();
+    for _ in 0..6 {
+        received.push(merged.next().await.unwrap().0);
+    }
+    assert_eq!(received, [0, 10, 1, 11, 2, 12]);
+    assert_eq!(merged.len(), 2);
+
+    drop(sender1);
+    assert!(merged.next().await.is_none());
+    assert!(merged.is_empty());
+}
+
+#[tokio::test]
+async fn test_recv_as() {
+    let (sender, mut receiver) = mpmc::unbounded::<MyProtocol>();
+    sender.send::<u32>(1u32).await.unwrap();
+    sender.send::<HelloWorld>("hello").await.unwrap();
+    sender.send::<u32>(2u32).await.unwrap();
+    sender.send::<HelloWorld>("world").await.unwrap();
+
+    assert_eq!(receiver.recv_as::<u32>().await.unwrap(), 1);
+    let Err(RecvAsError::Unexpected((MyProtocol::B(_), ()))) = receiver.recv_as::<u32>().await
+    else {
+        panic!("expected unexpected HelloWorld")
+    };
+
+    let mut skipped = Vec::new();
+    let HelloWorld(text) = receiver
+        .recv_as_or_else::<HelloWorld, _>(|protocol, ()| skipped.push(protocol))
+        .await
+        .unwrap();
+    assert_eq!(text, "world");
+    assert!(matches!(skipped[..], [MyProtocol::A(2)]));
+
+    drop(sender);
+    assert!(matches!(
+        receiver.recv_as::<u32>().await,
+        Err(RecvAsError::Closed)
+    ));
+}
+
+#[tokio::test]
+async fn test_inbox_requeue() {
+    let (sender, receiver) = mpmc::unbounded::<MyProtocol>();
+    let mut inbox = Inbox::with_capacity(receiver, 1);
+    sender.send::<HelloWorld>("later").await.unwrap();
+    sender.send::<u32>(1u32).await.unwrap();
+
+    // Defer the greeting until the number has been handled.
+    assert!(matches!(inbox.try_peek().unwrap(), (MyProtocol::B(_), ())));
+    let deferred = inbox.recv_protocol().await.unwrap();
+    assert!(matches!(
+        inbox.recv_protocol().await.unwrap(),
+        MyProtocol::A(1)
+    ));
+    inbox.requeue(deferred, ()).unwrap();
+    assert!(inbox.is_full());
+    assert!(inbox.requeue(MyProtocol::A(2), ()).is_err());
+
+    assert!(matches!(
+        inbox.recv_protocol().await.unwrap(),
+        MyProtocol::B(_)
+    ));
+    assert!(matches!(inbox.try_peek(), Err(TryRecvError::Empty)));
+}
+
+#[tokio::test]
+async fn test_mpmc_peek() {
+    let (sender, receiver) = mpmc::bounded::<MyProtocol>(2);
+    let mut receiver: mpmc::PeekableReceiver<_> = receiver.peekable();
+    assert!(matches!(receiver.try_peek(), Err(TryRecvError::Empty)));
+
+    sender.send::<u32>(1u32).await.unwrap();
+    sender.send::<u32>(2u32).await.unwrap();
+    assert!(matches!(
+        receiver.peek().await.unwrap(),
+        (MyProtocol::A(1), ())
+    ));
+    assert!(matches!(
+        receiver.try_peek().unwrap(),
+        (MyProtocol::A(1), ())
+    ));
+    // The peeked protocol no longer takes up space in the channel.
+    sender.try_send::<u32>(3u32).unwrap();
+
+    for expected in 1..=3 {
+        let MyProtocol::A(n) = receiver.recv_protocol().await.unwrap() else {
+            panic!("expected a number")
+        };
+        assert_eq!(n, expected);
+    }
+}
+
+#[tokio::test]
+async fn test_is_full_and_remaining() {
+    let (sender, _receiver) = mpmc::bounded::<MyProtocol>(2);
+    assert_eq!(sender.remaining(), Some(2));
+    sender.send::<u32>(1u32).await.unwrap();
+    assert!(!sender.is_full());
+    sender.send::<u32>(2u32).await.unwrap();
+    assert!(sender.is_full());
+    assert_eq!(sender.remaining(), Some(0));
+
+    let sender = sender.filter(|_| true);
+    assert!(sender.is_full());
+
+    let (sender, _receiver) = mpmc::unbounded::<MyProtocol>();
+    assert!(!sender.is_full());
+    assert_eq!(sender.remaining(), None);
+
+    let (mut sender, _receiver) = broadcast::channel::<u32>(1);
+    sender.send::<u32>(1u32).await.unwrap();
+    assert!(sender.is_full());
+    sender.inner_mut().set_overflow(true);
+    assert!(!sender.is_full());
+    assert_eq!(sender.remaining(), None);
+}
+
+#[test]
+fn test_request_try_recv_and_close() {
+    let (request, rx) = Request::<u32, String>::new(1);
+    assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+    request.reply("one".to_string()).unwrap();
+    assert_eq!(rx.try_recv().unwrap(), "one");
+    assert_eq!(rx.try_recv(), Err(TryRecvError::Closed));
+
+    let (request, rx) = Request::<u32, String>::new(2);
+    rx.close();
+    assert_eq!(request.reply("two".to_string()), Err("two".to_string()));
+}
+
+#[tokio::test]
+async fn test_request_into_parts() {
+    let (sender, receiver) = mpmc::unbounded::<MyProtocol>();
+    tokio::spawn(async move {
+        let MyProtocol::C(request) = receiver.recv_async().await.unwrap() else {
+            panic!("expected request")
+        };
+        let (msg, reply_sender) = request.into_parts();
+        let request = Request::from_parts(msg + 1, reply_sender);
+        let (msg, reply_sender) = request.into_parts();
+        tokio::spawn(async move { reply_sender.reply(msg.to_string()).unwrap() });
+    });
+
+    let reply = sender.request::<Request<u32, String>>(1u32).await.unwrap();
+    assert_eq!(reply, "2");
+}
+
+#[tokio::test]
+async fn test_request_forward_to() {
+    let (proxy, proxy_rx) = mpmc::unbounded::<MyProtocol>();
+    let (worker, worker_rx) = mpmc::unbounded::<MyProtocol>();
+    tokio::spawn(async move {
+        let MyProtocol::C(request) = proxy_rx.recv_async().await.unwrap() else {
+            panic!("expected request")
+        };
+        request.forward_to(&worker).await.unwrap();
+    });
+    tokio::spawn(async move {
+        let MyProtocol::C(request) = worker_rx.recv_async().await.unwrap() else {
+            panic!("expected request")
+        };
+        let msg = request.msg;
+        request.reply(format!("worker {msg}")).unwrap();
+    });
+
+    let reply = proxy.request::<Request<u32, String>>(1u32).await.unwrap();
+    assert_eq!(reply, "worker 1");
+
+    // The request is returned when the receiver is gone.
+    let (closed, _) = mpmc::unbounded::<MyProtocol>();
+    let (request, _rx) = Request::<u32, String>::new(2);
+    let request = request.try_forward_to(&closed).unwrap_err().into_inner();
+    assert_eq!(request.msg, 2);
+}
+
+#[tokio::test]
+async fn test_correlation() {
+    use std::sync::Arc;
+
+    let a = Correlated::<()>::default();
+    let b = Correlated::<()>::default();
+    assert_ne!(a.id, b.id);
+
+    let (requests, requests_rx) = mpmc::unbounded::<(CorrelationId, u32)>();
+    let (replies, replies_rx) = mpmc::unbounded::<(CorrelationId, String)>();
+    let correlator = Arc::new(Correlator::<String>::new());
+
+    // A server that replies out of order.
+    tokio::spawn(async move {
+        let mut received = Vec::new();
+        while let Ok(request) = requests_rx.recv_async().await {
+            received.push(request);
+        }
+        for (id, msg) in received.into_iter().rev() {
+            replies
+                .send::<(CorrelationId, String)>((id, msg.to_string()))
+                .await
+                .unwrap();
+        }
+    });
+    tokio::spawn({
+        let correlator = correlator.clone();
+        async move {
+            while let Ok((id, reply)) = replies_rx.recv_async().await {
+                correlator.resolve(id, reply).unwrap();
+            }
+        }
+    });
+
+    let mut pending = Vec::new();
+    for i in 0..10u32 {
+        let (id, reply) = correlator.register();
+        requests
+            .send::<(CorrelationId, u32)>((id, i))
+            .await
+            .unwrap();
+        pending.push(reply);
+    }
+    assert_eq!(correlator.len(), 10);
+    drop(requests);
+
+    for (i, reply) in pending.into_iter().enumerate() {
+        assert_eq!(reply.await.unwrap(), i.to_string());
+    }
+    assert!(correlator.is_empty());
+
+    let (id, _reply) = correlator.register();
+    assert!(correlator.unregister(id));
+    assert_eq!(correlator.resolve(id, "late".into()), Err("late".into()));
+}
+
+#[tokio::test]
+async fn test_race_request() {
+    let (closed, _) = mpmc::unbounded::<MyProtocol>();
+    let (slow, slow_rx) = mpmc::unbounded::<MyProtocol>();
+    let (fast, fast_rx) = mpmc::unbounded::<MyProtocol>();
+
+    tokio::spawn(async move {
+        let MyProtocol::C(request) = fast_rx.recv_async().await.unwrap() else {
+            panic!("expected request")
+        };
+        request.reply("fast".into()).unwrap();
+    });
+
+    let reply = race_request::<_, Request<u32, String>>([&closed, &slow, &fast], 1u32)
+        .await
+        .unwrap();
+    assert_eq!(reply, "fast");
+
+    // The slow request was cancelled.
+    let MyProtocol::C(request) = slow_rx.recv_async().await.unwrap() else {
+        panic!("expected request")
+    };
+    assert!(request.reply("slow".into()).is_err());
+
+    // All requests failed.
+    let result = race_request::<_, Request<u32, String>>([&closed], 2u32).await;
+    assert!(matches!(result, Err(RequestError::Full(2))));
+    let result = race_request::<mpmc::Sender<MyProtocol>, Request<u32, String>>([], 3u32).await;
+    assert!(matches!(result, Err(RequestError::Full(3))));
+}
+
+#[tokio::test]
+async fn test_barrier() {
+    #[derive(Debug, From, TryInto)]
+    enum Protocol {
+        A(u32),
+        Barrier(Barrier),
+    }
+
+    let (sender, receiver) = mpmc::unbounded::<Protocol>();
+    let processed = std::sync::Arc::new(std::sync::atomic::AtomicU32::new(0));
+    let handle = tokio::spawn({
+        let processed = processed.clone();
+        async move {
+            while let Ok(msg) = receiver.recv_async().await {
+                match msg {
+                    Protocol::A(n) => {
+                        tokio::task::yield_now().await;
+                        processed.fetch_add(n, std::sync::atomic::Ordering::SeqCst);
+                    }
+                    Protocol::Barrier(barrier) => {
+                        barrier.release();
+                    }
+                }
+            }
+        }
+    });
+
+    for _ in 0..100 {
+        sender.send::<u32>(1u32).await.unwrap();
+    }
+    sender.barrier().await.unwrap();
+    assert_eq!(processed.load(std::sync::atomic::Ordering::SeqCst), 100);
+
+    // A barrier that is dropped without being released fails.
+    let (dropping, dropping_rx) = mpmc::unbounded::<Protocol>();
+    tokio::spawn(async move { drop(dropping_rx.recv_async().await) });
+    assert!(matches!(
+        dropping.barrier().await,
+        Err(RequestError::NoReply(RecvError))
+    ));
+
+    drop(sender);
+    handle.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_shutdown_and_drain() {
+    #[derive(Debug, From, TryInto)]
+    enum Protocol {
+        A(u32),
+        Shutdown(Shutdown),
+    }
+
+    let (sender, mut receiver) = mpmc::unbounded::<Protocol>();
+    for i in 0..10u32 {
+        sender.send::<u32>(i).await.unwrap();
+    }
+    sender.send::<Shutdown>(()).await.unwrap().close();
+    sender.send::<u32>(10u32).await.unwrap();
+    let shutdown = sender.send::<Shutdown>(()).await.unwrap();
+    sender.send::<u32>(11u32).await.unwrap();
+
+    let handle = tokio::spawn(async move {
+        let mut received = Vec::new();
+        loop {
+            match receiver.recv_protocol().await.unwrap() {
+                Protocol::A(n) => received.push(n),
+                Protocol::Shutdown(shutdown) => {
+                    // The first shutdown was no longer awaited.
+                    if !shutdown.complete() {
+                        continue;
+                    }
+                    received.extend(receiver.drain_queued().filter_map(|(p, ())| match p {
+                        Protocol::A(n) => Some(n),
+                        Protocol::Shutdown(_) => None,
+                    }));
+                    drop(receiver);
+                    break received;
+                }
+            }
+        }
+    });
+
+    shutdown.await.unwrap();
+    assert_eq!(handle.await.unwrap(), (0..12).collect::<Vec<_>>());
+    assert!(sender.send::<u32>(12u32).await.is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_shutdown_closes_gate_while_draining() {
+    #[derive(Debug, From, TryInto)]
+    enum Protocol {
+        A(u32),
+        Shutdown(Shutdown),
+    }
+
+    let gate = Gate::new();
+    let (sender, mut receiver) = mpmc::bounded::<Protocol>(4);
+    let sender = sender.gated(&gate);
+
+    // Keeps sending until the gate is closed, often waiting on the full channel.
+    let producer = tokio::spawn({
+        let sender = sender.clone();
+        async move {
+            let mut sent = Vec::new();
+            let mut i = 0;
+            while sender.send::<u32>(i).await.is_ok() {
+                sent.push(i);
+                i += 1;
+            }
+            sent
+        }
+    });
+
+    let actor = tokio::spawn(async move {
+        let mut received = Vec::new();
+        loop {
+            match receiver.recv_protocol().await.unwrap() {
+                Protocol::A(n) => received.push(n),
+                Protocol::Shutdown(shutdown) => {
+                    let drained = gate.close_and_drain(&mut receiver).await;
+                    received.extend(drained.into_iter().filter_map(|(p, ())| match p {
+                        Protocol::A(n) => Some(n),
+                        Protocol::Shutdown(_) => None,
+                    }));
+                    assert!(shutdown.complete());
+                    break received;
+                }
+            }
+        }
+    });
+
+    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    sender.shutdown_and_drain().await.unwrap();
+    let sent = producer.await.unwrap();
+    assert!(!sent.is_empty());
+    assert_eq!(actor.await.unwrap(), sent);
+    assert!(sender.is_closed());
+    assert!(sender.try_send::<u32>(0u32).is_err());
+}
+
+#[tokio::test]
+async fn test_backpressure() {
+    let (sender, receiver) = mpmc::bounded_with::<u32>(2, Backpressure::Fail);
+    sender.send::<u32>(1u32).await.unwrap();
+    sender.send::<u32>(2u32).await.unwrap();
+    assert_eq!(sender.send::<u32>(3u32).await, Err(SendError(3)));
+    assert_eq!(sender.try_send::<u32>(3u32), Err(TrySendError::Full(3)));
+    assert_eq!(
+        sender.send_or_reject(3).await,
+        Err(BackpressureError::Rejected(3))
+    );
+    assert!(sender.is_full());
+    assert!(!sender.is_closed());
+    assert_eq!(receiver.drain().collect::<Vec<_>>(), [1, 2]);
+    drop(receiver);
+    assert_eq!(
+        sender.send_or_reject(3).await,
+        Err(BackpressureError::Closed(3))
+    );
+
+    let (sender, receiver) = mpmc::bounded_with::<u32>(2, Backpressure::DropNewest);
+    for i in 0..4u32 {
+        sender.send::<u32>(i).await.unwrap();
+    }
+    assert!(!sender.is_full());
+    assert_eq!(receiver.drain().collect::<Vec<_>>(), [0, 1]);
+
+    let (sender, receiver) = mpmc::bounded_with::<u32>(2, Backpressure::DropOldest);
+    assert_eq!(sender.receiver_count(), 1);
+    for i in 0..4u32 {
+        sender.send::<u32>(i).await.unwrap();
+    }
+    assert!(!sender.is_full());
+    assert_eq!(receiver.drain().collect::<Vec<_>>(), [2, 3]);
+    drop(receiver);
+    assert!(sender.is_closed());
+    assert_eq!(sender.send::<u32>(4u32).await, Err(SendError(4)));
+}
+
+#[tokio::test]
+async fn test_on_overflow() {
+    use std::sync::{Arc, Mutex};
+
+    let dropped = Arc::new(Mutex::new(Vec::new()));
+    let (sender, receiver) = mpmc::bounded_with::<u32>(2, Backpressure::DropOldest);
+    // The callback is shared with the clones that were created before it was set.
+    let clone = sender.clone();
+    let sender = sender.on_overflow({
+        let dropped = dropped.clone();
+        move |protocol| dropped.lock().unwrap().push(protocol)
+    });
+    for i in 0..3u32 {
+        sender.send::<u32>(i).await.unwrap();
+        clone.send::<u32>(i + 10).await.unwrap();
+    }
+    assert_eq!(receiver.drain().collect::<Vec<_>>(), [2, 12]);
+    assert_eq!(*dropped.lock().unwrap(), [0, 10, 1, 11]);
+
+    // Rejected protocols are returned instead.
+    let (sender, _receiver) = mpmc::bounded_with::<u32>(1, Backpressure::Fail);
+    let sender = sender.on_overflow(|_| panic!("not dropped"));
+    sender.send::<u32>(0u32).await.unwrap();
+    assert_eq!(sender.try_send::<u32>(1u32), Err(TrySendError::Full(1)));
+}
+
+#[tokio::test]
+async fn test_broadcast_config() {
+    let (mut sender, mut receiver) = broadcast::channel::<u32>(2);
+    assert!(!sender.overflow());
+    assert_eq!(sender.try_send::<u32>(1u32), Ok(()));
+    assert_eq!(sender.try_send::<u32>(2u32), Ok(()));
+    assert_eq!(sender.try_send::<u32>(3u32), Err(TrySendError::Full(3)));
+
+    sender.set_overflow(true);
+    assert!(sender.overflow());
+    sender.send::<u32>(3u32).await.unwrap();
+    assert_eq!(receiver.try_recv_protocol(), Ok(2));
+
+    sender.set_capacity(4);
+    assert_eq!(sender.capacity(), Some(4));
+    assert_eq!(sender.remaining(), None);
+
+    assert!(sender.await_active());
+    sender.set_await_active(false);
+    assert!(!sender.await_active());
+}
+
+#[tokio::test]
+async fn test_broadcast_inactive_receiver() {
+    let (sender, receiver) = broadcast::channel::<u32>(1);
+    let mut active = receiver.clone();
+    let inactive = receiver.deactivate();
+    assert_eq!(sender.receiver_count(), 1);
+    assert_eq!(sender.inactive_receiver_count(), 1);
+
+    // The inactive receiver does not hold up the channel.
+    for i in 0..10u32 {
+        sender.send::<u32>(i).await.unwrap();
+        assert_eq!(active.recv_protocol().await.unwrap(), i);
+    }
+
+    let mut reactivated = inactive.activate();
+    sender.send::<u32>(10u32).await.unwrap();
+    assert_eq!(reactivated.recv_protocol().await.unwrap(), 10);
+    assert_eq!(sender.inactive_receiver_count(), 0);
+}
+
+#[tokio::test]
+async fn test_broadcast_new_receiver() {
+    let (sender, mut receiver) = broadcast::channel::<u32>(4);
+    sender.send::<u32>(1u32).await.unwrap();
+
+    // A late subscriber only receives messages sent after it was created.
+    let mut late = sender.new_receiver();
+    assert_eq!(sender.receiver_count(), 2);
+    sender.send::<u32>(2u32).await.unwrap();
+    assert_eq!(receiver.recv_protocol().await.unwrap(), 1);
+    assert_eq!(receiver.recv_protocol().await.unwrap(), 2);
+    assert_eq!(late.recv_protocol().await.unwrap(), 2);
+
+    // Dropping the late subscriber is observed.
+    let (count, ()) = futures::join!(sender.receiver_count_changed(), async { drop(late) });
+    assert_eq!(count, 1);
+
+    // The channel is closed once all receivers are dropped.
+    drop(receiver);
+    assert!(sender.send::<u32>(3u32).await.is_err());
+}
+
+#[tokio::test]
+async fn test_broadcast_delivery_count() {
+    async fn fan_out<S: IsBroadcastSender + Sends<u32>>(sender: &S) -> usize
+    where
+        S::With: Default,
+    {
+        sender.send::<u32>(1u32).await.unwrap();
+        sender.delivery_count()
+    }
+
+    let (sender, receiver) = broadcast::channel::<u32>(4);
+    let mut active = receiver.clone();
+    let _inactive = receiver.deactivate();
+    let _late = sender.new_receiver();
+    assert_eq!(sender.subscriber_count(), 3);
+    assert_eq!(fan_out(&sender).await, 2);
+    assert_eq!(active.recv_protocol().await.unwrap(), 1);
+
+    let filtered = sender.clone().filter(|n: &u32| *n > 0);
+    assert_eq!(fan_out(&filtered).await, 2);
+    assert_eq!(filtered.subscriber_count(), 3);
+}
+
+#[tokio::test]
+async fn test_broadcast_lagged() {
+    use broadcast::{LaggedReceiverExt, RecvLaggedError};
+
+    let (mut sender, mut receiver) = broadcast::channel::<u32>(2);
+    let mut skipping = receiver.clone();
+    sender.set_overflow(true);
+    for i in 0..4u32 {
+        sender.send::<u32>(i).await.unwrap();
+    }
+
+    assert_eq!(
+        receiver.recv_lagged().await,
+        Err(RecvLaggedError::Lagged(2))
+    );
+    assert_eq!(receiver.recv_lagged().await, Ok(2));
+    assert_eq!(receiver.recv_lagged().await, Ok(3));
+
+    // The lag is skipped when receiving through `IsReceiver`.
+    assert_eq!(skipping.recv_protocol().await, Ok(2));
+
+    drop(sender);
+    assert_eq!(receiver.recv_lagged().await, Err(RecvLaggedError::Closed));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[cfg(blocking)]
+async fn test_request_variants() {
+    let (sender, receiver) = mpmc::bounded::<MyProtocol>(1);
+    let handle = std::thread::spawn(move || {
+        for _ in 0..2 {
+            let MyProtocol::C(request) = receiver.recv().unwrap() else {
+                panic!("expected request")
+            };
+            let msg = request.msg;
+            request.reply(msg.to_string()).unwrap();
+        }
+        receiver
+    });
+
+    let reply = tokio::task::spawn_blocking({
+        let sender = sender.clone();
+        move || sender.request_blocking::<Request<u32, String>>(1u32)
+    });
+    assert_eq!(reply.await.unwrap().unwrap(), "1");
+    assert_eq!(
+        sender
+            .try_request::<Request<u32, String>>(2u32)
+            .await
+            .unwrap(),
+        "2"
+    );
+
+    let receiver = handle.join().unwrap();
+    sender.send::<u32>(3u32).await.unwrap();
+    assert!(matches!(
+        sender.try_request::<Request<u32, String>>(4u32).await,
+        Err(TryRequestError::Full(4))
+    ));
+    drop(receiver);
+    assert!(matches!(
+        sender.request_blocking::<Request<u32, String>>(5u32),
+        Err(RequestError::Full(5))
+    ));
+}
+
+#[tokio::test]
+async fn test_std_messages() {
+    #[derive(Debug, From, TryInto)]
+    enum StdProtocol {
+        Duration(std::time::Duration),
+        Path(std::path::PathBuf),
+        Addr(std::net::SocketAddr),
+        Array([u8; 4]),
+        Label(std::borrow::Cow<'static, str>),
+        Name(std::sync::Arc<str>),
+        Text(Box<str>),
+    }
+
+    let (sender, receiver) = mpmc::unbounded::<StdProtocol>();
+    sender
+        .send::<std::time::Duration>(std::time::Duration::from_secs(1))
+        .await
+        .unwrap();
+    sender.send::<std::path::PathBuf>("/tmp").await.unwrap();
+    sender
+        .send::<std::net::SocketAddr>(([127, 0, 0, 1], 80))
+        .await
+        .unwrap();
+    sender.send::<[u8; 4]>([1, 2, 3, 4]).await.unwrap();
+    sender
+        .send::<std::borrow::Cow<'static, str>>("label")
+        .await
+        .unwrap();
+    sender.send::<std::sync::Arc<str>>("name").await.unwrap();
+    sender.send::<Box<str>>("text").await.unwrap();
+    assert_eq!(receiver.len(), 7);
+    assert!(matches!(
+        receiver.recv_async().await.unwrap(),
+        StdProtocol::Duration(_)
+    ));
+}
+
+#[test]
+fn test_msg_ergonomics() {
+    let mut msg: Msg<Vec<u32>> = vec![1, 2].into();
+    msg.push(3);
+    assert_eq!(msg.len(), 3);
+    assert_eq!(msg.as_ref(), &[1, 2, 3]);
+    assert_eq!(msg.into_inner(), vec![1, 2, 3]);
+}
+
+#[tokio::test]
+async fn test_channel_backend() {
+    async fn roundtrip<B: ChannelBackend<u32>>(config: B::Config) -> B::Receiver
+    where
+        B::Sender: IsStaticSender<With = ()>,
+    {
+        let (sender, receiver) = channel::<u32, B>(config);
+        sender.send::<u32>(1u32).await.unwrap();
+        receiver
+    }
+
+    let receiver = roundtrip::<mpmc::Mpmc>(mpmc::Config::bounded(1)).await;
+    assert_eq!(receiver.recv_async().await.unwrap(), 1);
+
+    let mut receiver = roundtrip::<broadcast::Broadcast>(1).await;
+    assert_eq!(receiver.recv().await.unwrap(), 1);
+
+    let (sender, receiver) = channel::<u32, priority::Priority<u8>>(None);
+    sender.send_with::<u32>(1u32, 3).await.unwrap();
+    assert_eq!(receiver.recv().await.unwrap(), (1, 3));
+}
+
+#[tokio::test]
+async fn test_run_mailbox() {
+    let (sender, receiver) = mpmc::unbounded::<Outer>();
+    let actor = tokio::spawn(run_mailbox(receiver, State::default()));
+    sender.send::<HelloWorld>("hello").await.unwrap();
+    sender.send::<HelloWorld>("world").await.unwrap();
+    drop(sender);
+
+    let state = actor.await.unwrap();
+    assert_eq!(state.greetings, ["hello", "world"]);
+}
+
+#[tokio::test]
+async fn test_broadcast_replay() {
+    let (sender, mut receiver) = broadcast::channel::<u32>(4);
+    let sender = sender.with_replay(2);
+    for i in 1..=3u32 {
+        sender.send::<u32>(i).await.unwrap();
+    }
+
+    // A late subscriber first receives the last two messages, from any clone of the sender.
+    let mut late = sender.clone().subscribe();
+    sender.send::<u32>(4u32).await.unwrap();
+    for n in [2, 3, 4] {
+        assert_eq!(late.recv_protocol().await.unwrap(), n);
+    }
+    assert!(late.try_recv_protocol().is_err());
+    for n in 1..=4 {
+        assert_eq!(receiver.recv_protocol().await.unwrap(), n);
+    }
+
+    // Without replay, subscribing is the same as creating a new receiver.
+    let (sender, _receiver) = broadcast::channel::<u32>(4);
+    sender.send::<u32>(1u32).await.unwrap();
+    assert!(sender.subscribe().try_recv_protocol().is_err());
+}
+
+#[tokio::test]
+async fn test_broadcast_replay_skips_unsent() {
+    let (sender, receiver) = broadcast::channel::<u32>(1);
+    let sender = sender.with_replay(2);
+    sender.send::<u32>(1u32).await.unwrap();
+
+    // A send that is cancelled while waiting for space is not stored.
+    assert!(futures::FutureExt::now_or_never(sender.send::<u32>(2u32)).is_none());
+    let mut late = sender.subscribe();
+    assert_eq!(late.try_recv_protocol().unwrap(), 1);
+    assert!(late.try_recv_protocol().is_err());
+
+    // Neither is a send that fails because the channel is closed.
+    drop((receiver, late));
+    assert!(sender.send::<u32>(3u32).await.is_err());
+    let mut late = sender.subscribe();
+    assert_eq!(late.try_recv_protocol().unwrap(), 1);
+    assert!(late.try_recv_protocol().is_err());
+}
+
+#[tokio::test]
+async fn test_broadcast_replay_sends_when_polled() {
+    let (sender, mut receiver) = broadcast::channel::<u32>(1);
+    let sender = sender.with_replay(2);
+
+    // A send that is never polled neither sends nor stores the message.
+    drop(sender.send::<u32>(1u32));
+    assert!(receiver.try_recv_protocol().is_err());
+    assert!(sender.subscribe().try_recv_protocol().is_err());
+
+    // A receiver that subscribes while a send waits for space receives it exactly once.
+    sender.send::<u32>(1u32).await.unwrap();
+    let mut send = Box::pin(sender.send::<u32>(2u32));
+    assert!(futures::poll!(send.as_mut()).is_pending());
+    let mut late = sender.subscribe();
+    assert_eq!(receiver.recv_protocol().await.unwrap(), 1);
+    send.await.unwrap();
+    for n in [1, 2] {
+        assert_eq!(late.try_recv_protocol().unwrap(), n);
+    }
+    assert!(late.try_recv_protocol().is_err());
+}
+
+#[tokio::test]
+async fn test_channel_stats() {
+    let (sender, receiver) = mpmc::bounded_with::<u32>(2, Backpressure::DropOldest);
+    let sender = sender.counted();
+    let sender2 = sender.clone();
+    for i in 0..3u32 {
+        sender.send::<u32>(i).await.unwrap();
+    }
+    sender2.try_send::<u32>(3u32).unwrap();
+
+    let stats = sender.stats();
+    assert_eq!((stats.len, stats.capacity), (2, Some(2)));
+    assert_eq!((stats.sender_count, stats.receiver_count), (2, 1));
+    assert_eq!((stats.sent, stats.dropped), (Some(4), Some(2)));
+    assert!(stats.created_at.is_some());
+    assert_eq!(sender2.filter(|_| true).stats(), stats);
+
+    // Counting sends is opt-in.
+    let stats = sender.into_inner().stats();
+    assert_eq!((stats.sent, stats.dropped), (None, Some(2)));
+    assert_eq!(stats.created_at, None);
+
+    // Channels without counters only report the counts.
+    let (sender, _receiver) = broadcast::channel::<u32>(2);
+    let stats = sender.stats();
+    assert_eq!((stats.sent, stats.dropped), (None, None));
+    drop(receiver);
+}
+
+#[tokio::test]
+async fn test_pointer_senders() {
+    async fn send_one<S: Sends<u32, With = ()>>(sender: S, n: u32) {
+        sender.send::<u32>(n).await.unwrap();
+    }
+
+    let (sender, receiver) = mpmc::unbounded::<MyProtocol>();
+    send_one(&sender, 1).await;
+    send_one(Box::new(sender.clone()), 2).await;
+    send_one(std::sync::Arc::new(sender.clone()), 3).await;
+    assert_eq!(std::sync::Arc::new(sender).len(), 3);
+
+    for expected in 1..=3 {
+        let MyProtocol::A(n) = receiver.recv_async().await.unwrap() else {
+            panic!("expected A");
+        };
+        assert_eq!(n, expected);
+    }
+}
+
+#[tokio::test]
+async fn test_lazy_message() {
+    use std::sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    };
+    let evaluated = Arc::new(AtomicU32::new(0));
+    let lazy = |evaluated: &Arc<AtomicU32>, value: u32| {
+        let evaluated = evaluated.clone();
+        move || {
+            evaluated.fetch_add(1, Ordering::Relaxed);
+            value
+        }
+    };
+
+    let (sender, receiver) = mpmc::unbounded::<Lazy<u32>>();
+    sender.send::<Lazy<u32>>(lazy(&evaluated, 1)).await.unwrap();
+    sender.send::<Lazy<u32>>(lazy(&evaluated, 2)).await.unwrap();
+    assert_eq!(evaluated.load(Ordering::Relaxed), 0);
+
+    assert_eq!(receiver.recv_async().await.unwrap().evaluate(), 1);
+    drop(receiver);
+    assert_eq!(evaluated.load(Ordering::Relaxed), 1);
+
+    // A message that fails to send is returned without being evaluated.
+    let lazy = sender
+        .send::<Lazy<u32>>(lazy(&evaluated, 3))
+        .await
+        .unwrap_err()
+        .0;
+    assert_eq!(evaluated.load(Ordering::Relaxed), 1);
+    assert_eq!(lazy.evaluate(), 3);
+}
+
+#[tokio::test]
+async fn test_mux_demux() {
+    use meslin::mux::{Demux, Mux};
+
+    let (sender, receiver) = mpmc::unbounded::<(&'static str, MyProtocol)>();
+    let (mux, demux) = (Mux::new(sender), Demux::new(receiver));
+    let (numbers, mut number_receiver) = (mux.channel("numbers"), demux.channel("numbers"));
+    let (texts, mut text_receiver) = (mux.channel("texts"), demux.channel("texts"));
+    let unknown = mux.channel("unknown");
+
+    // The text receiver waits while the number receiver receives its protocol for it.
+    let recv_text = async { text_receiver.recv_protocol().await.unwrap() };
+    let send = async {
+        unknown.send::<u32>(0u32).await.unwrap();
+        texts.send::<HelloWorld>("hi").await.unwrap();
+        numbers.send::<u32>(1u32).await.unwrap();
+        number_receiver.recv_protocol().await.unwrap()
+    };
+    let (text, number) = futures::join!(recv_text, send);
+    assert!(matches!(text, MyProtocol::B(HelloWorld(text)) if text == "hi"));
+    assert!(matches!(number, MyProtocol::A(1)));
+
+    texts.try_send::<u32>(2u32).unwrap();
+    assert!(number_receiver.try_recv_protocol().is_err());
+    assert!(matches!(
+        text_receiver.try_recv_protocol(),
+        Ok(MyProtocol::A(2))
+    ));
+
+    drop((mux, numbers, texts, unknown));
+    assert_eq!(
+        number_receiver.recv_protocol().await.unwrap_err(),
+        RecvError
+    );
+    assert_eq!(
+        text_receiver.try_recv_protocol().unwrap_err(),
+        TryRecvError::Closed
+    );
+}
+
+#[tokio::test]
+async fn test_request_mux() {
+    use std::sync::Arc;
+
+    let (requests, requests_rx) = mpmc::unbounded::<(CorrelationId, u32)>();
+    let (replies, replies_rx) = mpmc::unbounded::<(CorrelationId, String)>();
+    let mux = Arc::new(RequestMux::new(requests, replies_rx));
+
+    // A server that replies out of order, after all requests were received.
+    let server = tokio::spawn(async move {
+        let mut received = Vec::new();
+        for _ in 0..10 {
+            received.push(requests_rx.recv_async().await.unwrap());
+        }
+        for (id, msg) in received.into_iter().rev() {
+            replies
+                .send::<(CorrelationId, String)>((id, msg.to_string()))
+                .await
+                .unwrap();
+        }
+        requests_rx
+    });
+
+    let handles: Vec<_> = (0..10u32)
+        .map(|i| {
+            let mux = mux.clone();
+            tokio::spawn(async move { mux.request(i).await.unwrap() })
+        })
+        .collect();
+    for (i, handle) in handles.into_iter().enumerate() {
+        assert_eq!(handle.await.unwrap(), i.to_string());
+    }
+    assert!(mux.is_empty());
+
+    // The reply channel closed when the server dropped its reply sender.
+    let requests_rx = server.await.unwrap();
+    assert_eq!(
+        mux.request(10u32).await,
+        Err(RequestError::NoReply(RecvError))
+    );
+    drop(requests_rx);
+    assert_eq!(mux.request(11u32).await, Err(RequestError::Full(11)));
+}
+
+#[tokio::test]
+async fn test_mux_dropped_receive() {
+    use meslin::mux::{Demux, Mux};
+
+    let (sender, receiver) = mpmc::unbounded::<(u8, u32)>();
+    let (mux, demux) = (Mux::new(sender), Demux::new(receiver));
+    let (mut receiver1, mut receiver2) = (demux.channel(1), demux.channel(2));
+
+    // A dropped receive releases the physical receiver, so that other receivers can continue.
+    let mut recv = Box::pin(receiver1.recv_protocol());
+    assert!(futures::poll!(recv.as_mut()).is_pending());
+    drop(recv);
+    mux.channel(2).send::<u32>(2u32).await.unwrap();
+    assert_eq!(receiver2.recv_protocol().await.unwrap(), 2);
+    assert_eq!(receiver2.try_recv_protocol(), Err(TryRecvError::Empty));
+}
+
+#[tokio::test]
+async fn test_monitor() {
+    use std::sync::{Arc, Mutex};
+
+    let crossed = Arc::new(Mutex::new(Vec::new()));
+    let monitor = Monitor::new();
+    monitor.on_watermark(2, 0, {
+        let crossed = crossed.clone();
+        move |watermark| crossed.lock().unwrap().push(watermark)
+    });
+    let (sender, receiver) = mpmc::bounded::<u32>(2);
+    let (sender, mut receiver) = (sender.monitored(&monitor), receiver.monitored(&monitor));
+    sender.send::<u32>(1u32).await.unwrap();
+    sender.send::<u32>(2u32).await.unwrap();
+    assert_eq!(*crossed.lock().unwrap(), [Watermark::High]);
+
+    let wait = tokio::spawn({
+        let sender = sender.clone();
+        async move { sender.wait_for_capacity(1).await }
+    });
+    tokio::task::yield_now().await;
+    assert!(!wait.is_finished());
+    assert_eq!(receiver.recv_protocol().await.unwrap(), 1);
+    assert!(wait.await.unwrap());
+
+    assert_eq!(receiver.recv_protocol().await.unwrap(), 2);
+    assert_eq!(monitor.depth(), 0);
+    assert_eq!(*crossed.lock().unwrap(), [Watermark::High, Watermark::Low]);
+
+    sender.send::<u32>(3u32).await.unwrap();
+    sender.send::<u32>(4u32).await.unwrap();
+    let wait = tokio::spawn({
+        let sender = sender.clone();
+        async move { sender.wait_for_capacity(1).await }
+    });
+    tokio::task::yield_now().await;
+    drop(receiver);
+    assert!(!wait.await.unwrap());
+}
+
+#[tokio::test]
+async fn test_monitor_receive_before_send_returns() {
+    use std::sync::{Arc, Mutex};
+
+    // The watermark of the inner monitor receives the protocol, which happens after the protocol
+    // was sent but before the send through the outer monitor returns.
+    let (outer, inner) = (Monitor::new(), Monitor::new());
+    let (sender, receiver) = mpmc::unbounded::<u32>();
+    let receiver = Arc::new(Mutex::new(receiver.monitored(&outer)));
+    inner.on_watermark(1, 0, {
+        let receiver = receiver.clone();
+        move |_| {
+            receiver.lock().unwrap().try_recv_protocol().unwrap();
+        }
+    });
+    let sender = sender.monitored(&inner).monitored(&outer);
+    sender.send::<u32>(1u32).await.unwrap();
+    assert_eq!(outer.depth(), 0);
+}
+
+#[tokio::test]
+async fn test_reservable_sender() {
+    let (sender, receiver) = mpmc::bounded::<u32>(1);
+    let sender = sender.reservable();
+    let permit = sender.try_reserve().unwrap();
+    assert!(sender.is_full());
+    assert!(matches!(
+        sender.try_send::<u32>(1u32),
+        Err(TrySendError::Full(1))
+    ));
+
+    // A waiting send leaves the reserved slot free, and sends once the reservation is used.
+    let use_permit = async {
+        tokio::task::yield_now().await;
+        permit.send_msg(2u32).unwrap();
+        assert_eq!(sender.reserved(), 0);
+        assert_eq!(receiver.recv_async().await.unwrap(), 2);
+    };
+    let (sent, ()) = tokio::join!(sender.send::<u32>(3u32), use_permit);
+    sent.unwrap();
+    assert_eq!(receiver.recv_async().await.unwrap(), 3);
+}
+
+#[test]
+fn test_try_send_all_to_different_senders() {
+    let (sender1, receiver1) = mpmc::bounded::<u32>(1);
+    let (sender2, receiver2) = priority::bounded::<u32, u8>(2);
+    let (sender3, mut receiver3) = broadcast::channel::<u32>(2);
+    let (sender1, sender2, sender3) = (
+        sender1.reservable(),
+        sender2.reservable(),
+        sender3.reservable(),
+    );
+
+    try_send_all(&[&sender1, &sender2, &sender3], 1u32).unwrap();
+    assert_eq!(
+        try_send_all(&[&sender1, &sender2, &sender3], 2u32),
+        Err(TrySendError::Full(2))
+    );
+    assert_eq!(sender2.reserved(), 0);
+    assert_eq!(receiver1.try_recv().unwrap(), 1);
+    assert_eq!(receiver2.try_recv().unwrap(), (1, 0));
+    assert!(receiver2.try_recv().is_err());
+    assert_eq!(receiver3.try_recv().unwrap(), 1);
+    assert!(receiver3.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_broadcast_receiver_count_changed() {
+    let (sender, receiver) = broadcast::channel::<u32>(4);
+    let _inactive = receiver.deactivate();
+
+    let changed = sender.receiver_count_changed();
+    let subscriber = sender.subscribe();
+    assert_eq!(changed.await, 1);
+
+    let changed = sender.receiver_count_changed();
+    drop(subscriber);
+    assert_eq!(changed.await, 0);
+}
@@ -0,0 +1,44 @@
+use meslin::*;
+
+#[tokio::test]
+async fn mpsc_reports_single_receiver() {
+    let (sender, mut receiver) = mpsc::bounded::<u32>(2);
+    let sender2 = sender.clone();
+    assert_eq!(sender.receiver_count(), 1);
+    assert_eq!(receiver.sender_count(), 2);
+
+    sender.send::<u32>(1u32).await.unwrap();
+    sender2.try_send::<u32>(2u32).unwrap();
+    assert!(matches!(
+        sender.try_send::<u32>(3u32),
+        Err(TrySendError::Full(3))
+    ));
+    assert_eq!(receiver.len(), 2);
+    assert_eq!(receiver.recv().await.unwrap(), 1);
+    assert_eq!(receiver.recv_protocol().await.unwrap(), 2);
+    assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+
+    drop(receiver);
+    assert_eq!(sender.receiver_count(), 0);
+    assert!(sender.is_closed());
+}
+
+#[tokio::test]
+async fn mpsc_drop_oldest_reports_single_receiver() {
+    let (sender, mut receiver) = mpsc::bounded_with::<u32>(1, Backpressure::DropOldest);
+    assert_eq!(sender.receiver_count(), 1);
+    sender.try_send::<u32>(1u32).unwrap();
+    sender.try_send::<u32>(2u32).unwrap();
+    assert_eq!(receiver.drain().collect::<Vec<_>>(), [2]);
+
+    drop(sender);
+    assert_eq!(receiver.recv().await, Err(RecvError));
+}
+
+#[tokio::test]
+async fn mpsc_backend() {
+    let (sender, mut receiver) =
+        <mpsc::Mpsc as ChannelBackend<u32>>::create(mpmc::Config::unbounded());
+    sender.send::<u32>(1u32).await.unwrap();
+    assert_eq!(receiver.recv().await.unwrap(), 1);
+}
@@ -0,0 +1,83 @@
+use meslin::otel::*;
+use opentelemetry::{
+    global,
+    propagation::{text_map_propagator::FieldIter, Extractor, Injector, TextMapPropagator},
+    Context,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+struct Tag(String);
+
+/// A propagator that carries a single [`Tag`] under the `tag` field.
+#[derive(Debug)]
+struct TagPropagator(Vec<String>);
+
+impl TextMapPropagator for TagPropagator {
+    fn inject_context(&self, cx: &Context, injector: &mut dyn Injector) {
+        if let Some(Tag(tag)) = cx.get::<Tag>() {
+            injector.set("tag", tag.clone());
+        }
+    }
+
+    fn extract_with_context(&self, cx: &Context, extractor: &dyn Extractor) -> Context {
+        match extractor.get("tag") {
+            Some(tag) => cx.with_value(Tag(tag.to_string())),
+            None => cx.clone(),
+        }
+    }
+
+    fn fields(&self) -> FieldIter<'_> {
+        FieldIter::new(&self.0)
+    }
+}
+
+fn set_propagator() {
+    global::set_text_map_propagator(TagPropagator(vec!["tag".to_string()]));
+}
+
+#[test]
+fn inject_extract_roundtrip() {
+    set_propagator();
+    let cx = Context::new().with_value(Tag("hello".to_string()));
+
+    let carrier = TraceContext::inject(&cx);
+    assert!(!carrier.is_empty());
+    assert_eq!(carrier.fields().get("tag").unwrap(), "hello");
+
+    let extracted = carrier.extract();
+    assert_eq!(extracted.get::<Tag>(), Some(&Tag("hello".to_string())));
+}
+
+#[test]
+fn traced_attaches_the_sender_context() {
+    set_propagator();
+    let traced = {
+        let _guard = Context::new()
+            .with_value(Tag("sender".to_string()))
+            .attach();
+        Traced::new(5u32)
+    };
+    assert_eq!(Context::current().get::<Tag>(), None);
+
+    let (msg, _guard) = traced.attach();
+    assert_eq!(msg, 5);
+    assert_eq!(
+        Context::current().get::<Tag>(),
+        Some(&Tag("sender".to_string()))
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn trace_context_crosses_serialization() {
+    set_propagator();
+    let cx = Context::new().with_value(Tag("remote".to_string()));
+    let traced = Traced::from_parts(7u32, TraceContext::inject(&cx));
+
+    let bytes = bincode::serialize(&traced).unwrap();
+    let traced: Traced<u32> = bincode::deserialize(&bytes).unwrap();
+
+    let (msg, cx) = traced.into_parts();
+    assert_eq!(msg, 7);
+    assert_eq!(cx.extract().get::<Tag>(), Some(&Tag("remote".to_string())));
+}
@@ -0,0 +1,66 @@
+use meslin::{persist::*, *};
+use std::{path::PathBuf, sync::Arc};
+
+fn log_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("meslin-{name}-{}.log", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+#[tokio::test]
+async fn persist_recovers_backlog_after_restart() {
+    let path = log_path("recover");
+
+    {
+        let log = Arc::new(FileLog::<u32>::open(&path).unwrap());
+        let (sender, receiver) = mpmc::unbounded::<Persisted<u32>>();
+        let sender = PersistSender::new(sender, log.clone());
+        for i in 0..3u32 {
+            sender.send::<u32>(i).await.unwrap();
+        }
+
+        // Only the first message is processed before the "crash".
+        let Persisted { seq, protocol } = receiver.recv_async().await.unwrap();
+        assert_eq!(protocol, 0);
+        log.complete(seq).unwrap();
+        assert_eq!(log.pending_count(), 2);
+    }
+
+    let log = Arc::new(FileLog::<u32>::open(&path).unwrap());
+    assert_eq!(log.pending_count(), 2);
+    let (sender, receiver) = mpmc::unbounded::<Persisted<u32>>();
+    let sender = PersistSender::new(sender, log.clone());
+    assert_eq!(sender.recover().await.unwrap(), 2);
+    sender.send::<u32>(3u32).await.unwrap();
+
+    let received = receiver.drain().map(|p| p.protocol).collect::<Vec<_>>();
+    assert_eq!(received, vec![1, 2, 3]);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn persist_completes_unsent_messages() {
+    let path = log_path("unsent");
+    let log = Arc::new(FileLog::<u32>::open(&path).unwrap());
+    let (sender, receiver) = mpmc::bounded::<Persisted<u32>>(1);
+    let sender = PersistSender::new(sender, log.clone());
+
+    sender.try_send::<u32>(1u32).unwrap();
+    assert!(matches!(
+        sender.try_send::<u32>(2u32),
+        Err(TrySendError::Full(2))
+    ));
+    assert_eq!(log.pending_count(), 1);
+
+    drop(receiver);
+    assert!(sender.try_send::<u32>(3u32).is_err());
+    assert_eq!(log.pending_count(), 1);
+
+    log.compact().unwrap();
+    drop(log);
+    let log = FileLog::<u32>::open(&path).unwrap();
+    assert_eq!(log.pending().unwrap(), vec![(0, 1)]);
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,232 @@
+use meslin::{priority::WithPriority, *};
+
+#[derive(Debug, Message)]
+struct Shutdown;
+
+#[derive(Debug, Message)]
+struct Work(u32);
+
+#[derive(Debug, From, TryInto)]
+enum Protocol {
+    Shutdown(Shutdown),
+    Work(Work),
+}
+
+impl WithPriority<u8> for Protocol {
+    fn priority(&self) -> u8 {
+        match self {
+            Protocol::Shutdown(_) => 10,
+            Protocol::Work(_) => 0,
+        }
+    }
+}
+
+#[tokio::test]
+async fn priority_derived_from_protocol() {
+    let (sender, receiver) = priority::unbounded::<Protocol, u8>();
+    let sender = sender.into_derived();
+
+    sender.send::<Work>(Work(1)).await.unwrap();
+    sender.send::<Shutdown>(Shutdown).await.unwrap();
+    sender.try_send::<Work>(Work(2)).unwrap();
+
+    assert!(matches!(
+        receiver.recv().await.unwrap(),
+        (Protocol::Shutdown(_), 10)
+    ));
+    for expected in [1, 2] {
+        let (Protocol::Work(Work(n)), 0) = receiver.recv().await.unwrap() else {
+            panic!("expected work with priority 0")
+        };
+        assert_eq!(n, expected);
+    }
+}
+
+#[tokio::test]
+async fn priority_monitored_waits_for_capacity() {
+    let monitor = Monitor::new();
+    let (sender, receiver) = priority::bounded::<u32, u8>(1);
+    let (sender, mut receiver) = (sender.monitored(&monitor), receiver.monitored(&monitor));
+    sender.send_with::<u32>(1u32, 1).await.unwrap();
+
+    let wait = tokio::spawn({
+        let sender = sender.clone();
+        async move { sender.wait_for_capacity(1).await }
+    });
+    tokio::task::yield_now().await;
+    assert!(!wait.is_finished());
+    assert_eq!(receiver.recv_protocol_with().await.unwrap(), (1, 1));
+    assert!(wait.await.unwrap());
+    assert_eq!(monitor.depth(), 0);
+}
+
+/// Priorities of the protocol, determined per message.
+struct Priorities;
+
+impl WithDefault<Shutdown> for Priorities {
+    type With = u8;
+
+    fn with_default(&self, _msg: &Shutdown) -> u8 {
+        10
+    }
+}
+
+impl WithDefault<Work> for Priorities {
+    type With = u8;
+
+    fn with_default(&self, msg: &Work) -> u8 {
+        msg.0 as u8
+    }
+}
+
+#[test]
+fn priority_from_with_defaults() {
+    let (sender, receiver) = priority::unbounded::<Protocol, u8>();
+    let sender = sender.with_defaults(Priorities);
+
+    sender.try_send::<Work>(Work(3)).unwrap();
+    sender.try_send::<Shutdown>(Shutdown).unwrap();
+
+    assert!(matches!(
+        receiver.try_recv().unwrap(),
+        (Protocol::Shutdown(_), 10)
+    ));
+    assert!(matches!(
+        receiver.try_recv().unwrap(),
+        (Protocol::Work(_), 3)
+    ));
+}
+
+#[test]
+fn priority_from_with_fn() {
+    let (sender, receiver) = priority::unbounded::<Protocol, u8>();
+    let next = std::sync::atomic::AtomicU8::new(0);
+    let sender =
+        sender.with_fn(move |_: &Protocol| next.fetch_add(1, std::sync::atomic::Ordering::Relaxed));
+
+    sender.try_send::<Work>(Work(1)).unwrap();
+    sender.try_send::<Work>(Work(2)).unwrap();
+
+    assert!(matches!(
+        receiver.try_recv().unwrap(),
+        (Protocol::Work(_), 1)
+    ));
+    assert!(matches!(
+        receiver.try_recv().unwrap(),
+        (Protocol::Work(_), 0)
+    ));
+}
+
+#[test]
+fn priority_from_capturing_map_with() {
+    let (sender, receiver) = priority::unbounded::<Protocol, u8>();
+    let offset = 5u8;
+    let sender = sender.map_with_closures(move |with: u8| with + offset, move |with| with - offset);
+
+    sender.try_send_with::<Work>(Work(1), 2).unwrap();
+
+    assert!(matches!(
+        receiver.try_recv().unwrap(),
+        (Protocol::Work(_), 7)
+    ));
+}
+
+#[tokio::test]
+async fn priority_lowest_first() {
+    let (sender, mut receiver) = priority::unbounded_min::<Protocol, u64>();
+
+    sender.send_with::<Work>(Work(1), 30).await.unwrap();
+    sender.try_send_with::<Work>(Work(2), 10).unwrap();
+    sender.send_with::<Shutdown>(Shutdown, 20).await.unwrap();
+
+    let received = [
+        receiver.recv().await.unwrap().1,
+        receiver.try_recv().unwrap().1,
+        receiver.recv_protocol_with().await.unwrap().1,
+    ];
+    assert_eq!(received, [10, 20, 30]);
+}
+
+#[test]
+fn priority_alias_of_with() {
+    let (sender, receiver) = priority::unbounded::<Protocol, u8>();
+    sender
+        .clone()
+        .priority(4u8)
+        .try_send::<Work>(Work(1))
+        .unwrap();
+
+    let (reversed, reversed_receiver) = priority::unbounded::<Protocol, std::cmp::Reverse<u8>>();
+    reversed.priority(4u8).try_send::<Work>(Work(2)).unwrap();
+
+    assert!(matches!(
+        receiver.try_recv().unwrap(),
+        (Protocol::Work(Work(1)), 4)
+    ));
+    assert!(matches!(
+        reversed_receiver.try_recv().unwrap(),
+        (Protocol::Work(Work(2)), std::cmp::Reverse(4))
+    ));
+}
+
+#[tokio::test]
+async fn priority_adapter_reorders_backlog() {
+    let (sender, receiver) = mpmc::bounded::<u32>(1);
+    let sender = sender.priority_adapter::<u8>(2);
+    assert_eq!(sender.capacity(), Some(3));
+
+    sender.try_send_with::<u32>(1u32, 0).unwrap();
+    assert_eq!(sender.remaining(), Some(2));
+    sender.try_send_with::<u32>(2u32, 0).unwrap();
+    sender.try_send_with::<u32>(3u32, 0).unwrap();
+    assert_eq!(sender.len(), 3);
+    assert!(sender.is_full());
+    assert_eq!(sender.remaining(), Some(0));
+    let stats = sender.stats();
+    assert_eq!((stats.len, stats.capacity), (3, Some(3)));
+    assert!(matches!(
+        sender.try_send_with::<u32>(4u32, 5),
+        Err(TrySendError::Full((4, 5)))
+    ));
+
+    // Sending with a full buffer waits for space, forwarding the highest priority first.
+    let send = sender.send_with::<u32>(4u32, 5);
+    let recv = async {
+        let mut received = Vec::new();
+        for _ in 0..4 {
+            received.push(receiver.recv_async().await.unwrap());
+            sender.try_flush();
+        }
+        received
+    };
+    let (result, received) = futures::join!(send, recv);
+    result.unwrap();
+    assert_eq!(received, vec![1, 4, 2, 3]);
+    assert_eq!(sender.buffered(), 0);
+
+    drop(receiver);
+    assert!(matches!(
+        sender.try_send_with::<u32>(5u32, 0),
+        Err(TrySendError::Closed((5, 0)))
+    ));
+}
+
+#[tokio::test]
+async fn priority_adapter_cancelled_send_keeps_the_backlog() {
+    let monitor = Monitor::new();
+    let (sender, receiver) = mpmc::bounded::<u32>(1);
+    let (sender, mut receiver) = (sender.monitored(&monitor), receiver.monitored(&monitor));
+    let sender = sender.priority_adapter::<u8>(1);
+    sender.try_send_with::<u32>(1u32, 0).unwrap();
+    sender.try_send_with::<u32>(2u32, 5).unwrap();
+
+    // The send waits for space without taking the buffered protocol out of the buffer.
+    let mut send = Box::pin(sender.send_with::<u32>(3u32, 0));
+    assert!(futures::poll!(send.as_mut()).is_pending());
+    drop(send);
+    assert_eq!(sender.buffered(), 1);
+
+    assert_eq!(receiver.recv_protocol().await.unwrap(), 1);
+    sender.flush().await.unwrap();
+    assert_eq!(receiver.recv_protocol().await.unwrap(), 2);
+}
@@ -0,0 +1,180 @@
+use meslin::{remote::*, *};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+
+#[derive(Debug, Clone, Message, Serialize, Deserialize, PartialEq)]
+struct Ping(u32);
+
+impl SerializableMessage for Ping {
+    const NAME: &'static str = "ping";
+}
+
+#[derive(Debug, Clone, Message, Serialize, Deserialize, PartialEq)]
+struct Pong(u32);
+
+impl SerializableMessage for Pong {
+    const NAME: &'static str = "pong";
+}
+
+#[derive(Debug, From, TryInto, DynProtocol)]
+enum ServerProtocol {
+    Ping(Ping),
+    Double(Request<u32, u64>),
+}
+
+fn registry() -> Arc<MessageRegistry> {
+    Arc::new(
+        MessageRegistry::new()
+            .with::<Ping>()
+            .with::<Pong>()
+            .with_request::<u32, u64>("double"),
+    )
+}
+
+async fn start_server() -> (std::net::SocketAddr, mpmc::Receiver<ServerProtocol>) {
+    let (sender, receiver) = mpmc::unbounded::<ServerProtocol>();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(serve(listener, sender, registry()));
+    (addr, receiver)
+}
+
+#[tokio::test]
+async fn remote_send_and_request() {
+    let (addr, receiver) = start_server().await;
+    tokio::spawn(async move {
+        while let Ok(msg) = receiver.recv_async().await {
+            if let ServerProtocol::Double(Request { msg, tx }) = msg {
+                tx.send(msg as u64 * 2).unwrap();
+            }
+        }
+    });
+
+    let remote = RemoteSender::connect(addr, registry()).await.unwrap();
+    let remote: DynSender![Ping, Request<u32, u64>] = remote.try_into_dyn_sender().unwrap();
+
+    remote.send::<Ping>(Ping(1)).await.unwrap();
+    let reply = remote.request::<Request<u32, u64>>(21u32).await.unwrap();
+    assert_eq!(reply, 42);
+}
+
+#[tokio::test]
+async fn remote_not_accepted() {
+    let (addr, receiver) = start_server().await;
+    let remote = RemoteSender::connect(addr, registry()).await.unwrap();
+
+    // Registered, but not accepted by the server protocol.
+    let err = remote.dyn_send_msg(Pong(1)).await.unwrap_err();
+    assert!(matches!(err, DynSendError::NotAccepted(Pong(1))));
+
+    // Not registered at all.
+    let err = remote.dyn_send_msg(10u32).await.unwrap_err();
+    assert!(matches!(err, DynSendError::NotAccepted(10)));
+
+    remote.dyn_send_msg(Ping(2)).await.unwrap();
+    assert!(matches!(
+        receiver.recv_async().await.unwrap(),
+        ServerProtocol::Ping(Ping(2))
+    ));
+}
+
+#[tokio::test]
+async fn remote_connection_loss_closes() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (sender, _receiver) = mpmc::unbounded::<ServerProtocol>();
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        serve_connection(stream, sender, registry()).await
+    });
+    let remote = RemoteSender::connect(addr, registry()).await.unwrap();
+
+    // Drop the connection after the handshake.
+    server.abort();
+
+    let err = remote.dyn_send_msg(Ping(3)).await.unwrap_err();
+    assert!(matches!(err, DynSendError::Closed(Ping(3))));
+    assert!(remote.is_closed());
+}
+
+#[tokio::test]
+async fn remote_drop_closes_the_connection() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (sender, _receiver) = mpmc::unbounded::<ServerProtocol>();
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        serve_connection(stream, sender, registry()).await
+    });
+    let remote = RemoteSender::connect(addr, registry()).await.unwrap();
+    remote.dyn_send_msg(Ping(1)).await.unwrap();
+
+    // Once the sender is dropped, the connection is closed and the server returns.
+    drop(remote);
+    let served = tokio::time::timeout(std::time::Duration::from_secs(5), server).await;
+    served.unwrap().unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn remote_handshake_excludes_incompatible_versions() {
+    #[derive(Debug, Clone, Message, Serialize, Deserialize, PartialEq)]
+    struct PingV1(u32);
+
+    impl SerializableMessage for PingV1 {
+        const NAME: &'static str = "ping";
+        const VERSION: u32 = 1;
+    }
+
+    let (addr, _receiver) = start_server().await;
+    let client_registry = Arc::new(
+        MessageRegistry::new()
+            .with::<PingV1>()
+            .with_request::<u32, u64>("double"),
+    );
+    let remote = RemoteSender::connect(addr, client_registry).await.unwrap();
+    assert_eq!(
+        remote.members(),
+        &[std::any::TypeId::of::<Request<u32, u64>>()]
+    );
+
+    let err = remote.dyn_send_msg(PingV1(1)).await.unwrap_err();
+    assert!(matches!(err, DynSendError::NotAccepted(PingV1(1))));
+    assert!(TryIntoDynSender::<Set![PingV1], ()>::try_into_dyn_sender(remote).is_err());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn remote_over_unix_socket() {
+    let path = std::env::temp_dir().join(format!("meslin-remote-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let listener = tokio::net::UnixListener::bind(&path).unwrap();
+
+    let (sender, receiver) = mpmc::unbounded::<ServerProtocol>();
+    tokio::spawn(serve_unix(listener, sender, registry()));
+
+    let remote = RemoteSender::connect_unix(&path, registry()).await.unwrap();
+    remote.dyn_send_msg(Ping(4)).await.unwrap();
+    assert!(matches!(
+        receiver.recv_async().await.unwrap(),
+        ServerProtocol::Ping(Ping(4))
+    ));
+    let _ = std::fs::remove_file(&path);
+}
+
+#[cfg(feature = "postcard")]
+#[tokio::test]
+async fn remote_with_postcard() {
+    let registry = || Arc::new(MessageRegistry::<(), _>::with_format(Postcard).with::<Ping>());
+    let (sender, receiver) = mpmc::unbounded::<ServerProtocol>();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(serve(listener, sender, registry()));
+
+    let remote = RemoteSender::connect(addr, registry()).await.unwrap();
+    remote.dyn_send_msg(Ping(5)).await.unwrap();
+    assert!(matches!(
+        receiver.recv_async().await.unwrap(),
+        ServerProtocol::Ping(Ping(5))
+    ));
+}
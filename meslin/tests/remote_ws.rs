@@ -0,0 +1,110 @@
+use meslin::{remote::*, *};
+use serde::{Deserialize, Serialize};
+use std::{sync::Arc, time::Duration};
+use tokio::net::TcpListener;
+
+#[derive(Debug, Clone, Message, Serialize, Deserialize, PartialEq)]
+struct Ping(u32);
+
+impl SerializableMessage for Ping {
+    const NAME: &'static str = "ping";
+}
+
+#[derive(Debug, From, TryInto, DynProtocol)]
+enum ServerProtocol {
+    Ping(Ping),
+    Double(Request<u32, u64>),
+}
+
+fn registry() -> Arc<MessageRegistry> {
+    Arc::new(
+        MessageRegistry::new()
+            .with::<Ping>()
+            .with_request::<u32, u64>("double"),
+    )
+}
+
+#[tokio::test]
+async fn ws_send_and_request() {
+    let (sender, receiver) = mpmc::unbounded::<ServerProtocol>();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(serve_ws(listener, sender, registry()));
+    tokio::spawn(async move {
+        while let Ok(msg) = receiver.recv_async().await {
+            if let ServerProtocol::Double(Request { msg, tx }) = msg {
+                tx.send(msg as u64 * 2).unwrap();
+            }
+        }
+    });
+
+    let remote = RemoteSender::connect_ws(format!("ws://{addr}"), registry())
+        .await
+        .unwrap();
+    let remote: DynSender![Ping, Request<u32, u64>] = remote.try_into_dyn_sender().unwrap();
+
+    remote.send::<Ping>(Ping(1)).await.unwrap();
+    let reply = remote.request::<Request<u32, u64>>(21u32).await.unwrap();
+    assert_eq!(reply, 42);
+}
+
+#[tokio::test]
+async fn ws_reconnect() {
+    let (sender, receiver) = mpmc::unbounded::<ServerProtocol>();
+    let listener = Arc::new(TcpListener::bind("127.0.0.1:0").await.unwrap());
+    let addr = listener.local_addr().unwrap();
+    let serve_once = || {
+        let (listener, sender) = (listener.clone(), sender.clone());
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            serve_ws_connection(stream, sender, registry()).await
+        })
+    };
+
+    let server = serve_once();
+    let (connected, mut reconnected) = tokio::sync::mpsc::unbounded_channel();
+    let config = WsConfig::new()
+        .reconnect(|_| Some(Duration::from_millis(10)))
+        .on_connect(move || connected.send(()).unwrap());
+    let remote = RemoteSender::connect_ws_with(format!("ws://{addr}"), registry(), config)
+        .await
+        .unwrap();
+    remote.dyn_send_msg(Ping(1)).await.unwrap();
+    assert!(matches!(
+        receiver.recv_async().await.unwrap(),
+        ServerProtocol::Ping(Ping(1))
+    ));
+
+    // Drop the connection, after which the sender reconnects to the next server.
+    server.abort();
+    let _server = serve_once();
+    reconnected.recv().await.unwrap();
+
+    assert!(!remote.is_closed());
+    remote.dyn_send_msg(Ping(2)).await.unwrap();
+    assert!(matches!(
+        receiver.recv_async().await.unwrap(),
+        ServerProtocol::Ping(Ping(2))
+    ));
+}
+
+#[tokio::test]
+async fn ws_drop_closes_the_connection() {
+    let (sender, _receiver) = mpmc::unbounded::<ServerProtocol>();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        serve_ws_connection(stream, sender, registry()).await
+    });
+    let config = WsConfig::new().keepalive(Some(Duration::from_millis(10)));
+    let remote = RemoteSender::connect_ws_with(format!("ws://{addr}"), registry(), config)
+        .await
+        .unwrap();
+    remote.dyn_send_msg(Ping(1)).await.unwrap();
+
+    // Once the sender is dropped, the connection is closed and the server returns.
+    drop(remote);
+    let served = tokio::time::timeout(Duration::from_secs(5), server).await;
+    served.unwrap().unwrap().unwrap();
+}
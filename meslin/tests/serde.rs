@@ -0,0 +1,122 @@
+use meslin::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct Ping(u32);
+
+impl SerializableMessage for Ping {
+    const NAME: &'static str = "ping";
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct Pong {
+    id: u64,
+    text: String,
+}
+
+impl SerializableMessage for Pong {
+    const NAME: &'static str = "pong";
+}
+
+#[test]
+fn registry_roundtrip() {
+    let registry = MessageRegistry::<u8>::new().with::<Ping>().with::<Pong>();
+
+    let pong = Pong {
+        id: 3,
+        text: "hello".to_string(),
+    };
+    let bytes = registry.serialize(&BoxedMsg::new(pong, 7u8)).unwrap();
+    let msg = registry.deserialize(&bytes).unwrap();
+    let msg = msg.downcast::<Ping>().unwrap_err();
+    let (pong, with) = msg.downcast::<Pong>().unwrap();
+    assert_eq!(pong.text, "hello");
+    assert_eq!(with, 7);
+}
+
+#[test]
+fn registry_rejects_unknown_messages() {
+    let registry = MessageRegistry::<()>::new().with::<Ping>();
+    assert!(matches!(
+        registry.serialize(&BoxedMsg::new(10u32, ())),
+        Err(SerializeError::NotRegistered)
+    ));
+
+    let bytes = MessageRegistry::<()>::new()
+        .with::<Pong>()
+        .serialize(&BoxedMsg::new(
+            Pong {
+                id: 0,
+                text: String::new(),
+            },
+            (),
+        ))
+        .unwrap();
+    assert!(matches!(
+        registry.deserialize(&bytes),
+        Err(DeserializeError::NotRegistered(name)) if name == "pong"
+    ));
+}
+
+#[test]
+fn registry_rejects_incompatible_versions() {
+    #[derive(Serialize, Deserialize)]
+    struct PingV1(u32);
+
+    impl SerializableMessage for PingV1 {
+        const NAME: &'static str = "ping";
+        const VERSION: u32 = 1;
+    }
+
+    let registry = MessageRegistry::<()>::new().with::<PingV1>();
+    let bytes = registry.serialize(&BoxedMsg::new(PingV1(1), ())).unwrap();
+    assert_eq!(registry.version_of("ping"), Some(1));
+    assert!(matches!(
+        MessageRegistry::<()>::new()
+            .with::<Ping>()
+            .deserialize(&bytes),
+        Err(DeserializeError::Incompatible {
+            version: 1,
+            expected: 0,
+            ..
+        })
+    ));
+}
+
+#[test]
+#[should_panic(expected = "already registered")]
+fn registry_rejects_duplicate_names() {
+    #[derive(Serialize, Deserialize)]
+    struct OtherPing;
+
+    impl SerializableMessage for OtherPing {
+        const NAME: &'static str = "ping";
+    }
+
+    MessageRegistry::<()>::new()
+        .with::<Ping>()
+        .with::<OtherPing>();
+}
+
+#[cfg(feature = "postcard")]
+#[test]
+fn registry_roundtrip_postcard() {
+    let registry = MessageRegistry::<u8, _>::with_format(Postcard).with::<Pong>();
+
+    let pong = Pong {
+        id: 3,
+        text: "hello".to_string(),
+    };
+    let bytes = registry.serialize(&BoxedMsg::new(pong, 7u8)).unwrap();
+    let (pong, with) = registry
+        .deserialize(&bytes)
+        .unwrap()
+        .downcast::<Pong>()
+        .unwrap();
+    assert_eq!(pong.text, "hello");
+    assert_eq!(with, 7);
+
+    // The formats are not compatible with each other.
+    let bincode = MessageRegistry::<u8>::new().with::<Pong>();
+    assert!(bincode.deserialize(&bytes).is_err());
+}
@@ -0,0 +1,60 @@
+#![cfg(any(feature = "tokio", feature = "smol"))]
+use meslin::*;
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn test_spawn_tokio() {
+    let (sender, handle) =
+        task::spawn::<mpmc::Mpmc, u32, _, _>(mpmc::Config::bounded(1), |receiver| async move {
+            let mut sum = 0;
+            while let Ok(n) = receiver.recv_async().await {
+                sum += n;
+            }
+            sum
+        });
+    for i in 1..=3u32 {
+        sender.send::<u32>(i).await.unwrap();
+    }
+    drop(sender);
+    assert_eq!(handle.await.unwrap(), 6);
+}
+
+#[cfg(feature = "smol")]
+#[test]
+fn test_spawn_smol() {
+    smol::block_on(async {
+        let (sender, task) =
+            task::spawn_smol::<broadcast::Broadcast, u32, _, _>(1, |mut receiver| async move {
+                receiver.recv().await.unwrap()
+            });
+        sender.send::<u32>(7u32).await.unwrap();
+        assert_eq!(task.await, 7);
+    });
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn test_spawn_pool() {
+    let (sender, workers) =
+        task::spawn_pool::<u32, _, _>(3, mpmc::Config::unbounded(), |index, receiver| async move {
+            let mut received = Vec::new();
+            while let Ok(n) = receiver.recv_async().await {
+                received.push(n);
+            }
+            (index, received)
+        });
+    for i in 0..30u32 {
+        sender.send::<u32>(i).await.unwrap();
+    }
+    drop(sender);
+
+    let results = workers.await;
+    let mut all = Vec::new();
+    for (i, result) in results.into_iter().enumerate() {
+        let (index, received) = result.unwrap();
+        assert_eq!(index, i);
+        all.extend(received);
+    }
+    all.sort();
+    assert_eq!(all, (0..30).collect::<Vec<_>>());
+}
@@ -0,0 +1,55 @@
+use meslin::{
+    test_strategies::{
+        arbitrary::{Arbitrary, Unstructured},
+        proptest::prelude::*,
+    },
+    *,
+};
+
+#[derive(Debug, Message, From, TryInto)]
+enum Protocol {
+    Number(u32),
+    Text(Msg<String>),
+    Request(Request<u8, u8>),
+}
+
+fn summary(protocol: &Protocol) -> (u8, u32, String) {
+    match protocol {
+        Protocol::Number(n) => (0, *n, String::new()),
+        Protocol::Text(text) => (1, 0, text.0.clone()),
+        Protocol::Request(request) => (2, request.msg.into(), String::new()),
+    }
+}
+
+proptest! {
+    #[test]
+    fn protocol_traffic_is_received_in_order(
+        protocols in prop::collection::vec(
+            protocol_strategy![Protocol: u32, Msg<String>, Request<u8, u8>],
+            0..32,
+        )
+    ) {
+        let (sender, receiver) = mpmc::unbounded::<Protocol>();
+        let expected: Vec<_> = protocols.iter().map(summary).collect();
+        for protocol in protocols {
+            sender.try_send::<Protocol>(protocol).unwrap();
+        }
+        let received: Vec<_> = receiver.try_iter().map(|p| summary(&p)).collect();
+        prop_assert_eq!(received, expected);
+    }
+
+    #[test]
+    fn msg_strategy_wraps_value(msg in any::<Msg<u16>>()) {
+        prop_assert_eq!(Msg(*msg), msg);
+    }
+}
+
+#[test]
+fn arbitrary_messages() {
+    let mut u = Unstructured::new(&[1, 2, 3, 4, 5, 6, 7, 8]);
+    let msg = Msg::<u32>::arbitrary(&mut u).unwrap();
+    assert_eq!(msg, Msg(u32::from_le_bytes([1, 2, 3, 4])));
+
+    let request = Request::<u32, ()>::arbitrary(&mut u).unwrap();
+    assert_eq!(request.msg, u32::from_le_bytes([5, 6, 7, 8]));
+}
@@ -0,0 +1,316 @@
+use meslin::{testing::TestReceiver, *};
+use std::time::Duration;
+
+#[derive(Debug, From, TryInto)]
+pub enum MyProtocol {
+    A(u32),
+    B(HelloWorld),
+    C(Request<u32, String>),
+}
+
+#[derive(Debug, Message, From)]
+#[from(forward)]
+pub struct HelloWorld(pub String);
+
+#[tokio::test]
+async fn test_receiver_expectations() {
+    let (sender, receiver) = mpmc::unbounded::<MyProtocol>();
+    let mut receiver = TestReceiver::new(receiver);
+
+    sender.send::<u32>(1u32).await.unwrap();
+    sender.send::<HelloWorld>("hello").await.unwrap();
+    let reply = sender.send::<Request<u32, String>>(10u32).await.unwrap();
+
+    assert_eq!(receiver.expect_next::<u32>().await, 1);
+    assert_eq!(receiver.expect_next::<HelloWorld>().await.0, "hello");
+    let request = receiver.expect_request::<u32, String>().await;
+    let msg = request.msg;
+    request.reply(format!("Your number was: {msg}")).unwrap();
+    assert_eq!(reply.await.unwrap(), "Your number was: 10");
+
+    receiver.expect_no_message(Duration::from_millis(10)).await;
+    drop(sender);
+    receiver.expect_closed().await;
+}
+
+#[tokio::test]
+#[should_panic(expected = "Expected message")]
+async fn test_receiver_wrong_message() {
+    let (sender, receiver) = mpmc::unbounded::<MyProtocol>();
+    let mut receiver = TestReceiver::new(receiver);
+
+    sender.send::<u32>(1u32).await.unwrap();
+    receiver.expect_next::<HelloWorld>().await;
+}
+
+#[tokio::test]
+async fn pump_channel_releases_in_order() {
+    let (sender, pump, receiver) = testing::pump::channel::<MyProtocol>();
+    let mut receiver = TestReceiver::new(receiver);
+
+    sender.send::<u32>(1u32).await.unwrap();
+    sender.send::<u32>(2u32).await.unwrap();
+    assert_eq!(pump.staged(), 2);
+    receiver.expect_no_message(Duration::from_millis(10)).await;
+
+    assert_eq!(pump.pump(1), 1);
+    assert_eq!(receiver.expect_next::<u32>().await, 1);
+    receiver.expect_no_message(Duration::from_millis(10)).await;
+
+    drop(sender);
+    assert_eq!(pump.pump_all(), 1);
+    assert_eq!(receiver.expect_next::<u32>().await, 2);
+    receiver.expect_closed().await;
+}
+
+#[tokio::test]
+async fn manual_clock_drives_timeouts() {
+    let clock = time::ManualClock::new();
+    let (_sender, receiver) = mpmc::unbounded::<MyProtocol>();
+    let mut receiver = TestReceiver::new(receiver).with_clock(clock.clone());
+
+    let advance = async {
+        assert_eq!(clock.sleepers(), 1);
+        clock.advance(Duration::from_secs(60));
+    };
+    futures::join!(receiver.expect_no_message(Duration::from_secs(60)), advance);
+    assert_eq!(clock.sleepers(), 0);
+}
+
+#[tokio::test]
+async fn ask_drops_reply_slot_after_deadline() {
+    let clock = time::ManualClock::new();
+    let (sender, receiver) = mpmc::unbounded::<MyProtocol>();
+
+    let advance = async {
+        assert_eq!(clock.sleepers(), 1);
+        clock.advance(Duration::from_secs(5));
+    };
+    let (result, ()) = futures::join!(
+        time::ask_with_clock::<_, Request<u32, String>>(
+            &clock,
+            &sender,
+            1u32,
+            Duration::from_secs(5)
+        ),
+        advance
+    );
+    assert_eq!(result.unwrap_err(), time::AskError::Elapsed(None));
+
+    // The request was sent, but the reply is no longer awaited.
+    let MyProtocol::C(request) = receiver.recv_async().await.unwrap() else {
+        panic!("expected request")
+    };
+    assert_eq!(request.reply("late".into()), Err("late".to_string()));
+
+    drop(receiver);
+    let result = time::ask::<_, Request<u32, String>>(&sender, 2u32, Duration::from_secs(5)).await;
+    assert_eq!(result.unwrap_err(), time::AskError::Closed(2));
+}
+
+#[tokio::test]
+async fn send_timeout_elapses_on_full_channel() {
+    use time::TimeoutExt;
+    let clock = time::ManualClock::new();
+    let (sender, receiver) = mpmc::bounded::<MyProtocol>(1);
+    sender
+        .send::<u32>(1u32)
+        .timeout_with_clock(&clock, Duration::from_secs(1))
+        .await
+        .unwrap()
+        .unwrap();
+
+    let advance = async {
+        assert_eq!(clock.sleepers(), 1);
+        clock.advance(Duration::from_secs(1));
+    };
+    let (result, ()) = futures::join!(
+        sender
+            .send::<u32>(2u32)
+            .timeout_with_clock(&clock, Duration::from_secs(1)),
+        advance
+    );
+    assert_eq!(result.unwrap_err(), time::Elapsed(Duration::from_secs(1)));
+    assert_eq!(receiver.len(), 1);
+}
+
+#[tokio::test]
+async fn send_timeout_returns_the_message() {
+    let clock = time::ManualClock::new();
+    let (sender, receiver) = mpmc::bounded::<MyProtocol>(1);
+    let monitor = Monitor::new();
+    let (sender, _receiver) = (sender.monitored(&monitor), receiver.monitored(&monitor));
+    sender.send::<u32>(1u32).await.unwrap();
+
+    let advance = async {
+        assert_eq!(clock.sleepers(), 1);
+        clock.advance(Duration::from_secs(1));
+    };
+    let (result, ()) = futures::join!(
+        time::send_timeout_with_clock::<_, u32>(&clock, &sender, 2u32, Duration::from_secs(1)),
+        advance
+    );
+    assert_eq!(result, Err(time::SendTimeoutError::Elapsed(Some(2))));
+
+    let advance = async {
+        clock.advance(Duration::from_secs(1));
+    };
+    let (result, ()) = futures::join!(
+        time::ask_with_clock::<_, Request<u32, String>>(
+            &clock,
+            &sender,
+            3u32,
+            Duration::from_secs(1)
+        ),
+        advance
+    );
+    assert_eq!(result.unwrap_err(), time::AskError::Elapsed(Some(3)));
+    assert_eq!(monitor.depth(), 1);
+}
+
+#[tokio::test]
+async fn expired_messages_are_skipped() {
+    use time::Expires;
+    let clock = time::ManualClock::new();
+    let dead_letters = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let (sender, receiver) = priority::unbounded_min::<MyProtocol, Expires<u8>>();
+    let mut receiver = receiver.skip_expired_with_clock(clock.clone()).on_expired({
+        let dead_letters = dead_letters.clone();
+        move |protocol, _| dead_letters.lock().unwrap().push(protocol)
+    });
+
+    let ttl = |secs| Expires::after_with_clock(&clock, Duration::from_secs(secs), 0);
+    sender.try_send_with::<u32>(1u32, ttl(1)).unwrap();
+    sender.try_send_with::<u32>(2u32, ttl(3)).unwrap();
+    sender.try_send_with::<u32>(3u32, ttl(2)).unwrap();
+
+    clock.advance(Duration::from_secs(2));
+    let (MyProtocol::A(msg), 0) = receiver.recv_protocol_with().await.unwrap() else {
+        panic!("expected message")
+    };
+    assert_eq!(msg, 2);
+    assert_eq!(
+        receiver.try_recv_protocol().unwrap_err(),
+        TryRecvError::Empty
+    );
+
+    let expired = dead_letters.lock().unwrap();
+    assert!(matches!(expired[..], [MyProtocol::A(1), MyProtocol::A(3)]));
+}
+
+#[tokio::test]
+async fn throttle_per_variant() {
+    let clock = time::ManualClock::new();
+    let (sender, receiver) = mpmc::unbounded::<MyProtocol>();
+    let sender = sender
+        .throttle_with_clock(Duration::from_secs(1), clock.clone())
+        .per_variant();
+
+    sender.try_send::<u32>(1u32).unwrap();
+    sender.try_send::<HelloWorld>("hi").unwrap();
+    sender.send::<u32>(2u32).await.unwrap();
+    clock.advance(Duration::from_millis(500));
+    sender.try_send::<u32>(3u32).unwrap();
+    clock.advance(Duration::from_millis(500));
+    sender.try_send::<u32>(4u32).unwrap();
+
+    let received: Vec<_> = receiver.drain().collect();
+    assert!(matches!(
+        received[..],
+        [MyProtocol::A(1), MyProtocol::B(_), MyProtocol::A(4)]
+    ));
+}
+
+#[tokio::test]
+async fn debounce_forwards_latest_after_quiet_period() {
+    let clock = time::ManualClock::new();
+    let (sender, receiver) = mpmc::unbounded::<MyProtocol>();
+    let sender = sender.debounce_with_clock(Duration::from_secs(1), clock.clone());
+
+    let burst = async {
+        for i in 2..5u32 {
+            tokio::task::yield_now().await;
+            clock.advance(Duration::from_millis(500));
+            sender.try_send::<u32>(i).unwrap();
+        }
+        assert_eq!(receiver.len(), 0);
+        tokio::task::yield_now().await;
+        clock.advance(Duration::from_secs(1));
+    };
+    let (result, ()) = futures::join!(sender.send::<u32>(1u32), burst);
+    result.unwrap();
+
+    assert_eq!(sender.pending(), 0);
+    let received: Vec<_> = receiver.drain().collect();
+    assert!(matches!(received[..], [MyProtocol::A(4)]));
+}
+
+#[tokio::test]
+async fn debounce_drops_the_protocol_of_a_cancelled_send() {
+    let clock = time::ManualClock::new();
+    let (sender, receiver) = mpmc::unbounded::<MyProtocol>();
+    let sender = sender.debounce_with_clock(Duration::from_secs(1), clock.clone());
+
+    let mut send = Box::pin(sender.send::<u32>(1u32));
+    assert!(futures::poll!(send.as_mut()).is_pending());
+    assert_eq!(sender.pending(), 1);
+    drop(send);
+    assert_eq!(sender.pending(), 0);
+
+    // Without a waiting send, the protocol is forwarded immediately.
+    sender.try_send::<u32>(2u32).unwrap();
+    let received: Vec<_> = receiver.drain().collect();
+    assert!(matches!(received[..], [MyProtocol::A(2)]));
+}
+
+#[tokio::test]
+async fn batch_flushes_on_size_and_interval() {
+    let clock = time::ManualClock::new();
+    let (sender, receiver) = mpmc::unbounded::<Vec<u32>>();
+    let sender = sender.batch_with_clock::<u32>(3, Duration::from_secs(1), clock.clone());
+
+    for i in 0..4u32 {
+        sender.try_send::<u32>(i).unwrap();
+    }
+    assert_eq!(receiver.try_recv().unwrap(), [0, 1, 2]);
+    assert_eq!(sender.buffered(), 1);
+    sender.flush().await.unwrap();
+    assert_eq!(receiver.try_recv().unwrap(), [3]);
+
+    // The first message of a batch sends it once the interval has passed.
+    let rest = async {
+        sender.send::<u32>(5u32).await.unwrap();
+        assert_eq!(clock.sleepers(), 1);
+        clock.advance(Duration::from_secs(1));
+    };
+    let (result, ()) = futures::join!(sender.send::<u32>(4u32), rest);
+    result.unwrap();
+    assert_eq!(receiver.try_recv().unwrap(), [4, 5]);
+    assert!(receiver.is_empty());
+
+    drop(receiver);
+    sender.try_send::<u32>(6u32).unwrap();
+    sender.try_send::<u32>(7u32).unwrap();
+    assert_eq!(sender.try_send::<u32>(8u32).unwrap_err().into_inner(), 8);
+}
+
+#[tokio::test]
+async fn batch_timer_is_taken_over_after_a_cancelled_send() {
+    let clock = time::ManualClock::new();
+    let (sender, receiver) = mpmc::unbounded::<Vec<u32>>();
+    let sender = sender.batch_with_clock::<u32>(3, Duration::from_secs(1), clock.clone());
+
+    let mut send = Box::pin(sender.send::<u32>(1u32));
+    assert!(futures::poll!(send.as_mut()).is_pending());
+    drop(send);
+    assert_eq!(sender.buffered(), 1);
+
+    // The next send waits for the interval instead, and sends the batch.
+    let advance = async {
+        assert_eq!(clock.sleepers(), 1);
+        clock.advance(Duration::from_secs(1));
+    };
+    let (result, ()) = futures::join!(sender.send::<u32>(2u32), advance);
+    result.unwrap();
+    assert_eq!(receiver.try_recv().unwrap(), [1, 2]);
+}
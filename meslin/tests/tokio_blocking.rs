@@ -0,0 +1,49 @@
+#![cfg(blocking)]
+
+use meslin::*;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn blocking_within_multi_thread_runtime() {
+    let (sender, receiver) = mpmc::bounded::<Request<u32, u32>>(1);
+    tokio::spawn(async move {
+        while let Ok(request) = receiver.recv_async().await {
+            let msg = request.msg;
+            request.reply(msg + 1).unwrap();
+        }
+    });
+
+    // The reply is sent by a task on the same runtime, which can continue while blocking.
+    for i in 0..10u32 {
+        assert_eq!(
+            sender.request_blocking::<Request<u32, u32>>(i).unwrap(),
+            i + 1
+        );
+    }
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn blocking_within_current_thread_runtime() {
+    assert!(!can_block());
+
+    // Calls that don't have to wait succeed without blocking.
+    let (sender, mut receiver) = mpmc::bounded::<u32>(1);
+    sender.send_blocking::<u32>(1u32).unwrap();
+    assert_eq!(receiver.recv_protocol_blocking().unwrap(), 1);
+    drop(receiver);
+    assert_eq!(sender.send_blocking::<u32>(2u32), Err(SendError(2)));
+}
+
+#[tokio::test(flavor = "current_thread")]
+#[should_panic(expected = "current-thread Tokio runtime")]
+async fn blocking_within_current_thread_runtime_panics() {
+    let (_sender, mut receiver) = mpmc::bounded::<u32>(1);
+    let _ = receiver.recv_protocol_blocking();
+}
+
+#[tokio::test(flavor = "current_thread")]
+#[should_panic(expected = "current-thread Tokio runtime")]
+async fn request_blocking_within_current_thread_runtime_panics() {
+    let (sender, receiver) = mpmc::bounded::<Request<u32, u32>>(1);
+    let _ = sender.request_blocking::<Request<u32, u32>>(1u32);
+    drop(receiver);
+}